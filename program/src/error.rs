@@ -36,6 +36,100 @@ pub enum PaladinLockupError {
     /// The provided pool account did not match the lockup.
     #[error("Incorrect pool account")]
     IncorrectPool,
+    /// The unlock amount must be non-zero and less than the lockup amount.
+    #[error("Invalid unlock amount.")]
+    InvalidUnlockAmount,
+    /// Lockup has not been unlocked.
+    #[error("Lockup not unlocked.")]
+    LockupNotUnlocked,
+    /// Lockup is committed to a term that has not ended yet.
+    #[error("Lockup term has not ended.")]
+    LockupTermActive,
+    /// The vesting schedule is malformed.
+    #[error("Invalid vesting schedule.")]
+    InvalidVestingSchedule,
+    /// The operation is not supported for vesting lockups.
+    #[error("Lockup is vesting.")]
+    LockupVesting,
+    /// No vested tokens are available to withdraw.
+    #[error("No vested tokens to withdraw.")]
+    NothingVested,
+    /// The lockup's metadata was updated too recently.
+    #[error("Metadata updated too recently.")]
+    MetadataUpdateTooSoon,
+    /// Incorrect lockup address.
+    #[error("Incorrect lockup address.")]
+    IncorrectLockupAddress,
+    /// Incorrect lockup pool address.
+    #[error("Incorrect lockup pool address.")]
+    IncorrectLockupPoolAddress,
+    /// The pool does not accept new lockups.
+    #[error("Pool is not accepting new lockups.")]
+    LockupsDisabled,
+    /// The requested operation is paused for this pool.
+    #[error("Pool is paused.")]
+    PoolPaused,
+    /// The lockup already holds an entry in the pool.
+    #[error("Lockup is already in the pool.")]
+    LockupAlreadyInPool,
+    /// The requested pool capacity is smaller than the current capacity or
+    /// exceeds the maximum.
+    #[error("Invalid lockup pool capacity.")]
+    InvalidPoolCapacity,
+    /// The shard covering a lockup's amount was not provided.
+    #[error("Lockup pool shard not provided.")]
+    MissingLockupPoolShard,
+    /// The provided shard account does not match the pool's shards.
+    #[error("Incorrect lockup pool shard.")]
+    IncorrectLockupPoolShard,
+    /// The shard boundary is out of order with the pool's other shards, or the
+    /// pool has no room for another shard.
+    #[error("Invalid lockup pool shard boundary.")]
+    InvalidShardBoundary,
+    /// Moving entries would exceed a shard's capacity.
+    #[error("Lockup pool shard capacity exceeded.")]
+    ShardCapacityExceeded,
+    /// The provided lockup pool snapshot address does not match the pool and
+    /// current epoch.
+    #[error("Incorrect lockup pool snapshot address.")]
+    IncorrectLockupPoolSnapshotAddress,
+    /// The provided reward authority address does not match the pool's.
+    #[error("Incorrect reward authority address.")]
+    IncorrectRewardAuthorityAddress,
+    /// The provided reward vault does not match the pool's.
+    #[error("Incorrect reward vault.")]
+    IncorrectRewardVault,
+    /// Rewards cannot be distributed while the pool has no active lockups.
+    #[error("No active lockups to reward.")]
+    NoActiveLockups,
+    /// The lockup has no rewards to claim.
+    #[error("No rewards to claim.")]
+    NoRewards,
+    /// The provided lockup pool root address does not match the one derived
+    /// from the pool.
+    #[error("Incorrect lockup pool root address.")]
+    IncorrectLockupPoolRootAddress,
+    /// The mint has an extension lockup pools do not support.
+    #[error("Unsupported mint extension.")]
+    UnsupportedMintExtension,
+    /// Snapshots cannot be closed during the epoch they were taken in.
+    #[error("Snapshot epoch has not ended.")]
+    SnapshotEpochActive,
+    /// The pool's cooldown or metadata update interval is out of bounds, or
+    /// differs from the defaults for a pool without an authority.
+    #[error("Invalid lockup pool configuration.")]
+    InvalidPoolConfig,
+    /// The account of a lockup evicted from the pool was not provided.
+    #[error("Evicted lockup account not provided.")]
+    MissingEvictedLockup,
+    /// The lockup account is in the legacy layout, and must be migrated with
+    /// `MigrateLockup` first.
+    #[error("Lockup not migrated.")]
+    LockupNotMigrated,
+    /// The lockup pool account is in the legacy layout, and must be migrated
+    /// with `MigrateLockupPool` first.
+    #[error("Lockup pool not migrated.")]
+    LockupPoolNotMigrated,
 }
 
 impl PrintProgramError for PaladinLockupError {
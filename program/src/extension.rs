@@ -0,0 +1,101 @@
+//! Mint extension policy for lockup pools.
+//!
+//! Every Token-2022 extension type is classified explicitly, so supporting a
+//! new extension is a deliberate decision rather than a fallthrough.
+
+use spl_token_2022::extension::ExtensionType;
+
+/// How a lockup pool treats a mint extension.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ExtensionPolicy {
+    /// The extension has no bearing on lockups.
+    Allowed,
+    /// The extension is supported, subject to the described condition, which
+    /// is logged when a pool is initialized for the mint.
+    AllowedWithConditions(&'static str),
+    /// The extension is unsupported, for the described reason.
+    Rejected(&'static str),
+}
+
+/// Classifies the provided extension, as found on a pool's mint.
+pub fn mint_extension_policy(extension: ExtensionType) -> ExtensionPolicy {
+    match extension {
+        ExtensionType::Uninitialized
+        | ExtensionType::InterestBearingConfig
+        | ExtensionType::MetadataPointer
+        | ExtensionType::TokenMetadata
+        | ExtensionType::GroupPointer
+        | ExtensionType::TokenGroup
+        | ExtensionType::GroupMemberPointer
+        | ExtensionType::TokenGroupMember => ExtensionPolicy::Allowed,
+        ExtensionType::TransferFeeConfig => ExtensionPolicy::AllowedWithConditions(
+            "lockups record the amount received by the escrow, net of the fee",
+        ),
+        ExtensionType::TransferHook => ExtensionPolicy::AllowedWithConditions(
+            "the hook's extra accounts must be provided when moving tokens",
+        ),
+        ExtensionType::PermanentDelegate => {
+            ExtensionPolicy::Rejected("the delegate could move escrowed tokens")
+        }
+        ExtensionType::DefaultAccountState => {
+            ExtensionPolicy::Rejected("escrow token accounts could start frozen")
+        }
+        ExtensionType::MintCloseAuthority => ExtensionPolicy::Rejected(
+            "the mint could be closed and recreated with unsupported extensions",
+        ),
+        ExtensionType::NonTransferable => {
+            ExtensionPolicy::Rejected("tokens cannot be moved into escrow")
+        }
+        ExtensionType::ConfidentialTransferMint | ExtensionType::ConfidentialTransferFeeConfig => {
+            ExtensionPolicy::Rejected("confidential transfers are not supported")
+        }
+        ExtensionType::TransferFeeAmount
+        | ExtensionType::ConfidentialTransferAccount
+        | ExtensionType::ImmutableOwner
+        | ExtensionType::MemoTransfer
+        | ExtensionType::NonTransferableAccount
+        | ExtensionType::CpiGuard
+        | ExtensionType::TransferHookAccount
+        | ExtensionType::ConfidentialTransferFeeAmount => {
+            ExtensionPolicy::Rejected("not a mint extension")
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_mint_extension_policy() {
+        for extension in [
+            ExtensionType::MetadataPointer,
+            ExtensionType::TokenMetadata,
+            ExtensionType::InterestBearingConfig,
+            ExtensionType::GroupMemberPointer,
+        ] {
+            assert_eq!(mint_extension_policy(extension), ExtensionPolicy::Allowed);
+        }
+        for extension in [
+            ExtensionType::TransferFeeConfig,
+            ExtensionType::TransferHook,
+        ] {
+            assert!(matches!(
+                mint_extension_policy(extension),
+                ExtensionPolicy::AllowedWithConditions(_)
+            ));
+        }
+        for extension in [
+            ExtensionType::PermanentDelegate,
+            ExtensionType::DefaultAccountState,
+            ExtensionType::MintCloseAuthority,
+            ExtensionType::NonTransferable,
+            ExtensionType::ImmutableOwner,
+        ] {
+            assert!(matches!(
+                mint_extension_policy(extension),
+                ExtensionPolicy::Rejected(_)
+            ));
+        }
+    }
+}
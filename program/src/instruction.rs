@@ -1,37 +1,122 @@
 //! Program instruction types.
 
 use {
-    crate::state::get_escrow_authority_address,
+    crate::state::{
+        get_escrow_authority_address, get_lockup_address, get_lockup_pool_address,
+        get_lockup_pool_root_address, get_lockup_pool_snapshot_address,
+        get_pool_escrow_authority_address, get_reward_authority_address, LockupPool,
+        LockupPoolEntry, VestingSchedule,
+    },
     shank::ShankInstruction,
     solana_program::{
         instruction::{AccountMeta, Instruction},
         program_error::ProgramError,
         pubkey::Pubkey,
+        system_program,
     },
     spl_associated_token_account::get_associated_token_address_with_program_id,
+    std::num::NonZeroU64,
 };
 
 /// Instructions supported by the Paladin Lockup program.
+///
+/// Instructions that add, move or remove lockups in a sharded pool also expect
+/// the shards covering the lockups' amounts, and the shards below them, to be
+/// appended to their accounts (see `lockup_pool_shard_accounts`), which the
+/// instruction builders below work out from the pool's state.
+///
+/// Instructions inserting into the pool likewise take the lockups they may
+/// evict (see `lockup_pool_evicted_accounts`), so that their pool membership
+/// flags stay accurate. They fail if an evicted lockup's account is missing,
+/// or still in the legacy layout.
 #[rustfmt::skip]
 #[derive(Clone, Copy, Debug, PartialEq, ShankInstruction)]
 pub enum PaladinLockupInstruction {
-    /// Initialize a lockup pool.
+    /// Initialize a lockup pool at the PDA derived from the mint and pool id
+    /// (see `get_lockup_pool_address`).
+    ///
+    /// The program creates the pool account itself, funded by the payer. As
+    /// accounts can only grow by `MAX_PERMITTED_DATA_INCREASE` bytes per
+    /// instruction, this instruction must be repeated until the account has
+    /// reached `LockupPool::account_len(LockupPool::INITIAL_CAPACITY)` bytes, at
+    /// which point the pool is initialized.
+    ///
+    /// Unlocked lockups in the pool must wait `cooldown_seconds` before they
+    /// can be withdrawn, and lockups may only update their metadata once every
+    /// `metadata_update_interval_seconds` (zero for no limit). Neither may
+    /// exceed 30 days.
+    ///
+    /// The pool authority, if provided, must sign, and becomes able to update
+    /// the pool's configuration. For the canonical pool (pool id `0`), it
+    /// must be the mint's mint authority, if the mint has one, and is omitted
+    /// otherwise. Other pools may take any pool authority, or none. Pools
+    /// without a pool authority must use the default cooldown and no
+    /// metadata update interval.
+    ///
+    /// If `isolated_escrow` is set, the pool's tokens are escrowed under the
+    /// pool's own escrow authority (see `get_pool_escrow_authority_address`)
+    /// instead of the program-wide one. Instruction builders moving the
+    /// pool's tokens take an `isolated_escrow` flag to match.
+    ///
+    /// Accounts expected by this instruction:
+    ///
+    /// 0. `[w, s]` Payer.
+    /// 1. `[w]` Lockup pool account.
+    /// 2. `[ ]` Token mint.
+    /// 3. `[ ]` System program.
+    /// 4. `[s]` Pool authority, if any (see above).
     #[account(
         0,
+        writable,
+        signer,
+        name = "payer",
+        description = "Payer"
+    )]
+    #[account(
+        1,
+        writable,
         name = "lockup_pool",
         description = "Lockup pool"
     )]
     #[account(
-        1,
+        2,
         name = "mint",
         description = "Mint"
     )]
-    InitializeLockupPool,
+    #[account(
+        3,
+        name = "system_program",
+        description = "System program"
+    )]
+    #[account(
+        4,
+        optional,
+        signer,
+        name = "pool_authority",
+        description = "Pool authority"
+    )]
+    InitializeLockupPool {
+        pool_id: u64,
+        cooldown_seconds: u64,
+        metadata_update_interval_seconds: u64,
+        isolated_escrow: bool,
+    },
     /// Lock up tokens in a lockup account for an unspecified period of time.
     ///
+    /// An optional committed end timestamp can be provided, before which the
+    /// lockup cannot be unlocked. Instruction data omitting it entirely, as
+    /// packed by older clients, is accepted as having none.
+    ///
+    /// For mints with a transfer fee, the lockup records the amount received
+    /// by the escrow, net of the fee.
+    ///
     /// Expects an uninitialized lockup account with enough rent-exempt
     /// lamports to store lockup state, owned by the Paladin Lockup program.
     ///
+    /// If the pool is full, its smallest lockup is evicted. The evicted
+    /// lockup account must be appended to the accounts below to record that it
+    /// left the pool.
+    ///
     /// Accounts expected by this instruction:
     ///
     /// 0. `[ ]` Lockup authority.
@@ -93,14 +178,29 @@ pub enum PaladinLockupInstruction {
         name = "token_program",
         description = "Token program"
     )]
-    Lockup { metadata: [u8; 32], amount: u64 },
+    Lockup {
+        metadata: [u8; 32],
+        amount: u64,
+        committed_end_timestamp: Option<NonZeroU64>,
+    },
     /// Unlock a token lockup, enabling the tokens for withdrawal after cooldown.
     ///
+    /// A non-zero `amount` unlocks only part of the lockup, moving the
+    /// unlocked amount into a new lockup account. The remaining amount stays
+    /// locked up and is re-ranked in the pool, leaving the pool if it no
+    /// longer outranks the pool's smallest lockup. A zero `amount`, packed as
+    /// no data at all, unlocks the whole lockup.
+    ///
+    /// For a partial unlock, expects an uninitialized unlocked lockup account
+    /// with enough rent-exempt lamports to store lockup state, owned by the
+    /// Paladin Lockup program.
+    ///
     /// Accounts expected by this instruction:
     ///
     /// 0. `[s]` Lockup authority.
     /// 1. `[w]` Lockup pool account.
     /// 2. `[w]` Lockup account.
+    /// 3. `[w]` Unlocked lockup account, for a partial unlock.
     #[account(
         0,
         signer,
@@ -119,13 +219,30 @@ pub enum PaladinLockupInstruction {
         name = "lockup_account",
         description = "Lockup account"
     )]
-    Unlock,
+    #[account(
+        3,
+        optional,
+        writable,
+        name = "unlocked_lockup_account",
+        description = "Unlocked lockup account"
+    )]
+    Unlock { amount: u64 },
     /// Withdraw tokens from a lockup account.
     ///
-    /// Lockup must be unlocked and have waited 30 minutes before withdrawal.
+    /// Lockup must be unlocked and have waited for the pool's cooldown, as of
+    /// its unlock, before withdrawal.
+    ///
+    /// Vesting lockups instead release the vested-but-not-yet-withdrawn
+    /// portion of their tokens, and are only closed once fully withdrawn.
+    /// Their remaining amount is re-ranked in the pool, leaving the pool if it
+    /// no longer outranks the pool's smallest lockup.
+    ///
+    /// A lockup closed with unclaimed rewards has them paid out of the pool's
+    /// reward vault to the token destination, along with its tokens.
     ///
     /// Note this instruction accepts a destination account for both lamports
-    /// (from the closed lockup account's rent lamports) and tokens.
+    /// (from the closed lockup account's rent lamports) and tokens. For mints
+    /// with a transfer fee, the fee is withheld from the tokens received.
     ///
     /// Accounts expected by this instruction:
     ///
@@ -133,10 +250,13 @@ pub enum PaladinLockupInstruction {
     /// 1. `[w]` Lamport destination.
     /// 2. `[w]` Token destination.
     /// 3. `[w]` Lockup account.
-    /// 4. `[ ]` Escrow authority.
-    /// 5. `[w]` Escrow token account.
-    /// 6. `[ ]` Token mint.
-    /// 7. `[ ]` Token program.
+    /// 4. `[w]` Lockup pool account.
+    /// 5. `[ ]` Escrow authority.
+    /// 6. `[w]` Escrow token account.
+    /// 7. `[ ]` Token mint.
+    /// 8. `[ ]` Token program.
+    /// 9. `[ ]` Reward authority.
+    /// 10. `[w]` Reward vault.
     #[account(
         0,
         signer,
@@ -163,200 +283,2408 @@ pub enum PaladinLockupInstruction {
     )]
     #[account(
         4,
+        writable,
+        name = "lockup_pool",
+        description = "Lockup pool"
+    )]
+    #[account(
+        5,
         name = "escrow_authority",
         description = "Escrow authority"
     )]
     #[account(
-        5,
+        6,
         writable,
         name = "escrow_token_account",
         description = "Escrow token account"
     )]
     #[account(
-        6,
+        7,
         name = "token_mint",
         description = "Token mint"
     )]
     #[account(
-        7,
+        8,
         name = "token_program",
         description = "Token program"
     )]
+    #[account(
+        9,
+        name = "reward_authority",
+        description = "Reward authority"
+    )]
+    #[account(
+        10,
+        writable,
+        name = "reward_vault",
+        description = "Reward vault"
+    )]
     Withdraw,
-}
-
-impl PaladinLockupInstruction {
-    /// Packs a
-    /// [PaladinLockupInstruction](enum.PaladinLockupInstruction.html)
-    /// into a byte buffer.
-    pub fn pack(&self) -> Vec<u8> {
-        match self {
-            Self::InitializeLockupPool => vec![0],
-            Self::Lockup { metadata, amount } => {
-                let mut buf = Vec::with_capacity(1 + 32 + 8);
-                buf.push(1);
-                buf.extend_from_slice(metadata.as_slice());
-                buf.extend_from_slice(&amount.to_le_bytes());
-                buf
-            }
-            Self::Unlock => vec![2],
-            Self::Withdraw => vec![3],
-        }
-    }
-
-    /// Unpacks a byte buffer into a
-    /// [PaladinLockupInstruction](enum.PaladinLockupInstruction.html).
-    pub fn unpack(input: &[u8]) -> Result<Self, ProgramError> {
-        match input.split_first() {
-            Some((&0, _)) => Ok(Self::InitializeLockupPool),
-            Some((&1, rest)) if rest.len() == 40 => {
-                let metadata = rest[..32].try_into().unwrap();
-                let amount = u64::from_le_bytes(rest[32..40].try_into().unwrap());
-
-                Ok(Self::Lockup { metadata, amount })
-            }
-            Some((&2, _)) => Ok(Self::Unlock),
-            Some((&3, _)) => Ok(Self::Withdraw),
-            _ => Err(ProgramError::InvalidInstructionData),
-        }
-    }
-}
-
-/// Creates a
-/// [InitializeLockupPool](enum.PaladinInitializeLockupPoolInstruction.html)
-/// instruction.
-#[allow(clippy::too_many_arguments)]
-pub fn initialize_lockup_pool(pool: Pubkey, mint: Pubkey) -> Instruction {
-    let accounts = vec![
-        AccountMeta::new(pool, false),
-        AccountMeta::new_readonly(mint, false),
-    ];
-    let data = PaladinLockupInstruction::InitializeLockupPool.pack();
-
-    Instruction::new_with_bytes(crate::id(), &data, accounts)
-}
-
-/// Creates a
-/// [Lockup](enum.PaladinLockupInstruction.html)
-/// instruction.
-#[allow(clippy::too_many_arguments)]
-pub fn lockup(
-    lockup_authority_address: &Pubkey,
-    token_owner_address: &Pubkey,
-    token_account_address: &Pubkey,
-    pool: Pubkey,
-    lockup_address: &Pubkey,
-    mint_address: &Pubkey,
-    metadata: [u8; 32],
-    amount: u64,
-    token_program_id: &Pubkey,
-    extra_accounts: &[AccountMeta],
-) -> Instruction {
-    let escrow_authority_address = get_escrow_authority_address(&crate::id());
-    let escrow_token_account_address = get_associated_token_address_with_program_id(
-        &escrow_authority_address,
-        mint_address,
-        token_program_id,
-    );
-    let accounts = [
-        &[
-            AccountMeta::new_readonly(*lockup_authority_address, false),
-            AccountMeta::new_readonly(*token_owner_address, true),
-            AccountMeta::new(*token_account_address, false),
-            AccountMeta::new(pool, false),
-            AccountMeta::new(*lockup_address, false),
-            AccountMeta::new_readonly(escrow_authority_address, false),
-            AccountMeta::new(escrow_token_account_address, false),
-            AccountMeta::new_readonly(*mint_address, false),
-            AccountMeta::new_readonly(*token_program_id, false),
-        ],
-        extra_accounts,
-    ]
-    .concat();
-    let data = PaladinLockupInstruction::Lockup { metadata, amount }.pack();
-
-    Instruction::new_with_bytes(crate::id(), &data, accounts)
-}
-
-/// Creates an
-/// [Unlock](enum.PaladinLockupInstruction.html)
-/// instruction.
-pub fn unlock(
-    lockup_authority_address: &Pubkey,
-    lockup_pool: Pubkey,
-    lockup_address: &Pubkey,
-) -> Instruction {
-    let accounts = vec![
-        AccountMeta::new_readonly(*lockup_authority_address, true),
-        AccountMeta::new(lockup_pool, false),
-        AccountMeta::new(*lockup_address, false),
-    ];
-    let data = PaladinLockupInstruction::Unlock.pack();
-
-    Instruction::new_with_bytes(crate::id(), &data, accounts)
-}
-
-/// Creates a
-/// [Withdraw](enum.PaladinLockupInstruction.html)
-/// instruction.
-pub fn withdraw(
-    lockup_authority_address: &Pubkey,
-    lamport_destination_address: &Pubkey,
-    token_destination_address: &Pubkey,
-    lockup_address: &Pubkey,
-    mint_address: &Pubkey,
-    token_program_id: &Pubkey,
-) -> Instruction {
-    let escrow_authority_address = get_escrow_authority_address(&crate::id());
-    let escrow_token_account_address = get_associated_token_address_with_program_id(
-        &escrow_authority_address,
-        mint_address,
-        token_program_id,
-    );
-    let accounts = vec![
-        AccountMeta::new_readonly(*lockup_authority_address, true),
-        AccountMeta::new(*lamport_destination_address, false),
-        AccountMeta::new(*token_destination_address, false),
-        AccountMeta::new(*lockup_address, false),
-        AccountMeta::new_readonly(escrow_authority_address, false),
-        AccountMeta::new(escrow_token_account_address, false),
-        AccountMeta::new_readonly(*mint_address, false),
-        AccountMeta::new_readonly(*token_program_id, false),
-    ];
-    let data = PaladinLockupInstruction::Withdraw.pack();
-    Instruction::new_with_bytes(crate::id(), &data, accounts)
-}
-
-#[cfg(test)]
-mod tests {
-    use super::*;
-
-    fn test_pack_unpack(instruction: PaladinLockupInstruction) {
-        let packed = instruction.pack();
-        let unpacked = PaladinLockupInstruction::unpack(&packed).unwrap();
-        assert_eq!(instruction, unpacked);
-    }
-
-    #[test]
-    fn test_pack_unpack_initialize_lockup_pool() {
-        test_pack_unpack(PaladinLockupInstruction::InitializeLockupPool);
-    }
-
-    #[test]
-    fn test_pack_unpack_lockup() {
-        test_pack_unpack(PaladinLockupInstruction::Lockup {
-            metadata: Pubkey::new_unique().to_bytes(),
-            amount: 42,
-        });
-    }
-
-    #[test]
-    fn test_pack_unpack_unlock() {
-        test_pack_unpack(PaladinLockupInstruction::Unlock);
-    }
-
-    #[test]
-    fn test_pack_unpack_withdraw() {
-        test_pack_unpack(PaladinLockupInstruction::Withdraw);
+    /// Increase the amount of tokens locked up in an active lockup account.
+    ///
+    /// Both the lockup authority and the token owner must sign, so tokens
+    /// can't be added to a lockup without its authority's consent.
+    ///
+    /// The lockup is moved to its new rank in the pool, and re-inserted into
+    /// the pool if it was previously evicted. An evicted lockup that still
+    /// doesn't outrank the pool's smallest lockup stays out of the pool.
+    ///
+    /// If the pool is full, its smallest lockup is evicted. The evicted
+    /// lockup account must be appended to the accounts below to record that it
+    /// left the pool.
+    ///
+    /// Accounts expected by this instruction:
+    ///
+    /// 0. `[s]` Lockup authority.
+    /// 1. `[s]` Token owner.
+    /// 2. `[w]` Depositor token account.
+    /// 3. `[w]` Lockup pool account.
+    /// 4. `[w]` Lockup account.
+    /// 5. `[ ]` Escrow authority.
+    /// 6. `[w]` Escrow token account.
+    /// 7. `[ ]` Token mint.
+    /// 8. `[ ]` Token program.
+    #[account(
+        0,
+        signer,
+        name = "lockup_authority",
+        description = "Lockup authority"
+    )]
+    #[account(
+        1,
+        signer,
+        name = "token_owner",
+        description = "Token owner"
+    )]
+    #[account(
+        2,
+        writable,
+        name = "depositor_token_account",
+        description = "Depositor token account"
+    )]
+    #[account(
+        3,
+        writable,
+        name = "lockup_pool",
+        description = "Lockup pool"
+    )]
+    #[account(
+        4,
+        writable,
+        name = "lockup_account",
+        description = "Lockup account"
+    )]
+    #[account(
+        5,
+        name = "escrow_authority",
+        description = "Escrow authority"
+    )]
+    #[account(
+        6,
+        writable,
+        name = "escrow_token_account",
+        description = "Escrow token account"
+    )]
+    #[account(
+        7,
+        name = "token_mint",
+        description = "Token mint"
+    )]
+    #[account(
+        8,
+        name = "token_program",
+        description = "Token program"
+    )]
+    IncreaseLockup { amount: u64 },
+    /// Relock an unlocked token lockup that has not yet been withdrawn,
+    /// cancelling its cooldown and re-inserting it into the pool.
+    ///
+    /// The lockup's amount must meet the pool's minimum lockup amount. A
+    /// relocked lockup that doesn't outrank the pool's smallest lockup stays
+    /// out of the pool.
+    ///
+    /// If the pool is full, its smallest lockup is evicted. The evicted
+    /// lockup account must be appended to the accounts below to record that it
+    /// left the pool.
+    ///
+    /// Accounts expected by this instruction:
+    ///
+    /// 0. `[s]` Lockup authority.
+    /// 1. `[w]` Lockup pool account.
+    /// 2. `[w]` Lockup account.
+    #[account(
+        0,
+        signer,
+        name = "lockup_authority",
+        description = "Lockup authority"
+    )]
+    #[account(
+        1,
+        writable,
+        name = "lockup_pool",
+        description = "Lockup pool"
+    )]
+    #[account(
+        2,
+        writable,
+        name = "lockup_account",
+        description = "Lockup account"
+    )]
+    Relock,
+    /// Propose a new authority for a lockup account.
+    ///
+    /// The current authority remains in control of the lockup until the
+    /// proposed authority accepts. Proposing the current authority cancels a
+    /// pending transfer.
+    ///
+    /// Accounts expected by this instruction:
+    ///
+    /// 0. `[s]` Lockup authority.
+    /// 1. `[w]` Lockup account.
+    /// 2. `[ ]` Proposed lockup authority.
+    #[account(
+        0,
+        signer,
+        name = "lockup_authority",
+        description = "Lockup authority"
+    )]
+    #[account(
+        1,
+        writable,
+        name = "lockup_account",
+        description = "Lockup account"
+    )]
+    #[account(
+        2,
+        name = "proposed_authority",
+        description = "Proposed lockup authority"
+    )]
+    ProposeAuthority,
+    /// Accept a pending authority transfer for a lockup account.
+    ///
+    /// Accounts expected by this instruction:
+    ///
+    /// 0. `[s]` Proposed lockup authority.
+    /// 1. `[w]` Lockup account.
+    #[account(
+        0,
+        signer,
+        name = "proposed_authority",
+        description = "Proposed lockup authority"
+    )]
+    #[account(
+        1,
+        writable,
+        name = "lockup_account",
+        description = "Lockup account"
+    )]
+    AcceptAuthority,
+    /// Merge a source lockup into a destination lockup.
+    ///
+    /// Both lockups must be active and share the same authority, mint and
+    /// pool. The amounts are summed, the earlier start timestamp is kept and
+    /// the destination's metadata is retained. The source lockup account is
+    /// closed and its rent lamports are sent to the lamport destination,
+    /// which must be another account. A merged lockup that doesn't outrank
+    /// the pool's smallest lockup stays out of the pool.
+    ///
+    /// If the pool is full, its smallest lockup is evicted. The evicted
+    /// lockup account must be appended to the accounts below to record that it
+    /// left the pool.
+    ///
+    /// Accounts expected by this instruction:
+    ///
+    /// 0. `[s]` Lockup authority.
+    /// 1. `[w]` Lamport destination.
+    /// 2. `[w]` Lockup pool account.
+    /// 3. `[w]` Destination lockup account.
+    /// 4. `[w]` Source lockup account.
+    #[account(
+        0,
+        signer,
+        name = "lockup_authority",
+        description = "Lockup authority"
+    )]
+    #[account(
+        1,
+        writable,
+        name = "lamport_destination",
+        description = "Lamport destination"
+    )]
+    #[account(
+        2,
+        writable,
+        name = "lockup_pool",
+        description = "Lockup pool"
+    )]
+    #[account(
+        3,
+        writable,
+        name = "destination_lockup_account",
+        description = "Destination lockup account"
+    )]
+    #[account(
+        4,
+        writable,
+        name = "source_lockup_account",
+        description = "Source lockup account"
+    )]
+    MergeLockups,
+    /// Lock up tokens in a vesting lockup account, releasing them linearly
+    /// over the provided schedule.
+    ///
+    /// Nothing can be withdrawn before the cliff, after which the tokens
+    /// vested since the start of the schedule (rounded down to whole periods,
+    /// if a period is set) become withdrawable. Vesting lockups cannot be
+    /// unlocked; they are closed once fully withdrawn.
+    ///
+    /// Expects an uninitialized lockup account with enough rent-exempt
+    /// lamports to store lockup state, owned by the Paladin Lockup program.
+    ///
+    /// If the pool is full, its smallest lockup is evicted. The evicted
+    /// lockup account must be appended to the accounts below to record that it
+    /// left the pool.
+    ///
+    /// Accounts expected by this instruction:
+    ///
+    /// 0. `[ ]` Lockup authority.
+    /// 1. `[s]` Token owner.
+    /// 2. `[w]` Depositor token account.
+    /// 3. `[w]` Lockup pool account.
+    /// 4. `[w]` Lockup account.
+    /// 5. `[ ]` Escrow authority.
+    /// 6. `[w]` Escrow token account.
+    /// 7. `[ ]` Token mint.
+    /// 8. `[ ]` Token program.
+    #[account(
+        0,
+        name = "lockup_authority",
+        description = "Lockup authority"
+    )]
+    #[account(
+        1,
+        signer,
+        name = "token_owner",
+        description = "Token owner"
+    )]
+    #[account(
+        2,
+        writable,
+        name = "depositor_token_account",
+        description = "Depositor token account"
+    )]
+    #[account(
+        3,
+        writable,
+        name = "lockup_pool",
+        description = "Lockup pool"
+    )]
+    #[account(
+        4,
+        writable,
+        name = "lockup_account",
+        description = "Lockup account"
+    )]
+    #[account(
+        5,
+        name = "escrow_authority",
+        description = "Escrow authority"
+    )]
+    #[account(
+        6,
+        writable,
+        name = "escrow_token_account",
+        description = "Escrow token account"
+    )]
+    #[account(
+        7,
+        name = "token_mint",
+        description = "Token mint"
+    )]
+    #[account(
+        8,
+        name = "token_program",
+        description = "Token program"
+    )]
+    VestingLockup {
+        metadata: [u8; 32],
+        amount: u64,
+        schedule: VestingSchedule,
+    },
+    /// Update the metadata of a lockup account, and of its pool entry if the
+    /// lockup is still in the pool.
+    ///
+    /// Fails if the pool's metadata update interval has not passed since the
+    /// lockup's metadata was last updated.
+    ///
+    /// Accounts expected by this instruction:
+    ///
+    /// 0. `[s]` Lockup authority.
+    /// 1. `[w]` Lockup pool account.
+    /// 2. `[w]` Lockup account.
+    #[account(
+        0,
+        signer,
+        name = "lockup_authority",
+        description = "Lockup authority"
+    )]
+    #[account(
+        1,
+        writable,
+        name = "lockup_pool",
+        description = "Lockup pool"
+    )]
+    #[account(
+        2,
+        writable,
+        name = "lockup_account",
+        description = "Lockup account"
+    )]
+    UpdateMetadata { metadata: [u8; 32] },
+    /// Lock up tokens in a lockup account created by the program.
+    ///
+    /// Behaves like `Lockup`, except the lockup account is created through
+    /// the system program at the PDA derived from the pool, the lockup
+    /// authority and the provided nonce (see `get_lockup_address`), with its
+    /// rent paid by the rent payer.
+    ///
+    /// If the pool is full, its smallest lockup is evicted. The evicted
+    /// lockup account must be appended to the accounts below to record that it
+    /// left the pool.
+    ///
+    /// Accounts expected by this instruction:
+    ///
+    /// 0. `[w, s]` Rent payer.
+    /// 1. `[ ]` System program.
+    /// 2. `[ ]` Lockup authority.
+    /// 3. `[s]` Token owner.
+    /// 4. `[w]` Depositor token account.
+    /// 5. `[w]` Lockup pool account.
+    /// 6. `[w]` Lockup account.
+    /// 7. `[ ]` Escrow authority.
+    /// 8. `[w]` Escrow token account.
+    /// 9. `[ ]` Token mint.
+    /// 10. `[ ]` Token program.
+    #[account(
+        0,
+        writable,
+        signer,
+        name = "rent_payer",
+        description = "Rent payer"
+    )]
+    #[account(
+        1,
+        name = "system_program",
+        description = "System program"
+    )]
+    #[account(
+        2,
+        name = "lockup_authority",
+        description = "Lockup authority"
+    )]
+    #[account(
+        3,
+        signer,
+        name = "token_owner",
+        description = "Token owner"
+    )]
+    #[account(
+        4,
+        writable,
+        name = "depositor_token_account",
+        description = "Depositor token account"
+    )]
+    #[account(
+        5,
+        writable,
+        name = "lockup_pool",
+        description = "Lockup pool"
+    )]
+    #[account(
+        6,
+        writable,
+        name = "lockup_account",
+        description = "Lockup account"
+    )]
+    #[account(
+        7,
+        name = "escrow_authority",
+        description = "Escrow authority"
+    )]
+    #[account(
+        8,
+        writable,
+        name = "escrow_token_account",
+        description = "Escrow token account"
+    )]
+    #[account(
+        9,
+        name = "token_mint",
+        description = "Token mint"
+    )]
+    #[account(
+        10,
+        name = "token_program",
+        description = "Token program"
+    )]
+    CreateLockup {
+        metadata: [u8; 32],
+        amount: u64,
+        committed_end_timestamp: Option<NonZeroU64>,
+        nonce: u64,
+    },
+    /// Update the configuration of a lockup pool.
+    ///
+    /// Only the provided settings are changed, and the cooldown and metadata
+    /// update interval may not exceed 30 days. A new cooldown only applies to
+    /// lockups unlocked afterwards. Handing the authority over to
+    /// the default pubkey leaves the pool without an authority.
+    ///
+    /// Accounts expected by this instruction:
+    ///
+    /// 0. `[s]` Pool authority.
+    /// 1. `[w]` Lockup pool account.
+    #[account(
+        0,
+        signer,
+        name = "pool_authority",
+        description = "Pool authority"
+    )]
+    #[account(
+        1,
+        writable,
+        name = "lockup_pool",
+        description = "Lockup pool"
+    )]
+    UpdatePoolConfig {
+        min_lockup_amount: Option<u64>,
+        cooldown_seconds: Option<u64>,
+        metadata_update_interval_seconds: Option<u64>,
+        lockups_enabled: Option<bool>,
+        authority: Option<Pubkey>,
+    },
+    /// Pause or resume new lockups, unlocks and withdrawals for a lockup
+    /// pool.
+    ///
+    /// Pausing only blocks the matching instructions and never moves any
+    /// escrowed tokens. Pausing lockups also pauses increases, relocks,
+    /// reinsertions and reward deposits, and pausing withdrawals also pauses
+    /// reward claims.
+    ///
+    /// Accounts expected by this instruction:
+    ///
+    /// 0. `[s]` Pool authority.
+    /// 1. `[w]` Lockup pool account.
+    #[account(
+        0,
+        signer,
+        name = "pool_authority",
+        description = "Pool authority"
+    )]
+    #[account(
+        1,
+        writable,
+        name = "lockup_pool",
+        description = "Lockup pool"
+    )]
+    SetPoolPaused {
+        lockups_paused: bool,
+        unlocks_paused: bool,
+        withdrawals_paused: bool,
+    },
+    /// Re-insert an active lockup that is not in its pool, such as one that
+    /// was evicted, into the pool.
+    ///
+    /// Anyone may invoke this instruction. It fails if the pool is full and
+    /// the lockup does not outrank its smallest lockup, which is then
+    /// evicted. The evicted lockup account must be appended to the accounts
+    /// below to record that it left the pool. Like new lockups, it also fails
+    /// while the pool's lockups are paused or disabled.
+    ///
+    /// Accounts expected by this instruction:
+    ///
+    /// 0. `[w]` Lockup pool account.
+    /// 1. `[w]` Lockup account.
+    #[account(
+        0,
+        writable,
+        name = "lockup_pool",
+        description = "Lockup pool"
+    )]
+    #[account(
+        1,
+        writable,
+        name = "lockup_account",
+        description = "Lockup account"
+    )]
+    ReinsertLockup,
+    /// Grow a lockup pool's capacity to `capacity` entries, up to
+    /// `LockupPool::MAX_CAPACITY`.
+    ///
+    /// The funder pays for the additional rent. As accounts can only grow by
+    /// `MAX_PERMITTED_DATA_INCREASE` bytes per instruction, this instruction
+    /// must be repeated until the pool has reached the requested capacity.
+    /// Pools cannot be shrunk. Pools without an authority can be grown by
+    /// anyone.
+    ///
+    /// Accounts expected by this instruction:
+    ///
+    /// 0. `[w, s]` Funder.
+    /// 1. `[s]` Pool authority, or any account for pools without one.
+    /// 2. `[w]` Lockup pool account.
+    /// 3. `[]` System program.
+    #[account(
+        0,
+        writable,
+        signer,
+        name = "funder",
+        description = "Funder"
+    )]
+    #[account(
+        1,
+        signer,
+        name = "pool_authority",
+        description = "Pool authority"
+    )]
+    #[account(
+        2,
+        writable,
+        name = "lockup_pool",
+        description = "Lockup pool"
+    )]
+    #[account(
+        3,
+        name = "system_program",
+        description = "System program"
+    )]
+    ResizeLockupPool { capacity: u64 },
+    /// Add a shard to a lockup pool, covering all amounts below `max_amount`.
+    ///
+    /// The shard account must be allocated & assigned to the program
+    /// beforehand, its size determining its capacity. Entries below
+    /// `max_amount` are moved into the shard from the pool's last shard, or
+    /// from the pool itself if it has no shards yet.
+    ///
+    /// Accounts expected by this instruction:
+    ///
+    /// 0. `[s]` Pool authority.
+    /// 1. `[w]` Lockup pool account.
+    /// 2. `[w]` Lockup pool shard account.
+    /// 3. `[w]` The pool's last shard account, if the pool has shards.
+    #[account(
+        0,
+        signer,
+        name = "pool_authority",
+        description = "Pool authority"
+    )]
+    #[account(
+        1,
+        writable,
+        name = "lockup_pool",
+        description = "Lockup pool"
+    )]
+    #[account(
+        2,
+        writable,
+        name = "lockup_pool_shard",
+        description = "Lockup pool shard"
+    )]
+    #[account(
+        3,
+        optional,
+        writable,
+        name = "last_lockup_pool_shard",
+        description = "Last lockup pool shard"
+    )]
+    AddLockupPoolShard { max_amount: u64 },
+    /// Move the upper boundary of a lockup pool shard to `max_amount`, moving
+    /// entries between the shard and the shard above it (or the pool itself,
+    /// for the first shard).
+    ///
+    /// Accounts expected by this instruction:
+    ///
+    /// 0. `[s]` Pool authority.
+    /// 1. `[w]` Lockup pool account.
+    /// 2. `[w]` Lockup pool shard account.
+    /// 3. `[w]` The shard account above it, unless it is the first shard.
+    #[account(
+        0,
+        signer,
+        name = "pool_authority",
+        description = "Pool authority"
+    )]
+    #[account(
+        1,
+        writable,
+        name = "lockup_pool",
+        description = "Lockup pool"
+    )]
+    #[account(
+        2,
+        writable,
+        name = "lockup_pool_shard",
+        description = "Lockup pool shard"
+    )]
+    #[account(
+        3,
+        optional,
+        writable,
+        name = "upper_lockup_pool_shard",
+        description = "Upper lockup pool shard"
+    )]
+    RebalanceLockupPoolShard { max_amount: u64 },
+    /// Snapshot a lockup pool's entries, along with the current slot, epoch
+    /// and timestamp, into a new account at the PDA derived from the pool and
+    /// the current epoch (see `get_lockup_pool_snapshot_address`).
+    ///
+    /// The snapshot covers the entries of the pool and all of its shards, which
+    /// must be provided in order. As accounts can only grow by
+    /// `MAX_PERMITTED_DATA_INCREASE` bytes per instruction, this instruction
+    /// must be repeated until the account has room for all entries, at which
+    /// point the snapshot is taken. Once taken, a snapshot never changes. The
+    /// payer of the first instruction becomes the snapshot's rent payer.
+    ///
+    /// Accounts expected by this instruction:
+    ///
+    /// 0. `[w, s]` Payer.
+    /// 1. `[ ]` Lockup pool account.
+    /// 2. `[w]` Lockup pool snapshot account.
+    /// 3. `[ ]` System program.
+    /// 4. `[ ]` The pool's shard accounts, in order, if the pool has shards.
+    #[account(
+        0,
+        writable,
+        signer,
+        name = "payer",
+        description = "Payer"
+    )]
+    #[account(
+        1,
+        name = "lockup_pool",
+        description = "Lockup pool"
+    )]
+    #[account(
+        2,
+        writable,
+        name = "lockup_pool_snapshot",
+        description = "Lockup pool snapshot"
+    )]
+    #[account(
+        3,
+        name = "system_program",
+        description = "System program"
+    )]
+    SnapshotPool,
+    /// Close a lockup pool snapshot, returning its rent lamports.
+    ///
+    /// Snapshots can only be closed once the epoch they were taken in has
+    /// ended. Snapshots that were started but never completed can be closed
+    /// the same way.
+    ///
+    /// Accounts expected by this instruction:
+    ///
+    /// 0. `[s]` The snapshot's rent payer.
+    /// 1. `[w]` Lamport destination.
+    /// 2. `[w]` Lockup pool snapshot account.
+    #[account(
+        0,
+        signer,
+        name = "rent_payer",
+        description = "Snapshot rent payer"
+    )]
+    #[account(
+        1,
+        writable,
+        name = "lamport_destination",
+        description = "Lamport destination"
+    )]
+    #[account(
+        2,
+        writable,
+        name = "lockup_pool_snapshot",
+        description = "Lockup pool snapshot"
+    )]
+    CloseSnapshot,
+    /// Deposit rewards into a lockup pool's reward vault, sharing them among
+    /// the pool's active lockups in proportion to their amounts.
+    ///
+    /// The deposit, along with the rewards left to stream, is streamed over
+    /// the following `LockupPoolRewards::REWARD_PERIOD_SECONDS`, so lockups
+    /// only earn on it while they stay active.
+    ///
+    /// Rewards are paid in the pool's mint. The reward vault is the reward
+    /// authority's associated token account (see
+    /// `get_reward_authority_address`), and must exist beforehand.
+    ///
+    /// Accounts expected by this instruction:
+    ///
+    /// 0. `[s]` Depositor.
+    /// 1. `[w]` Depositor token account.
+    /// 2. `[w]` Lockup pool account.
+    /// 3. `[ ]` Reward authority.
+    /// 4. `[w]` Reward vault.
+    /// 5. `[ ]` Token mint.
+    /// 6. `[ ]` Token program.
+    #[account(
+        0,
+        signer,
+        name = "depositor",
+        description = "Depositor"
+    )]
+    #[account(
+        1,
+        writable,
+        name = "depositor_token_account",
+        description = "Depositor token account"
+    )]
+    #[account(
+        2,
+        writable,
+        name = "lockup_pool",
+        description = "Lockup pool"
+    )]
+    #[account(
+        3,
+        name = "reward_authority",
+        description = "Reward authority"
+    )]
+    #[account(
+        4,
+        writable,
+        name = "reward_vault",
+        description = "Reward vault"
+    )]
+    #[account(
+        5,
+        name = "token_mint",
+        description = "Token mint"
+    )]
+    #[account(
+        6,
+        name = "token_program",
+        description = "Token program"
+    )]
+    DepositRewards { amount: u64 },
+    /// Claim the rewards a lockup has earned from its pool's reward vault.
+    ///
+    /// Lockups earn the rewards streamed until they are unlocked. Any rewards
+    /// left unclaimed are paid out by the `Withdraw` closing the lockup.
+    ///
+    /// Accounts expected by this instruction:
+    ///
+    /// 0. `[s]` Lockup authority.
+    /// 1. `[w]` Token destination.
+    /// 2. `[w]` Lockup pool account.
+    /// 3. `[w]` Lockup account.
+    /// 4. `[ ]` Reward authority.
+    /// 5. `[w]` Reward vault.
+    /// 6. `[ ]` Token mint.
+    /// 7. `[ ]` Token program.
+    #[account(
+        0,
+        signer,
+        name = "lockup_authority",
+        description = "Lockup authority"
+    )]
+    #[account(
+        1,
+        writable,
+        name = "token_destination",
+        description = "Token destination"
+    )]
+    #[account(
+        2,
+        writable,
+        name = "lockup_pool",
+        description = "Lockup pool"
+    )]
+    #[account(
+        3,
+        writable,
+        name = "lockup_account",
+        description = "Lockup account"
+    )]
+    #[account(
+        4,
+        name = "reward_authority",
+        description = "Reward authority"
+    )]
+    #[account(
+        5,
+        writable,
+        name = "reward_vault",
+        description = "Reward vault"
+    )]
+    #[account(
+        6,
+        name = "token_mint",
+        description = "Token mint"
+    )]
+    #[account(
+        7,
+        name = "token_program",
+        description = "Token program"
+    )]
+    ClaimRewards,
+    /// Compute the Merkle root of a lockup pool's entries and store it, along
+    /// with the current slot, in the account at the PDA derived from the pool
+    /// (see `get_lockup_pool_root_address`), creating it if needed.
+    ///
+    /// The root covers the entries of the pool and all of its shards, which
+    /// must be provided in order. See `merkle` for building and verifying
+    /// proofs against the root. Hashing large pools may require a higher
+    /// compute unit limit.
+    ///
+    /// Accounts expected by this instruction:
+    ///
+    /// 0. `[w, s]` Payer.
+    /// 1. `[ ]` Lockup pool account.
+    /// 2. `[w]` Lockup pool root account.
+    /// 3. `[ ]` System program.
+    /// 4. `[ ]` The pool's shard accounts, in order, if the pool has shards.
+    #[account(
+        0,
+        writable,
+        signer,
+        name = "payer",
+        description = "Payer"
+    )]
+    #[account(
+        1,
+        name = "lockup_pool",
+        description = "Lockup pool"
+    )]
+    #[account(
+        2,
+        writable,
+        name = "lockup_pool_root",
+        description = "Lockup pool root"
+    )]
+    #[account(
+        3,
+        name = "system_program",
+        description = "System program"
+    )]
+    UpdatePoolRoot,
+    /// Migrate a lockup account created before lockups tracked their
+    /// authority transfers, vesting, flags & rewards (see
+    /// `Lockup::LEGACY_LEN`) to the current layout, so it can be used with
+    /// the other instructions. Legacy pools must be migrated first, see
+    /// `MigrateLockupPool`.
+    ///
+    /// The payer covers the additional rent. Active lockups are counted
+    /// towards the pool's stats and start earning rewards from the pool's
+    /// current reward-per-token index, and unlocked lockups towards the
+    /// amount in cooldown.
+    ///
+    /// An active lockup is only marked as a member of the pool if its entry
+    /// is still there, since it may have been evicted after the pool was
+    /// migrated.
+    ///
+    /// Accounts expected by this instruction:
+    ///
+    /// 0. `[w, s]` Payer.
+    /// 1. `[w]` Lockup account.
+    /// 2. `[w]` Lockup pool account.
+    /// 3. `[ ]` System program.
+    #[account(
+        0,
+        writable,
+        signer,
+        name = "payer",
+        description = "Payer"
+    )]
+    #[account(
+        1,
+        writable,
+        name = "lockup",
+        description = "Lockup"
+    )]
+    #[account(
+        2,
+        writable,
+        name = "lockup_pool",
+        description = "Lockup pool"
+    )]
+    #[account(
+        3,
+        name = "system_program",
+        description = "System program"
+    )]
+    MigrateLockup,
+    /// Migrate a lockup pool account created before pools had a
+    /// configuration (see `LockupPool::LEGACY_LEN`) to the current layout,
+    /// keeping its entries.
+    ///
+    /// The payer covers the additional rent. The pool gets the default
+    /// configuration, with the mint's mint authority, if any, as its pool
+    /// authority. Its stats start out empty, and count each lockup once it is
+    /// migrated with `MigrateLockup`.
+    ///
+    /// Accounts expected by this instruction:
+    ///
+    /// 0. `[w, s]` Payer.
+    /// 1. `[w]` Lockup pool account.
+    /// 2. `[ ]` Token mint.
+    /// 3. `[ ]` System program.
+    #[account(
+        0,
+        writable,
+        signer,
+        name = "payer",
+        description = "Payer"
+    )]
+    #[account(
+        1,
+        writable,
+        name = "lockup_pool",
+        description = "Lockup pool"
+    )]
+    #[account(
+        2,
+        name = "mint",
+        description = "Mint"
+    )]
+    #[account(
+        3,
+        name = "system_program",
+        description = "System program"
+    )]
+    MigrateLockupPool,
+}
+
+impl PaladinLockupInstruction {
+    /// Packs a
+    /// [PaladinLockupInstruction](enum.PaladinLockupInstruction.html)
+    /// into a byte buffer.
+    pub fn pack(&self) -> Vec<u8> {
+        match self {
+            Self::InitializeLockupPool {
+                pool_id,
+                cooldown_seconds,
+                metadata_update_interval_seconds,
+                isolated_escrow,
+            } => {
+                let mut buf = Vec::with_capacity(1 + 8 + 8 + 8 + 1);
+                buf.push(0);
+                buf.extend_from_slice(&pool_id.to_le_bytes());
+                buf.extend_from_slice(&cooldown_seconds.to_le_bytes());
+                buf.extend_from_slice(&metadata_update_interval_seconds.to_le_bytes());
+                buf.push(*isolated_escrow as u8);
+                buf
+            }
+            Self::Lockup {
+                metadata,
+                amount,
+                committed_end_timestamp,
+            } => {
+                let mut buf = Vec::with_capacity(1 + 32 + 8 + 8);
+                buf.push(1);
+                buf.extend_from_slice(metadata.as_slice());
+                buf.extend_from_slice(&amount.to_le_bytes());
+                buf.extend_from_slice(
+                    &committed_end_timestamp
+                        .map_or(0, NonZeroU64::get)
+                        .to_le_bytes(),
+                );
+                buf
+            }
+            // A full unlock keeps its original, amount-less encoding.
+            Self::Unlock { amount: 0 } => vec![2],
+            Self::Unlock { amount } => {
+                let mut buf = Vec::with_capacity(1 + 8);
+                buf.push(2);
+                buf.extend_from_slice(&amount.to_le_bytes());
+                buf
+            }
+            Self::Withdraw => vec![3],
+            Self::IncreaseLockup { amount } => {
+                let mut buf = Vec::with_capacity(1 + 8);
+                buf.push(4);
+                buf.extend_from_slice(&amount.to_le_bytes());
+                buf
+            }
+            Self::Relock => vec![5],
+            Self::ProposeAuthority => vec![6],
+            Self::AcceptAuthority => vec![7],
+            Self::MergeLockups => vec![8],
+            Self::VestingLockup {
+                metadata,
+                amount,
+                schedule,
+            } => {
+                let mut buf = Vec::with_capacity(1 + 32 + 8 + 32);
+                buf.push(9);
+                buf.extend_from_slice(metadata.as_slice());
+                buf.extend_from_slice(&amount.to_le_bytes());
+                buf.extend_from_slice(bytemuck::bytes_of(schedule));
+                buf
+            }
+            Self::UpdateMetadata { metadata } => {
+                let mut buf = Vec::with_capacity(1 + 32);
+                buf.push(10);
+                buf.extend_from_slice(metadata.as_slice());
+                buf
+            }
+            Self::CreateLockup {
+                metadata,
+                amount,
+                committed_end_timestamp,
+                nonce,
+            } => {
+                let mut buf = Vec::with_capacity(1 + 32 + 8 + 8 + 8);
+                buf.push(11);
+                buf.extend_from_slice(metadata.as_slice());
+                buf.extend_from_slice(&amount.to_le_bytes());
+                buf.extend_from_slice(
+                    &committed_end_timestamp
+                        .map_or(0, NonZeroU64::get)
+                        .to_le_bytes(),
+                );
+                buf.extend_from_slice(&nonce.to_le_bytes());
+                buf
+            }
+            Self::UpdatePoolConfig {
+                min_lockup_amount,
+                cooldown_seconds,
+                metadata_update_interval_seconds,
+                lockups_enabled,
+                authority,
+            } => {
+                let mut buf = Vec::with_capacity(1 + 9 + 9 + 9 + 2 + 33);
+                buf.push(12);
+                pack_option(&mut buf, min_lockup_amount.map(u64::to_le_bytes));
+                pack_option(&mut buf, cooldown_seconds.map(u64::to_le_bytes));
+                pack_option(
+                    &mut buf,
+                    metadata_update_interval_seconds.map(u64::to_le_bytes),
+                );
+                pack_option(&mut buf, lockups_enabled.map(|enabled| [enabled as u8]));
+                pack_option(&mut buf, authority.map(Pubkey::to_bytes));
+                buf
+            }
+            Self::SetPoolPaused {
+                lockups_paused,
+                unlocks_paused,
+                withdrawals_paused,
+            } => vec![
+                13,
+                *lockups_paused as u8,
+                *unlocks_paused as u8,
+                *withdrawals_paused as u8,
+            ],
+            Self::ReinsertLockup => vec![14],
+            Self::ResizeLockupPool { capacity } => {
+                let mut buf = Vec::with_capacity(1 + 8);
+                buf.push(15);
+                buf.extend_from_slice(&capacity.to_le_bytes());
+                buf
+            }
+            Self::AddLockupPoolShard { max_amount } => {
+                let mut buf = Vec::with_capacity(1 + 8);
+                buf.push(16);
+                buf.extend_from_slice(&max_amount.to_le_bytes());
+                buf
+            }
+            Self::RebalanceLockupPoolShard { max_amount } => {
+                let mut buf = Vec::with_capacity(1 + 8);
+                buf.push(17);
+                buf.extend_from_slice(&max_amount.to_le_bytes());
+                buf
+            }
+            Self::SnapshotPool => vec![18],
+            Self::CloseSnapshot => vec![19],
+            Self::DepositRewards { amount } => {
+                let mut buf = Vec::with_capacity(1 + 8);
+                buf.push(20);
+                buf.extend_from_slice(&amount.to_le_bytes());
+                buf
+            }
+            Self::ClaimRewards => vec![21],
+            Self::UpdatePoolRoot => vec![22],
+            Self::MigrateLockup => vec![23],
+            Self::MigrateLockupPool => vec![24],
+        }
+    }
+
+    /// Unpacks a byte buffer into a
+    /// [PaladinLockupInstruction](enum.PaladinLockupInstruction.html).
+    pub fn unpack(input: &[u8]) -> Result<Self, ProgramError> {
+        match input.split_first() {
+            Some((&0, rest)) if rest.len() == 25 => {
+                let pool_id = u64::from_le_bytes(rest[..8].try_into().unwrap());
+                let cooldown_seconds = u64::from_le_bytes(rest[8..16].try_into().unwrap());
+                let metadata_update_interval_seconds =
+                    u64::from_le_bytes(rest[16..24].try_into().unwrap());
+                let isolated_escrow = unpack_bool(rest[24])?;
+
+                Ok(Self::InitializeLockupPool {
+                    pool_id,
+                    cooldown_seconds,
+                    metadata_update_interval_seconds,
+                    isolated_escrow,
+                })
+            }
+            // Lockups packed before committed terms existed omit the committed
+            // end timestamp.
+            Some((&1, rest)) if rest.len() == 40 || rest.len() == 48 => {
+                let metadata = rest[..32].try_into().unwrap();
+                let amount = u64::from_le_bytes(rest[32..40].try_into().unwrap());
+                let committed_end_timestamp = rest.get(40..48).and_then(|bytes| {
+                    NonZeroU64::new(u64::from_le_bytes(bytes.try_into().unwrap()))
+                });
+
+                Ok(Self::Lockup {
+                    metadata,
+                    amount,
+                    committed_end_timestamp,
+                })
+            }
+            Some((&2, rest)) if rest.len() == 8 => {
+                let amount = u64::from_le_bytes(rest.try_into().unwrap());
+
+                Ok(Self::Unlock { amount })
+            }
+            Some((&2, [])) => Ok(Self::Unlock { amount: 0 }),
+            Some((&3, _)) => Ok(Self::Withdraw),
+            Some((&4, rest)) if rest.len() == 8 => {
+                let amount = u64::from_le_bytes(rest.try_into().unwrap());
+
+                Ok(Self::IncreaseLockup { amount })
+            }
+            Some((&5, _)) => Ok(Self::Relock),
+            Some((&6, _)) => Ok(Self::ProposeAuthority),
+            Some((&7, _)) => Ok(Self::AcceptAuthority),
+            Some((&8, _)) => Ok(Self::MergeLockups),
+            Some((&9, rest)) if rest.len() == 72 => {
+                let metadata = rest[..32].try_into().unwrap();
+                let amount = u64::from_le_bytes(rest[32..40].try_into().unwrap());
+                let schedule = bytemuck::pod_read_unaligned(&rest[40..72]);
+
+                Ok(Self::VestingLockup {
+                    metadata,
+                    amount,
+                    schedule,
+                })
+            }
+            Some((&10, rest)) if rest.len() == 32 => {
+                let metadata = rest.try_into().unwrap();
+
+                Ok(Self::UpdateMetadata { metadata })
+            }
+            Some((&11, rest)) if rest.len() == 56 => {
+                let metadata = rest[..32].try_into().unwrap();
+                let amount = u64::from_le_bytes(rest[32..40].try_into().unwrap());
+                let committed_end_timestamp =
+                    NonZeroU64::new(u64::from_le_bytes(rest[40..48].try_into().unwrap()));
+                let nonce = u64::from_le_bytes(rest[48..56].try_into().unwrap());
+
+                Ok(Self::CreateLockup {
+                    metadata,
+                    amount,
+                    committed_end_timestamp,
+                    nonce,
+                })
+            }
+            Some((&12, rest)) if rest.len() == 62 => {
+                let min_lockup_amount = unpack_option(&rest[..9])?.map(u64::from_le_bytes);
+                let cooldown_seconds = unpack_option(&rest[9..18])?.map(u64::from_le_bytes);
+                let metadata_update_interval_seconds =
+                    unpack_option(&rest[18..27])?.map(u64::from_le_bytes);
+                let lockups_enabled = match unpack_option::<1>(&rest[27..29])? {
+                    None => None,
+                    Some([0]) => Some(false),
+                    Some([1]) => Some(true),
+                    Some(_) => return Err(ProgramError::InvalidInstructionData),
+                };
+                let authority = unpack_option(&rest[29..62])?.map(Pubkey::new_from_array);
+
+                Ok(Self::UpdatePoolConfig {
+                    min_lockup_amount,
+                    cooldown_seconds,
+                    metadata_update_interval_seconds,
+                    lockups_enabled,
+                    authority,
+                })
+            }
+            Some((&13, rest)) if rest.len() == 3 => Ok(Self::SetPoolPaused {
+                lockups_paused: unpack_bool(rest[0])?,
+                unlocks_paused: unpack_bool(rest[1])?,
+                withdrawals_paused: unpack_bool(rest[2])?,
+            }),
+            Some((&14, _)) => Ok(Self::ReinsertLockup),
+            Some((&15, rest)) if rest.len() == 8 => {
+                let capacity = u64::from_le_bytes(rest.try_into().unwrap());
+
+                Ok(Self::ResizeLockupPool { capacity })
+            }
+            Some((&16, rest)) if rest.len() == 8 => {
+                let max_amount = u64::from_le_bytes(rest.try_into().unwrap());
+
+                Ok(Self::AddLockupPoolShard { max_amount })
+            }
+            Some((&17, rest)) if rest.len() == 8 => {
+                let max_amount = u64::from_le_bytes(rest.try_into().unwrap());
+
+                Ok(Self::RebalanceLockupPoolShard { max_amount })
+            }
+            Some((&18, _)) => Ok(Self::SnapshotPool),
+            Some((&19, _)) => Ok(Self::CloseSnapshot),
+            Some((&20, rest)) if rest.len() == 8 => {
+                let amount = u64::from_le_bytes(rest.try_into().unwrap());
+
+                Ok(Self::DepositRewards { amount })
+            }
+            Some((&21, _)) => Ok(Self::ClaimRewards),
+            Some((&22, _)) => Ok(Self::UpdatePoolRoot),
+            Some((&23, _)) => Ok(Self::MigrateLockup),
+            Some((&24, _)) => Ok(Self::MigrateLockupPool),
+            _ => Err(ProgramError::InvalidInstructionData),
+        }
+    }
+}
+
+/// Packs an optional value as a presence byte followed by the value, or by
+/// zeroes if absent.
+fn pack_option<const N: usize>(buf: &mut Vec<u8>, value: Option<[u8; N]>) {
+    buf.push(value.is_some() as u8);
+    buf.extend_from_slice(&value.unwrap_or([0; N]));
+}
+
+/// Unpacks a boolean packed as a single byte.
+fn unpack_bool(byte: u8) -> Result<bool, ProgramError> {
+    match byte {
+        0 => Ok(false),
+        1 => Ok(true),
+        _ => Err(ProgramError::InvalidInstructionData),
+    }
+}
+
+/// Unpacks an optional value packed by `pack_option`.
+fn unpack_option<const N: usize>(input: &[u8]) -> Result<Option<[u8; N]>, ProgramError> {
+    match input.split_first() {
+        Some((&0, _)) => Ok(None),
+        Some((&1, value)) => Ok(Some(
+            value
+                .try_into()
+                .map_err(|_| ProgramError::InvalidInstructionData)?,
+        )),
+        _ => Err(ProgramError::InvalidInstructionData),
+    }
+}
+
+/// Returns the escrow authority & escrow token account holding the pool's
+/// tokens, which are the pool's own for pools with
+/// `LockupPool::FLAG_ISOLATED_ESCROW` set.
+fn escrow_addresses(
+    lockup_pool: &Pubkey,
+    mint_address: &Pubkey,
+    token_program_id: &Pubkey,
+    isolated_escrow: bool,
+) -> (Pubkey, Pubkey) {
+    let escrow_authority_address = match isolated_escrow {
+        true => get_pool_escrow_authority_address(lockup_pool, &crate::id()),
+        false => get_escrow_authority_address(&crate::id()),
+    };
+    let escrow_token_account_address = get_associated_token_address_with_program_id(
+        &escrow_authority_address,
+        mint_address,
+        token_program_id,
+    );
+
+    (escrow_authority_address, escrow_token_account_address)
+}
+
+/// Creates a
+/// [InitializeLockupPool](enum.PaladinInitializeLockupPoolInstruction.html)
+/// instruction.
+#[allow(clippy::too_many_arguments)]
+pub fn initialize_lockup_pool(
+    payer: &Pubkey,
+    mint: Pubkey,
+    pool_id: u64,
+    cooldown_seconds: u64,
+    metadata_update_interval_seconds: u64,
+    pool_authority: Option<&Pubkey>,
+    isolated_escrow: bool,
+) -> Instruction {
+    let pool = get_lockup_pool_address(&mint, pool_id, &crate::id());
+    let mut accounts = vec![
+        AccountMeta::new(*payer, true),
+        AccountMeta::new(pool, false),
+        AccountMeta::new_readonly(mint, false),
+        AccountMeta::new_readonly(system_program::id(), false),
+    ];
+    if let Some(pool_authority) = pool_authority {
+        accounts.push(AccountMeta::new_readonly(*pool_authority, true));
+    }
+    let data = PaladinLockupInstruction::InitializeLockupPool {
+        pool_id,
+        cooldown_seconds,
+        metadata_update_interval_seconds,
+        isolated_escrow,
+    }
+    .pack();
+
+    Instruction::new_with_bytes(crate::id(), &data, accounts)
+}
+
+/// Creates a
+/// [Lockup](enum.PaladinLockupInstruction.html)
+/// instruction.
+#[allow(clippy::too_many_arguments)]
+pub fn lockup(
+    lockup_authority_address: &Pubkey,
+    token_owner_address: &Pubkey,
+    token_account_address: &Pubkey,
+    pool: Pubkey,
+    lockup_pool_state: &LockupPool,
+    lockup_pool_entries: &[LockupPoolEntry],
+    lockup_address: &Pubkey,
+    mint_address: &Pubkey,
+    metadata: [u8; 32],
+    amount: u64,
+    committed_end_timestamp: Option<NonZeroU64>,
+    token_program_id: &Pubkey,
+    isolated_escrow: bool,
+) -> Instruction {
+    let (escrow_authority_address, escrow_token_account_address) =
+        escrow_addresses(&pool, mint_address, token_program_id, isolated_escrow);
+    let accounts = [
+        &[
+            AccountMeta::new_readonly(*lockup_authority_address, false),
+            AccountMeta::new_readonly(*token_owner_address, true),
+            AccountMeta::new(*token_account_address, false),
+            AccountMeta::new(pool, false),
+            AccountMeta::new(*lockup_address, false),
+            AccountMeta::new_readonly(escrow_authority_address, false),
+            AccountMeta::new(escrow_token_account_address, false),
+            AccountMeta::new_readonly(*mint_address, false),
+            AccountMeta::new_readonly(*token_program_id, false),
+        ],
+        &lockup_pool_shard_accounts(lockup_pool_state, &[amount])[..],
+        &lockup_pool_evicted_accounts(lockup_pool_entries, &[lockup_address])[..],
+    ]
+    .concat();
+    let data = PaladinLockupInstruction::Lockup {
+        metadata,
+        amount,
+        committed_end_timestamp,
+    }
+    .pack();
+
+    Instruction::new_with_bytes(crate::id(), &data, accounts)
+}
+
+/// Creates an
+/// [Unlock](enum.PaladinLockupInstruction.html)
+/// instruction.
+pub fn unlock(
+    lockup_authority_address: &Pubkey,
+    lockup_pool: Pubkey,
+    lockup_pool_state: &LockupPool,
+    lockup_address: &Pubkey,
+    lockup_amount: u64,
+) -> Instruction {
+    let accounts = [
+        &[
+            AccountMeta::new_readonly(*lockup_authority_address, true),
+            AccountMeta::new(lockup_pool, false),
+            AccountMeta::new(*lockup_address, false),
+        ],
+        &lockup_pool_shard_accounts(lockup_pool_state, &[lockup_amount])[..],
+    ]
+    .concat();
+    let data = PaladinLockupInstruction::Unlock { amount: 0 }.pack();
+
+    Instruction::new_with_bytes(crate::id(), &data, accounts)
+}
+
+/// Creates a
+/// [Withdraw](enum.PaladinLockupInstruction.html)
+/// instruction.
+#[allow(clippy::too_many_arguments)]
+pub fn withdraw(
+    lockup_authority_address: &Pubkey,
+    lamport_destination_address: &Pubkey,
+    token_destination_address: &Pubkey,
+    lockup_address: &Pubkey,
+    lockup_amount: u64,
+    lockup_pool: Pubkey,
+    lockup_pool_state: &LockupPool,
+    lockup_pool_entries: &[LockupPoolEntry],
+    mint_address: &Pubkey,
+    token_program_id: &Pubkey,
+    isolated_escrow: bool,
+) -> Instruction {
+    let (escrow_authority_address, escrow_token_account_address) = escrow_addresses(
+        &lockup_pool,
+        mint_address,
+        token_program_id,
+        isolated_escrow,
+    );
+    let reward_authority_address = get_reward_authority_address(&lockup_pool, &crate::id());
+    let reward_vault_address = get_associated_token_address_with_program_id(
+        &reward_authority_address,
+        mint_address,
+        token_program_id,
+    );
+    let accounts = [
+        &[
+            AccountMeta::new_readonly(*lockup_authority_address, true),
+            AccountMeta::new(*lamport_destination_address, false),
+            AccountMeta::new(*token_destination_address, false),
+            AccountMeta::new(*lockup_address, false),
+            AccountMeta::new(lockup_pool, false),
+            AccountMeta::new_readonly(escrow_authority_address, false),
+            AccountMeta::new(escrow_token_account_address, false),
+            AccountMeta::new_readonly(*mint_address, false),
+            AccountMeta::new_readonly(*token_program_id, false),
+            AccountMeta::new_readonly(reward_authority_address, false),
+            AccountMeta::new(reward_vault_address, false),
+        ],
+        &lockup_pool_shard_accounts(lockup_pool_state, &[lockup_amount])[..],
+        &lockup_pool_evicted_accounts(lockup_pool_entries, &[lockup_address])[..],
+    ]
+    .concat();
+    let data = PaladinLockupInstruction::Withdraw.pack();
+    Instruction::new_with_bytes(crate::id(), &data, accounts)
+}
+
+/// Creates an
+/// [Unlock](enum.PaladinLockupInstruction.html)
+/// instruction, unlocking only `amount` of the lockup.
+#[allow(clippy::too_many_arguments)]
+pub fn partial_unlock(
+    lockup_authority_address: &Pubkey,
+    lockup_pool: Pubkey,
+    lockup_pool_state: &LockupPool,
+    lockup_pool_entries: &[LockupPoolEntry],
+    lockup_address: &Pubkey,
+    lockup_amount: u64,
+    unlocked_lockup_address: &Pubkey,
+    amount: u64,
+) -> Instruction {
+    let accounts = [
+        &[
+            AccountMeta::new_readonly(*lockup_authority_address, true),
+            AccountMeta::new(lockup_pool, false),
+            AccountMeta::new(*lockup_address, false),
+            AccountMeta::new(*unlocked_lockup_address, false),
+        ],
+        &lockup_pool_shard_accounts(lockup_pool_state, &[lockup_amount])[..],
+        &lockup_pool_evicted_accounts(lockup_pool_entries, &[lockup_address])[..],
+    ]
+    .concat();
+    let data = PaladinLockupInstruction::Unlock { amount }.pack();
+
+    Instruction::new_with_bytes(crate::id(), &data, accounts)
+}
+
+/// Creates an
+/// [IncreaseLockup](enum.PaladinLockupInstruction.html)
+/// instruction.
+#[allow(clippy::too_many_arguments)]
+pub fn increase_lockup(
+    lockup_authority_address: &Pubkey,
+    token_owner_address: &Pubkey,
+    token_account_address: &Pubkey,
+    pool: Pubkey,
+    lockup_pool_state: &LockupPool,
+    lockup_pool_entries: &[LockupPoolEntry],
+    lockup_address: &Pubkey,
+    lockup_amount: u64,
+    mint_address: &Pubkey,
+    amount: u64,
+    token_program_id: &Pubkey,
+    isolated_escrow: bool,
+) -> Instruction {
+    let increased_amount = lockup_amount.saturating_add(amount);
+    let (escrow_authority_address, escrow_token_account_address) =
+        escrow_addresses(&pool, mint_address, token_program_id, isolated_escrow);
+    let accounts = [
+        &[
+            AccountMeta::new_readonly(*lockup_authority_address, true),
+            AccountMeta::new_readonly(*token_owner_address, true),
+            AccountMeta::new(*token_account_address, false),
+            AccountMeta::new(pool, false),
+            AccountMeta::new(*lockup_address, false),
+            AccountMeta::new_readonly(escrow_authority_address, false),
+            AccountMeta::new(escrow_token_account_address, false),
+            AccountMeta::new_readonly(*mint_address, false),
+            AccountMeta::new_readonly(*token_program_id, false),
+        ],
+        &lockup_pool_shard_accounts(lockup_pool_state, &[lockup_amount, increased_amount])[..],
+        &lockup_pool_evicted_accounts(lockup_pool_entries, &[lockup_address])[..],
+    ]
+    .concat();
+    let data = PaladinLockupInstruction::IncreaseLockup { amount }.pack();
+
+    Instruction::new_with_bytes(crate::id(), &data, accounts)
+}
+
+/// Creates a
+/// [Relock](enum.PaladinLockupInstruction.html)
+/// instruction.
+pub fn relock(
+    lockup_authority_address: &Pubkey,
+    lockup_pool: Pubkey,
+    lockup_pool_state: &LockupPool,
+    lockup_pool_entries: &[LockupPoolEntry],
+    lockup_address: &Pubkey,
+    lockup_amount: u64,
+) -> Instruction {
+    let accounts = [
+        &[
+            AccountMeta::new_readonly(*lockup_authority_address, true),
+            AccountMeta::new(lockup_pool, false),
+            AccountMeta::new(*lockup_address, false),
+        ],
+        &lockup_pool_shard_accounts(lockup_pool_state, &[lockup_amount])[..],
+        &lockup_pool_evicted_accounts(lockup_pool_entries, &[lockup_address])[..],
+    ]
+    .concat();
+    let data = PaladinLockupInstruction::Relock.pack();
+
+    Instruction::new_with_bytes(crate::id(), &data, accounts)
+}
+
+/// Creates a
+/// [ProposeAuthority](enum.PaladinLockupInstruction.html)
+/// instruction.
+pub fn propose_authority(
+    lockup_authority_address: &Pubkey,
+    lockup_address: &Pubkey,
+    proposed_authority_address: &Pubkey,
+) -> Instruction {
+    let accounts = vec![
+        AccountMeta::new_readonly(*lockup_authority_address, true),
+        AccountMeta::new(*lockup_address, false),
+        AccountMeta::new_readonly(*proposed_authority_address, false),
+    ];
+    let data = PaladinLockupInstruction::ProposeAuthority.pack();
+
+    Instruction::new_with_bytes(crate::id(), &data, accounts)
+}
+
+/// Creates an
+/// [AcceptAuthority](enum.PaladinLockupInstruction.html)
+/// instruction.
+pub fn accept_authority(
+    proposed_authority_address: &Pubkey,
+    lockup_address: &Pubkey,
+) -> Instruction {
+    let accounts = vec![
+        AccountMeta::new_readonly(*proposed_authority_address, true),
+        AccountMeta::new(*lockup_address, false),
+    ];
+    let data = PaladinLockupInstruction::AcceptAuthority.pack();
+
+    Instruction::new_with_bytes(crate::id(), &data, accounts)
+}
+
+/// Creates a
+/// [MergeLockups](enum.PaladinLockupInstruction.html)
+/// instruction.
+#[allow(clippy::too_many_arguments)]
+pub fn merge_lockups(
+    lockup_authority_address: &Pubkey,
+    lamport_destination_address: &Pubkey,
+    lockup_pool: Pubkey,
+    lockup_pool_state: &LockupPool,
+    lockup_pool_entries: &[LockupPoolEntry],
+    destination_lockup_address: &Pubkey,
+    destination_lockup_amount: u64,
+    source_lockup_address: &Pubkey,
+    source_lockup_amount: u64,
+) -> Instruction {
+    let merged_amount = destination_lockup_amount.saturating_add(source_lockup_amount);
+    let accounts = [
+        &[
+            AccountMeta::new_readonly(*lockup_authority_address, true),
+            AccountMeta::new(*lamport_destination_address, false),
+            AccountMeta::new(lockup_pool, false),
+            AccountMeta::new(*destination_lockup_address, false),
+            AccountMeta::new(*source_lockup_address, false),
+        ],
+        &lockup_pool_shard_accounts(
+            lockup_pool_state,
+            &[
+                destination_lockup_amount,
+                source_lockup_amount,
+                merged_amount,
+            ],
+        )[..],
+        &lockup_pool_evicted_accounts(
+            lockup_pool_entries,
+            &[destination_lockup_address, source_lockup_address],
+        )[..],
+    ]
+    .concat();
+    let data = PaladinLockupInstruction::MergeLockups.pack();
+
+    Instruction::new_with_bytes(crate::id(), &data, accounts)
+}
+
+/// Creates a
+/// [VestingLockup](enum.PaladinLockupInstruction.html)
+/// instruction.
+#[allow(clippy::too_many_arguments)]
+pub fn vesting_lockup(
+    lockup_authority_address: &Pubkey,
+    token_owner_address: &Pubkey,
+    token_account_address: &Pubkey,
+    pool: Pubkey,
+    lockup_pool_state: &LockupPool,
+    lockup_pool_entries: &[LockupPoolEntry],
+    lockup_address: &Pubkey,
+    mint_address: &Pubkey,
+    metadata: [u8; 32],
+    amount: u64,
+    schedule: VestingSchedule,
+    token_program_id: &Pubkey,
+    isolated_escrow: bool,
+) -> Instruction {
+    let (escrow_authority_address, escrow_token_account_address) =
+        escrow_addresses(&pool, mint_address, token_program_id, isolated_escrow);
+    let accounts = [
+        &[
+            AccountMeta::new_readonly(*lockup_authority_address, false),
+            AccountMeta::new_readonly(*token_owner_address, true),
+            AccountMeta::new(*token_account_address, false),
+            AccountMeta::new(pool, false),
+            AccountMeta::new(*lockup_address, false),
+            AccountMeta::new_readonly(escrow_authority_address, false),
+            AccountMeta::new(escrow_token_account_address, false),
+            AccountMeta::new_readonly(*mint_address, false),
+            AccountMeta::new_readonly(*token_program_id, false),
+        ],
+        &lockup_pool_shard_accounts(lockup_pool_state, &[amount])[..],
+        &lockup_pool_evicted_accounts(lockup_pool_entries, &[lockup_address])[..],
+    ]
+    .concat();
+    let data = PaladinLockupInstruction::VestingLockup {
+        metadata,
+        amount,
+        schedule,
+    }
+    .pack();
+
+    Instruction::new_with_bytes(crate::id(), &data, accounts)
+}
+
+/// Creates an
+/// [UpdateMetadata](enum.PaladinLockupInstruction.html)
+/// instruction.
+pub fn update_metadata(
+    lockup_authority_address: &Pubkey,
+    lockup_pool: Pubkey,
+    lockup_pool_state: &LockupPool,
+    lockup_address: &Pubkey,
+    lockup_amount: u64,
+    metadata: [u8; 32],
+) -> Instruction {
+    let accounts = [
+        &[
+            AccountMeta::new_readonly(*lockup_authority_address, true),
+            AccountMeta::new(lockup_pool, false),
+            AccountMeta::new(*lockup_address, false),
+        ],
+        &lockup_pool_shard_accounts(lockup_pool_state, &[lockup_amount])[..],
+    ]
+    .concat();
+    let data = PaladinLockupInstruction::UpdateMetadata { metadata }.pack();
+
+    Instruction::new_with_bytes(crate::id(), &data, accounts)
+}
+
+/// Creates a
+/// [CreateLockup](enum.PaladinLockupInstruction.html)
+/// instruction.
+#[allow(clippy::too_many_arguments)]
+pub fn create_lockup(
+    rent_payer_address: &Pubkey,
+    lockup_authority_address: &Pubkey,
+    token_owner_address: &Pubkey,
+    token_account_address: &Pubkey,
+    pool: Pubkey,
+    lockup_pool_state: &LockupPool,
+    lockup_pool_entries: &[LockupPoolEntry],
+    mint_address: &Pubkey,
+    metadata: [u8; 32],
+    amount: u64,
+    committed_end_timestamp: Option<NonZeroU64>,
+    nonce: u64,
+    token_program_id: &Pubkey,
+    isolated_escrow: bool,
+) -> Instruction {
+    let lockup_address = get_lockup_address(&pool, lockup_authority_address, nonce, &crate::id());
+    let (escrow_authority_address, escrow_token_account_address) =
+        escrow_addresses(&pool, mint_address, token_program_id, isolated_escrow);
+    let accounts = [
+        &[
+            AccountMeta::new(*rent_payer_address, true),
+            AccountMeta::new_readonly(system_program::id(), false),
+            AccountMeta::new_readonly(*lockup_authority_address, false),
+            AccountMeta::new_readonly(*token_owner_address, true),
+            AccountMeta::new(*token_account_address, false),
+            AccountMeta::new(pool, false),
+            AccountMeta::new(lockup_address, false),
+            AccountMeta::new_readonly(escrow_authority_address, false),
+            AccountMeta::new(escrow_token_account_address, false),
+            AccountMeta::new_readonly(*mint_address, false),
+            AccountMeta::new_readonly(*token_program_id, false),
+        ],
+        &lockup_pool_shard_accounts(lockup_pool_state, &[amount])[..],
+        &lockup_pool_evicted_accounts(lockup_pool_entries, &[&lockup_address])[..],
+    ]
+    .concat();
+    let data = PaladinLockupInstruction::CreateLockup {
+        metadata,
+        amount,
+        committed_end_timestamp,
+        nonce,
+    }
+    .pack();
+
+    Instruction::new_with_bytes(crate::id(), &data, accounts)
+}
+
+/// Creates an
+/// [UpdatePoolConfig](enum.PaladinLockupInstruction.html)
+/// instruction.
+pub fn update_pool_config(
+    pool_authority_address: &Pubkey,
+    lockup_pool: Pubkey,
+    min_lockup_amount: Option<u64>,
+    cooldown_seconds: Option<u64>,
+    metadata_update_interval_seconds: Option<u64>,
+    lockups_enabled: Option<bool>,
+    authority: Option<Pubkey>,
+) -> Instruction {
+    let accounts = vec![
+        AccountMeta::new_readonly(*pool_authority_address, true),
+        AccountMeta::new(lockup_pool, false),
+    ];
+    let data = PaladinLockupInstruction::UpdatePoolConfig {
+        min_lockup_amount,
+        cooldown_seconds,
+        metadata_update_interval_seconds,
+        lockups_enabled,
+        authority,
+    }
+    .pack();
+
+    Instruction::new_with_bytes(crate::id(), &data, accounts)
+}
+
+/// Creates a
+/// [SetPoolPaused](enum.PaladinLockupInstruction.html)
+/// instruction.
+pub fn set_pool_paused(
+    pool_authority_address: &Pubkey,
+    lockup_pool: Pubkey,
+    lockups_paused: bool,
+    unlocks_paused: bool,
+    withdrawals_paused: bool,
+) -> Instruction {
+    let accounts = vec![
+        AccountMeta::new_readonly(*pool_authority_address, true),
+        AccountMeta::new(lockup_pool, false),
+    ];
+    let data = PaladinLockupInstruction::SetPoolPaused {
+        lockups_paused,
+        unlocks_paused,
+        withdrawals_paused,
+    }
+    .pack();
+
+    Instruction::new_with_bytes(crate::id(), &data, accounts)
+}
+
+/// Creates a
+/// [ReinsertLockup](enum.PaladinLockupInstruction.html)
+/// instruction.
+pub fn reinsert_lockup(
+    lockup_pool: Pubkey,
+    lockup_pool_state: &LockupPool,
+    lockup_pool_entries: &[LockupPoolEntry],
+    lockup_address: &Pubkey,
+    lockup_amount: u64,
+) -> Instruction {
+    let accounts = [
+        &[
+            AccountMeta::new(lockup_pool, false),
+            AccountMeta::new(*lockup_address, false),
+        ],
+        &lockup_pool_shard_accounts(lockup_pool_state, &[lockup_amount])[..],
+        &lockup_pool_evicted_accounts(lockup_pool_entries, &[lockup_address])[..],
+    ]
+    .concat();
+    let data = PaladinLockupInstruction::ReinsertLockup.pack();
+
+    Instruction::new_with_bytes(crate::id(), &data, accounts)
+}
+
+/// Creates a
+/// [ResizeLockupPool](enum.PaladinLockupInstruction.html)
+/// instruction.
+pub fn resize_lockup_pool(
+    funder_address: &Pubkey,
+    pool_authority_address: &Pubkey,
+    lockup_pool: Pubkey,
+    capacity: u64,
+) -> Instruction {
+    let accounts = vec![
+        AccountMeta::new(*funder_address, true),
+        AccountMeta::new_readonly(*pool_authority_address, true),
+        AccountMeta::new(lockup_pool, false),
+        AccountMeta::new_readonly(system_program::id(), false),
+    ];
+    let data = PaladinLockupInstruction::ResizeLockupPool { capacity }.pack();
+
+    Instruction::new_with_bytes(crate::id(), &data, accounts)
+}
+
+/// Creates an
+/// [AddLockupPoolShard](enum.PaladinLockupInstruction.html)
+/// instruction.
+pub fn add_lockup_pool_shard(
+    pool_authority_address: &Pubkey,
+    lockup_pool: Pubkey,
+    lockup_pool_state: &LockupPool,
+    lockup_pool_shard_address: &Pubkey,
+    max_amount: u64,
+) -> Instruction {
+    let mut accounts = vec![
+        AccountMeta::new_readonly(*pool_authority_address, true),
+        AccountMeta::new(lockup_pool, false),
+        AccountMeta::new(*lockup_pool_shard_address, false),
+    ];
+    if let Some(last_shard) = lockup_pool_state.shards().last() {
+        accounts.push(AccountMeta::new(last_shard.address, false));
+    }
+    let data = PaladinLockupInstruction::AddLockupPoolShard { max_amount }.pack();
+
+    Instruction::new_with_bytes(crate::id(), &data, accounts)
+}
+
+/// Creates a
+/// [RebalanceLockupPoolShard](enum.PaladinLockupInstruction.html)
+/// instruction for the pool's shard at the provided index.
+pub fn rebalance_lockup_pool_shard(
+    pool_authority_address: &Pubkey,
+    lockup_pool: Pubkey,
+    lockup_pool_state: &LockupPool,
+    shard_index: usize,
+    max_amount: u64,
+) -> Instruction {
+    let shards = lockup_pool_state.shards();
+    let mut accounts = vec![
+        AccountMeta::new_readonly(*pool_authority_address, true),
+        AccountMeta::new(lockup_pool, false),
+        AccountMeta::new(shards[shard_index].address, false),
+    ];
+    if let Some(upper_shard) = shard_index.checked_sub(1).map(|index| shards[index]) {
+        accounts.push(AccountMeta::new(upper_shard.address, false));
+    }
+    let data = PaladinLockupInstruction::RebalanceLockupPoolShard { max_amount }.pack();
+
+    Instruction::new_with_bytes(crate::id(), &data, accounts)
+}
+
+/// Creates a
+/// [SnapshotPool](enum.PaladinLockupInstruction.html)
+/// instruction, for a snapshot taken during the provided epoch.
+pub fn snapshot_pool(
+    payer: &Pubkey,
+    lockup_pool: Pubkey,
+    lockup_pool_state: &LockupPool,
+    epoch: u64,
+) -> Instruction {
+    let snapshot = get_lockup_pool_snapshot_address(&lockup_pool, epoch, &crate::id());
+    let mut accounts = vec![
+        AccountMeta::new(*payer, true),
+        AccountMeta::new_readonly(lockup_pool, false),
+        AccountMeta::new(snapshot, false),
+        AccountMeta::new_readonly(system_program::id(), false),
+    ];
+    accounts.extend(
+        lockup_pool_state
+            .shards()
+            .iter()
+            .map(|shard| AccountMeta::new_readonly(shard.address, false)),
+    );
+    let data = PaladinLockupInstruction::SnapshotPool.pack();
+
+    Instruction::new_with_bytes(crate::id(), &data, accounts)
+}
+
+/// Creates a
+/// [CloseSnapshot](enum.PaladinLockupInstruction.html)
+/// instruction.
+pub fn close_snapshot(
+    rent_payer: &Pubkey,
+    lamport_destination: &Pubkey,
+    lockup_pool_snapshot: &Pubkey,
+) -> Instruction {
+    let accounts = vec![
+        AccountMeta::new_readonly(*rent_payer, true),
+        AccountMeta::new(*lamport_destination, false),
+        AccountMeta::new(*lockup_pool_snapshot, false),
+    ];
+    let data = PaladinLockupInstruction::CloseSnapshot.pack();
+
+    Instruction::new_with_bytes(crate::id(), &data, accounts)
+}
+
+/// Creates a
+/// [DepositRewards](enum.PaladinLockupInstruction.html)
+/// instruction.
+pub fn deposit_rewards(
+    depositor_address: &Pubkey,
+    depositor_token_account_address: &Pubkey,
+    lockup_pool: Pubkey,
+    mint_address: &Pubkey,
+    amount: u64,
+    token_program_id: &Pubkey,
+) -> Instruction {
+    let reward_authority_address = get_reward_authority_address(&lockup_pool, &crate::id());
+    let reward_vault_address = get_associated_token_address_with_program_id(
+        &reward_authority_address,
+        mint_address,
+        token_program_id,
+    );
+    let accounts = vec![
+        AccountMeta::new_readonly(*depositor_address, true),
+        AccountMeta::new(*depositor_token_account_address, false),
+        AccountMeta::new(lockup_pool, false),
+        AccountMeta::new_readonly(reward_authority_address, false),
+        AccountMeta::new(reward_vault_address, false),
+        AccountMeta::new_readonly(*mint_address, false),
+        AccountMeta::new_readonly(*token_program_id, false),
+    ];
+    let data = PaladinLockupInstruction::DepositRewards { amount }.pack();
+
+    Instruction::new_with_bytes(crate::id(), &data, accounts)
+}
+
+/// Creates a
+/// [ClaimRewards](enum.PaladinLockupInstruction.html)
+/// instruction.
+pub fn claim_rewards(
+    lockup_authority_address: &Pubkey,
+    token_destination_address: &Pubkey,
+    lockup_pool: Pubkey,
+    lockup_address: &Pubkey,
+    mint_address: &Pubkey,
+    token_program_id: &Pubkey,
+) -> Instruction {
+    let reward_authority_address = get_reward_authority_address(&lockup_pool, &crate::id());
+    let reward_vault_address = get_associated_token_address_with_program_id(
+        &reward_authority_address,
+        mint_address,
+        token_program_id,
+    );
+    let accounts = vec![
+        AccountMeta::new_readonly(*lockup_authority_address, true),
+        AccountMeta::new(*token_destination_address, false),
+        AccountMeta::new(lockup_pool, false),
+        AccountMeta::new(*lockup_address, false),
+        AccountMeta::new_readonly(reward_authority_address, false),
+        AccountMeta::new(reward_vault_address, false),
+        AccountMeta::new_readonly(*mint_address, false),
+        AccountMeta::new_readonly(*token_program_id, false),
+    ];
+    let data = PaladinLockupInstruction::ClaimRewards.pack();
+
+    Instruction::new_with_bytes(crate::id(), &data, accounts)
+}
+
+/// Creates an
+/// [UpdatePoolRoot](enum.PaladinLockupInstruction.html)
+/// instruction.
+pub fn update_pool_root(
+    payer: &Pubkey,
+    lockup_pool: Pubkey,
+    lockup_pool_state: &LockupPool,
+) -> Instruction {
+    let root = get_lockup_pool_root_address(&lockup_pool, &crate::id());
+    let mut accounts = vec![
+        AccountMeta::new(*payer, true),
+        AccountMeta::new_readonly(lockup_pool, false),
+        AccountMeta::new(root, false),
+        AccountMeta::new_readonly(system_program::id(), false),
+    ];
+    accounts.extend(
+        lockup_pool_state
+            .shards()
+            .iter()
+            .map(|shard| AccountMeta::new_readonly(shard.address, false)),
+    );
+    let data = PaladinLockupInstruction::UpdatePoolRoot.pack();
+
+    Instruction::new_with_bytes(crate::id(), &data, accounts)
+}
+
+/// Creates a
+/// [MigrateLockup](enum.PaladinLockupInstruction.html)
+/// instruction.
+pub fn migrate_lockup(
+    payer: &Pubkey,
+    lockup_address: &Pubkey,
+    lockup_amount: u64,
+    lockup_pool: Pubkey,
+    lockup_pool_state: &LockupPool,
+) -> Instruction {
+    let accounts = [
+        &[
+            AccountMeta::new(*payer, true),
+            AccountMeta::new(*lockup_address, false),
+            AccountMeta::new(lockup_pool, false),
+            AccountMeta::new_readonly(system_program::id(), false),
+        ],
+        &lockup_pool_shard_accounts(lockup_pool_state, &[lockup_amount])[..],
+    ]
+    .concat();
+    let data = PaladinLockupInstruction::MigrateLockup.pack();
+
+    Instruction::new_with_bytes(crate::id(), &data, accounts)
+}
+
+/// Creates a
+/// [MigrateLockupPool](enum.PaladinLockupInstruction.html)
+/// instruction.
+pub fn migrate_lockup_pool(
+    payer: &Pubkey,
+    lockup_pool: Pubkey,
+    mint_address: &Pubkey,
+) -> Instruction {
+    let accounts = vec![
+        AccountMeta::new(*payer, true),
+        AccountMeta::new(lockup_pool, false),
+        AccountMeta::new_readonly(*mint_address, false),
+        AccountMeta::new_readonly(system_program::id(), false),
+    ];
+    let data = PaladinLockupInstruction::MigrateLockupPool.pack();
+
+    Instruction::new_with_bytes(crate::id(), &data, accounts)
+}
+
+/// Returns the accounts of the pool's shards covering the provided amounts,
+/// along with every shard below them, which the instruction builders append to
+/// instructions adding, moving or removing lockups with those amounts.
+///
+/// The shards below are needed because inserting into a full segment moves
+/// its smallest entries down into the next shard. For example,
+/// `IncreaseLockup` needs the shards covering both the lockup's current and
+/// increased amounts, as well as any shard they may spill into.
+pub fn lockup_pool_shard_accounts(
+    lockup_pool_state: &LockupPool,
+    amounts: &[u64],
+) -> Vec<AccountMeta> {
+    let shards = lockup_pool_state.shards();
+    let first_shard_index = amounts
+        .iter()
+        .map(|amount| lockup_pool_state.shard_index(*amount).unwrap_or(0))
+        .min()
+        .unwrap_or(shards.len());
+
+    shards[first_shard_index..]
+        .iter()
+        .map(|shard| AccountMeta::new(shard.address, false))
+        .collect()
+}
+
+/// Returns the account of the lockup that an instruction inserting into the
+/// pool may evict, provided the entries of the pool's last segment: its last
+/// shard's entries, or the pool's own entries if it has no shards.
+///
+/// An insert into a full pool evicts the smallest entry of its last segment,
+/// so the instruction builders append that lockup's account, unless it is one
+/// of the instruction's own lockups. The entries may be either the occupied
+/// ones or the segment's full capacity, as returned by `LockupPool::unpack`,
+/// whose unoccupied entries are zeroed and skipped.
+///
+/// # Note
+///
+/// Tied entries spilling down a sharded pool together may evict several of
+/// the last shard's smallest entries, whose accounts must then be appended
+/// too, or the instruction fails with `MissingEvictedLockup`.
+pub fn lockup_pool_evicted_accounts(
+    lockup_pool_entries: &[LockupPoolEntry],
+    lockup_addresses: &[&Pubkey],
+) -> Vec<AccountMeta> {
+    lockup_pool_entries
+        .iter()
+        .rev()
+        .find(|entry| entry.lockup != Pubkey::default())
+        .filter(|entry| !lockup_addresses.contains(&&entry.lockup))
+        .map(|entry| AccountMeta::new(entry.lockup, false))
+        .into_iter()
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_pack_unpack(instruction: PaladinLockupInstruction) {
+        let packed = instruction.pack();
+        let unpacked = PaladinLockupInstruction::unpack(&packed).unwrap();
+        assert_eq!(instruction, unpacked);
+    }
+
+    #[test]
+    fn test_pack_unpack_initialize_lockup_pool() {
+        test_pack_unpack(PaladinLockupInstruction::InitializeLockupPool {
+            pool_id: 3,
+            cooldown_seconds: 42,
+            metadata_update_interval_seconds: 7,
+            isolated_escrow: true,
+        });
+    }
+
+    #[test]
+    fn test_pack_unpack_lockup() {
+        test_pack_unpack(PaladinLockupInstruction::Lockup {
+            metadata: Pubkey::new_unique().to_bytes(),
+            amount: 42,
+            committed_end_timestamp: None,
+        });
+    }
+
+    #[test]
+    fn test_pack_unpack_lockup_with_committed_end_timestamp() {
+        test_pack_unpack(PaladinLockupInstruction::Lockup {
+            metadata: Pubkey::new_unique().to_bytes(),
+            amount: 42,
+            committed_end_timestamp: NonZeroU64::new(1_700_000_000),
+        });
+    }
+
+    #[test]
+    fn test_unpack_legacy_lockup() {
+        let metadata = Pubkey::new_unique().to_bytes();
+        let mut packed = vec![1];
+        packed.extend_from_slice(&metadata);
+        packed.extend_from_slice(&42u64.to_le_bytes());
+        assert_eq!(
+            PaladinLockupInstruction::unpack(&packed).unwrap(),
+            PaladinLockupInstruction::Lockup {
+                metadata,
+                amount: 42,
+                committed_end_timestamp: None,
+            }
+        );
+    }
+
+    #[test]
+    fn test_pack_unpack_unlock() {
+        test_pack_unpack(PaladinLockupInstruction::Unlock { amount: 0 });
+    }
+
+    #[test]
+    fn test_unpack_unlock_invalid_length() {
+        assert_eq!(
+            PaladinLockupInstruction::Unlock { amount: 0 }.pack(),
+            vec![2]
+        );
+        assert_eq!(
+            PaladinLockupInstruction::unpack(&[2, 42]),
+            Err(ProgramError::InvalidInstructionData)
+        );
+    }
+
+    #[test]
+    fn test_pack_unpack_partial_unlock() {
+        test_pack_unpack(PaladinLockupInstruction::Unlock { amount: 42 });
+    }
+
+    #[test]
+    fn test_pack_unpack_withdraw() {
+        test_pack_unpack(PaladinLockupInstruction::Withdraw);
+    }
+
+    #[test]
+    fn test_pack_unpack_increase_lockup() {
+        test_pack_unpack(PaladinLockupInstruction::IncreaseLockup { amount: 42 });
+    }
+
+    #[test]
+    fn test_pack_unpack_relock() {
+        test_pack_unpack(PaladinLockupInstruction::Relock);
+    }
+
+    #[test]
+    fn test_pack_unpack_propose_authority() {
+        test_pack_unpack(PaladinLockupInstruction::ProposeAuthority);
+    }
+
+    #[test]
+    fn test_pack_unpack_accept_authority() {
+        test_pack_unpack(PaladinLockupInstruction::AcceptAuthority);
+    }
+
+    #[test]
+    fn test_pack_unpack_merge_lockups() {
+        test_pack_unpack(PaladinLockupInstruction::MergeLockups);
+    }
+
+    #[test]
+    fn test_pack_unpack_vesting_lockup() {
+        test_pack_unpack(PaladinLockupInstruction::VestingLockup {
+            metadata: Pubkey::new_unique().to_bytes(),
+            amount: 42,
+            schedule: VestingSchedule {
+                start_timestamp: 100,
+                cliff_timestamp: 200,
+                end_timestamp: 1_000,
+                period_seconds: 10,
+            },
+        });
+    }
+
+    #[test]
+    fn test_pack_unpack_update_metadata() {
+        test_pack_unpack(PaladinLockupInstruction::UpdateMetadata {
+            metadata: Pubkey::new_unique().to_bytes(),
+        });
+    }
+
+    #[test]
+    fn test_pack_unpack_create_lockup() {
+        test_pack_unpack(PaladinLockupInstruction::CreateLockup {
+            metadata: Pubkey::new_unique().to_bytes(),
+            amount: 42,
+            committed_end_timestamp: NonZeroU64::new(1_700_000_000),
+            nonce: 7,
+        });
+    }
+
+    #[test]
+    fn test_pack_unpack_update_pool_config() {
+        test_pack_unpack(PaladinLockupInstruction::UpdatePoolConfig {
+            min_lockup_amount: Some(42),
+            cooldown_seconds: None,
+            metadata_update_interval_seconds: Some(0),
+            lockups_enabled: Some(false),
+            authority: Some(Pubkey::new_unique()),
+        });
+    }
+
+    #[test]
+    fn test_pack_unpack_update_pool_config_empty() {
+        test_pack_unpack(PaladinLockupInstruction::UpdatePoolConfig {
+            min_lockup_amount: None,
+            cooldown_seconds: None,
+            metadata_update_interval_seconds: None,
+            lockups_enabled: None,
+            authority: None,
+        });
+    }
+
+    #[test]
+    fn test_pack_unpack_set_pool_paused() {
+        test_pack_unpack(PaladinLockupInstruction::SetPoolPaused {
+            lockups_paused: true,
+            unlocks_paused: false,
+            withdrawals_paused: true,
+        });
+    }
+
+    #[test]
+    fn test_pack_unpack_reinsert_lockup() {
+        test_pack_unpack(PaladinLockupInstruction::ReinsertLockup);
+    }
+
+    #[test]
+    fn test_pack_unpack_resize_lockup_pool() {
+        test_pack_unpack(PaladinLockupInstruction::ResizeLockupPool { capacity: 4096 });
+    }
+
+    #[test]
+    fn test_pack_unpack_add_lockup_pool_shard() {
+        test_pack_unpack(PaladinLockupInstruction::AddLockupPoolShard { max_amount: 1_000 });
+    }
+
+    #[test]
+    fn test_pack_unpack_rebalance_lockup_pool_shard() {
+        test_pack_unpack(PaladinLockupInstruction::RebalanceLockupPoolShard { max_amount: 1_000 });
+    }
+
+    #[test]
+    fn test_pack_unpack_snapshot_pool() {
+        test_pack_unpack(PaladinLockupInstruction::SnapshotPool);
+    }
+
+    #[test]
+    fn test_pack_unpack_close_snapshot() {
+        test_pack_unpack(PaladinLockupInstruction::CloseSnapshot);
+    }
+
+    #[test]
+    fn test_pack_unpack_deposit_rewards() {
+        test_pack_unpack(PaladinLockupInstruction::DepositRewards { amount: 1_000 });
+    }
+
+    #[test]
+    fn test_pack_unpack_claim_rewards() {
+        test_pack_unpack(PaladinLockupInstruction::ClaimRewards);
+    }
+
+    #[test]
+    fn test_pack_unpack_update_pool_root() {
+        test_pack_unpack(PaladinLockupInstruction::UpdatePoolRoot);
+    }
+
+    #[test]
+    fn test_pack_unpack_migrate_lockup() {
+        test_pack_unpack(PaladinLockupInstruction::MigrateLockup);
+    }
+
+    #[test]
+    fn test_pack_unpack_migrate_lockup_pool() {
+        test_pack_unpack(PaladinLockupInstruction::MigrateLockupPool);
+    }
+
+    fn pool_entry(amount: u64) -> LockupPoolEntry {
+        LockupPoolEntry {
+            lockup: Pubkey::new_unique(),
+            amount,
+            metadata: [0; 32],
+        }
+    }
+
+    #[test]
+    fn test_lockup_pool_evicted_accounts_not_full() {
+        // The full capacity of a pool with two occupied entries.
+        let mut entries = vec![LockupPoolEntry::default(); LockupPool::INITIAL_CAPACITY];
+        entries[0] = pool_entry(20);
+        entries[1] = pool_entry(10);
+        let lockup = Pubkey::new_unique();
+
+        let instruction = reinsert_lockup(
+            Pubkey::new_unique(),
+            &bytemuck::Zeroable::zeroed(),
+            &entries,
+            &lockup,
+            5,
+        );
+        assert_eq!(
+            instruction.accounts[2..],
+            [AccountMeta::new(entries[1].lockup, false)]
+        );
+        assert_eq!(
+            lockup_pool_evicted_accounts(&entries[..2], &[&lockup]),
+            instruction.accounts[2..]
+        );
+    }
+
+    #[test]
+    fn test_lockup_pool_evicted_accounts_own_lockup() {
+        let entries = [pool_entry(20), pool_entry(10)];
+        assert!(lockup_pool_evicted_accounts(&entries, &[&entries[1].lockup]).is_empty());
+        assert!(lockup_pool_evicted_accounts(&[], &[&entries[1].lockup]).is_empty());
     }
 }
@@ -4,16 +4,25 @@
 //! Allows for the creation of lockups that can be used to restrict the
 //! transfer of tokens.
 //!
-//! Lockups are created with a duration of 30 minutes and will not allow
-//! withdrawal of the locked tokens until the duration has passed.
+//! Once unlocked, lockups will not allow withdrawal of the locked tokens until
+//! their pool's cooldown (30 minutes by default) has passed.
 
 #[cfg(all(target_os = "solana", feature = "bpf-entrypoint"))]
 mod entrypoint;
 pub mod error;
+pub mod extension;
 pub mod instruction;
+pub mod merkle;
 pub mod processor;
 pub mod state;
 
 solana_program::declare_id!("2fzrtg7ckXTFxjgsXbAWLLvXr4wJK9xfUjJJ69uZ7chb");
 
+/// The default cooldown for lockup pools.
 pub const LOCKUP_COOLDOWN_SECONDS: u64 = 30 * 60; // 30 minutes
+
+/// The longest cooldown a lockup pool can be configured with.
+pub const MAX_LOCKUP_COOLDOWN_SECONDS: u64 = 30 * 24 * 60 * 60; // 30 days
+
+/// The longest metadata update interval a lockup pool can be configured with.
+pub const MAX_METADATA_UPDATE_INTERVAL_SECONDS: u64 = 30 * 24 * 60 * 60; // 30 days
@@ -0,0 +1,212 @@
+//! Merkle tree over lockup pool entries.
+//!
+//! Leaves commit to an entry along with its index, i.e. its rank in the pool,
+//! so a proof shows both that a lockup is in the pool and how it ranks. Leaves
+//! and nodes are hashed with distinct prefixes, and a level's last node is
+//! promoted unchanged when it has no sibling.
+
+use {
+    crate::state::LockupPoolEntry,
+    solana_program::hash::{hashv, HASH_BYTES},
+};
+
+/// The root of an empty tree.
+pub const EMPTY_ROOT: [u8; HASH_BYTES] = [0; HASH_BYTES];
+
+const LEAF_PREFIX: &[u8] = &[0];
+const NODE_PREFIX: &[u8] = &[1];
+
+/// Hashes the entry at the provided index into a leaf.
+pub fn hash_leaf(index: usize, entry: &LockupPoolEntry) -> [u8; HASH_BYTES] {
+    hashv(&[
+        LEAF_PREFIX,
+        &(index as u64).to_le_bytes(),
+        entry.lockup.as_ref(),
+        &entry.amount.to_le_bytes(),
+        &entry.metadata,
+    ])
+    .to_bytes()
+}
+
+/// Hashes two sibling nodes into their parent.
+pub fn hash_nodes(left: &[u8; HASH_BYTES], right: &[u8; HASH_BYTES]) -> [u8; HASH_BYTES] {
+    hashv(&[NODE_PREFIX, left, right]).to_bytes()
+}
+
+/// Computes the root of a tree one leaf at a time, keeping a single pending
+/// node per level.
+#[derive(Default)]
+pub struct MerkleRootBuilder {
+    leaves_len: usize,
+    levels: Vec<Option<[u8; HASH_BYTES]>>,
+}
+
+impl MerkleRootBuilder {
+    /// Appends the next entry.
+    pub fn push(&mut self, entry: &LockupPoolEntry) {
+        let mut node = hash_leaf(self.leaves_len, entry);
+        self.leaves_len = self.leaves_len.saturating_add(1);
+
+        for level in self.levels.iter_mut() {
+            match level.take() {
+                Some(left) => node = hash_nodes(&left, &node),
+                None => {
+                    *level = Some(node);
+                    return;
+                }
+            }
+        }
+        self.levels.push(Some(node));
+    }
+
+    /// Returns the root of the entries pushed so far.
+    pub fn finish(self) -> [u8; HASH_BYTES] {
+        self.levels
+            .into_iter()
+            .flatten()
+            .reduce(|right, left| hash_nodes(&left, &right))
+            .unwrap_or(EMPTY_ROOT)
+    }
+}
+
+/// Computes the root of the provided entries.
+pub fn compute_root<'a>(
+    entries: impl IntoIterator<Item = &'a LockupPoolEntry>,
+) -> [u8; HASH_BYTES] {
+    let mut builder = MerkleRootBuilder::default();
+    entries.into_iter().for_each(|entry| builder.push(entry));
+    builder.finish()
+}
+
+/// Builds the proof for the entry at the provided index, or `None` if the
+/// index is out of bounds.
+pub fn build_proof(entries: &[LockupPoolEntry], index: usize) -> Option<Vec<[u8; HASH_BYTES]>> {
+    if index >= entries.len() {
+        return None;
+    }
+
+    let mut proof = Vec::new();
+    let mut level = entries
+        .iter()
+        .enumerate()
+        .map(|(index, entry)| hash_leaf(index, entry))
+        .collect::<Vec<_>>();
+    let mut index = index;
+    while level.len() > 1 {
+        if let Some(sibling) = level.get(index ^ 1) {
+            proof.push(*sibling);
+        }
+        level = level
+            .chunks(2)
+            .map(|pair| match pair {
+                [left, right] => hash_nodes(left, right),
+                [node] => *node,
+                _ => unreachable!(),
+            })
+            .collect();
+        index /= 2;
+    }
+
+    Some(proof)
+}
+
+/// Verifies that the provided entry is at the provided index of a tree with
+/// the provided root & number of entries.
+pub fn verify_proof(
+    root: &[u8; HASH_BYTES],
+    entries_len: usize,
+    index: usize,
+    entry: &LockupPoolEntry,
+    proof: &[[u8; HASH_BYTES]],
+) -> bool {
+    if index >= entries_len {
+        return false;
+    }
+
+    let mut proof = proof.iter();
+    let mut node = hash_leaf(index, entry);
+    let mut index = index;
+    let mut level_len = entries_len;
+    while level_len > 1 {
+        // The level's last node has no sibling when the level is odd.
+        if index ^ 1 < level_len {
+            let Some(sibling) = proof.next() else {
+                return false;
+            };
+            node = match index % 2 {
+                0 => hash_nodes(&node, sibling),
+                _ => hash_nodes(sibling, &node),
+            };
+        }
+        index /= 2;
+        level_len = level_len.div_ceil(2);
+    }
+
+    proof.next().is_none() && &node == root
+}
+
+#[cfg(test)]
+mod tests {
+    use {super::*, solana_program::pubkey::Pubkey};
+
+    fn entries(len: usize) -> Vec<LockupPoolEntry> {
+        (0..len as u64)
+            .rev()
+            .map(|amount| LockupPoolEntry {
+                lockup: Pubkey::new_unique(),
+                amount,
+                metadata: [amount as u8; 32],
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_compute_root_empty() {
+        assert_eq!(compute_root(&[]), EMPTY_ROOT);
+    }
+
+    #[test]
+    fn test_compute_root_single() {
+        let entries = entries(1);
+        assert_eq!(compute_root(&entries), hash_leaf(0, &entries[0]));
+    }
+
+    #[test]
+    fn test_compute_root_promotes_last_node() {
+        let entries = entries(3);
+        let leaves = entries
+            .iter()
+            .enumerate()
+            .map(|(index, entry)| hash_leaf(index, entry))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            compute_root(&entries),
+            hash_nodes(&hash_nodes(&leaves[0], &leaves[1]), &leaves[2])
+        );
+    }
+
+    #[test]
+    fn test_verify_proof() {
+        for len in 1..=17 {
+            let entries = entries(len);
+            let root = compute_root(&entries);
+            for (index, entry) in entries.iter().enumerate() {
+                let proof = build_proof(&entries, index).unwrap();
+                assert!(verify_proof(&root, len, index, entry, &proof));
+
+                // Wrong index & amount.
+                assert!(!verify_proof(&root, len, index + 1, entry, &proof));
+                let wrong_entry = LockupPoolEntry {
+                    amount: entry.amount + 1,
+                    ..*entry
+                };
+                assert!(!verify_proof(&root, len, index, &wrong_entry, &proof));
+            }
+        }
+    }
+
+    #[test]
+    fn test_build_proof_out_of_bounds() {
+        assert_eq!(build_proof(&entries(4), 4), None);
+    }
+}
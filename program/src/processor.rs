@@ -3,64 +3,549 @@
 use {
     crate::{
         error::PaladinLockupError,
+        extension::{mint_extension_policy, ExtensionPolicy},
         instruction::PaladinLockupInstruction,
+        merkle::MerkleRootBuilder,
         state::{
-            collect_escrow_authority_signer_seeds, get_escrow_authority_address,
-            get_escrow_authority_address_and_bump_seed, Lockup, LockupPool, LockupPoolEntry,
+            collect_escrow_authority_signer_seeds, collect_lockup_pool_root_signer_seeds,
+            collect_lockup_pool_signer_seeds, collect_lockup_pool_snapshot_signer_seeds,
+            collect_lockup_signer_seeds, collect_pool_escrow_authority_signer_seeds,
+            collect_reward_authority_signer_seeds, get_lockup_address_and_bump_seed,
+            get_lockup_pool_address_and_bump_seed, get_lockup_pool_root_address_and_bump_seed,
+            get_lockup_pool_snapshot_address, get_lockup_pool_snapshot_address_and_bump_seed,
+            get_reward_authority_address_and_bump_seed, move_entries, Lockup, LockupPool,
+            LockupPoolEntries, LockupPoolEntry, LockupPoolMut, LockupPoolRoot, LockupPoolShard,
+            LockupPoolShardInfo, LockupPoolShardMut, LockupPoolSnapshot, VestingSchedule,
         },
-        LOCKUP_COOLDOWN_SECONDS,
+        LOCKUP_COOLDOWN_SECONDS, MAX_LOCKUP_COOLDOWN_SECONDS, MAX_METADATA_UPDATE_INTERVAL_SECONDS,
     },
     solana_program::{
         account_info::{next_account_info, AccountInfo},
         clock::Clock,
-        entrypoint::ProgramResult,
+        entrypoint::{ProgramResult, MAX_PERMITTED_DATA_INCREASE},
         msg,
+        program::{invoke, invoke_signed},
         program_error::ProgramError,
+        program_option::COption,
         pubkey::Pubkey,
-        system_program,
+        rent::Rent,
+        system_instruction, system_program,
         sysvar::Sysvar,
     },
     spl_associated_token_account::get_associated_token_address_with_program_id,
     spl_discriminator::{ArrayDiscriminator, SplDiscriminate},
     spl_token_2022::{
-        extension::{BaseStateWithExtensions, ExtensionType, StateWithExtensions},
+        extension::{
+            transfer_fee::TransferFeeConfig, BaseStateWithExtensions, StateWithExtensions,
+        },
         state::Mint,
     },
-    std::{cmp::Reverse, num::NonZeroU64},
+    std::{cell::Ref, num::NonZeroU64},
 };
 
+/// Ensures the lockup is not committed to a term ending after the provided
+/// timestamp.
+fn check_committed_end_timestamp(state: &Lockup, timestamp: u64) -> ProgramResult {
+    if let Some(committed_end_timestamp) = state.committed_end_timestamp {
+        if committed_end_timestamp.get() > timestamp {
+            msg!(
+                "Lockup term has not ended yet. {} seconds remaining.",
+                committed_end_timestamp.get().saturating_sub(timestamp)
+            );
+            return Err(PaladinLockupError::LockupTermActive.into());
+        }
+    }
+
+    Ok(())
+}
+
+/// Ensures the lockup account is initialized in the current layout. Legacy
+/// lockups predate authority transfers, and must be migrated first.
+fn check_lockup_initialized(lockup_info: &AccountInfo) -> ProgramResult {
+    let data_len = lockup_info.data_len();
+    if (data_len != Lockup::LEN && data_len != Lockup::LEGACY_LEN)
+        || &lockup_info.try_borrow_data()?[0..8] != Lockup::SPL_DISCRIMINATOR_SLICE
+    {
+        return Err(ProgramError::UninitializedAccount);
+    }
+    if data_len == Lockup::LEGACY_LEN {
+        return Err(PaladinLockupError::LockupNotMigrated.into());
+    }
+
+    Ok(())
+}
+
+/// Ensures the provided pause flag is not set on the pool.
+fn check_pool_not_paused(lockup_pool_state: &LockupPool, pause_flag: u64) -> ProgramResult {
+    if lockup_pool_state.is_paused(pause_flag) {
+        return Err(PaladinLockupError::PoolPaused.into());
+    }
+
+    Ok(())
+}
+
+/// Takes the unclaimed rewards of a lockup being closed, recording them as
+/// claimed, so that they are paid out along with its tokens.
+fn take_rewards(lockup_pool_state: &mut LockupPoolMut, state: &mut Lockup) -> u64 {
+    let reward_amount = std::mem::take(&mut state.unclaimed_rewards);
+    lockup_pool_state.rewards.claimed_amount = lockup_pool_state
+        .rewards
+        .claimed_amount
+        .saturating_add(reward_amount);
+
+    reward_amount
+}
+
+/// Streams the pool's rewards into its reward-per-token index up to the
+/// current time. Must be called before the pool's active amount changes or
+/// any of its lockups' rewards accrue.
+fn update_rewards(lockup_pool_state: &mut LockupPoolMut) -> ProgramResult {
+    let timestamp = Clock::get()?.unix_timestamp as u64;
+    let active_amount = lockup_pool_state.stats.active_amount;
+    lockup_pool_state.rewards.update(timestamp, active_amount);
+
+    Ok(())
+}
+
+/// Clears the pool membership of the lockups evicted from the pool, whose
+/// accounts must be provided, in the current layout.
+fn record_eviction(
+    program_id: &Pubkey,
+    evicted: &[LockupPoolEntry],
+    accounts: &[AccountInfo],
+) -> ProgramResult {
+    for evicted in evicted {
+        msg!("Lockup {} evicted from the pool.", evicted.lockup);
+
+        let evicted_lockup_info = accounts
+            .iter()
+            .find(|info| info.key == &evicted.lockup)
+            .ok_or(PaladinLockupError::MissingEvictedLockup)?;
+        if evicted_lockup_info.owner != program_id {
+            return Err(ProgramError::InvalidAccountOwner);
+        }
+        if evicted_lockup_info.data_len() == Lockup::LEGACY_LEN {
+            msg!("Evicted lockup {} must be migrated first.", evicted.lockup);
+            return Err(ProgramError::InvalidAccountData);
+        }
+        let mut data = evicted_lockup_info.try_borrow_mut_data()?;
+        let state = bytemuck::try_from_bytes_mut::<Lockup>(&mut data)
+            .map_err(|_| ProgramError::InvalidAccountData)?;
+        state.flags &= !Lockup::FLAG_IN_POOL;
+    }
+
+    Ok(())
+}
+
+/// Sets or clears the lockup's pool membership.
+fn set_in_pool(state: &mut Lockup, in_pool: bool) {
+    match in_pool {
+        true => state.flags |= Lockup::FLAG_IN_POOL,
+        false => state.flags &= !Lockup::FLAG_IN_POOL,
+    }
+}
+
+/// Validates & deserializes a shard of the provided lockup pool.
+fn unpack_lockup_pool_shard<'a>(
+    program_id: &Pubkey,
+    lockup_pool: &Pubkey,
+    shard_info: &AccountInfo,
+    shard_data: &'a mut [u8],
+) -> Result<LockupPoolShardMut<'a>, ProgramError> {
+    if shard_info.owner != program_id
+        || shard_data.get(0..8) != Some(LockupPoolShard::SPL_DISCRIMINATOR_SLICE)
+    {
+        return Err(PaladinLockupError::IncorrectLockupPoolShard.into());
+    }
+    let shard = LockupPoolShard::unpack_mut(shard_data)?;
+    if &shard.header.pool != lockup_pool {
+        return Err(PaladinLockupError::IncorrectLockupPoolShard.into());
+    }
+
+    Ok(shard)
+}
+
+/// Runs `f` on the entries of the provided shard, which must be among the
+/// provided accounts, or on the pool's own entries if no shard is provided.
+fn with_shard_entries<T>(
+    program_id: &Pubkey,
+    lockup_pool: &Pubkey,
+    lockup_pool_state: &mut LockupPoolMut,
+    accounts: &[AccountInfo],
+    shard_index: Option<usize>,
+    f: impl FnOnce(&mut LockupPoolEntries) -> Result<T, ProgramError>,
+) -> Result<T, ProgramError> {
+    let Some(shard_index) = shard_index else {
+        return f(&mut lockup_pool_state.entries_mut());
+    };
+
+    let shard_address = lockup_pool_state.shards[shard_index].address;
+    let shard_info = accounts
+        .iter()
+        .find(|info| info.key == &shard_address)
+        .ok_or(PaladinLockupError::MissingLockupPoolShard)?;
+    let mut shard_data = shard_info.try_borrow_mut_data()?;
+    let mut shard = unpack_lockup_pool_shard(program_id, lockup_pool, shard_info, &mut shard_data)?;
+
+    f(&mut shard.entries_mut())
+}
+
+/// Runs `f` on the pool entries covering the provided amount: the pool's own
+/// entries, or those of the shard covering it, which must be among the
+/// provided accounts.
+fn with_pool_entries<T>(
+    program_id: &Pubkey,
+    lockup_pool: &Pubkey,
+    lockup_pool_state: &mut LockupPoolMut,
+    accounts: &[AccountInfo],
+    amount: u64,
+    f: impl FnOnce(&mut LockupPoolEntries) -> Result<T, ProgramError>,
+) -> Result<T, ProgramError> {
+    let shard_index = lockup_pool_state.shard_index(amount);
+    with_shard_entries(
+        program_id,
+        lockup_pool,
+        lockup_pool_state,
+        accounts,
+        shard_index,
+        f,
+    )
+}
+
+/// Inserts an entry into the pool, returning the entries it evicted.
+///
+/// A full pool or shard makes room by moving its smallest entries down into
+/// the next shard, raising the boundary between them, so that entries are
+/// only evicted from the end of the pool's last shard. An entry reaching a
+/// full last shard without outranking its smallest entry is evicted itself,
+/// so the insert never fails half way down the shards. Every shard the entry
+/// reaches must be among the provided accounts.
+fn insert_pool_entry(
+    program_id: &Pubkey,
+    lockup_pool: &Pubkey,
+    lockup_pool_state: &mut LockupPoolMut,
+    accounts: &[AccountInfo],
+    entry: LockupPoolEntry,
+) -> Result<Vec<LockupPoolEntry>, ProgramError> {
+    let mut shard_index = lockup_pool_state.shard_index(entry.amount);
+    let mut entries = vec![entry];
+    loop {
+        // The shard below, if any, receives the entries that don't fit.
+        let next_shard_index = shard_index.map_or(0, |index| index.saturating_add(1));
+        let is_last = next_shard_index >= lockup_pool_state.shards_len;
+
+        // Entries that don't fit the last shard are evicted.
+        let overflow = with_shard_entries(
+            program_id,
+            lockup_pool,
+            lockup_pool_state,
+            accounts,
+            shard_index,
+            |pool_entries| {
+                if !is_last {
+                    return Ok(pool_entries.insert_spilling(entries)?);
+                }
+                let mut evicted = Vec::new();
+                for entry in entries {
+                    match pool_entries.insert_entry(entry) {
+                        Ok(smallest) => evicted.extend(smallest),
+                        Err(PaladinLockupError::AmountTooLow) => evicted.push(entry),
+                        Err(err) => return Err(err.into()),
+                    }
+                }
+                Ok(evicted)
+            },
+        )?;
+        if is_last || overflow.is_empty() {
+            return Ok(overflow);
+        }
+
+        // Move the boundary above the spilled entries.
+        lockup_pool_state.shards[next_shard_index].max_amount =
+            overflow[0]
+                .amount
+                .checked_add(1)
+                .ok_or(PaladinLockupError::ShardCapacityExceeded)?;
+        shard_index = Some(next_shard_index);
+        entries = overflow;
+    }
+}
+
+/// Inserts the lockup's entry into the pool, clearing the pool membership of
+/// the other lockups it evicted, and returns whether the lockup itself holds
+/// a seat, i.e. whether it outranked the smallest entry of a full pool.
+fn insert_lockup_entry(
+    program_id: &Pubkey,
+    lockup_pool: &Pubkey,
+    lockup_pool_state: &mut LockupPoolMut,
+    accounts: &[AccountInfo],
+    entry: LockupPoolEntry,
+) -> Result<bool, ProgramError> {
+    let mut evicted =
+        insert_pool_entry(program_id, lockup_pool, lockup_pool_state, accounts, entry)?;
+    let evicted_len = evicted.len();
+    evicted.retain(|evicted| evicted.lockup != entry.lockup);
+    record_eviction(program_id, &evicted, accounts)?;
+
+    Ok(evicted.len() == evicted_len)
+}
+
+/// Removes the lockup's entry from the pool, if it holds one, as recorded by
+/// its pool membership flag.
+fn remove_lockup_entry(
+    program_id: &Pubkey,
+    lockup_pool: &Pubkey,
+    lockup_pool_state: &mut LockupPoolMut,
+    accounts: &[AccountInfo],
+    lockup: &Pubkey,
+    state: &Lockup,
+) -> Result<Option<LockupPoolEntry>, ProgramError> {
+    if !state.in_pool() {
+        return Ok(None);
+    }
+
+    remove_pool_entry(
+        program_id,
+        lockup_pool,
+        lockup_pool_state,
+        accounts,
+        lockup,
+        state.amount,
+    )
+}
+
+/// Removes the lockup's entry from the pool, if it is present.
+fn remove_pool_entry(
+    program_id: &Pubkey,
+    lockup_pool: &Pubkey,
+    lockup_pool_state: &mut LockupPoolMut,
+    accounts: &[AccountInfo],
+    lockup: &Pubkey,
+    amount: u64,
+) -> Result<Option<LockupPoolEntry>, ProgramError> {
+    with_pool_entries(
+        program_id,
+        lockup_pool,
+        lockup_pool_state,
+        accounts,
+        amount,
+        |entries| {
+            Ok(entries
+                .find_entry(lockup, amount)
+                .map(|index| entries.remove_entry(index)))
+        },
+    )
+}
+
+/// Borrows the data of all of the pool's shards, which must be the next
+/// accounts, in order.
+fn borrow_lockup_pool_shards<'a, 'b>(
+    program_id: &Pubkey,
+    lockup_pool_state: &LockupPool,
+    accounts_iter: &mut std::slice::Iter<'a, AccountInfo<'b>>,
+) -> Result<Vec<Ref<'a, &'a mut [u8]>>, ProgramError> {
+    let mut shard_datas = Vec::with_capacity(lockup_pool_state.shards_len);
+    for shard in lockup_pool_state.shards() {
+        let shard_info = next_account_info(accounts_iter)
+            .ok()
+            .filter(|info| info.key == &shard.address)
+            .ok_or(PaladinLockupError::MissingLockupPoolShard)?;
+        if shard_info.owner != program_id {
+            return Err(PaladinLockupError::IncorrectLockupPoolShard.into());
+        }
+        shard_datas.push(shard_info.try_borrow_data()?);
+    }
+
+    Ok(shard_datas)
+}
+
+/// Gathers the pool's entries, from the pool itself down to its last shard.
+fn collect_lockup_pool_entries<'a>(
+    lockup_pool: &Pubkey,
+    lockup_pool_state: &LockupPool,
+    pool_entries: &'a [LockupPoolEntry],
+    shard_datas: &'a [Ref<&mut [u8]>],
+) -> Result<Vec<&'a [LockupPoolEntry]>, ProgramError> {
+    let mut segments = vec![&pool_entries[..lockup_pool_state.entries_len]];
+    for shard_data in shard_datas {
+        let (shard, shard_entries) = LockupPoolShard::unpack(shard_data)?;
+        if shard.discriminator != LockupPoolShard::SPL_DISCRIMINATOR_SLICE
+            || &shard.pool != lockup_pool
+        {
+            return Err(PaladinLockupError::IncorrectLockupPoolShard.into());
+        }
+        segments.push(&shard_entries[..shard.entries_len]);
+    }
+
+    Ok(segments)
+}
+
+/// Ensures the provided cooldown & metadata update interval are within the
+/// program's bounds.
+fn check_pool_config(
+    cooldown_seconds: u64,
+    metadata_update_interval_seconds: u64,
+) -> ProgramResult {
+    if cooldown_seconds > MAX_LOCKUP_COOLDOWN_SECONDS
+        || metadata_update_interval_seconds > MAX_METADATA_UPDATE_INTERVAL_SECONDS
+    {
+        return Err(PaladinLockupError::InvalidPoolConfig.into());
+    }
+    Ok(())
+}
+
 /// Processes a
 /// [InitializeLockupPool](enum.PaladinInitializeLockupPoolInstruction.html)
 /// instruction.
-fn process_initialize_lockup_pool(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+fn process_initialize_lockup_pool(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    pool_id: u64,
+    cooldown_seconds: u64,
+    metadata_update_interval_seconds: u64,
+    isolated_escrow: bool,
+) -> ProgramResult {
     let accounts_iter = &mut accounts.iter();
+    let payer_info = next_account_info(accounts_iter)?;
     let lockup_pool_info = next_account_info(accounts_iter)?;
     let mint_info = next_account_info(accounts_iter)?;
+    // NB: System program is just needed for CPIs.
+    let _ = next_account_info(accounts_iter)?;
+    let pool_authority_info = next_account_info(accounts_iter).ok();
+
+    // Ensure the provided lockup pool address is correct.
+    let (lockup_pool_address, bump_seed) =
+        get_lockup_pool_address_and_bump_seed(mint_info.key, pool_id, program_id);
+    if lockup_pool_info.key != &lockup_pool_address {
+        return Err(PaladinLockupError::IncorrectLockupPoolAddress.into());
+    }
+
+    let pool_len = LockupPool::account_len(LockupPool::INITIAL_CAPACITY);
+    if lockup_pool_info.owner == &system_program::ID {
+        // Fund the pool for its initial size, topping up rather than creating in
+        // case the address was pre-funded.
+        let required_lamports = Rent::get()?
+            .minimum_balance(pool_len)
+            .saturating_sub(lockup_pool_info.lamports());
+        if required_lamports > 0 {
+            invoke(
+                &system_instruction::transfer(
+                    payer_info.key,
+                    lockup_pool_info.key,
+                    required_lamports,
+                ),
+                &[payer_info.clone(), lockup_pool_info.clone()],
+            )?;
+        }
+
+        // Allocate as much as a single instruction allows & take ownership.
+        let pool_id = pool_id.to_le_bytes();
+        let bump_seed = [bump_seed];
+        let lockup_pool_signer_seeds =
+            collect_lockup_pool_signer_seeds(mint_info.key, &pool_id, &bump_seed);
+        invoke_signed(
+            &system_instruction::allocate(
+                lockup_pool_info.key,
+                pool_len.min(MAX_PERMITTED_DATA_INCREASE) as u64,
+            ),
+            std::slice::from_ref(lockup_pool_info),
+            &[&lockup_pool_signer_seeds],
+        )?;
+        invoke_signed(
+            &system_instruction::assign(lockup_pool_info.key, program_id),
+            std::slice::from_ref(lockup_pool_info),
+            &[&lockup_pool_signer_seeds],
+        )?;
+    } else {
+        // Validate the lockup pool account.
+        assert_eq!(lockup_pool_info.owner, program_id);
+
+        // Grow the account towards its initial size.
+        let data_len = lockup_pool_info.data_len();
+        if data_len < pool_len {
+            let new_len = data_len
+                .saturating_add(MAX_PERMITTED_DATA_INCREASE)
+                .min(pool_len);
+            lockup_pool_info.realloc(new_len, true)?;
+        }
+    }
 
-    // Validate the lockup pool account.
-    assert_eq!(lockup_pool_info.owner, program_id);
-    assert_eq!(lockup_pool_info.data_len(), LockupPool::LEN);
+    // Wait for subsequent instructions to finish growing the account.
+    if lockup_pool_info.data_len() < pool_len {
+        msg!(
+            "Lockup pool allocated {} of {} bytes.",
+            lockup_pool_info.data_len(),
+            pool_len
+        );
+        return Ok(());
+    }
 
     // Validate the mint.
-    assert_eq!(mint_info.owner, &spl_token_2022::ID);
+    spl_token_2022::check_spl_token_program_account(mint_info.owner)?;
     let mint_data = mint_info.try_borrow_data()?;
     let mint = StateWithExtensions::<Mint>::unpack(&mint_data)?;
-    assert!(
-        mint.get_extension_types()?
-            .iter()
-            .all(|extension| matches!(extension, ExtensionType::TransferHook)),
-        "Invalid extension found in mint"
-    );
+    for extension in mint.get_extension_types()? {
+        match mint_extension_policy(extension) {
+            ExtensionPolicy::Allowed => {}
+            ExtensionPolicy::AllowedWithConditions(condition) => {
+                msg!(
+                    "Mint extension {:?} is supported, but {}",
+                    extension,
+                    condition
+                );
+            }
+            ExtensionPolicy::Rejected(reason) => {
+                msg!("Unsupported mint extension {:?}: {}", extension, reason);
+                return Err(PaladinLockupError::UnsupportedMintExtension.into());
+            }
+        }
+    }
+
+    // The mint authority, if any, must initialize the canonical pool as its
+    // authority, so that nobody else can initialize it without one. Other
+    // pools take whichever authority signs their initialization, if any.
+    if let Some(pool_authority_info) = pool_authority_info {
+        if !pool_authority_info.is_signer {
+            return Err(ProgramError::MissingRequiredSignature);
+        }
+    }
+    let pool_authority = match (pool_id, mint.base.mint_authority, pool_authority_info) {
+        (0, COption::Some(mint_authority), Some(pool_authority_info)) => {
+            if pool_authority_info.key != &mint_authority {
+                return Err(ProgramError::IncorrectAuthority);
+            }
+            mint_authority
+        }
+        (0, COption::Some(_), None) => return Err(ProgramError::MissingRequiredSignature),
+        (0, COption::None, Some(_)) => return Err(ProgramError::IncorrectAuthority),
+        (_, _, Some(pool_authority_info)) => *pool_authority_info.key,
+        (_, _, None) => Pubkey::default(),
+    };
+
+    // Only a pool authority may configure the pool, so pools without one use
+    // the defaults.
+    check_pool_config(cooldown_seconds, metadata_update_interval_seconds)?;
+    if pool_authority == Pubkey::default()
+        && (cooldown_seconds != LOCKUP_COOLDOWN_SECONDS || metadata_update_interval_seconds != 0)
+    {
+        return Err(PaladinLockupError::InvalidPoolConfig.into());
+    }
 
-    // Write the discriminator & mint.
+    // Write the discriminator, mint & config.
     let mut lockup_pool_data = lockup_pool_info.data.borrow_mut();
-    let lockup_pool_state = bytemuck::from_bytes_mut::<LockupPool>(&mut lockup_pool_data);
+    let mut lockup_pool_state = LockupPool::unpack_mut(&mut lockup_pool_data)?;
     assert_eq!(
         lockup_pool_state.discriminator,
         ArrayDiscriminator::UNINITIALIZED.as_slice()
     );
     lockup_pool_state.discriminator = LockupPool::SPL_DISCRIMINATOR.into();
     lockup_pool_state.mint = *mint_info.key;
+    lockup_pool_state.cooldown_seconds = cooldown_seconds;
+    lockup_pool_state.metadata_update_interval_seconds = metadata_update_interval_seconds;
+    lockup_pool_state.authority = pool_authority;
+    if isolated_escrow {
+        lockup_pool_state.flags |= LockupPool::FLAG_ISOLATED_ESCROW;
+    }
 
     Ok(())
 }
@@ -73,6 +558,8 @@ fn process_lockup(
     accounts: &[AccountInfo],
     metadata: [u8; 32],
     amount: u64,
+    committed_end_timestamp: Option<NonZeroU64>,
+    vesting: VestingSchedule,
 ) -> ProgramResult {
     let accounts_iter = &mut accounts.iter();
 
@@ -84,8 +571,7 @@ fn process_lockup(
     let escrow_authority_info = next_account_info(accounts_iter)?;
     let escrow_token_account_info = next_account_info(accounts_iter)?;
     let mint_info = next_account_info(accounts_iter)?;
-    // NB: Token program is just needed for CPIs.
-    let _ = next_account_info(accounts_iter)?;
+    let token_program_info = next_account_info(accounts_iter)?;
 
     // Validate & deserialize the lockup pool.
     assert_eq!(
@@ -93,22 +579,23 @@ fn process_lockup(
         "lockup_pool invalid owner"
     );
     let mut lockup_pool_data = lockup_pool_info.data.borrow_mut();
-    assert_eq!(
-        lockup_pool_data.len(),
-        LockupPool::LEN,
-        "lockup_pool incorrect size"
-    );
     assert_eq!(
         &lockup_pool_data[0..8],
         LockupPool::SPL_DISCRIMINATOR_SLICE,
         "lockup_pool uninitialized"
     );
-    let lockup_pool_state = bytemuck::from_bytes_mut::<LockupPool>(&mut lockup_pool_data);
+    let mut lockup_pool_state = LockupPool::unpack_mut(&mut lockup_pool_data)?;
     assert_eq!(
         &lockup_pool_state.mint, mint_info.key,
         "Incorrect mint for lockup pool"
     );
 
+    // Ensure the pool is accepting new lockups.
+    check_pool_not_paused(&lockup_pool_state, LockupPool::FLAG_LOCKUP_PAUSED)?;
+    if !lockup_pool_state.lockups_enabled() {
+        return Err(PaladinLockupError::LockupsDisabled.into());
+    }
+
     // Ensure the lockup account is owned by the Paladin Lockup program.
     if lockup_info.owner != program_id {
         return Err(ProgramError::InvalidAccountOwner);
@@ -124,70 +611,69 @@ fn process_lockup(
         return Err(ProgramError::AccountAlreadyInitialized);
     }
 
-    // Ensure the provided escrow authority address is correct.
-    if escrow_authority_info.key != &get_escrow_authority_address(program_id) {
-        return Err(PaladinLockupError::IncorrectEscrowAuthorityAddress.into());
-    }
+    check_escrow(
+        program_id,
+        lockup_pool_info.key,
+        &lockup_pool_state,
+        escrow_authority_info,
+        escrow_token_account_info,
+        mint_info.key,
+        token_program_info.key,
+    )?;
 
-    // Ensure the provided escrow token account address is correct.
-    if escrow_token_account_info.key
-        != &get_associated_token_address_with_program_id(
-            escrow_authority_info.key,
-            mint_info.key,
-            &spl_token_2022::ID,
-        )
-    {
-        return Err(PaladinLockupError::IncorrectEscrowTokenAccount.into());
+    // Only the tokens the escrow receives, net of any transfer fee, are
+    // locked, and must meet the pool's minimum.
+    let received_amount = net_transfer_amount(mint_info, amount)?;
+    if received_amount < lockup_pool_state.min_lockup_amount {
+        return Err(PaladinLockupError::AmountTooLow.into());
     }
 
+    // Bring the pool's rewards up to date, so that the lockup only earns
+    // rewards streamed from now on.
+    update_rewards(&mut lockup_pool_state)?;
+
     // Write the data.
     let mut data = lockup_info.try_borrow_mut_data()?;
     *bytemuck::try_from_bytes_mut(&mut data).map_err(|_| ProgramError::InvalidAccountData)? =
         Lockup {
             discriminator: Lockup::SPL_DISCRIMINATOR.into(),
-            amount,
+            amount: received_amount,
             authority: *lockup_authority_info.key,
             lockup_start_timestamp: Clock::get()?.unix_timestamp as u64,
             lockup_end_timestamp: None,
             mint: *mint_info.key,
             pool: *lockup_pool_info.key,
             metadata,
+            proposed_authority: Pubkey::default(),
+            committed_end_timestamp,
+            vesting,
+            withdrawn_amount: 0,
+            metadata_updated_timestamp: 0,
+            flags: Lockup::FLAG_IN_POOL,
+            reward_per_token_checkpoint: lockup_pool_state.rewards.reward_per_token,
+            unclaimed_rewards: 0,
+            cooldown_end_timestamp: None,
         };
 
-    // Evict the smallest lock if necessary.
-    let last_index = std::cmp::min(
-        lockup_pool_state.entries_len,
-        LockupPool::LOCKUP_CAPACITY - 1,
-    );
-    let last_amount = lockup_pool_state.entries[last_index].amount;
-    match (
-        lockup_pool_state.entries_len == lockup_pool_state.entries.len(),
-        amount > last_amount,
-    ) {
-        (true, true) => {}
-        (true, false) => return Err(PaladinLockupError::AmountTooLow.into()),
-        (false, _) => {
-            lockup_pool_state.entries_len = lockup_pool_state.entries_len.checked_add(1).unwrap()
-        }
+    // Insert the entry, evicting the smallest lock if necessary.
+    if !insert_lockup_entry(
+        program_id,
+        lockup_pool_info.key,
+        &mut lockup_pool_state,
+        accounts_iter.as_slice(),
+        LockupPoolEntry {
+            lockup: *lockup_info.key,
+            amount: received_amount,
+            metadata,
+        },
+    )? {
+        return Err(PaladinLockupError::AmountTooLow.into());
     }
-
-    // Binary search & insert the entry.
-    let index = match lockup_pool_state
-        .entries
-        .binary_search_by_key(&Reverse(amount), |entry| Reverse(entry.amount))
-    {
-        Ok(index) => index,
-        Err(index) => index,
-    };
-    *lockup_pool_state.entries.last_mut().unwrap() = LockupPoolEntry {
-        lockup: *lockup_info.key,
-        amount,
-        metadata,
-    };
-    lockup_pool_state.entries[index..].rotate_right(1);
+    lockup_pool_state.stats.record_lock(received_amount, 1);
 
     // Transfer the tokens to the escrow token account.
     {
+        check_token_program(token_program_info, mint_info)?;
         let decimals = {
             let mint_data = mint_info.try_borrow_data()?;
             let mint = StateWithExtensions::<Mint>::unpack(&mint_data)?;
@@ -195,7 +681,7 @@ fn process_lockup(
         };
 
         spl_token_2022::onchain::invoke_transfer_checked(
-            &spl_token_2022::id(),
+            token_program_info.key,
             token_account_info.clone(),
             mint_info.clone(),
             escrow_token_account_info.clone(),
@@ -210,28 +696,146 @@ fn process_lockup(
     Ok(())
 }
 
+/// Processes a
+/// [VestingLockup](enum.PaladinLockupInstruction.html)
+/// instruction.
+fn process_vesting_lockup(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    metadata: [u8; 32],
+    amount: u64,
+    schedule: VestingSchedule,
+) -> ProgramResult {
+    // Ensure the schedule is well formed.
+    if !schedule.is_valid() {
+        return Err(PaladinLockupError::InvalidVestingSchedule.into());
+    }
+
+    process_lockup(program_id, accounts, metadata, amount, None, schedule)
+}
+
+/// Processes a
+/// [CreateLockup](enum.PaladinLockupInstruction.html)
+/// instruction.
+fn process_create_lockup(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    metadata: [u8; 32],
+    amount: u64,
+    committed_end_timestamp: Option<NonZeroU64>,
+    nonce: u64,
+) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+
+    let rent_payer_info = next_account_info(accounts_iter)?;
+    // NB: System program is just needed for CPIs.
+    let _ = next_account_info(accounts_iter)?;
+
+    // The remaining accounts are those expected by `Lockup`.
+    let lockup_accounts = accounts_iter.as_slice();
+    let lockup_authority_info = next_account_info(accounts_iter)?;
+    let _token_owner_info = next_account_info(accounts_iter)?;
+    let _token_account_info = next_account_info(accounts_iter)?;
+    let lockup_pool_info = next_account_info(accounts_iter)?;
+    let lockup_info = next_account_info(accounts_iter)?;
+
+    // Ensure the provided lockup address is correct.
+    let (lockup_address, bump_seed) = get_lockup_address_and_bump_seed(
+        lockup_pool_info.key,
+        lockup_authority_info.key,
+        nonce,
+        program_id,
+    );
+    if lockup_info.key != &lockup_address {
+        return Err(PaladinLockupError::IncorrectLockupAddress.into());
+    }
+
+    // Create the lockup account, owned by this program.
+    {
+        let nonce = nonce.to_le_bytes();
+        let bump_seed = [bump_seed];
+        let lockup_signer_seeds = collect_lockup_signer_seeds(
+            lockup_pool_info.key,
+            lockup_authority_info.key,
+            &nonce,
+            &bump_seed,
+        );
+        let space = Lockup::LEN;
+        let required_lamports = Rent::get()?
+            .minimum_balance(space)
+            .saturating_sub(lockup_info.lamports());
+
+        // Top up rather than create, in case the address was pre-funded.
+        if required_lamports > 0 {
+            invoke(
+                &system_instruction::transfer(
+                    rent_payer_info.key,
+                    lockup_info.key,
+                    required_lamports,
+                ),
+                &[rent_payer_info.clone(), lockup_info.clone()],
+            )?;
+        }
+        invoke_signed(
+            &system_instruction::allocate(lockup_info.key, space as u64),
+            std::slice::from_ref(lockup_info),
+            &[&lockup_signer_seeds],
+        )?;
+        invoke_signed(
+            &system_instruction::assign(lockup_info.key, program_id),
+            std::slice::from_ref(lockup_info),
+            &[&lockup_signer_seeds],
+        )?;
+    }
+
+    process_lockup(
+        program_id,
+        lockup_accounts,
+        metadata,
+        amount,
+        committed_end_timestamp,
+        VestingSchedule::default(),
+    )
+}
+
 /// Processes an
-/// [Unlock](enum.PaladinLockupInstruction.html)
+/// [IncreaseLockup](enum.PaladinLockupInstruction.html)
 /// instruction.
-fn process_unlock(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+fn process_increase_lockup(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    amount: u64,
+) -> ProgramResult {
     let accounts_iter = &mut accounts.iter();
 
     let lockup_authority_info = next_account_info(accounts_iter)?;
+    let token_owner_info = next_account_info(accounts_iter)?;
+    let token_account_info = next_account_info(accounts_iter)?;
     let lockup_pool_info = next_account_info(accounts_iter)?;
     let lockup_info = next_account_info(accounts_iter)?;
+    let escrow_authority_info = next_account_info(accounts_iter)?;
+    let escrow_token_account_info = next_account_info(accounts_iter)?;
+    let mint_info = next_account_info(accounts_iter)?;
+    let token_program_info = next_account_info(accounts_iter)?;
 
     // Validate & deserialize the lockup pool.
     assert_eq!(
         lockup_pool_info.owner, program_id,
         "lockup_pool invalid owner"
     );
+    let mut lockup_pool_data = lockup_pool_info.data.borrow_mut();
     assert_eq!(
-        lockup_pool_info.data_len(),
-        LockupPool::LEN,
+        &lockup_pool_data[0..8],
+        LockupPool::SPL_DISCRIMINATOR_SLICE,
         "lockup_pool uninitialized"
     );
-    let mut lockup_pool_data = lockup_pool_info.data.borrow_mut();
-    let lockup_pool_state = bytemuck::from_bytes_mut::<LockupPool>(&mut lockup_pool_data);
+    let mut lockup_pool_state = LockupPool::unpack_mut(&mut lockup_pool_data)?;
+
+    // Ensure the pool is accepting more tokens.
+    check_pool_not_paused(&lockup_pool_state, LockupPool::FLAG_LOCKUP_PAUSED)?;
+    if !lockup_pool_state.lockups_enabled() {
+        return Err(PaladinLockupError::LockupsDisabled.into());
+    }
 
     // Ensure the lockup authority is a signer.
     if !lockup_authority_info.is_signer {
@@ -244,75 +848,1850 @@ fn process_unlock(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResul
     }
 
     // Ensure the lockup account is initialized.
-    if lockup_info.data_len() != std::mem::size_of::<Lockup>()
-        || &lockup_info.try_borrow_data()?[0..8] != Lockup::SPL_DISCRIMINATOR_SLICE
-    {
-        return Err(ProgramError::UninitializedAccount);
-    }
+    check_lockup_initialized(lockup_info)?;
 
-    let mut data = lockup_info.try_borrow_mut_data()?;
-    let state = bytemuck::try_from_bytes_mut::<Lockup>(&mut data)
-        .map_err(|_| ProgramError::InvalidAccountData)?;
+    check_escrow(
+        program_id,
+        lockup_pool_info.key,
+        &lockup_pool_state,
+        escrow_authority_info,
+        escrow_token_account_info,
+        mint_info.key,
+        token_program_info.key,
+    )?;
 
-    // Ensure the provided authority is the same as the lockup's authority.
-    if state.authority != *lockup_authority_info.key {
-        return Err(ProgramError::IncorrectAuthority);
-    }
+    {
+        let mut data = lockup_info.try_borrow_mut_data()?;
+        let state = bytemuck::try_from_bytes_mut::<Lockup>(&mut data)
+            .map_err(|_| ProgramError::InvalidAccountData)?;
 
-    // Ensure the lockup account has not already been unlocked.
-    if state.lockup_end_timestamp.is_some() {
-        return Err(PaladinLockupError::LockupAlreadyUnlocked.into());
-    }
+        // Ensure the provided authority is the same as the lockup's authority.
+        if state.authority != *lockup_authority_info.key {
+            return Err(ProgramError::IncorrectAuthority);
+        }
 
-    // Get the timestamp from the clock sysvar, and use it to set the end
-    // timestamp of the lockup, effectively unlocking the funds.
-    let clock = <Clock as Sysvar>::get()?;
-    state.lockup_end_timestamp = NonZeroU64::new(clock.unix_timestamp as u64);
+        // Ensure the lockup matches the pool.
+        if lockup_pool_info.key != &state.pool {
+            return Err(PaladinLockupError::IncorrectPool.into());
+        }
 
-    // Ensure the lockup matches the pool.
-    if lockup_pool_info.key != &state.pool {
-        return Err(PaladinLockupError::IncorrectPool.into());
-    }
+        // Ensure the provided mint is the same as the lockup's mint.
+        if state.mint != *mint_info.key {
+            return Err(PaladinLockupError::IncorrectMint.into());
+        }
 
-    // Remove the entry from the pool (if it exists).
-    let partition_point = lockup_pool_state
-        .entries
-        .partition_point(|entry| entry.amount > state.amount);
-    if partition_point != lockup_pool_state.entries_len {
-        let offset = lockup_pool_state.entries[partition_point..]
-            .iter()
-            .take_while(|entry| entry.amount == state.amount)
-            .position(|entry| &entry.lockup == lockup_info.key)
-            .unwrap();
-        #[allow(clippy::arithmetic_side_effects)]
-        let index = partition_point + offset;
+        // Ensure the lockup account has not been unlocked.
+        if state.lockup_end_timestamp.is_some() {
+            return Err(PaladinLockupError::LockupAlreadyUnlocked.into());
+        }
+
+        // Ensure the lockup is not vesting, as that would alter its schedule.
+        if state.is_vesting() {
+            return Err(PaladinLockupError::LockupVesting.into());
+        }
+
+        // Only the tokens the escrow receives, net of any transfer fee, are
+        // locked.
+        let received_amount = net_transfer_amount(mint_info, amount)?;
+        let new_amount = state
+            .amount
+            .checked_add(received_amount)
+            .ok_or(ProgramError::ArithmeticOverflow)?;
+
+        // Move the entry to its new rank, re-inserting it if it was evicted.
+        // An evicted lockup that still doesn't outrank the pool's smallest
+        // entry keeps its tokens, but stays out of the pool.
+        let metadata = remove_lockup_entry(
+            program_id,
+            lockup_pool_info.key,
+            &mut lockup_pool_state,
+            accounts_iter.as_slice(),
+            lockup_info.key,
+            state,
+        )?
+        .map_or(state.metadata, |entry| entry.metadata);
+        let in_pool = insert_lockup_entry(
+            program_id,
+            lockup_pool_info.key,
+            &mut lockup_pool_state,
+            accounts_iter.as_slice(),
+            LockupPoolEntry {
+                lockup: *lockup_info.key,
+                amount: new_amount,
+                metadata,
+            },
+        )?;
+        set_in_pool(state, in_pool);
+
+        update_rewards(&mut lockup_pool_state)?;
+        state.accrue_rewards(lockup_pool_state.rewards.reward_per_token);
+        state.amount = new_amount;
+        lockup_pool_state.stats.record_lock(received_amount, 0);
+    }
+
+    // Transfer the tokens to the escrow token account.
+    {
+        check_token_program(token_program_info, mint_info)?;
+        let decimals = {
+            let mint_data = mint_info.try_borrow_data()?;
+            let mint = StateWithExtensions::<Mint>::unpack(&mint_data)?;
+            mint.base.decimals
+        };
+
+        spl_token_2022::onchain::invoke_transfer_checked(
+            token_program_info.key,
+            token_account_info.clone(),
+            mint_info.clone(),
+            escrow_token_account_info.clone(),
+            token_owner_info.clone(),
+            accounts_iter.as_slice(),
+            amount,
+            decimals,
+            &[],
+        )?;
+    }
+
+    Ok(())
+}
+
+/// Processes an
+/// [Unlock](enum.PaladinLockupInstruction.html)
+/// instruction.
+fn process_unlock(program_id: &Pubkey, accounts: &[AccountInfo], amount: u64) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+
+    let lockup_authority_info = next_account_info(accounts_iter)?;
+    let lockup_pool_info = next_account_info(accounts_iter)?;
+    let lockup_info = next_account_info(accounts_iter)?;
+    // A partial unlock moves the unlocked amount into a new lockup account.
+    let unlocked_lockup_info = match amount {
+        0 => None,
+        _ => Some(next_account_info(accounts_iter)?),
+    };
+
+    // Validate & deserialize the lockup pool.
+    assert_eq!(
+        lockup_pool_info.owner, program_id,
+        "lockup_pool invalid owner"
+    );
+    let mut lockup_pool_data = lockup_pool_info.data.borrow_mut();
+    assert_eq!(
+        &lockup_pool_data[0..8],
+        LockupPool::SPL_DISCRIMINATOR_SLICE,
+        "lockup_pool uninitialized"
+    );
+    let mut lockup_pool_state = LockupPool::unpack_mut(&mut lockup_pool_data)?;
+
+    // Ensure unlocks are not paused.
+    check_pool_not_paused(&lockup_pool_state, LockupPool::FLAG_UNLOCK_PAUSED)?;
+
+    // Ensure the lockup authority is a signer.
+    if !lockup_authority_info.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    // Ensure the lockup account is owned by the Paladin Lockup program.
+    if lockup_info.owner != program_id {
+        return Err(ProgramError::InvalidAccountOwner);
+    }
+
+    // Ensure the lockup account is initialized.
+    check_lockup_initialized(lockup_info)?;
+
+    // Ensure the unlocked lockup account is a distinct account, owned by the
+    // Paladin Lockup program, with enough space and not yet initialized.
+    if let Some(unlocked_lockup_info) = unlocked_lockup_info {
+        if unlocked_lockup_info.key == lockup_info.key {
+            return Err(ProgramError::InvalidArgument);
+        }
+        if unlocked_lockup_info.owner != program_id {
+            return Err(ProgramError::InvalidAccountOwner);
+        }
+        if unlocked_lockup_info.data_len() != std::mem::size_of::<Lockup>() {
+            return Err(ProgramError::InvalidAccountData);
+        }
+        if &unlocked_lockup_info.try_borrow_data()?[0..8]
+            != ArrayDiscriminator::UNINITIALIZED.as_slice()
+        {
+            return Err(ProgramError::AccountAlreadyInitialized);
+        }
+    }
+
+    let mut data = lockup_info.try_borrow_mut_data()?;
+    let state = bytemuck::try_from_bytes_mut::<Lockup>(&mut data)
+        .map_err(|_| ProgramError::InvalidAccountData)?;
+
+    // Ensure the provided authority is the same as the lockup's authority.
+    if state.authority != *lockup_authority_info.key {
+        return Err(ProgramError::IncorrectAuthority);
+    }
+
+    // Ensure the lockup account has not already been unlocked.
+    if state.lockup_end_timestamp.is_some() {
+        return Err(PaladinLockupError::LockupAlreadyUnlocked.into());
+    }
+
+    // Ensure the lockup is not vesting, as its tokens are released on its
+    // schedule instead.
+    if state.is_vesting() {
+        return Err(PaladinLockupError::LockupVesting.into());
+    }
+
+    // Ensure the lockup matches the pool.
+    if lockup_pool_info.key != &state.pool {
+        return Err(PaladinLockupError::IncorrectPool.into());
+    }
+
+    // Get the timestamp from the clock sysvar, and use it to set the end
+    // timestamp of the lockup, effectively unlocking the funds.
+    let clock = <Clock as Sysvar>::get()?;
+    let timestamp = clock.unix_timestamp as u64;
+
+    // Ensure the lockup's committed term (if any) has ended.
+    check_committed_end_timestamp(state, timestamp)?;
+
+    update_rewards(&mut lockup_pool_state)?;
+    state.accrue_rewards(lockup_pool_state.rewards.reward_per_token);
+
+    // The unlocked tokens may be withdrawn once the pool's current cooldown
+    // has passed.
+    let cooldown_end_timestamp =
+        NonZeroU64::new(timestamp.saturating_add(lockup_pool_state.cooldown_seconds));
+
+    let Some(unlocked_lockup_info) = unlocked_lockup_info else {
+        state.lockup_end_timestamp = NonZeroU64::new(timestamp);
+        state.cooldown_end_timestamp = cooldown_end_timestamp;
+
+        // Remove the entry from the pool, unless the lockup was evicted.
+        remove_lockup_entry(
+            program_id,
+            lockup_pool_info.key,
+            &mut lockup_pool_state,
+            accounts_iter.as_slice(),
+            lockup_info.key,
+            state,
+        )?;
+        state.flags &= !Lockup::FLAG_IN_POOL;
+        lockup_pool_state.stats.record_cooldown(state.amount, 1);
+
+        return Ok(());
+    };
+
+    // Ensure the amount leaves a non-zero remainder in the lockup.
+    let remaining_amount = match state.amount.checked_sub(amount) {
+        Some(remaining_amount) if remaining_amount > 0 => remaining_amount,
+        _ => return Err(PaladinLockupError::InvalidUnlockAmount.into()),
+    };
+
+    // Re-rank the remaining lockup in the pool (if it exists), dropping it
+    // from the pool if it no longer outranks the pool's smallest entry.
+    if let Some(entry) = remove_lockup_entry(
+        program_id,
+        lockup_pool_info.key,
+        &mut lockup_pool_state,
+        accounts_iter.as_slice(),
+        lockup_info.key,
+        state,
+    )? {
+        let in_pool = insert_lockup_entry(
+            program_id,
+            lockup_pool_info.key,
+            &mut lockup_pool_state,
+            accounts_iter.as_slice(),
+            LockupPoolEntry {
+                amount: remaining_amount,
+                ..entry
+            },
+        )?;
+        if !in_pool {
+            state.flags &= !Lockup::FLAG_IN_POOL;
+        }
+    }
+    state.amount = remaining_amount;
+    lockup_pool_state.stats.record_cooldown(amount, 0);
+
+    // Write the unlocked lockup, starting its cooldown. It keeps the lockup's
+    // authority & metadata, but not any pending authority transfer, and earns
+    // rewards from now on.
+    let mut unlocked_data = unlocked_lockup_info.try_borrow_mut_data()?;
+    *bytemuck::try_from_bytes_mut(&mut unlocked_data)
+        .map_err(|_| ProgramError::InvalidAccountData)? = Lockup {
+        discriminator: Lockup::SPL_DISCRIMINATOR.into(),
+        amount,
+        authority: state.authority,
+        lockup_start_timestamp: state.lockup_start_timestamp,
+        lockup_end_timestamp: NonZeroU64::new(timestamp),
+        mint: state.mint,
+        pool: state.pool,
+        metadata: state.metadata,
+        proposed_authority: Pubkey::default(),
+        committed_end_timestamp: state.committed_end_timestamp,
+        vesting: VestingSchedule::default(),
+        withdrawn_amount: 0,
+        metadata_updated_timestamp: state.metadata_updated_timestamp,
+        flags: 0,
+        reward_per_token_checkpoint: lockup_pool_state.rewards.reward_per_token,
+        unclaimed_rewards: 0,
+        cooldown_end_timestamp,
+    };
+
+    Ok(())
+}
+
+/// Processes a
+/// [Relock](enum.PaladinLockupInstruction.html)
+/// instruction.
+fn process_relock(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+
+    let lockup_authority_info = next_account_info(accounts_iter)?;
+    let lockup_pool_info = next_account_info(accounts_iter)?;
+    let lockup_info = next_account_info(accounts_iter)?;
+
+    // Validate & deserialize the lockup pool.
+    assert_eq!(
+        lockup_pool_info.owner, program_id,
+        "lockup_pool invalid owner"
+    );
+    let mut lockup_pool_data = lockup_pool_info.data.borrow_mut();
+    assert_eq!(
+        &lockup_pool_data[0..8],
+        LockupPool::SPL_DISCRIMINATOR_SLICE,
+        "lockup_pool uninitialized"
+    );
+    let mut lockup_pool_state = LockupPool::unpack_mut(&mut lockup_pool_data)?;
+
+    // Ensure the pool is accepting lockups, as relocking starts a new one.
+    check_pool_not_paused(&lockup_pool_state, LockupPool::FLAG_LOCKUP_PAUSED)?;
+    if !lockup_pool_state.lockups_enabled() {
+        return Err(PaladinLockupError::LockupsDisabled.into());
+    }
+
+    // Ensure the lockup authority is a signer.
+    if !lockup_authority_info.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    // Ensure the lockup account is owned by the Paladin Lockup program.
+    if lockup_info.owner != program_id {
+        return Err(ProgramError::InvalidAccountOwner);
+    }
+
+    // Ensure the lockup account is initialized.
+    check_lockup_initialized(lockup_info)?;
+
+    let mut data = lockup_info.try_borrow_mut_data()?;
+    let state = bytemuck::try_from_bytes_mut::<Lockup>(&mut data)
+        .map_err(|_| ProgramError::InvalidAccountData)?;
+
+    // Ensure the provided authority is the same as the lockup's authority.
+    if state.authority != *lockup_authority_info.key {
+        return Err(ProgramError::IncorrectAuthority);
+    }
+
+    // Ensure the lockup account has been unlocked.
+    if state.lockup_end_timestamp.is_none() {
+        return Err(PaladinLockupError::LockupNotUnlocked.into());
+    }
+
+    // Ensure the lockup matches the pool.
+    if lockup_pool_info.key != &state.pool {
+        return Err(PaladinLockupError::IncorrectPool.into());
+    }
+
+    // The relocked amount must meet the pool's minimum, as for a new lockup.
+    if state.amount < lockup_pool_state.min_lockup_amount {
+        return Err(PaladinLockupError::AmountTooLow.into());
+    }
+
+    // Clear the end timestamp, cancelling the cooldown.
+    update_rewards(&mut lockup_pool_state)?;
+    state.accrue_rewards(lockup_pool_state.rewards.reward_per_token);
+    state.lockup_end_timestamp = None;
+    state.cooldown_end_timestamp = None;
+    lockup_pool_state.stats.record_relock(state.amount);
+
+    // Insert the entry, evicting the smallest lock if necessary. A lockup
+    // that doesn't outrank the smallest lock of a full pool stays out of it.
+    let in_pool = insert_lockup_entry(
+        program_id,
+        lockup_pool_info.key,
+        &mut lockup_pool_state,
+        accounts_iter.as_slice(),
+        LockupPoolEntry {
+            lockup: *lockup_info.key,
+            amount: state.amount,
+            metadata: state.metadata,
+        },
+    )?;
+    set_in_pool(state, in_pool);
+
+    Ok(())
+}
+
+/// Processes a
+/// [Withdraw](enum.PaladinLockupInstruction.html)
+/// instruction.
+fn process_withdraw(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+
+    let lockup_authority_info = next_account_info(accounts_iter)?;
+    let lamport_destination_info = next_account_info(accounts_iter)?;
+    let token_destination_info = next_account_info(accounts_iter)?;
+    let lockup_info = next_account_info(accounts_iter)?;
+    let lockup_pool_info = next_account_info(accounts_iter)?;
+    let escrow_authority_info = next_account_info(accounts_iter)?;
+    let escrow_token_account_info = next_account_info(accounts_iter)?;
+    let mint_info = next_account_info(accounts_iter)?;
+    let token_program_info = next_account_info(accounts_iter)?;
+    let reward_authority_info = next_account_info(accounts_iter)?;
+    let reward_vault_info = next_account_info(accounts_iter)?;
+
+    // Note that the token program's `TransferChecked` processor will assert the
+    // provided token account is for the provided mint.
+
+    // Ensure the lockup authority is a signer.
+    if !lockup_authority_info.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    // Ensure the lockup account is owned by the Paladin Lockup program.
+    if lockup_info.owner != program_id {
+        return Err(ProgramError::InvalidAccountOwner);
+    }
+
+    // Ensure the lockup account is initialized.
+    check_lockup_initialized(lockup_info)?;
+
+    let (
+        withdraw_amount,
+        reward_amount,
+        close_lockup,
+        isolated_escrow,
+        bump_seed,
+        reward_bump_seed,
+    ) = {
+        let mut data = lockup_info.try_borrow_mut_data()?;
+        let state = bytemuck::try_from_bytes_mut::<Lockup>(&mut data)
+            .map_err(|_| ProgramError::InvalidAccountData)?;
+
+        // Ensure the provided authority is the same as the lockup's authority.
+        if state.authority != *lockup_authority_info.key {
+            return Err(ProgramError::IncorrectAuthority);
+        }
+
+        // Ensure the provided mint is the same as the lockup's mint.
+        if state.mint != *mint_info.key {
+            return Err(PaladinLockupError::IncorrectMint.into());
+        }
+
+        // Ensure the lockup matches the pool.
+        if lockup_pool_info.key != &state.pool {
+            return Err(PaladinLockupError::IncorrectPool.into());
+        }
+
+        // Validate & deserialize the lockup pool.
+        assert_eq!(
+            lockup_pool_info.owner, program_id,
+            "lockup_pool invalid owner"
+        );
+        let mut lockup_pool_data = lockup_pool_info.data.borrow_mut();
+        assert_eq!(
+            &lockup_pool_data[0..8],
+            LockupPool::SPL_DISCRIMINATOR_SLICE,
+            "lockup_pool uninitialized"
+        );
+        let mut lockup_pool_state = LockupPool::unpack_mut(&mut lockup_pool_data)?;
+
+        let isolated_escrow = lockup_pool_state.has_isolated_escrow();
+        let bump_seed = check_escrow(
+            program_id,
+            lockup_pool_info.key,
+            &lockup_pool_state,
+            escrow_authority_info,
+            escrow_token_account_info,
+            mint_info.key,
+            token_program_info.key,
+        )?;
+
+        let reward_bump_seed = check_reward_vault(
+            program_id,
+            lockup_pool_info.key,
+            reward_authority_info,
+            reward_vault_info,
+            mint_info.key,
+            token_program_info.key,
+        )?;
+
+        // Ensure withdrawals are not paused.
+        check_pool_not_paused(&lockup_pool_state, LockupPool::FLAG_WITHDRAW_PAUSED)?;
+
+        let clock = <Clock as Sysvar>::get()?;
+        let timestamp = clock.unix_timestamp as u64;
+
+        if state.is_vesting() {
+            // Release whatever has vested but not yet been withdrawn.
+            let total_amount = state
+                .amount
+                .checked_add(state.withdrawn_amount)
+                .ok_or(ProgramError::ArithmeticOverflow)?;
+            let withdraw_amount = state
+                .vesting
+                .vested_amount(total_amount, timestamp)
+                .saturating_sub(state.withdrawn_amount);
+            if withdraw_amount == 0 {
+                return Err(PaladinLockupError::NothingVested.into());
+            }
+            let remaining_amount = state.amount.saturating_sub(withdraw_amount);
+            update_rewards(&mut lockup_pool_state)?;
+            state.accrue_rewards(lockup_pool_state.rewards.reward_per_token);
+
+            // Re-rank the lockup's pool entry, if it is still in the pool,
+            // dropping it from the pool if it no longer outranks the pool's
+            // smallest entry.
+            if let Some(entry) = remove_lockup_entry(
+                program_id,
+                lockup_pool_info.key,
+                &mut lockup_pool_state,
+                accounts_iter.as_slice(),
+                lockup_info.key,
+                state,
+            )? {
+                let in_pool = remaining_amount != 0
+                    && insert_lockup_entry(
+                        program_id,
+                        lockup_pool_info.key,
+                        &mut lockup_pool_state,
+                        accounts_iter.as_slice(),
+                        LockupPoolEntry {
+                            amount: remaining_amount,
+                            ..entry
+                        },
+                    )?;
+                if !in_pool {
+                    state.flags &= !Lockup::FLAG_IN_POOL;
+                }
+            }
+
+            state.amount = remaining_amount;
+            state.withdrawn_amount = total_amount.saturating_sub(remaining_amount);
+            lockup_pool_state
+                .stats
+                .record_vested_withdraw(withdraw_amount, u64::from(remaining_amount == 0));
+            let reward_amount = match remaining_amount {
+                0 => take_rewards(&mut lockup_pool_state, state),
+                _ => 0,
+            };
+
+            (
+                withdraw_amount,
+                reward_amount,
+                remaining_amount == 0,
+                isolated_escrow,
+                bump_seed,
+                reward_bump_seed,
+            )
+        } else {
+            // Ensure the lockup's cooldown, fixed when it was unlocked, has
+            // ended.
+            let unlock_timestamp = state
+                .cooldown_end_timestamp
+                .ok_or(PaladinLockupError::LockupActive)?
+                .get();
+            if unlock_timestamp > timestamp {
+                msg!(
+                    "Lockup has not ended yet. {} seconds remaining.",
+                    unlock_timestamp.saturating_sub(timestamp)
+                );
+                return Err(PaladinLockupError::LockupActive.into());
+            }
+
+            lockup_pool_state.stats.record_withdraw(state.amount);
+            state.accrue_rewards(lockup_pool_state.rewards.reward_per_token);
+            let reward_amount = take_rewards(&mut lockup_pool_state, state);
+
+            (
+                state.amount,
+                reward_amount,
+                true,
+                isolated_escrow,
+                bump_seed,
+                reward_bump_seed,
+            )
+        }
+    };
+
+    // Transfer the tokens to the depositor.
+    {
+        let bump_seed = [bump_seed];
+        let program_escrow_authority_signer_seeds =
+            collect_escrow_authority_signer_seeds(&bump_seed);
+        let pool_escrow_authority_signer_seeds =
+            collect_pool_escrow_authority_signer_seeds(lockup_pool_info.key, &bump_seed);
+        let escrow_authority_signer_seeds: &[&[u8]] = match isolated_escrow {
+            true => &pool_escrow_authority_signer_seeds,
+            false => &program_escrow_authority_signer_seeds,
+        };
+        check_token_program(token_program_info, mint_info)?;
+        let decimals = {
+            let mint_data = mint_info.try_borrow_data()?;
+            let mint = StateWithExtensions::<Mint>::unpack(&mint_data)?;
+            mint.base.decimals
+        };
+
+        spl_token_2022::onchain::invoke_transfer_checked(
+            token_program_info.key,
+            escrow_token_account_info.clone(),
+            mint_info.clone(),
+            token_destination_info.clone(),
+            escrow_authority_info.clone(),
+            accounts_iter.as_slice(),
+            withdraw_amount,
+            decimals,
+            &[escrow_authority_signer_seeds],
+        )?;
+
+        // Pay out the unclaimed rewards of a closed lockup.
+        if reward_amount > 0 {
+            let reward_bump_seed = [reward_bump_seed];
+            let reward_authority_signer_seeds =
+                collect_reward_authority_signer_seeds(lockup_pool_info.key, &reward_bump_seed);
+            spl_token_2022::onchain::invoke_transfer_checked(
+                token_program_info.key,
+                reward_vault_info.clone(),
+                mint_info.clone(),
+                token_destination_info.clone(),
+                reward_authority_info.clone(),
+                accounts_iter.as_slice(),
+                reward_amount,
+                decimals,
+                &[&reward_authority_signer_seeds],
+            )?;
+        }
+    }
+
+    // Close the lockup account once it has been fully withdrawn.
+    if close_lockup {
+        let new_destination_lamports = lockup_info
+            .lamports()
+            .checked_add(lamport_destination_info.lamports())
+            .ok_or(ProgramError::ArithmeticOverflow)?;
+
+        **lockup_info.try_borrow_mut_lamports()? = 0;
+        **lamport_destination_info.try_borrow_mut_lamports()? = new_destination_lamports;
+
+        lockup_info.realloc(0, true)?;
+        lockup_info.assign(&system_program::id());
+    }
+
+    Ok(())
+}
+
+/// Processes an
+/// [UpdateMetadata](enum.PaladinLockupInstruction.html)
+/// instruction.
+fn process_update_metadata(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    metadata: [u8; 32],
+) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+
+    let lockup_authority_info = next_account_info(accounts_iter)?;
+    let lockup_pool_info = next_account_info(accounts_iter)?;
+    let lockup_info = next_account_info(accounts_iter)?;
+
+    // Validate & deserialize the lockup pool.
+    assert_eq!(
+        lockup_pool_info.owner, program_id,
+        "lockup_pool invalid owner"
+    );
+    let mut lockup_pool_data = lockup_pool_info.data.borrow_mut();
+    assert_eq!(
+        &lockup_pool_data[0..8],
+        LockupPool::SPL_DISCRIMINATOR_SLICE,
+        "lockup_pool uninitialized"
+    );
+    let mut lockup_pool_state = LockupPool::unpack_mut(&mut lockup_pool_data)?;
+
+    // Ensure the lockup authority is a signer.
+    if !lockup_authority_info.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    // Ensure the lockup account is owned by the Paladin Lockup program.
+    if lockup_info.owner != program_id {
+        return Err(ProgramError::InvalidAccountOwner);
+    }
+
+    // Ensure the lockup account is initialized.
+    check_lockup_initialized(lockup_info)?;
+
+    let mut data = lockup_info.try_borrow_mut_data()?;
+    let state = bytemuck::try_from_bytes_mut::<Lockup>(&mut data)
+        .map_err(|_| ProgramError::InvalidAccountData)?;
+
+    // Ensure the provided authority is the same as the lockup's authority.
+    if state.authority != *lockup_authority_info.key {
+        return Err(ProgramError::IncorrectAuthority);
+    }
+
+    // Ensure the lockup matches the pool.
+    if lockup_pool_info.key != &state.pool {
+        return Err(PaladinLockupError::IncorrectPool.into());
+    }
+
+    // Ensure the pool's update interval has passed since the last update.
+    let clock = <Clock as Sysvar>::get()?;
+    let timestamp = clock.unix_timestamp as u64;
+    if state.metadata_updated_timestamp != 0 {
+        let next_update_timestamp = state
+            .metadata_updated_timestamp
+            .saturating_add(lockup_pool_state.metadata_update_interval_seconds);
+        if next_update_timestamp > timestamp {
+            msg!(
+                "Metadata updated too recently. {} seconds remaining.",
+                next_update_timestamp.saturating_sub(timestamp)
+            );
+            return Err(PaladinLockupError::MetadataUpdateTooSoon.into());
+        }
+    }
+
+    // Update the pool entry, if the lockup is still in the pool.
+    if state.in_pool() {
+        with_pool_entries(
+            program_id,
+            lockup_pool_info.key,
+            &mut lockup_pool_state,
+            accounts_iter.as_slice(),
+            state.amount,
+            |entries| {
+                if let Some(index) = entries.find_entry(lockup_info.key, state.amount) {
+                    entries.entries[index].metadata = metadata;
+                }
+                Ok(())
+            },
+        )?;
+    }
+
+    state.metadata = metadata;
+    state.metadata_updated_timestamp = timestamp;
+
+    Ok(())
+}
+
+/// Processes a
+/// [ProposeAuthority](enum.PaladinLockupInstruction.html)
+/// instruction.
+fn process_propose_authority(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+
+    let lockup_authority_info = next_account_info(accounts_iter)?;
+    let lockup_info = next_account_info(accounts_iter)?;
+    let proposed_authority_info = next_account_info(accounts_iter)?;
+
+    // Ensure the lockup authority is a signer.
+    if !lockup_authority_info.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    // Ensure the lockup account is owned by the Paladin Lockup program.
+    if lockup_info.owner != program_id {
+        return Err(ProgramError::InvalidAccountOwner);
+    }
+
+    // Ensure the lockup account is initialized.
+    check_lockup_initialized(lockup_info)?;
+
+    let mut data = lockup_info.try_borrow_mut_data()?;
+    let state = bytemuck::try_from_bytes_mut::<Lockup>(&mut data)
+        .map_err(|_| ProgramError::InvalidAccountData)?;
+
+    // Ensure the provided authority is the same as the lockup's authority.
+    if state.authority != *lockup_authority_info.key {
+        return Err(ProgramError::IncorrectAuthority);
+    }
+
+    // Record the proposed authority, proposing the current authority cancels
+    // any pending transfer.
+    state.proposed_authority = match proposed_authority_info.key == &state.authority {
+        true => Pubkey::default(),
+        false => *proposed_authority_info.key,
+    };
+
+    Ok(())
+}
+
+/// Processes an
+/// [AcceptAuthority](enum.PaladinLockupInstruction.html)
+/// instruction.
+fn process_accept_authority(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+
+    let proposed_authority_info = next_account_info(accounts_iter)?;
+    let lockup_info = next_account_info(accounts_iter)?;
+
+    // Ensure the proposed authority is a signer.
+    if !proposed_authority_info.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    // Ensure the lockup account is owned by the Paladin Lockup program.
+    if lockup_info.owner != program_id {
+        return Err(ProgramError::InvalidAccountOwner);
+    }
+
+    // Ensure the lockup account is initialized.
+    check_lockup_initialized(lockup_info)?;
+
+    let mut data = lockup_info.try_borrow_mut_data()?;
+    let state = bytemuck::try_from_bytes_mut::<Lockup>(&mut data)
+        .map_err(|_| ProgramError::InvalidAccountData)?;
+
+    // Ensure the signer is the pending proposed authority.
+    if state.proposed_authority == Pubkey::default()
+        || state.proposed_authority != *proposed_authority_info.key
+    {
+        return Err(ProgramError::IncorrectAuthority);
+    }
+
+    // Hand over the lockup.
+    state.authority = state.proposed_authority;
+    state.proposed_authority = Pubkey::default();
+
+    Ok(())
+}
+
+/// Processes a
+/// [MergeLockups](enum.PaladinLockupInstruction.html)
+/// instruction.
+fn process_merge_lockups(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+
+    let lockup_authority_info = next_account_info(accounts_iter)?;
+    let lamport_destination_info = next_account_info(accounts_iter)?;
+    let lockup_pool_info = next_account_info(accounts_iter)?;
+    let destination_lockup_info = next_account_info(accounts_iter)?;
+    let source_lockup_info = next_account_info(accounts_iter)?;
+
+    // Validate & deserialize the lockup pool.
+    assert_eq!(
+        lockup_pool_info.owner, program_id,
+        "lockup_pool invalid owner"
+    );
+    let mut lockup_pool_data = lockup_pool_info.data.borrow_mut();
+    assert_eq!(
+        &lockup_pool_data[0..8],
+        LockupPool::SPL_DISCRIMINATOR_SLICE,
+        "lockup_pool uninitialized"
+    );
+    let mut lockup_pool_state = LockupPool::unpack_mut(&mut lockup_pool_data)?;
+
+    // Ensure the lockup authority is a signer.
+    if !lockup_authority_info.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    // Ensure the lockups are distinct accounts.
+    if destination_lockup_info.key == source_lockup_info.key {
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    // Ensure the source's lamports don't go back to the account being closed.
+    if lamport_destination_info.key == source_lockup_info.key {
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    for lockup_info in [destination_lockup_info, source_lockup_info] {
+        // Ensure the lockup account is owned by the Paladin Lockup program.
+        if lockup_info.owner != program_id {
+            return Err(ProgramError::InvalidAccountOwner);
+        }
+
+        // Ensure the lockup account is initialized.
+        check_lockup_initialized(lockup_info)?;
+    }
+
+    {
+        let mut destination_data = destination_lockup_info.try_borrow_mut_data()?;
+        let destination = bytemuck::try_from_bytes_mut::<Lockup>(&mut destination_data)
+            .map_err(|_| ProgramError::InvalidAccountData)?;
+        let source_data = source_lockup_info.try_borrow_data()?;
+        let source = bytemuck::try_from_bytes::<Lockup>(&source_data)
+            .map_err(|_| ProgramError::InvalidAccountData)?;
+
+        // Ensure the provided authority is the same as both lockups' authority.
+        if destination.authority != *lockup_authority_info.key
+            || source.authority != *lockup_authority_info.key
+        {
+            return Err(ProgramError::IncorrectAuthority);
+        }
+
+        // Ensure both lockups are for the same mint.
+        if destination.mint != source.mint {
+            return Err(PaladinLockupError::IncorrectMint.into());
+        }
+
+        // Ensure both lockups match the pool.
+        if lockup_pool_info.key != &destination.pool || lockup_pool_info.key != &source.pool {
+            return Err(PaladinLockupError::IncorrectPool.into());
+        }
+
+        // Ensure neither lockup has been unlocked.
+        if destination.lockup_end_timestamp.is_some() || source.lockup_end_timestamp.is_some() {
+            return Err(PaladinLockupError::LockupAlreadyUnlocked.into());
+        }
+
+        // Ensure neither lockup is vesting.
+        if destination.is_vesting() || source.is_vesting() {
+            return Err(PaladinLockupError::LockupVesting.into());
+        }
+
+        let merged_amount = destination
+            .amount
+            .checked_add(source.amount)
+            .ok_or(ProgramError::ArithmeticOverflow)?;
+
+        // Replace both pool entries (if they exist) with the merged entry.
+        remove_lockup_entry(
+            program_id,
+            lockup_pool_info.key,
+            &mut lockup_pool_state,
+            accounts_iter.as_slice(),
+            source_lockup_info.key,
+            source,
+        )?;
+        remove_lockup_entry(
+            program_id,
+            lockup_pool_info.key,
+            &mut lockup_pool_state,
+            accounts_iter.as_slice(),
+            destination_lockup_info.key,
+            destination,
+        )?;
+        let in_pool = insert_lockup_entry(
+            program_id,
+            lockup_pool_info.key,
+            &mut lockup_pool_state,
+            accounts_iter.as_slice(),
+            LockupPoolEntry {
+                lockup: *destination_lockup_info.key,
+                amount: merged_amount,
+                metadata: destination.metadata,
+            },
+        )?;
+
+        // Carry the source's rewards over to the destination.
+        update_rewards(&mut lockup_pool_state)?;
+        let reward_per_token = lockup_pool_state.rewards.reward_per_token;
+        destination.accrue_rewards(reward_per_token);
+        destination.unclaimed_rewards = destination
+            .unclaimed_rewards
+            .saturating_add(source.pending_rewards(reward_per_token));
+        destination.amount = merged_amount;
+        set_in_pool(destination, in_pool);
+        lockup_pool_state.stats.record_merge();
+        destination.lockup_start_timestamp = std::cmp::min(
+            destination.lockup_start_timestamp,
+            source.lockup_start_timestamp,
+        );
+        destination.committed_end_timestamp = std::cmp::max(
+            destination.committed_end_timestamp,
+            source.committed_end_timestamp,
+        );
+    }
+
+    // Close the source lockup, returning its rent lamports.
+    let new_destination_lamports = source_lockup_info
+        .lamports()
+        .checked_add(lamport_destination_info.lamports())
+        .ok_or(ProgramError::ArithmeticOverflow)?;
+
+    **source_lockup_info.try_borrow_mut_lamports()? = 0;
+    **lamport_destination_info.try_borrow_mut_lamports()? = new_destination_lamports;
+
+    source_lockup_info.realloc(0, true)?;
+    source_lockup_info.assign(&system_program::id());
+
+    Ok(())
+}
+
+/// Processes an
+/// [UpdatePoolConfig](enum.PaladinLockupInstruction.html)
+/// instruction.
+fn process_update_pool_config(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    min_lockup_amount: Option<u64>,
+    cooldown_seconds: Option<u64>,
+    metadata_update_interval_seconds: Option<u64>,
+    lockups_enabled: Option<bool>,
+    authority: Option<Pubkey>,
+) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+
+    let pool_authority_info = next_account_info(accounts_iter)?;
+    let lockup_pool_info = next_account_info(accounts_iter)?;
+
+    // Validate & deserialize the lockup pool.
+    assert_eq!(
+        lockup_pool_info.owner, program_id,
+        "lockup_pool invalid owner"
+    );
+    let mut lockup_pool_data = lockup_pool_info.data.borrow_mut();
+    assert_eq!(
+        &lockup_pool_data[0..8],
+        LockupPool::SPL_DISCRIMINATOR_SLICE,
+        "lockup_pool uninitialized"
+    );
+    let mut lockup_pool_state = LockupPool::unpack_mut(&mut lockup_pool_data)?;
+
+    // Ensure the pool authority is a signer.
+    if !pool_authority_info.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    // Ensure the pool has an authority & it matches the provided one.
+    if lockup_pool_state.authority == Pubkey::default()
+        || lockup_pool_state.authority != *pool_authority_info.key
+    {
+        return Err(ProgramError::IncorrectAuthority);
+    }
+
+    if let Some(min_lockup_amount) = min_lockup_amount {
+        lockup_pool_state.min_lockup_amount = min_lockup_amount;
+    }
+    if let Some(cooldown_seconds) = cooldown_seconds {
+        lockup_pool_state.cooldown_seconds = cooldown_seconds;
+    }
+    if let Some(metadata_update_interval_seconds) = metadata_update_interval_seconds {
+        lockup_pool_state.metadata_update_interval_seconds = metadata_update_interval_seconds;
+    }
+    if let Some(lockups_enabled) = lockups_enabled {
+        if lockups_enabled {
+            lockup_pool_state.flags &= !LockupPool::FLAG_LOCKUPS_DISABLED;
+        } else {
+            lockup_pool_state.flags |= LockupPool::FLAG_LOCKUPS_DISABLED;
+        }
+    }
+    if let Some(authority) = authority {
+        lockup_pool_state.authority = authority;
+    }
+
+    check_pool_config(
+        lockup_pool_state.cooldown_seconds,
+        lockup_pool_state.metadata_update_interval_seconds,
+    )
+}
+
+/// Processes a
+/// [SetPoolPaused](enum.PaladinLockupInstruction.html)
+/// instruction.
+fn process_set_pool_paused(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    lockups_paused: bool,
+    unlocks_paused: bool,
+    withdrawals_paused: bool,
+) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+
+    let pool_authority_info = next_account_info(accounts_iter)?;
+    let lockup_pool_info = next_account_info(accounts_iter)?;
+
+    // Validate & deserialize the lockup pool.
+    assert_eq!(
+        lockup_pool_info.owner, program_id,
+        "lockup_pool invalid owner"
+    );
+    let mut lockup_pool_data = lockup_pool_info.data.borrow_mut();
+    assert_eq!(
+        &lockup_pool_data[0..8],
+        LockupPool::SPL_DISCRIMINATOR_SLICE,
+        "lockup_pool uninitialized"
+    );
+    let mut lockup_pool_state = LockupPool::unpack_mut(&mut lockup_pool_data)?;
+
+    // Ensure the pool authority is a signer.
+    if !pool_authority_info.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    // Ensure the pool has an authority & it matches the provided one.
+    if lockup_pool_state.authority == Pubkey::default()
+        || lockup_pool_state.authority != *pool_authority_info.key
+    {
+        return Err(ProgramError::IncorrectAuthority);
+    }
+
+    // Replace all pause flags, leaving the other flags untouched.
+    let mut pause_flags = 0;
+    if lockups_paused {
+        pause_flags |= LockupPool::FLAG_LOCKUP_PAUSED;
+    }
+    if unlocks_paused {
+        pause_flags |= LockupPool::FLAG_UNLOCK_PAUSED;
+    }
+    if withdrawals_paused {
+        pause_flags |= LockupPool::FLAG_WITHDRAW_PAUSED;
+    }
+    lockup_pool_state.flags = (lockup_pool_state.flags & !LockupPool::PAUSE_FLAGS) | pause_flags;
+
+    Ok(())
+}
+
+/// Processes a
+/// [ReinsertLockup](enum.PaladinLockupInstruction.html)
+/// instruction.
+fn process_reinsert_lockup(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+
+    let lockup_pool_info = next_account_info(accounts_iter)?;
+    let lockup_info = next_account_info(accounts_iter)?;
+
+    // Validate & deserialize the lockup pool.
+    assert_eq!(
+        lockup_pool_info.owner, program_id,
+        "lockup_pool invalid owner"
+    );
+    let mut lockup_pool_data = lockup_pool_info.data.borrow_mut();
+    assert_eq!(
+        &lockup_pool_data[0..8],
+        LockupPool::SPL_DISCRIMINATOR_SLICE,
+        "lockup_pool uninitialized"
+    );
+    let mut lockup_pool_state = LockupPool::unpack_mut(&mut lockup_pool_data)?;
+
+    // Ensure the pool is accepting lockups, as reinserting adds one back.
+    check_pool_not_paused(&lockup_pool_state, LockupPool::FLAG_LOCKUP_PAUSED)?;
+    if !lockup_pool_state.lockups_enabled() {
+        return Err(PaladinLockupError::LockupsDisabled.into());
+    }
+
+    // Ensure the lockup account is owned by the Paladin Lockup program.
+    if lockup_info.owner != program_id {
+        return Err(ProgramError::InvalidAccountOwner);
+    }
+
+    // Ensure the lockup account is initialized.
+    check_lockup_initialized(lockup_info)?;
+
+    let mut data = lockup_info.try_borrow_mut_data()?;
+    let state = bytemuck::try_from_bytes_mut::<Lockup>(&mut data)
+        .map_err(|_| ProgramError::InvalidAccountData)?;
+
+    // Ensure the lockup matches the pool.
+    if lockup_pool_info.key != &state.pool {
+        return Err(PaladinLockupError::IncorrectPool.into());
+    }
+
+    // Ensure the lockup is still active.
+    if state.lockup_end_timestamp.is_some() {
+        return Err(PaladinLockupError::LockupAlreadyUnlocked.into());
+    }
+
+    // Ensure the lockup is not already in the pool.
+    if state.in_pool() {
+        return Err(PaladinLockupError::LockupAlreadyInPool.into());
+    }
+
+    // Insert the entry, evicting the smallest lock if necessary.
+    if !insert_lockup_entry(
+        program_id,
+        lockup_pool_info.key,
+        &mut lockup_pool_state,
+        accounts_iter.as_slice(),
+        LockupPoolEntry {
+            lockup: *lockup_info.key,
+            amount: state.amount,
+            metadata: state.metadata,
+        },
+    )? {
+        return Err(PaladinLockupError::AmountTooLow.into());
+    }
+    state.flags |= Lockup::FLAG_IN_POOL;
+
+    Ok(())
+}
+
+/// Processes a
+/// [ResizeLockupPool](enum.PaladinLockupInstruction.html)
+/// instruction.
+fn process_resize_lockup_pool(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    capacity: u64,
+) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+
+    let funder_info = next_account_info(accounts_iter)?;
+    let pool_authority_info = next_account_info(accounts_iter)?;
+    let lockup_pool_info = next_account_info(accounts_iter)?;
+    // NB: System program is just needed for CPIs.
+    let _ = next_account_info(accounts_iter)?;
+
+    // Validate & deserialize the lockup pool.
+    assert_eq!(
+        lockup_pool_info.owner, program_id,
+        "lockup_pool invalid owner"
+    );
+    let current_capacity = {
+        let mut lockup_pool_data = lockup_pool_info.data.borrow_mut();
+        assert_eq!(
+            &lockup_pool_data[0..8],
+            LockupPool::SPL_DISCRIMINATOR_SLICE,
+            "lockup_pool uninitialized"
+        );
+        let lockup_pool_state = LockupPool::unpack_mut(&mut lockup_pool_data)?;
+
+        // Pools without an authority can be grown by anyone, since the funder
+        // pays for the rent.
+        if lockup_pool_state.authority != Pubkey::default() {
+            // Ensure the pool authority is a signer.
+            if !pool_authority_info.is_signer {
+                return Err(ProgramError::MissingRequiredSignature);
+            }
+
+            // Ensure the pool authority matches the provided one.
+            if lockup_pool_state.authority != *pool_authority_info.key {
+                return Err(ProgramError::IncorrectAuthority);
+            }
+        }
+
+        lockup_pool_state.capacity()
+    };
+
+    // Ensure the pool is only grown, and not beyond the account size limit.
+    let capacity = usize::try_from(capacity)
+        .ok()
+        .filter(|capacity| (current_capacity..=LockupPool::MAX_CAPACITY).contains(capacity))
+        .ok_or(PaladinLockupError::InvalidPoolCapacity)?;
+    if capacity == current_capacity {
+        return Ok(());
+    }
+
+    // Grow towards the requested capacity by as many whole entries as a single
+    // instruction allows.
+    #[allow(clippy::arithmetic_side_effects)]
+    let step_capacity = MAX_PERMITTED_DATA_INCREASE / std::mem::size_of::<LockupPoolEntry>();
+    let new_capacity = capacity.min(current_capacity.saturating_add(step_capacity));
+    let new_len = LockupPool::account_len(new_capacity);
+
+    // Top up the pool's rent.
+    let required_lamports = Rent::get()?
+        .minimum_balance(new_len)
+        .saturating_sub(lockup_pool_info.lamports());
+    if required_lamports > 0 {
+        invoke(
+            &system_instruction::transfer(funder_info.key, lockup_pool_info.key, required_lamports),
+            &[funder_info.clone(), lockup_pool_info.clone()],
+        )?;
+    }
+
+    lockup_pool_info.realloc(new_len, true)?;
+
+    msg!(
+        "Lockup pool resized to {} of {} entries.",
+        new_capacity,
+        capacity
+    );
+
+    Ok(())
+}
+
+/// Processes an
+/// [AddLockupPoolShard](enum.PaladinLockupInstruction.html)
+/// instruction.
+fn process_add_lockup_pool_shard(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    max_amount: u64,
+) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+
+    let pool_authority_info = next_account_info(accounts_iter)?;
+    let lockup_pool_info = next_account_info(accounts_iter)?;
+    let shard_info = next_account_info(accounts_iter)?;
+    let last_shard_info = next_account_info(accounts_iter).ok();
+
+    // Validate & deserialize the lockup pool.
+    assert_eq!(
+        lockup_pool_info.owner, program_id,
+        "lockup_pool invalid owner"
+    );
+    let mut lockup_pool_data = lockup_pool_info.data.borrow_mut();
+    assert_eq!(
+        &lockup_pool_data[0..8],
+        LockupPool::SPL_DISCRIMINATOR_SLICE,
+        "lockup_pool uninitialized"
+    );
+    let mut lockup_pool_state = LockupPool::unpack_mut(&mut lockup_pool_data)?;
+
+    // Ensure the pool authority is a signer.
+    if !pool_authority_info.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    // Ensure the pool has an authority & it matches the provided one.
+    if lockup_pool_state.authority == Pubkey::default()
+        || lockup_pool_state.authority != *pool_authority_info.key
+    {
+        return Err(ProgramError::IncorrectAuthority);
+    }
+
+    // Ensure the pool has room for the shard, and that the shard covers the
+    // lowest amounts.
+    let shards_len = lockup_pool_state.shards_len;
+    let last_shard = lockup_pool_state.shards().last().copied();
+    if shards_len >= LockupPool::MAX_SHARDS
+        || max_amount == 0
+        || last_shard.is_some_and(|last_shard| max_amount >= last_shard.max_amount)
+    {
+        return Err(PaladinLockupError::InvalidShardBoundary.into());
+    }
+
+    // Ensure the shard account is owned by the Paladin Lockup program.
+    if shard_info.owner != program_id {
+        return Err(ProgramError::InvalidAccountOwner);
+    }
+
+    // Ensure the shard account is not initialized.
+    let mut shard_data = shard_info.try_borrow_mut_data()?;
+    if shard_data.get(0..8) != Some(ArrayDiscriminator::UNINITIALIZED.as_slice()) {
+        return Err(ProgramError::AccountAlreadyInitialized);
+    }
+
+    // Write the shard header.
+    let mut shard = LockupPoolShard::unpack_mut(&mut shard_data)?;
+    shard.header.discriminator = LockupPoolShard::SPL_DISCRIMINATOR.into();
+    shard.header.pool = *lockup_pool_info.key;
+
+    // Move the entries below the shard's boundary into the shard.
+    match last_shard {
+        None => move_entries(
+            &mut lockup_pool_state.entries_mut(),
+            &mut shard.entries_mut(),
+            max_amount,
+        )?,
+        Some(last_shard) => {
+            let last_shard_info = last_shard_info
+                .filter(|info| info.key == &last_shard.address)
+                .ok_or(PaladinLockupError::MissingLockupPoolShard)?;
+            let mut last_shard_data = last_shard_info.try_borrow_mut_data()?;
+            let mut last_shard = unpack_lockup_pool_shard(
+                program_id,
+                lockup_pool_info.key,
+                last_shard_info,
+                &mut last_shard_data,
+            )?;
+            move_entries(
+                &mut last_shard.entries_mut(),
+                &mut shard.entries_mut(),
+                max_amount,
+            )?;
+        }
+    }
+
+    lockup_pool_state.shards[shards_len] = LockupPoolShardInfo {
+        address: *shard_info.key,
+        max_amount,
+    };
+    lockup_pool_state.shards_len = shards_len.saturating_add(1);
+
+    Ok(())
+}
+
+/// Processes a
+/// [RebalanceLockupPoolShard](enum.PaladinLockupInstruction.html)
+/// instruction.
+fn process_rebalance_lockup_pool_shard(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    max_amount: u64,
+) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+
+    let pool_authority_info = next_account_info(accounts_iter)?;
+    let lockup_pool_info = next_account_info(accounts_iter)?;
+    let shard_info = next_account_info(accounts_iter)?;
+    let upper_shard_info = next_account_info(accounts_iter).ok();
+
+    // Validate & deserialize the lockup pool.
+    assert_eq!(
+        lockup_pool_info.owner, program_id,
+        "lockup_pool invalid owner"
+    );
+    let mut lockup_pool_data = lockup_pool_info.data.borrow_mut();
+    assert_eq!(
+        &lockup_pool_data[0..8],
+        LockupPool::SPL_DISCRIMINATOR_SLICE,
+        "lockup_pool uninitialized"
+    );
+    let mut lockup_pool_state = LockupPool::unpack_mut(&mut lockup_pool_data)?;
+
+    // Ensure the pool authority is a signer.
+    if !pool_authority_info.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    // Ensure the pool has an authority & it matches the provided one.
+    if lockup_pool_state.authority == Pubkey::default()
+        || lockup_pool_state.authority != *pool_authority_info.key
+    {
+        return Err(ProgramError::IncorrectAuthority);
+    }
+
+    // Ensure the shard belongs to the pool.
+    let shards = lockup_pool_state.shards();
+    let index = shards
+        .iter()
+        .position(|shard| &shard.address == shard_info.key)
+        .ok_or(PaladinLockupError::IncorrectLockupPoolShard)?;
+
+    // Ensure the new boundary stays between those of the neighbouring shards.
+    let min_amount = shards
+        .get(index.saturating_add(1))
+        .map_or(0, |shard| shard.max_amount);
+    let upper_shard = index.checked_sub(1).map(|index| shards[index]);
+    if max_amount <= min_amount
+        || upper_shard.is_some_and(|upper_shard| max_amount >= upper_shard.max_amount)
+    {
+        return Err(PaladinLockupError::InvalidShardBoundary.into());
+    }
+
+    // Move the entries across the new boundary.
+    let mut shard_data = shard_info.try_borrow_mut_data()?;
+    let mut shard = unpack_lockup_pool_shard(
+        program_id,
+        lockup_pool_info.key,
+        shard_info,
+        &mut shard_data,
+    )?;
+    match upper_shard {
+        None => move_entries(
+            &mut lockup_pool_state.entries_mut(),
+            &mut shard.entries_mut(),
+            max_amount,
+        )?,
+        Some(upper_shard) => {
+            let upper_shard_info = upper_shard_info
+                .filter(|info| info.key == &upper_shard.address)
+                .ok_or(PaladinLockupError::MissingLockupPoolShard)?;
+            let mut upper_shard_data = upper_shard_info.try_borrow_mut_data()?;
+            let mut upper_shard = unpack_lockup_pool_shard(
+                program_id,
+                lockup_pool_info.key,
+                upper_shard_info,
+                &mut upper_shard_data,
+            )?;
+            move_entries(
+                &mut upper_shard.entries_mut(),
+                &mut shard.entries_mut(),
+                max_amount,
+            )?;
+        }
+    }
+
+    lockup_pool_state.shards[index].max_amount = max_amount;
+
+    Ok(())
+}
+
+/// Processes a
+/// [SnapshotPool](enum.PaladinLockupInstruction.html)
+/// instruction.
+fn process_snapshot_pool(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+
+    let payer_info = next_account_info(accounts_iter)?;
+    let lockup_pool_info = next_account_info(accounts_iter)?;
+    let snapshot_info = next_account_info(accounts_iter)?;
+    // NB: System program is just needed for CPIs.
+    let _ = next_account_info(accounts_iter)?;
+
+    // Validate & deserialize the lockup pool.
+    assert_eq!(
+        lockup_pool_info.owner, program_id,
+        "lockup_pool invalid owner"
+    );
+    let lockup_pool_data = lockup_pool_info.try_borrow_data()?;
+    assert_eq!(
+        &lockup_pool_data[0..8],
+        LockupPool::SPL_DISCRIMINATOR_SLICE,
+        "lockup_pool uninitialized"
+    );
+    let (lockup_pool_state, pool_entries) = LockupPool::unpack(&lockup_pool_data)?;
+
+    let shard_datas = borrow_lockup_pool_shards(program_id, lockup_pool_state, accounts_iter)?;
+    let segments = collect_lockup_pool_entries(
+        lockup_pool_info.key,
+        lockup_pool_state,
+        pool_entries,
+        &shard_datas,
+    )?;
+    let entries_len = segments.iter().map(|segment| segment.len()).sum();
+
+    // Ensure the provided snapshot address is correct.
+    let clock = <Clock as Sysvar>::get()?;
+    let (snapshot_address, bump_seed) = get_lockup_pool_snapshot_address_and_bump_seed(
+        lockup_pool_info.key,
+        clock.epoch,
+        program_id,
+    );
+    if snapshot_info.key != &snapshot_address {
+        return Err(PaladinLockupError::IncorrectLockupPoolSnapshotAddress.into());
+    }
+
+    // Fund the snapshot for its full size, topping up in case the pool grew
+    // since a previous instruction.
+    let snapshot_len = LockupPoolSnapshot::account_len(entries_len);
+    let required_lamports = Rent::get()?
+        .minimum_balance(snapshot_len)
+        .saturating_sub(snapshot_info.lamports());
+    if required_lamports > 0 {
+        invoke(
+            &system_instruction::transfer(payer_info.key, snapshot_info.key, required_lamports),
+            &[payer_info.clone(), snapshot_info.clone()],
+        )?;
+    }
+
+    if snapshot_info.owner == &system_program::ID {
+        // Allocate as much as a single instruction allows & take ownership.
+        let epoch = clock.epoch.to_le_bytes();
+        let bump_seed = [bump_seed];
+        let snapshot_signer_seeds =
+            collect_lockup_pool_snapshot_signer_seeds(lockup_pool_info.key, &epoch, &bump_seed);
+        invoke_signed(
+            &system_instruction::allocate(
+                snapshot_info.key,
+                snapshot_len.min(MAX_PERMITTED_DATA_INCREASE) as u64,
+            ),
+            std::slice::from_ref(snapshot_info),
+            &[&snapshot_signer_seeds],
+        )?;
+        invoke_signed(
+            &system_instruction::assign(snapshot_info.key, program_id),
+            std::slice::from_ref(snapshot_info),
+            &[&snapshot_signer_seeds],
+        )?;
+
+        // Record who started the snapshot, leaving it uninitialized, so that
+        // they can close it even if it is never completed.
+        let mut snapshot_data = snapshot_info.try_borrow_mut_data()?;
+        let snapshot = bytemuck::try_from_bytes_mut::<LockupPoolSnapshot>(
+            &mut snapshot_data[..LockupPoolSnapshot::LEN],
+        )
+        .map_err(|_| ProgramError::InvalidAccountData)?;
+        snapshot.pool = *lockup_pool_info.key;
+        snapshot.rent_payer = *payer_info.key;
+        snapshot.epoch = clock.epoch;
+    } else {
+        // Ensure the snapshot has not been taken yet.
+        if snapshot_info.owner != program_id {
+            return Err(ProgramError::InvalidAccountOwner);
+        }
+        if snapshot_info.try_borrow_data()?.get(0..8)
+            != Some(ArrayDiscriminator::UNINITIALIZED.as_slice())
+        {
+            return Err(ProgramError::AccountAlreadyInitialized);
+        }
+
+        // Resize the account towards the snapshot's size, which may also have
+        // shrunk since a previous instruction.
+        let data_len = snapshot_info.data_len();
+        if data_len != snapshot_len {
+            let new_len = data_len
+                .saturating_add(MAX_PERMITTED_DATA_INCREASE)
+                .min(snapshot_len);
+            snapshot_info.realloc(new_len, true)?;
+        }
+    }
+
+    // Wait for subsequent instructions to finish growing the account.
+    if snapshot_info.data_len() < snapshot_len {
+        msg!(
+            "Lockup pool snapshot allocated {} of {} bytes.",
+            snapshot_info.data_len(),
+            snapshot_len
+        );
+        return Ok(());
+    }
+
+    // Take the snapshot.
+    let mut snapshot_data = snapshot_info.try_borrow_mut_data()?;
+    let (snapshot, snapshot_entries) = LockupPoolSnapshot::unpack_mut(&mut snapshot_data)?;
+    *snapshot = LockupPoolSnapshot {
+        discriminator: LockupPoolSnapshot::SPL_DISCRIMINATOR.into(),
+        pool: *lockup_pool_info.key,
+        rent_payer: snapshot.rent_payer,
+        slot: clock.slot,
+        epoch: clock.epoch,
+        timestamp: clock.unix_timestamp as u64,
+        entries_len,
+    };
+    for (entry, source) in snapshot_entries
+        .iter_mut()
+        .zip(segments.into_iter().flatten())
+    {
+        *entry = *source;
+    }
+
+    Ok(())
+}
+
+/// Processes a
+/// [CloseSnapshot](enum.PaladinLockupInstruction.html)
+/// instruction.
+fn process_close_snapshot(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+
+    let rent_payer_info = next_account_info(accounts_iter)?;
+    let lamport_destination_info = next_account_info(accounts_iter)?;
+    let snapshot_info = next_account_info(accounts_iter)?;
+
+    // Ensure the rent payer is a signer.
+    if !rent_payer_info.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    // Ensure the snapshot account is owned by the Paladin Lockup program.
+    if snapshot_info.owner != program_id {
+        return Err(ProgramError::InvalidAccountOwner);
+    }
+
+    {
+        // Ensure the snapshot has been started. An incomplete snapshot only
+        // has its pool, rent payer & epoch recorded, so its address must be
+        // checked against them.
+        let snapshot_data = snapshot_info.try_borrow_data()?;
+        let snapshot = snapshot_data
+            .get(..LockupPoolSnapshot::LEN)
+            .and_then(|data| bytemuck::try_from_bytes::<LockupPoolSnapshot>(data).ok())
+            .ok_or(ProgramError::UninitializedAccount)?;
+        if snapshot.discriminator == ArrayDiscriminator::UNINITIALIZED.as_slice() {
+            if snapshot_info.key
+                != &get_lockup_pool_snapshot_address(&snapshot.pool, snapshot.epoch, program_id)
+            {
+                return Err(ProgramError::UninitializedAccount);
+            }
+        } else if snapshot.discriminator != LockupPoolSnapshot::SPL_DISCRIMINATOR_SLICE {
+            return Err(ProgramError::UninitializedAccount);
+        }
+
+        // Ensure the provided rent payer is the snapshot's rent payer.
+        if snapshot.rent_payer != *rent_payer_info.key {
+            return Err(ProgramError::IncorrectAuthority);
+        }
+
+        // Ensure the snapshot's epoch has ended, so that it cannot be closed
+        // and retaken with different entries.
+        if Clock::get()?.epoch <= snapshot.epoch {
+            return Err(PaladinLockupError::SnapshotEpochActive.into());
+        }
+    }
+
+    // Close the snapshot, returning its rent lamports.
+    let new_destination_lamports = snapshot_info
+        .lamports()
+        .checked_add(lamport_destination_info.lamports())
+        .ok_or(ProgramError::ArithmeticOverflow)?;
+
+    **snapshot_info.try_borrow_mut_lamports()? = 0;
+    **lamport_destination_info.try_borrow_mut_lamports()? = new_destination_lamports;
+
+    snapshot_info.realloc(0, true)?;
+    snapshot_info.assign(&system_program::id());
+
+    Ok(())
+}
+
+/// Ensures the provided token program is either SPL Token or Token-2022, and is
+/// the one owning the provided mint.
+fn check_token_program(token_program_info: &AccountInfo, mint_info: &AccountInfo) -> ProgramResult {
+    spl_token_2022::check_spl_token_program_account(token_program_info.key)?;
+    if mint_info.owner != token_program_info.key {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+
+    Ok(())
+}
+
+/// Returns the amount received when transferring the provided amount of the
+/// mint's tokens, net of the current epoch's transfer fee, if any.
+fn net_transfer_amount(mint_info: &AccountInfo, amount: u64) -> Result<u64, ProgramError> {
+    let mint_data = mint_info.try_borrow_data()?;
+    let mint = StateWithExtensions::<Mint>::unpack(&mint_data)?;
+    let fee = match mint.get_extension::<TransferFeeConfig>() {
+        Ok(transfer_fee_config) => transfer_fee_config
+            .calculate_epoch_fee(Clock::get()?.epoch, amount)
+            .ok_or(ProgramError::ArithmeticOverflow)?,
+        Err(_) => 0,
+    };
+
+    amount
+        .checked_sub(fee)
+        .ok_or(ProgramError::ArithmeticOverflow)
+}
+
+/// Ensures the provided escrow authority & token account are the ones holding
+/// the pool's tokens, returning the escrow authority's bump seed.
+fn check_escrow(
+    program_id: &Pubkey,
+    lockup_pool: &Pubkey,
+    lockup_pool_state: &LockupPool,
+    escrow_authority_info: &AccountInfo,
+    escrow_token_account_info: &AccountInfo,
+    mint: &Pubkey,
+    token_program_id: &Pubkey,
+) -> Result<u8, ProgramError> {
+    // Ensure the provided escrow authority address is correct.
+    let (escrow_authority_address, bump_seed) =
+        lockup_pool_state.escrow_authority_address_and_bump_seed(lockup_pool, program_id);
+    if escrow_authority_info.key != &escrow_authority_address {
+        return Err(PaladinLockupError::IncorrectEscrowAuthorityAddress.into());
+    }
+
+    // Ensure the provided escrow token account address is correct.
+    if escrow_token_account_info.key
+        != &get_associated_token_address_with_program_id(
+            escrow_authority_info.key,
+            mint,
+            token_program_id,
+        )
+    {
+        return Err(PaladinLockupError::IncorrectEscrowTokenAccount.into());
+    }
+
+    Ok(bump_seed)
+}
+
+/// Ensures the provided reward authority & vault are the pool's.
+fn check_reward_vault(
+    program_id: &Pubkey,
+    lockup_pool: &Pubkey,
+    reward_authority_info: &AccountInfo,
+    reward_vault_info: &AccountInfo,
+    mint: &Pubkey,
+    token_program_id: &Pubkey,
+) -> Result<u8, ProgramError> {
+    let (reward_authority_address, bump_seed) =
+        get_reward_authority_address_and_bump_seed(lockup_pool, program_id);
+    if reward_authority_info.key != &reward_authority_address {
+        return Err(PaladinLockupError::IncorrectRewardAuthorityAddress.into());
+    }
+    if reward_vault_info.key
+        != &get_associated_token_address_with_program_id(
+            reward_authority_info.key,
+            mint,
+            token_program_id,
+        )
+    {
+        return Err(PaladinLockupError::IncorrectRewardVault.into());
+    }
+
+    Ok(bump_seed)
+}
+
+/// Processes a
+/// [DepositRewards](enum.PaladinLockupInstruction.html)
+/// instruction.
+fn process_deposit_rewards(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    amount: u64,
+) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+
+    let depositor_info = next_account_info(accounts_iter)?;
+    let depositor_token_account_info = next_account_info(accounts_iter)?;
+    let lockup_pool_info = next_account_info(accounts_iter)?;
+    let reward_authority_info = next_account_info(accounts_iter)?;
+    let reward_vault_info = next_account_info(accounts_iter)?;
+    let mint_info = next_account_info(accounts_iter)?;
+    let token_program_info = next_account_info(accounts_iter)?;
+
+    {
+        // Validate & deserialize the lockup pool.
+        assert_eq!(
+            lockup_pool_info.owner, program_id,
+            "lockup_pool invalid owner"
+        );
+        let mut lockup_pool_data = lockup_pool_info.data.borrow_mut();
+        assert_eq!(
+            &lockup_pool_data[0..8],
+            LockupPool::SPL_DISCRIMINATOR_SLICE,
+            "lockup_pool uninitialized"
+        );
+        let mut lockup_pool_state = LockupPool::unpack_mut(&mut lockup_pool_data)?;
+
+        // Ensure the pool is accepting tokens, like for new lockups.
+        check_pool_not_paused(&lockup_pool_state, LockupPool::FLAG_LOCKUP_PAUSED)?;
+
+        // Ensure the provided mint is the pool's mint.
+        if &lockup_pool_state.mint != mint_info.key {
+            return Err(PaladinLockupError::IncorrectMint.into());
+        }
+
+        check_reward_vault(
+            program_id,
+            lockup_pool_info.key,
+            reward_authority_info,
+            reward_vault_info,
+            mint_info.key,
+            token_program_info.key,
+        )?;
+
+        // Stream the rewards to the pool's active lockups, starting now.
+        if lockup_pool_state.stats.active_amount == 0 {
+            return Err(PaladinLockupError::NoActiveLockups.into());
+        }
+        let received_amount = net_transfer_amount(mint_info, amount)?;
+        update_rewards(&mut lockup_pool_state)?;
+        let timestamp = Clock::get()?.unix_timestamp as u64;
+        lockup_pool_state
+            .rewards
+            .deposit(received_amount, timestamp);
+    }
+
+    // Transfer the rewards to the reward vault.
+    {
+        check_token_program(token_program_info, mint_info)?;
+        let decimals = {
+            let mint_data = mint_info.try_borrow_data()?;
+            let mint = StateWithExtensions::<Mint>::unpack(&mint_data)?;
+            mint.base.decimals
+        };
 
-        lockup_pool_state.entries[index] = LockupPoolEntry::default();
-        lockup_pool_state.entries[index..].rotate_left(1);
-        lockup_pool_state.entries_len = lockup_pool_state.entries_len.checked_sub(1).unwrap();
+        spl_token_2022::onchain::invoke_transfer_checked(
+            token_program_info.key,
+            depositor_token_account_info.clone(),
+            mint_info.clone(),
+            reward_vault_info.clone(),
+            depositor_info.clone(),
+            accounts_iter.as_slice(),
+            amount,
+            decimals,
+            &[],
+        )?;
     }
 
     Ok(())
 }
 
 /// Processes a
-/// [Withdraw](enum.PaladinLockupInstruction.html)
+/// [ClaimRewards](enum.PaladinLockupInstruction.html)
 /// instruction.
-fn process_withdraw(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+fn process_claim_rewards(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
     let accounts_iter = &mut accounts.iter();
 
     let lockup_authority_info = next_account_info(accounts_iter)?;
-    let lamport_destination_info = next_account_info(accounts_iter)?;
     let token_destination_info = next_account_info(accounts_iter)?;
+    let lockup_pool_info = next_account_info(accounts_iter)?;
     let lockup_info = next_account_info(accounts_iter)?;
-    let escrow_authority_info = next_account_info(accounts_iter)?;
-    let escrow_token_account_info = next_account_info(accounts_iter)?;
+    let reward_authority_info = next_account_info(accounts_iter)?;
+    let reward_vault_info = next_account_info(accounts_iter)?;
     let mint_info = next_account_info(accounts_iter)?;
-    // NB: Token program is just needed for CPIs.
-    let _ = next_account_info(accounts_iter)?;
-
-    // Note that Token-2022's `TransferChecked` processor will assert the
-    // provided token account is for the provided mint.
+    let token_program_info = next_account_info(accounts_iter)?;
 
     // Ensure the lockup authority is a signer.
     if !lockup_authority_info.is_signer {
@@ -325,33 +2704,11 @@ fn process_withdraw(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramRes
     }
 
     // Ensure the lockup account is initialized.
-    if lockup_info.data_len() != std::mem::size_of::<Lockup>()
-        || &lockup_info.try_borrow_data()?[0..8] != Lockup::SPL_DISCRIMINATOR_SLICE
-    {
-        return Err(ProgramError::UninitializedAccount);
-    }
-
-    // Ensure the provided escrow authority address is correct.
-    let (escrow_authority_address, bump_seed) =
-        get_escrow_authority_address_and_bump_seed(program_id);
-    if escrow_authority_info.key != &escrow_authority_address {
-        return Err(PaladinLockupError::IncorrectEscrowAuthorityAddress.into());
-    }
-
-    // Ensure the provided escrow token account address is correct.
-    if escrow_token_account_info.key
-        != &get_associated_token_address_with_program_id(
-            escrow_authority_info.key,
-            mint_info.key,
-            &spl_token_2022::ID,
-        )
-    {
-        return Err(PaladinLockupError::IncorrectEscrowTokenAccount.into());
-    }
+    check_lockup_initialized(lockup_info)?;
 
-    let withdraw_amount = {
-        let data = lockup_info.try_borrow_data()?;
-        let state = bytemuck::try_from_bytes::<Lockup>(&data)
+    let (claim_amount, bump_seed) = {
+        let mut data = lockup_info.try_borrow_mut_data()?;
+        let state = bytemuck::try_from_bytes_mut::<Lockup>(&mut data)
             .map_err(|_| ProgramError::InvalidAccountData)?;
 
         // Ensure the provided authority is the same as the lockup's authority.
@@ -364,29 +2721,58 @@ fn process_withdraw(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramRes
             return Err(PaladinLockupError::IncorrectMint.into());
         }
 
-        // Ensure the lockup has ended.
-        let clock = <Clock as Sysvar>::get()?;
-        let timestamp = clock.unix_timestamp as u64;
-        let unlock_timestamp = state
-            .lockup_end_timestamp
-            .ok_or(PaladinLockupError::LockupActive)?
-            .get()
-            .saturating_add(LOCKUP_COOLDOWN_SECONDS);
-        if unlock_timestamp > timestamp {
-            msg!(
-                "Lockup has not ended yet. {} seconds remaining.",
-                unlock_timestamp.saturating_sub(timestamp)
-            );
-            return Err(PaladinLockupError::LockupActive.into());
+        // Ensure the lockup matches the pool.
+        if lockup_pool_info.key != &state.pool {
+            return Err(PaladinLockupError::IncorrectPool.into());
+        }
+
+        // Validate & deserialize the lockup pool.
+        assert_eq!(
+            lockup_pool_info.owner, program_id,
+            "lockup_pool invalid owner"
+        );
+        let mut lockup_pool_data = lockup_pool_info.data.borrow_mut();
+        assert_eq!(
+            &lockup_pool_data[0..8],
+            LockupPool::SPL_DISCRIMINATOR_SLICE,
+            "lockup_pool uninitialized"
+        );
+        let mut lockup_pool_state = LockupPool::unpack_mut(&mut lockup_pool_data)?;
+
+        // Ensure withdrawals are not paused, as claiming moves tokens out.
+        check_pool_not_paused(&lockup_pool_state, LockupPool::FLAG_WITHDRAW_PAUSED)?;
+
+        let bump_seed = check_reward_vault(
+            program_id,
+            lockup_pool_info.key,
+            reward_authority_info,
+            reward_vault_info,
+            mint_info.key,
+            token_program_info.key,
+        )?;
+
+        // Settle the lockup's rewards.
+        update_rewards(&mut lockup_pool_state)?;
+        state.accrue_rewards(lockup_pool_state.rewards.reward_per_token);
+        let claim_amount = state.unclaimed_rewards;
+        if claim_amount == 0 {
+            return Err(PaladinLockupError::NoRewards.into());
         }
+        state.unclaimed_rewards = 0;
+        lockup_pool_state.rewards.claimed_amount = lockup_pool_state
+            .rewards
+            .claimed_amount
+            .saturating_add(claim_amount);
 
-        state.amount
+        (claim_amount, bump_seed)
     };
 
-    // Transfer the tokens to the depositor.
+    // Transfer the rewards to the lockup authority.
     {
         let bump_seed = [bump_seed];
-        let escrow_authority_signer_seeds = collect_escrow_authority_signer_seeds(&bump_seed);
+        let reward_authority_signer_seeds =
+            collect_reward_authority_signer_seeds(lockup_pool_info.key, &bump_seed);
+        check_token_program(token_program_info, mint_info)?;
         let decimals = {
             let mint_data = mint_info.try_borrow_data()?;
             let mint = StateWithExtensions::<Mint>::unpack(&mint_data)?;
@@ -394,28 +2780,276 @@ fn process_withdraw(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramRes
         };
 
         spl_token_2022::onchain::invoke_transfer_checked(
-            &spl_token_2022::id(),
-            escrow_token_account_info.clone(),
+            token_program_info.key,
+            reward_vault_info.clone(),
             mint_info.clone(),
             token_destination_info.clone(),
-            escrow_authority_info.clone(),
+            reward_authority_info.clone(),
             accounts_iter.as_slice(),
-            withdraw_amount,
+            claim_amount,
             decimals,
-            &[&escrow_authority_signer_seeds],
+            &[&reward_authority_signer_seeds],
         )?;
     }
 
-    let new_destination_lamports = lockup_info
-        .lamports()
-        .checked_add(lamport_destination_info.lamports())
-        .ok_or(ProgramError::ArithmeticOverflow)?;
+    Ok(())
+}
 
-    **lockup_info.try_borrow_mut_lamports()? = 0;
-    **lamport_destination_info.try_borrow_mut_lamports()? = new_destination_lamports;
+/// Processes an
+/// [UpdatePoolRoot](enum.PaladinLockupInstruction.html)
+/// instruction.
+fn process_update_pool_root(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+
+    let payer_info = next_account_info(accounts_iter)?;
+    let lockup_pool_info = next_account_info(accounts_iter)?;
+    let root_info = next_account_info(accounts_iter)?;
+    // NB: System program is just needed for CPIs.
+    let _ = next_account_info(accounts_iter)?;
+
+    // Validate & deserialize the lockup pool.
+    assert_eq!(
+        lockup_pool_info.owner, program_id,
+        "lockup_pool invalid owner"
+    );
+    let lockup_pool_data = lockup_pool_info.try_borrow_data()?;
+    assert_eq!(
+        &lockup_pool_data[0..8],
+        LockupPool::SPL_DISCRIMINATOR_SLICE,
+        "lockup_pool uninitialized"
+    );
+    let (lockup_pool_state, pool_entries) = LockupPool::unpack(&lockup_pool_data)?;
+
+    let shard_datas = borrow_lockup_pool_shards(program_id, lockup_pool_state, accounts_iter)?;
+    let segments = collect_lockup_pool_entries(
+        lockup_pool_info.key,
+        lockup_pool_state,
+        pool_entries,
+        &shard_datas,
+    )?;
+
+    // Ensure the provided root address is correct.
+    let (root_address, bump_seed) =
+        get_lockup_pool_root_address_and_bump_seed(lockup_pool_info.key, program_id);
+    if root_info.key != &root_address {
+        return Err(PaladinLockupError::IncorrectLockupPoolRootAddress.into());
+    }
+
+    // Create the root account on first use.
+    if root_info.owner == &system_program::ID {
+        let bump_seed = [bump_seed];
+        let root_signer_seeds =
+            collect_lockup_pool_root_signer_seeds(lockup_pool_info.key, &bump_seed);
+        let required_lamports = Rent::get()?
+            .minimum_balance(LockupPoolRoot::LEN)
+            .saturating_sub(root_info.lamports());
+
+        // Top up rather than create, in case the address was pre-funded.
+        if required_lamports > 0 {
+            invoke(
+                &system_instruction::transfer(payer_info.key, root_info.key, required_lamports),
+                &[payer_info.clone(), root_info.clone()],
+            )?;
+        }
+        invoke_signed(
+            &system_instruction::allocate(root_info.key, LockupPoolRoot::LEN as u64),
+            std::slice::from_ref(root_info),
+            &[&root_signer_seeds],
+        )?;
+        invoke_signed(
+            &system_instruction::assign(root_info.key, program_id),
+            std::slice::from_ref(root_info),
+            &[&root_signer_seeds],
+        )?;
+    } else if root_info.owner != program_id {
+        return Err(ProgramError::InvalidAccountOwner);
+    }
+
+    // Hash the entries, in descending order of amount.
+    let mut builder = MerkleRootBuilder::default();
+    let mut entries_len: usize = 0;
+    for entry in segments.into_iter().flatten() {
+        builder.push(entry);
+        entries_len = entries_len.saturating_add(1);
+    }
+
+    let clock = <Clock as Sysvar>::get()?;
+    let mut root_data = root_info.try_borrow_mut_data()?;
+    *bytemuck::try_from_bytes_mut(&mut root_data)
+        .map_err(|_| ProgramError::InvalidAccountData)? = LockupPoolRoot {
+        discriminator: LockupPoolRoot::SPL_DISCRIMINATOR.into(),
+        pool: *lockup_pool_info.key,
+        slot: clock.slot,
+        entries_len,
+        root: builder.finish(),
+    };
+
+    Ok(())
+}
+
+/// Processes a
+/// [MigrateLockup](enum.PaladinLockupInstruction.html)
+/// instruction.
+fn process_migrate_lockup(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+
+    let payer_info = next_account_info(accounts_iter)?;
+    let lockup_info = next_account_info(accounts_iter)?;
+    let lockup_pool_info = next_account_info(accounts_iter)?;
+    // NB: System program is just needed for CPIs.
+    let _ = next_account_info(accounts_iter)?;
+
+    // Validate & deserialize the lockup pool.
+    assert_eq!(
+        lockup_pool_info.owner, program_id,
+        "lockup_pool invalid owner"
+    );
+    let mut lockup_pool_data = lockup_pool_info.data.borrow_mut();
+    assert_eq!(
+        &lockup_pool_data[0..8],
+        LockupPool::SPL_DISCRIMINATOR_SLICE,
+        "lockup_pool uninitialized"
+    );
+    let mut lockup_pool_state = LockupPool::unpack_mut(&mut lockup_pool_data)?;
+
+    // Ensure the lockup account is owned by the Paladin Lockup program.
+    if lockup_info.owner != program_id {
+        return Err(ProgramError::InvalidAccountOwner);
+    }
+
+    // Ensure the lockup account is an initialized legacy lockup.
+    if lockup_info.data_len() != Lockup::LEGACY_LEN
+        || &lockup_info.try_borrow_data()?[0..8] != Lockup::SPL_DISCRIMINATOR_SLICE
+    {
+        return Err(ProgramError::InvalidAccountData);
+    }
+
+    // Top up the lockup's rent & grow it to the current layout, zeroing the
+    // new fields.
+    let required_lamports = Rent::get()?
+        .minimum_balance(Lockup::LEN)
+        .saturating_sub(lockup_info.lamports());
+    if required_lamports > 0 {
+        invoke(
+            &system_instruction::transfer(payer_info.key, lockup_info.key, required_lamports),
+            &[payer_info.clone(), lockup_info.clone()],
+        )?;
+    }
+    lockup_info.realloc(Lockup::LEN, true)?;
+
+    let mut data = lockup_info.try_borrow_mut_data()?;
+    let state = bytemuck::try_from_bytes_mut::<Lockup>(&mut data)
+        .map_err(|_| ProgramError::InvalidAccountData)?;
+
+    // Ensure the lockup matches the pool.
+    if lockup_pool_info.key != &state.pool {
+        return Err(PaladinLockupError::IncorrectPool.into());
+    }
+
+    // Bring the pool's rewards up to date, so that the lockup only earns
+    // rewards streamed from now on.
+    update_rewards(&mut lockup_pool_state)?;
+
+    // Legacy lockups left the pool when unlocked, so only active ones may
+    // still hold an entry, unless they were evicted since the pool's
+    // migration.
+    match state.lockup_end_timestamp {
+        None => {
+            let in_pool = with_pool_entries(
+                program_id,
+                lockup_pool_info.key,
+                &mut lockup_pool_state,
+                accounts_iter.as_slice(),
+                state.amount,
+                |entries| Ok(entries.find_entry(lockup_info.key, state.amount).is_some()),
+            )?;
+            set_in_pool(state, in_pool);
+            lockup_pool_state.stats.record_lock(state.amount, 1);
+        }
+        Some(lockup_end_timestamp) => {
+            // Legacy lockups waited out the pool's cooldown from their end.
+            state.cooldown_end_timestamp = NonZeroU64::new(
+                lockup_end_timestamp
+                    .get()
+                    .saturating_add(lockup_pool_state.cooldown_seconds),
+            );
+            lockup_pool_state.stats.unlocking_amount = lockup_pool_state
+                .stats
+                .unlocking_amount
+                .saturating_add(state.amount);
+        }
+    }
+    state.reward_per_token_checkpoint = lockup_pool_state.rewards.reward_per_token;
+
+    Ok(())
+}
+
+/// Processes a
+/// [MigrateLockupPool](enum.PaladinLockupInstruction.html)
+/// instruction.
+fn process_migrate_lockup_pool(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+
+    let payer_info = next_account_info(accounts_iter)?;
+    let lockup_pool_info = next_account_info(accounts_iter)?;
+    let mint_info = next_account_info(accounts_iter)?;
+    // NB: System program is just needed for CPIs.
+    let _ = next_account_info(accounts_iter)?;
+
+    // Ensure the lockup pool is an initialized legacy pool.
+    assert_eq!(
+        lockup_pool_info.owner, program_id,
+        "lockup_pool invalid owner"
+    );
+    if lockup_pool_info.data_len() != LockupPool::LEGACY_LEN
+        || &lockup_pool_info.try_borrow_data()?[0..8] != LockupPool::SPL_DISCRIMINATOR_SLICE
+    {
+        return Err(ProgramError::InvalidAccountData);
+    }
+
+    // Ensure the mint matches the pool, and make its mint authority the pool
+    // authority, as for new pools.
+    if lockup_pool_info.try_borrow_data()?[8..40] != mint_info.key.to_bytes() {
+        return Err(PaladinLockupError::IncorrectMint.into());
+    }
+    spl_token_2022::check_spl_token_program_account(mint_info.owner)?;
+    let pool_authority = {
+        let mint_data = mint_info.try_borrow_data()?;
+        let mint = StateWithExtensions::<Mint>::unpack(&mint_data)?;
+        match mint.base.mint_authority {
+            COption::Some(mint_authority) => mint_authority,
+            COption::None => Pubkey::default(),
+        }
+    };
 
-    lockup_info.realloc(0, true)?;
-    lockup_info.assign(&system_program::id());
+    // Top up the pool's rent & grow it to the current layout.
+    let pool_len = LockupPool::account_len(LockupPool::INITIAL_CAPACITY);
+    let required_lamports = Rent::get()?
+        .minimum_balance(pool_len)
+        .saturating_sub(lockup_pool_info.lamports());
+    if required_lamports > 0 {
+        invoke(
+            &system_instruction::transfer(payer_info.key, lockup_pool_info.key, required_lamports),
+            &[payer_info.clone(), lockup_pool_info.clone()],
+        )?;
+    }
+    lockup_pool_info.realloc(pool_len, false)?;
+
+    // Move the entries behind the new header, & clear the rest of the header.
+    let mut lockup_pool_data = lockup_pool_info.try_borrow_mut_data()?;
+    let (entries_start, entries_len_start) = (40, LockupPool::LEGACY_LEN - 8);
+    let entries_len = usize::from_le_bytes(
+        lockup_pool_data[entries_len_start..LockupPool::LEGACY_LEN]
+            .try_into()
+            .map_err(|_| ProgramError::InvalidAccountData)?,
+    );
+    lockup_pool_data.copy_within(entries_start..entries_len_start, LockupPool::LEN);
+    lockup_pool_data[entries_start..LockupPool::LEN].fill(0);
+
+    let mut lockup_pool_state = LockupPool::unpack_mut(&mut lockup_pool_data)?;
+    lockup_pool_state.cooldown_seconds = LOCKUP_COOLDOWN_SECONDS;
+    lockup_pool_state.authority = pool_authority;
+    lockup_pool_state.entries_len = entries_len;
 
     Ok(())
 }
@@ -425,21 +3059,168 @@ fn process_withdraw(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramRes
 pub fn process(program_id: &Pubkey, accounts: &[AccountInfo], input: &[u8]) -> ProgramResult {
     let instruction = PaladinLockupInstruction::unpack(input)?;
     match instruction {
-        PaladinLockupInstruction::InitializeLockupPool => {
+        PaladinLockupInstruction::InitializeLockupPool {
+            pool_id,
+            cooldown_seconds,
+            metadata_update_interval_seconds,
+            isolated_escrow,
+        } => {
             msg!("Instruction: InitializeLockupPool");
-            process_initialize_lockup_pool(program_id, accounts)
+            process_initialize_lockup_pool(
+                program_id,
+                accounts,
+                pool_id,
+                cooldown_seconds,
+                metadata_update_interval_seconds,
+                isolated_escrow,
+            )
         }
-        PaladinLockupInstruction::Lockup { metadata, amount } => {
+        PaladinLockupInstruction::Lockup {
+            metadata,
+            amount,
+            committed_end_timestamp,
+        } => {
             msg!("Instruction: Lockup");
-            process_lockup(program_id, accounts, metadata, amount)
+            process_lockup(
+                program_id,
+                accounts,
+                metadata,
+                amount,
+                committed_end_timestamp,
+                VestingSchedule::default(),
+            )
         }
-        PaladinLockupInstruction::Unlock => {
+        PaladinLockupInstruction::Unlock { amount } => {
             msg!("Instruction: Unlock");
-            process_unlock(program_id, accounts)
+            process_unlock(program_id, accounts, amount)
         }
         PaladinLockupInstruction::Withdraw => {
             msg!("Instruction: Withdraw");
             process_withdraw(program_id, accounts)
         }
+        PaladinLockupInstruction::IncreaseLockup { amount } => {
+            msg!("Instruction: IncreaseLockup");
+            process_increase_lockup(program_id, accounts, amount)
+        }
+        PaladinLockupInstruction::Relock => {
+            msg!("Instruction: Relock");
+            process_relock(program_id, accounts)
+        }
+        PaladinLockupInstruction::ProposeAuthority => {
+            msg!("Instruction: ProposeAuthority");
+            process_propose_authority(program_id, accounts)
+        }
+        PaladinLockupInstruction::AcceptAuthority => {
+            msg!("Instruction: AcceptAuthority");
+            process_accept_authority(program_id, accounts)
+        }
+        PaladinLockupInstruction::MergeLockups => {
+            msg!("Instruction: MergeLockups");
+            process_merge_lockups(program_id, accounts)
+        }
+        PaladinLockupInstruction::VestingLockup {
+            metadata,
+            amount,
+            schedule,
+        } => {
+            msg!("Instruction: VestingLockup");
+            process_vesting_lockup(program_id, accounts, metadata, amount, schedule)
+        }
+        PaladinLockupInstruction::UpdateMetadata { metadata } => {
+            msg!("Instruction: UpdateMetadata");
+            process_update_metadata(program_id, accounts, metadata)
+        }
+        PaladinLockupInstruction::CreateLockup {
+            metadata,
+            amount,
+            committed_end_timestamp,
+            nonce,
+        } => {
+            msg!("Instruction: CreateLockup");
+            process_create_lockup(
+                program_id,
+                accounts,
+                metadata,
+                amount,
+                committed_end_timestamp,
+                nonce,
+            )
+        }
+        PaladinLockupInstruction::UpdatePoolConfig {
+            min_lockup_amount,
+            cooldown_seconds,
+            metadata_update_interval_seconds,
+            lockups_enabled,
+            authority,
+        } => {
+            msg!("Instruction: UpdatePoolConfig");
+            process_update_pool_config(
+                program_id,
+                accounts,
+                min_lockup_amount,
+                cooldown_seconds,
+                metadata_update_interval_seconds,
+                lockups_enabled,
+                authority,
+            )
+        }
+        PaladinLockupInstruction::SetPoolPaused {
+            lockups_paused,
+            unlocks_paused,
+            withdrawals_paused,
+        } => {
+            msg!("Instruction: SetPoolPaused");
+            process_set_pool_paused(
+                program_id,
+                accounts,
+                lockups_paused,
+                unlocks_paused,
+                withdrawals_paused,
+            )
+        }
+        PaladinLockupInstruction::ReinsertLockup => {
+            msg!("Instruction: ReinsertLockup");
+            process_reinsert_lockup(program_id, accounts)
+        }
+        PaladinLockupInstruction::ResizeLockupPool { capacity } => {
+            msg!("Instruction: ResizeLockupPool");
+            process_resize_lockup_pool(program_id, accounts, capacity)
+        }
+        PaladinLockupInstruction::AddLockupPoolShard { max_amount } => {
+            msg!("Instruction: AddLockupPoolShard");
+            process_add_lockup_pool_shard(program_id, accounts, max_amount)
+        }
+        PaladinLockupInstruction::RebalanceLockupPoolShard { max_amount } => {
+            msg!("Instruction: RebalanceLockupPoolShard");
+            process_rebalance_lockup_pool_shard(program_id, accounts, max_amount)
+        }
+        PaladinLockupInstruction::SnapshotPool => {
+            msg!("Instruction: SnapshotPool");
+            process_snapshot_pool(program_id, accounts)
+        }
+        PaladinLockupInstruction::CloseSnapshot => {
+            msg!("Instruction: CloseSnapshot");
+            process_close_snapshot(program_id, accounts)
+        }
+        PaladinLockupInstruction::DepositRewards { amount } => {
+            msg!("Instruction: DepositRewards");
+            process_deposit_rewards(program_id, accounts, amount)
+        }
+        PaladinLockupInstruction::ClaimRewards => {
+            msg!("Instruction: ClaimRewards");
+            process_claim_rewards(program_id, accounts)
+        }
+        PaladinLockupInstruction::UpdatePoolRoot => {
+            msg!("Instruction: UpdatePoolRoot");
+            process_update_pool_root(program_id, accounts)
+        }
+        PaladinLockupInstruction::MigrateLockup => {
+            msg!("Instruction: MigrateLockup");
+            process_migrate_lockup(program_id, accounts)
+        }
+        PaladinLockupInstruction::MigrateLockupPool => {
+            msg!("Instruction: MigrateLockupPool");
+            process_migrate_lockup_pool(program_id, accounts)
+        }
     }
 }
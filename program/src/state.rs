@@ -1,9 +1,16 @@
 use {
+    crate::{error::PaladinLockupError, merkle},
     bytemuck::{Pod, Zeroable},
     shank::{ShankAccount, ShankType},
-    solana_program::pubkey::Pubkey,
+    solana_program::{
+        program_error::ProgramError, pubkey::Pubkey, system_instruction::MAX_PERMITTED_DATA_LENGTH,
+    },
     spl_discriminator::SplDiscriminate,
-    std::num::NonZeroU64,
+    std::{
+        cmp::Reverse,
+        num::NonZeroU64,
+        ops::{Deref, DerefMut},
+    },
 };
 
 /// The seed prefix (`"escrow_authority"`) in bytes used to derive the address
@@ -29,24 +36,1013 @@ pub(crate) fn collect_escrow_authority_signer_seeds(bump_seed: &[u8]) -> [&[u8];
     [SEED_PREFIX_ESCROW_AUTHORITY, bump_seed]
 }
 
-/// Lockup pool account.
+/// The seed prefix (`"pool_escrow_authority"`) in bytes used to derive the
+/// address of a lockup pool's isolated escrow authority.
+/// Seeds: `"pool_escrow_authority" + pool_address`.
+pub const SEED_PREFIX_POOL_ESCROW_AUTHORITY: &[u8] = b"pool_escrow_authority";
+
+/// Derive the address of a lockup pool's isolated escrow authority.
+pub fn get_pool_escrow_authority_address(pool_address: &Pubkey, program_id: &Pubkey) -> Pubkey {
+    get_pool_escrow_authority_address_and_bump_seed(pool_address, program_id).0
+}
+
+/// Derive the address of a lockup pool's isolated escrow authority, with bump
+/// seed.
+pub fn get_pool_escrow_authority_address_and_bump_seed(
+    pool_address: &Pubkey,
+    program_id: &Pubkey,
+) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &collect_pool_escrow_authority_seeds(pool_address),
+        program_id,
+    )
+}
+
+pub(crate) fn collect_pool_escrow_authority_seeds(pool_address: &Pubkey) -> [&[u8]; 2] {
+    [SEED_PREFIX_POOL_ESCROW_AUTHORITY, pool_address.as_ref()]
+}
+
+pub(crate) fn collect_pool_escrow_authority_signer_seeds<'a>(
+    pool_address: &'a Pubkey,
+    bump_seed: &'a [u8],
+) -> [&'a [u8]; 3] {
+    [
+        SEED_PREFIX_POOL_ESCROW_AUTHORITY,
+        pool_address.as_ref(),
+        bump_seed,
+    ]
+}
+
+/// The seed prefix (`"lockup_pool"`) in bytes used to derive the address of a
+/// lockup pool.
+/// Seeds: `"lockup_pool" + mint_address + pool_id`.
+pub const SEED_PREFIX_LOCKUP_POOL: &[u8] = b"lockup_pool";
+
+/// Derive the address of a lockup pool.
+///
+/// Pool id `0` is the canonical pool for the mint.
+pub fn get_lockup_pool_address(mint_address: &Pubkey, pool_id: u64, program_id: &Pubkey) -> Pubkey {
+    get_lockup_pool_address_and_bump_seed(mint_address, pool_id, program_id).0
+}
+
+/// Derive the address of a lockup pool, with bump seed.
+pub fn get_lockup_pool_address_and_bump_seed(
+    mint_address: &Pubkey,
+    pool_id: u64,
+    program_id: &Pubkey,
+) -> (Pubkey, u8) {
+    let pool_id = pool_id.to_le_bytes();
+    Pubkey::find_program_address(
+        &collect_lockup_pool_seeds(mint_address, &pool_id),
+        program_id,
+    )
+}
+
+pub(crate) fn collect_lockup_pool_seeds<'a>(
+    mint_address: &'a Pubkey,
+    pool_id: &'a [u8; 8],
+) -> [&'a [u8]; 3] {
+    [SEED_PREFIX_LOCKUP_POOL, mint_address.as_ref(), pool_id]
+}
+
+pub(crate) fn collect_lockup_pool_signer_seeds<'a>(
+    mint_address: &'a Pubkey,
+    pool_id: &'a [u8; 8],
+    bump_seed: &'a [u8],
+) -> [&'a [u8]; 4] {
+    [
+        SEED_PREFIX_LOCKUP_POOL,
+        mint_address.as_ref(),
+        pool_id,
+        bump_seed,
+    ]
+}
+
+/// The seed prefix (`"lockup"`) in bytes used to derive the address of a
+/// program-created lockup account.
+/// Seeds: `"lockup" + pool_address + authority_address + nonce`.
+pub const SEED_PREFIX_LOCKUP: &[u8] = b"lockup";
+
+/// Derive the address of a program-created lockup account.
+pub fn get_lockup_address(
+    pool_address: &Pubkey,
+    authority_address: &Pubkey,
+    nonce: u64,
+    program_id: &Pubkey,
+) -> Pubkey {
+    get_lockup_address_and_bump_seed(pool_address, authority_address, nonce, program_id).0
+}
+
+/// Derive the address of a program-created lockup account, with bump seed.
+pub fn get_lockup_address_and_bump_seed(
+    pool_address: &Pubkey,
+    authority_address: &Pubkey,
+    nonce: u64,
+    program_id: &Pubkey,
+) -> (Pubkey, u8) {
+    let nonce = nonce.to_le_bytes();
+    Pubkey::find_program_address(
+        &collect_lockup_seeds(pool_address, authority_address, &nonce),
+        program_id,
+    )
+}
+
+pub(crate) fn collect_lockup_seeds<'a>(
+    pool_address: &'a Pubkey,
+    authority_address: &'a Pubkey,
+    nonce: &'a [u8; 8],
+) -> [&'a [u8]; 4] {
+    [
+        SEED_PREFIX_LOCKUP,
+        pool_address.as_ref(),
+        authority_address.as_ref(),
+        nonce,
+    ]
+}
+
+pub(crate) fn collect_lockup_signer_seeds<'a>(
+    pool_address: &'a Pubkey,
+    authority_address: &'a Pubkey,
+    nonce: &'a [u8; 8],
+    bump_seed: &'a [u8],
+) -> [&'a [u8]; 5] {
+    [
+        SEED_PREFIX_LOCKUP,
+        pool_address.as_ref(),
+        authority_address.as_ref(),
+        nonce,
+        bump_seed,
+    ]
+}
+
+/// The seed prefix (`"reward_authority"`) in bytes used to derive the address
+/// of a lockup pool's reward authority, which owns the pool's reward vault.
+/// Seeds: `"reward_authority" + pool_address`.
+pub const SEED_PREFIX_REWARD_AUTHORITY: &[u8] = b"reward_authority";
+
+/// Derive the address of a lockup pool's reward authority.
+pub fn get_reward_authority_address(pool_address: &Pubkey, program_id: &Pubkey) -> Pubkey {
+    get_reward_authority_address_and_bump_seed(pool_address, program_id).0
+}
+
+/// Derive the address of a lockup pool's reward authority, with bump seed.
+pub fn get_reward_authority_address_and_bump_seed(
+    pool_address: &Pubkey,
+    program_id: &Pubkey,
+) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&collect_reward_authority_seeds(pool_address), program_id)
+}
+
+pub(crate) fn collect_reward_authority_seeds(pool_address: &Pubkey) -> [&[u8]; 2] {
+    [SEED_PREFIX_REWARD_AUTHORITY, pool_address.as_ref()]
+}
+
+pub(crate) fn collect_reward_authority_signer_seeds<'a>(
+    pool_address: &'a Pubkey,
+    bump_seed: &'a [u8],
+) -> [&'a [u8]; 3] {
+    [
+        SEED_PREFIX_REWARD_AUTHORITY,
+        pool_address.as_ref(),
+        bump_seed,
+    ]
+}
+
+/// The seed prefix (`"lockup_pool_snapshot"`) in bytes used to derive the
+/// address of a lockup pool snapshot.
+/// Seeds: `"lockup_pool_snapshot" + pool_address + epoch`.
+pub const SEED_PREFIX_LOCKUP_POOL_SNAPSHOT: &[u8] = b"lockup_pool_snapshot";
+
+/// Derive the address of a lockup pool's snapshot for the provided epoch.
+pub fn get_lockup_pool_snapshot_address(
+    pool_address: &Pubkey,
+    epoch: u64,
+    program_id: &Pubkey,
+) -> Pubkey {
+    get_lockup_pool_snapshot_address_and_bump_seed(pool_address, epoch, program_id).0
+}
+
+/// Derive the address of a lockup pool's snapshot for the provided epoch,
+/// with bump seed.
+pub fn get_lockup_pool_snapshot_address_and_bump_seed(
+    pool_address: &Pubkey,
+    epoch: u64,
+    program_id: &Pubkey,
+) -> (Pubkey, u8) {
+    let epoch = epoch.to_le_bytes();
+    Pubkey::find_program_address(
+        &collect_lockup_pool_snapshot_seeds(pool_address, &epoch),
+        program_id,
+    )
+}
+
+pub(crate) fn collect_lockup_pool_snapshot_seeds<'a>(
+    pool_address: &'a Pubkey,
+    epoch: &'a [u8; 8],
+) -> [&'a [u8]; 3] {
+    [
+        SEED_PREFIX_LOCKUP_POOL_SNAPSHOT,
+        pool_address.as_ref(),
+        epoch,
+    ]
+}
+
+pub(crate) fn collect_lockup_pool_snapshot_signer_seeds<'a>(
+    pool_address: &'a Pubkey,
+    epoch: &'a [u8; 8],
+    bump_seed: &'a [u8],
+) -> [&'a [u8]; 4] {
+    [
+        SEED_PREFIX_LOCKUP_POOL_SNAPSHOT,
+        pool_address.as_ref(),
+        epoch,
+        bump_seed,
+    ]
+}
+
+/// The seed prefix (`"lockup_pool_root"`) in bytes used to derive the address
+/// of a lockup pool's Merkle root account.
+/// Seeds: `"lockup_pool_root" + pool_address`.
+pub const SEED_PREFIX_LOCKUP_POOL_ROOT: &[u8] = b"lockup_pool_root";
+
+/// Derive the address of a lockup pool's Merkle root account.
+pub fn get_lockup_pool_root_address(pool_address: &Pubkey, program_id: &Pubkey) -> Pubkey {
+    get_lockup_pool_root_address_and_bump_seed(pool_address, program_id).0
+}
+
+/// Derive the address of a lockup pool's Merkle root account, with bump seed.
+pub fn get_lockup_pool_root_address_and_bump_seed(
+    pool_address: &Pubkey,
+    program_id: &Pubkey,
+) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&collect_lockup_pool_root_seeds(pool_address), program_id)
+}
+
+pub(crate) fn collect_lockup_pool_root_seeds(pool_address: &Pubkey) -> [&[u8]; 2] {
+    [SEED_PREFIX_LOCKUP_POOL_ROOT, pool_address.as_ref()]
+}
+
+pub(crate) fn collect_lockup_pool_root_signer_seeds<'a>(
+    pool_address: &'a Pubkey,
+    bump_seed: &'a [u8],
+) -> [&'a [u8]; 3] {
+    [
+        SEED_PREFIX_LOCKUP_POOL_ROOT,
+        pool_address.as_ref(),
+        bump_seed,
+    ]
+}
+
+/// Lockup pool account header.
+///
+/// # Note
+///
+/// The header is followed by the pool's entries, which take up the rest of the
+/// account. The pool's capacity therefore grows with the account, see
+/// `ResizeLockupPool`.
 #[derive(Clone, Copy, Debug, PartialEq, Pod, ShankAccount, SplDiscriminate, Zeroable)]
 #[discriminator_hash_input("lockup::state::lockup_pool")]
 #[repr(C)]
 pub struct LockupPool {
     pub discriminator: [u8; 8],
     pub mint: Pubkey,
-    pub entries: [LockupPoolEntry; 1024],
+    /// The number of seconds an unlocked lockup must wait before it can be
+    /// withdrawn.
+    pub cooldown_seconds: u64,
+    /// The minimum number of seconds between metadata updates of a lockup, or
+    /// zero to allow updates at any time.
+    pub metadata_update_interval_seconds: u64,
+    /// The authority allowed to update the pool's configuration.
+    ///
+    /// # Note
+    ///
+    /// Set to the default pubkey when the pool has no authority.
+    pub authority: Pubkey,
+    /// The minimum amount of tokens a new lockup must lock up.
+    pub min_lockup_amount: u64,
+    /// Pool flags, see `LockupPool::FLAG_*`.
+    pub flags: u64,
+    /// The number of occupied entries.
     pub entries_len: usize,
+    /// The number of shards in use.
+    pub shards_len: usize,
+    /// The pool's shards, in descending order of the amounts they cover.
+    ///
+    /// # Note
+    ///
+    /// The pool's own entries cover all amounts from the first shard's
+    /// `max_amount` upwards, or all amounts if the pool has no shards.
+    pub shards: [LockupPoolShardInfo; 16],
+    /// Running totals across all of the pool's lockups.
+    pub stats: LockupPoolStats,
+    /// The pool's reward distribution state.
+    pub rewards: LockupPoolRewards,
 }
 
 impl LockupPool {
+    /// The length of the header.
     pub const LEN: usize = std::mem::size_of::<LockupPool>();
-    pub const LOCKUP_CAPACITY: usize = 1024;
+    /// The capacity a pool is initialized with.
+    pub const INITIAL_CAPACITY: usize = 1024;
+    /// The largest capacity a pool may be resized to.
+    pub const MAX_CAPACITY: usize =
+        (MAX_PERMITTED_DATA_LENGTH as usize - Self::LEN) / std::mem::size_of::<LockupPoolEntry>();
+    /// The length of lockup pool accounts created before pools had a
+    /// configuration, see `MigrateLockupPool`.
+    ///
+    /// # Note
+    ///
+    /// The legacy layout is the discriminator & mint, followed by 1024 entries
+    /// and the number of occupied entries, without the cooldown. Other
+    /// instructions reject legacy pools with
+    /// `PaladinLockupError::LockupPoolNotMigrated`.
+    pub const LEGACY_LEN: usize = 73_776;
+
+    /// Set when the pool does not accept new lockups.
+    pub const FLAG_LOCKUPS_DISABLED: u64 = 1 << 0;
+    /// Set when new lockups are paused.
+    pub const FLAG_LOCKUP_PAUSED: u64 = 1 << 1;
+    /// Set when unlocks are paused.
+    pub const FLAG_UNLOCK_PAUSED: u64 = 1 << 2;
+    /// Set when withdrawals are paused.
+    pub const FLAG_WITHDRAW_PAUSED: u64 = 1 << 3;
+    /// Set when the pool escrows its tokens under its own escrow authority
+    /// (see `get_pool_escrow_authority_address`) rather than the program-wide
+    /// one. Fixed at initialization.
+    pub const FLAG_ISOLATED_ESCROW: u64 = 1 << 4;
+    /// All pause flags.
+    pub const PAUSE_FLAGS: u64 =
+        Self::FLAG_LOCKUP_PAUSED | Self::FLAG_UNLOCK_PAUSED | Self::FLAG_WITHDRAW_PAUSED;
+
+    /// The maximum number of shards a pool may have.
+    pub const MAX_SHARDS: usize = 16;
+
+    /// The account length of a pool with the provided capacity.
+    #[allow(clippy::arithmetic_side_effects)]
+    pub const fn account_len(capacity: usize) -> usize {
+        Self::LEN + capacity * std::mem::size_of::<LockupPoolEntry>()
+    }
+
+    /// Whether the pool accepts new lockups.
+    pub fn lockups_enabled(&self) -> bool {
+        self.flags & Self::FLAG_LOCKUPS_DISABLED == 0
+    }
+
+    /// Whether the pool has its own escrow authority.
+    pub fn has_isolated_escrow(&self) -> bool {
+        self.flags & Self::FLAG_ISOLATED_ESCROW != 0
+    }
+
+    /// Derive the address of the escrow authority holding the pool's tokens,
+    /// with bump seed.
+    pub fn escrow_authority_address_and_bump_seed(
+        &self,
+        pool_address: &Pubkey,
+        program_id: &Pubkey,
+    ) -> (Pubkey, u8) {
+        match self.has_isolated_escrow() {
+            true => get_pool_escrow_authority_address_and_bump_seed(pool_address, program_id),
+            false => get_escrow_authority_address_and_bump_seed(program_id),
+        }
+    }
+
+    /// Whether the provided pause flag is set.
+    pub fn is_paused(&self, pause_flag: u64) -> bool {
+        self.flags & pause_flag != 0
+    }
+
+    /// The shards in use.
+    pub fn shards(&self) -> &[LockupPoolShardInfo] {
+        &self.shards[..self.shards_len]
+    }
+
+    /// The index of the shard covering the provided amount, or `None` if the
+    /// pool's own entries cover it.
+    pub fn shard_index(&self, amount: u64) -> Option<usize> {
+        self.shards()
+            .iter()
+            .rposition(|shard| amount < shard.max_amount)
+    }
+
+    /// Splits lockup pool account data into its header & entries.
+    ///
+    /// # Note
+    ///
+    /// The returned entries are the pool's full capacity, only the first
+    /// `entries_len` are occupied.
+    pub fn unpack(data: &[u8]) -> Result<(&Self, &[LockupPoolEntry]), ProgramError> {
+        check_migrated(data.len())?;
+        split_entries(data)
+    }
+
+    /// Splits lockup pool account data into its header & entries, mutably.
+    pub fn unpack_mut(data: &mut [u8]) -> Result<LockupPoolMut<'_>, ProgramError> {
+        check_migrated(data.len())?;
+        let (header, entries) = split_entries_mut(data)?;
+
+        Ok(LockupPoolMut { header, entries })
+    }
+}
+
+/// Ensures lockup pool account data is not in the legacy layout, which
+/// predates the pool's cooldown.
+fn check_migrated(len: usize) -> Result<(), ProgramError> {
+    if len == LockupPool::LEGACY_LEN {
+        return Err(PaladinLockupError::LockupPoolNotMigrated.into());
+    }
+
+    Ok(())
+}
+
+// Pools in the current layout are never as long as legacy pools.
+const _: () = assert!(!(LockupPool::LEGACY_LEN - LockupPool::LEN)
+    .is_multiple_of(std::mem::size_of::<LockupPoolEntry>()));
+
+/// Splits account data into a header followed by entries.
+fn split_entries<H: Pod>(data: &[u8]) -> Result<(&H, &[LockupPoolEntry]), ProgramError> {
+    check_entries_len::<H>(data.len())?;
+    let (header, entries) = data.split_at(std::mem::size_of::<H>());
+
+    Ok((bytemuck::from_bytes(header), bytemuck::cast_slice(entries)))
+}
+
+/// Splits account data into a header followed by entries, mutably.
+fn split_entries_mut<H: Pod>(
+    data: &mut [u8],
+) -> Result<(&mut H, &mut [LockupPoolEntry]), ProgramError> {
+    check_entries_len::<H>(data.len())?;
+    let (header, entries) = data.split_at_mut(std::mem::size_of::<H>());
+
+    Ok((
+        bytemuck::from_bytes_mut(header),
+        bytemuck::cast_slice_mut(entries),
+    ))
+}
+
+fn check_entries_len<H: Pod>(len: usize) -> Result<(), ProgramError> {
+    match len.checked_sub(std::mem::size_of::<H>()) {
+        Some(entries_len)
+            if entries_len.checked_rem(std::mem::size_of::<LockupPoolEntry>()) == Some(0) =>
+        {
+            Ok(())
+        }
+        _ => Err(ProgramError::InvalidAccountData),
+    }
+}
+
+/// Mutable view of a lockup pool account, dereferencing to its header.
+pub struct LockupPoolMut<'a> {
+    pub header: &'a mut LockupPool,
+    pub entries: &'a mut [LockupPoolEntry],
+}
+
+impl Deref for LockupPoolMut<'_> {
+    type Target = LockupPool;
+
+    fn deref(&self) -> &LockupPool {
+        self.header
+    }
+}
+
+impl DerefMut for LockupPoolMut<'_> {
+    fn deref_mut(&mut self) -> &mut LockupPool {
+        self.header
+    }
+}
+
+impl LockupPoolMut<'_> {
+    /// The number of entries the pool can hold.
+    pub fn capacity(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// The pool's own entries.
+    pub fn entries_mut(&mut self) -> LockupPoolEntries<'_> {
+        LockupPoolEntries {
+            entries_len: &mut self.header.entries_len,
+            entries: self.entries,
+        }
+    }
+}
+
+/// Sorted entries of a lockup pool or one of its shards.
+pub struct LockupPoolEntries<'a> {
+    pub entries_len: &'a mut usize,
+    pub entries: &'a mut [LockupPoolEntry],
+}
+
+impl LockupPoolEntries<'_> {
+    /// The number of entries that fit.
+    pub fn capacity(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Inserts an entry into the pool, maintaining descending order by amount.
+    ///
+    /// If the pool is full the smallest entry is evicted and returned, unless
+    /// the new entry is not larger than it, in which case the insert is
+    /// rejected.
+    pub(crate) fn insert_entry(
+        &mut self,
+        entry: LockupPoolEntry,
+    ) -> Result<Option<LockupPoolEntry>, PaladinLockupError> {
+        let capacity = self.capacity();
+        if capacity == 0 {
+            return Err(PaladinLockupError::AmountTooLow);
+        }
+
+        // Evict the smallest lock if necessary.
+        let last_index = std::cmp::min(*self.entries_len, capacity.saturating_sub(1));
+        let last_amount = self.entries[last_index].amount;
+        let evicted = match (*self.entries_len == capacity, entry.amount > last_amount) {
+            (true, true) => Some(self.entries[last_index]),
+            (true, false) => return Err(PaladinLockupError::AmountTooLow),
+            (false, _) => {
+                *self.entries_len = self.entries_len.checked_add(1).unwrap();
+                None
+            }
+        };
+
+        // Binary search & insert the entry, only shifting occupied entries.
+        let entries = &mut self.entries[..=last_index];
+        let index = match entries
+            .binary_search_by_key(&Reverse(entry.amount), |entry| Reverse(entry.amount))
+        {
+            Ok(index) => index,
+            Err(index) => index,
+        };
+        *entries.last_mut().unwrap() = entry;
+        entries[index..].rotate_right(1);
+
+        Ok(evicted)
+    }
+
+    /// Inserts entries into a segment of a sharded pool other than its last,
+    /// i.e. the pool's own entries or a shard with shards below it,
+    /// maintaining descending order by amount.
+    ///
+    /// The provided entries must be in descending order. If they don't all
+    /// fit, the segment's smallest entries (which may include provided ones)
+    /// are spilled instead, along with any entries tied with them, and
+    /// returned in descending order to be moved into the next segment.
+    pub(crate) fn insert_spilling(
+        &mut self,
+        mut entries: Vec<LockupPoolEntry>,
+    ) -> Result<Vec<LockupPoolEntry>, PaladinLockupError> {
+        let mut spilled: Vec<LockupPoolEntry> = Vec::new();
+        loop {
+            // Find the smallest remaining entry, of the segment's own & the
+            // provided ones.
+            let last = self.entries[..*self.entries_len].last().copied();
+            let (smallest, is_own) = match (last, entries.last()) {
+                (Some(last), Some(entry)) if last.amount < entry.amount => (last, true),
+                (_, Some(entry)) => (*entry, false),
+                (Some(last), None) => (last, true),
+                (None, None) => break,
+            };
+
+            // Stop once the provided entries fit, without splitting tied
+            // entries between segments.
+            let room = self.capacity().saturating_sub(*self.entries_len);
+            let tied = spilled
+                .last()
+                .is_some_and(|entry| entry.amount == smallest.amount);
+            if entries.len() <= room && !tied {
+                break;
+            }
+
+            match is_own {
+                true => {
+                    self.remove_entry(self.entries_len.saturating_sub(1));
+                }
+                false => {
+                    entries.pop();
+                }
+            }
+            spilled.push(smallest);
+        }
+
+        for entry in entries {
+            self.insert_entry(entry)?;
+        }
+        spilled.reverse();
+
+        Ok(spilled)
+    }
+
+    /// Finds the index of the entry for the provided lockup, if it is present
+    /// in the pool with the provided amount.
+    pub(crate) fn find_entry(&self, lockup: &Pubkey, amount: u64) -> Option<usize> {
+        let entries = &self.entries[..*self.entries_len];
+        let partition_point = entries.partition_point(|entry| entry.amount > amount);
+        let offset = entries[partition_point..]
+            .iter()
+            .take_while(|entry| entry.amount == amount)
+            .position(|entry| &entry.lockup == lockup)?;
 
-    const _ASSERT_LOCKUP_CAPACITY: () = assert!(
-        Self::LOCKUP_CAPACITY * std::mem::size_of::<LockupPoolEntry>() + 8 + 8 == Self::LEN
-    );
+        #[allow(clippy::arithmetic_side_effects)]
+        Some(partition_point + offset)
+    }
+
+    /// Removes the entry at the provided index, shifting all smaller entries
+    /// up by one.
+    pub(crate) fn remove_entry(&mut self, index: usize) -> LockupPoolEntry {
+        let entries = &mut self.entries[..*self.entries_len];
+        let entry = std::mem::take(&mut entries[index]);
+        entries[index..].rotate_left(1);
+        *self.entries_len = self.entries_len.checked_sub(1).unwrap();
+
+        entry
+    }
+}
+
+/// Moves entries between two adjacent sets of pool entries, so that `upper`
+/// only holds amounts of at least `boundary` and `lower` only holds smaller
+/// amounts.
+///
+/// # Note
+///
+/// All entries of `upper` must already be larger than those of `lower`, so
+/// entries only ever move across the end of `upper` & the start of `lower`.
+pub(crate) fn move_entries(
+    upper: &mut LockupPoolEntries,
+    lower: &mut LockupPoolEntries,
+    boundary: u64,
+) -> Result<(), PaladinLockupError> {
+    let upper_len = *upper.entries_len;
+    let lower_len = *lower.entries_len;
+
+    // Move the smallest entries of `upper` to the start of `lower`.
+    let split = upper.entries[..upper_len].partition_point(|entry| entry.amount >= boundary);
+    let count = upper_len.saturating_sub(split);
+    if count > 0 {
+        let new_lower_len = lower_len
+            .checked_add(count)
+            .filter(|len| *len <= lower.capacity())
+            .ok_or(PaladinLockupError::ShardCapacityExceeded)?;
+        lower.entries[..new_lower_len].rotate_right(count);
+        lower.entries[..count].copy_from_slice(&upper.entries[split..upper_len]);
+        upper.entries[split..upper_len].fill(LockupPoolEntry::default());
+        *upper.entries_len = split;
+        *lower.entries_len = new_lower_len;
+    }
+
+    // Move the largest entries of `lower` to the end of `upper`.
+    let count =
+        lower.entries[..*lower.entries_len].partition_point(|entry| entry.amount >= boundary);
+    if count > 0 {
+        let new_upper_len = upper_len
+            .checked_add(count)
+            .filter(|len| *len <= upper.capacity())
+            .ok_or(PaladinLockupError::ShardCapacityExceeded)?;
+        upper.entries[upper_len..new_upper_len].copy_from_slice(&lower.entries[..count]);
+        lower.entries[..lower_len].rotate_left(count);
+        lower.entries[lower_len.saturating_sub(count)..lower_len].fill(LockupPoolEntry::default());
+        *upper.entries_len = new_upper_len;
+        *lower.entries_len = lower_len.saturating_sub(count);
+    }
+
+    Ok(())
+}
+
+/// Running totals across all lockups of a pool, including those without an
+/// entry in the pool.
+///
+/// # Note
+///
+/// The totals are informational, so updates saturate rather than fail.
+#[derive(Default, Clone, Copy, Debug, PartialEq, ShankType, Pod, Zeroable)]
+#[repr(C)]
+pub struct LockupPoolStats {
+    /// The amount of tokens held by active (not unlocked) lockups.
+    pub active_amount: u64,
+    /// The number of active (not unlocked) lockups.
+    pub active_lockups: u64,
+    /// The amount of tokens held by unlocked lockups that have not been
+    /// withdrawn.
+    pub unlocking_amount: u64,
+    /// The amount of tokens withdrawn from the pool's lockups.
+    pub withdrawn_amount: u64,
+}
+
+impl LockupPoolStats {
+    /// Records tokens locked up, by `lockups` new lockups.
+    pub(crate) fn record_lock(&mut self, amount: u64, lockups: u64) {
+        self.active_amount = self.active_amount.saturating_add(amount);
+        self.active_lockups = self.active_lockups.saturating_add(lockups);
+    }
+
+    /// Records tokens leaving active lockups, with `lockups` lockups ending.
+    pub(crate) fn record_unlock(&mut self, amount: u64, lockups: u64) {
+        self.active_amount = self.active_amount.saturating_sub(amount);
+        self.active_lockups = self.active_lockups.saturating_sub(lockups);
+    }
+
+    /// Records tokens starting their cooldown.
+    pub(crate) fn record_cooldown(&mut self, amount: u64, lockups: u64) {
+        self.record_unlock(amount, lockups);
+        self.unlocking_amount = self.unlocking_amount.saturating_add(amount);
+    }
+
+    /// Records a lockup merged into another, whose tokens stay active while
+    /// the merged lockup ends.
+    pub(crate) fn record_merge(&mut self) {
+        self.active_lockups = self.active_lockups.saturating_sub(1);
+    }
+
+    /// Records an unlocked lockup being locked up again.
+    pub(crate) fn record_relock(&mut self, amount: u64) {
+        self.unlocking_amount = self.unlocking_amount.saturating_sub(amount);
+        self.record_lock(amount, 1);
+    }
+
+    /// Records tokens withdrawn at the end of their cooldown.
+    pub(crate) fn record_withdraw(&mut self, amount: u64) {
+        self.unlocking_amount = self.unlocking_amount.saturating_sub(amount);
+        self.withdrawn_amount = self.withdrawn_amount.saturating_add(amount);
+    }
+
+    /// Records vested tokens withdrawn straight from an active lockup.
+    pub(crate) fn record_vested_withdraw(&mut self, amount: u64, lockups: u64) {
+        self.record_unlock(amount, lockups);
+        self.withdrawn_amount = self.withdrawn_amount.saturating_add(amount);
+    }
+}
+
+/// A 128-bit unsigned integer, stored as little-endian bytes so that it only
+/// requires byte alignment.
+#[derive(Default, Clone, Copy, Debug, PartialEq, Pod, Zeroable)]
+#[repr(transparent)]
+pub struct PodU128(pub [u8; 16]);
+
+impl From<u128> for PodU128 {
+    fn from(value: u128) -> Self {
+        Self(value.to_le_bytes())
+    }
+}
+
+impl From<PodU128> for u128 {
+    fn from(value: PodU128) -> Self {
+        u128::from_le_bytes(value.0)
+    }
+}
+
+/// Reward distribution state of a lockup pool.
+///
+/// # Note
+///
+/// Rewards are paid in the pool's mint, from a vault owned by the pool's
+/// reward authority (see `get_reward_authority_address`). Each deposit is
+/// streamed, along with the rewards still left to stream, over the following
+/// `LockupPoolRewards::REWARD_PERIOD_SECONDS`. The streamed rewards are shared
+/// among the active lockups at the time, in proportion to their amounts, by
+/// raising the pool's reward-per-token index. Lockups checkpoint the index
+/// whenever their active amount changes, so a lockup only earns rewards
+/// streamed while it was active.
+///
+/// The index must be brought up to date with `LockupPoolRewards::update`
+/// before the pool's active amount changes or any lockup's rewards accrue.
+/// While the pool has no active tokens, the stream is paused rather than lost.
+#[derive(Default, Clone, Copy, Debug, PartialEq, ShankType, Pod, Zeroable)]
+#[repr(C)]
+pub struct LockupPoolRewards {
+    /// The cumulative rewards per active token, scaled by
+    /// `LockupPoolRewards::REWARD_PER_TOKEN_SCALE`.
+    ///
+    /// # Note
+    ///
+    /// The index wraps around on overflow, as only differences between its
+    /// values are meaningful.
+    pub reward_per_token: PodU128,
+    /// The amount of rewards deposited.
+    pub deposited_amount: u64,
+    /// The amount of rewards claimed.
+    pub claimed_amount: u64,
+    /// The rewards streamed per second, scaled by
+    /// `LockupPoolRewards::REWARD_PER_TOKEN_SCALE`.
+    pub reward_rate: PodU128,
+    /// The time the current stream ends at.
+    pub period_end_timestamp: u64,
+    /// The time the index was last brought up to date at.
+    pub updated_timestamp: u64,
+}
+
+impl LockupPoolRewards {
+    /// The scale of the reward-per-token index.
+    pub const REWARD_PER_TOKEN_SCALE: u128 = 1_000_000_000_000_000_000;
+
+    /// The length of the period each deposit is streamed over.
+    pub const REWARD_PERIOD_SECONDS: u64 = 7 * 24 * 60 * 60;
+
+    /// The reward-per-token index at the provided time, given the pool's
+    /// current amount of active tokens.
+    ///
+    /// Clients can use it with `Lockup::pending_rewards` to compute a
+    /// lockup's current rewards.
+    pub fn reward_per_token_at(&self, timestamp: u64, active_amount: u64) -> PodU128 {
+        let elapsed = timestamp
+            .min(self.period_end_timestamp)
+            .saturating_sub(self.updated_timestamp);
+        let increase = u128::from(self.reward_rate)
+            .saturating_mul(u128::from(elapsed))
+            .checked_div(u128::from(active_amount))
+            .unwrap_or(0);
+
+        u128::from(self.reward_per_token)
+            .wrapping_add(increase)
+            .into()
+    }
+
+    /// Streams the rewards due since the last update into the index, shared
+    /// among the provided amount of active tokens, or pauses the stream if
+    /// there are none.
+    pub(crate) fn update(&mut self, timestamp: u64, active_amount: u64) {
+        if active_amount == 0 {
+            if self.updated_timestamp < self.period_end_timestamp {
+                self.period_end_timestamp = self
+                    .period_end_timestamp
+                    .saturating_add(timestamp.saturating_sub(self.updated_timestamp));
+            }
+        } else {
+            self.reward_per_token = self.reward_per_token_at(timestamp, active_amount);
+        }
+        self.updated_timestamp = timestamp;
+    }
+
+    /// Streams the provided amount of rewards, along with the rewards left to
+    /// stream, over a new period starting at the provided time. The index
+    /// must be up to date.
+    pub(crate) fn deposit(&mut self, amount: u64, timestamp: u64) {
+        let remaining = u128::from(self.reward_rate).saturating_mul(u128::from(
+            self.period_end_timestamp.saturating_sub(timestamp),
+        ));
+        let reward_rate = u128::from(amount)
+            .saturating_mul(Self::REWARD_PER_TOKEN_SCALE)
+            .saturating_add(remaining)
+            .checked_div(u128::from(Self::REWARD_PERIOD_SECONDS))
+            .unwrap_or(0);
+
+        self.reward_rate = reward_rate.into();
+        self.period_end_timestamp = timestamp.saturating_add(Self::REWARD_PERIOD_SECONDS);
+        self.updated_timestamp = timestamp;
+        self.deposited_amount = self.deposited_amount.saturating_add(amount);
+    }
+}
+
+/// A shard of a lockup pool.
+#[derive(Default, Clone, Copy, Debug, PartialEq, ShankType, Pod, Zeroable)]
+#[repr(C)]
+pub struct LockupPoolShardInfo {
+    /// The address of the shard account.
+    pub address: Pubkey,
+    /// The (exclusive) upper bound of the amounts the shard covers.
+    ///
+    /// # Note
+    ///
+    /// The shard covers all amounts from the next shard's `max_amount`, or
+    /// zero if it is the last shard.
+    pub max_amount: u64,
+}
+
+/// Lockup pool shard account header.
+///
+/// # Note
+///
+/// Like the pool itself, the header is followed by the shard's entries, which
+/// take up the rest of the account.
+#[derive(Clone, Copy, Debug, PartialEq, Pod, ShankAccount, SplDiscriminate, Zeroable)]
+#[discriminator_hash_input("lockup::state::lockup_pool_shard")]
+#[repr(C)]
+pub struct LockupPoolShard {
+    pub discriminator: [u8; 8],
+    /// The pool this shard belongs to.
+    pub pool: Pubkey,
+    /// The number of occupied entries.
+    pub entries_len: usize,
+}
+
+impl LockupPoolShard {
+    /// The length of the header.
+    pub const LEN: usize = std::mem::size_of::<LockupPoolShard>();
+
+    /// The account length of a shard with the provided capacity.
+    #[allow(clippy::arithmetic_side_effects)]
+    pub const fn account_len(capacity: usize) -> usize {
+        Self::LEN + capacity * std::mem::size_of::<LockupPoolEntry>()
+    }
+
+    /// Splits lockup pool shard account data into its header & entries.
+    pub fn unpack(data: &[u8]) -> Result<(&Self, &[LockupPoolEntry]), ProgramError> {
+        split_entries(data)
+    }
+
+    /// Splits lockup pool shard account data into its header & entries,
+    /// mutably.
+    pub fn unpack_mut(data: &mut [u8]) -> Result<LockupPoolShardMut<'_>, ProgramError> {
+        let (header, entries) = split_entries_mut(data)?;
+
+        Ok(LockupPoolShardMut { header, entries })
+    }
+}
+
+/// Mutable view of a lockup pool shard account.
+pub struct LockupPoolShardMut<'a> {
+    pub header: &'a mut LockupPoolShard,
+    pub entries: &'a mut [LockupPoolEntry],
+}
+
+impl LockupPoolShardMut<'_> {
+    /// The shard's entries.
+    pub fn entries_mut(&mut self) -> LockupPoolEntries<'_> {
+        LockupPoolEntries {
+            entries_len: &mut self.header.entries_len,
+            entries: self.entries,
+        }
+    }
+}
+
+/// Lockup pool snapshot account header.
+///
+/// # Note
+///
+/// The header is followed by a copy of the pool's entries at the time of the
+/// snapshot, including those of its shards, in descending order of amount.
+///
+/// Until the snapshot is taken, the discriminator stays uninitialized, and
+/// only the pool, rent payer & epoch are recorded.
+#[derive(Clone, Copy, Debug, PartialEq, Pod, ShankAccount, SplDiscriminate, Zeroable)]
+#[discriminator_hash_input("lockup::state::lockup_pool_snapshot")]
+#[repr(C)]
+pub struct LockupPoolSnapshot {
+    pub discriminator: [u8; 8],
+    /// The pool this snapshot was taken of.
+    pub pool: Pubkey,
+    /// The account that started the snapshot, which may close it to reclaim
+    /// its rent.
+    pub rent_payer: Pubkey,
+    /// The slot the snapshot was taken at.
+    pub slot: u64,
+    /// The epoch the snapshot was taken in.
+    pub epoch: u64,
+    /// The time the snapshot was taken at.
+    pub timestamp: u64,
+    /// The number of entries.
+    pub entries_len: usize,
+}
+
+impl LockupPoolSnapshot {
+    /// The length of the header.
+    pub const LEN: usize = std::mem::size_of::<LockupPoolSnapshot>();
+
+    /// The account length of a snapshot with the provided number of entries.
+    #[allow(clippy::arithmetic_side_effects)]
+    pub const fn account_len(entries_len: usize) -> usize {
+        Self::LEN + entries_len * std::mem::size_of::<LockupPoolEntry>()
+    }
+
+    /// Splits lockup pool snapshot account data into its header & entries.
+    pub fn unpack(data: &[u8]) -> Result<(&Self, &[LockupPoolEntry]), ProgramError> {
+        split_entries(data)
+    }
+
+    /// Splits lockup pool snapshot account data into its header & entries,
+    /// mutably.
+    pub fn unpack_mut(
+        data: &mut [u8],
+    ) -> Result<(&mut Self, &mut [LockupPoolEntry]), ProgramError> {
+        split_entries_mut(data)
+    }
+}
+
+/// Lockup pool Merkle root account.
+///
+/// Commits to the pool's entries, including those of its shards, in descending
+/// order of amount, so other programs can check a lockup's rank & amount
+/// without reading the pool itself (see `merkle`).
+#[derive(Clone, Copy, Debug, PartialEq, Pod, ShankAccount, SplDiscriminate, Zeroable)]
+#[discriminator_hash_input("lockup::state::lockup_pool_root")]
+#[repr(C)]
+pub struct LockupPoolRoot {
+    pub discriminator: [u8; 8],
+    /// The pool the root commits to.
+    pub pool: Pubkey,
+    /// The slot the root was computed at.
+    pub slot: u64,
+    /// The number of entries the root commits to.
+    pub entries_len: usize,
+    /// The Merkle root of the entries.
+    pub root: [u8; 32],
+}
+
+impl LockupPoolRoot {
+    /// The length of the account.
+    pub const LEN: usize = std::mem::size_of::<LockupPoolRoot>();
+
+    /// Verifies that the provided entry is the pool's `index`th largest entry,
+    /// using a proof built by `merkle::build_proof`.
+    pub fn verify(&self, index: usize, entry: &LockupPoolEntry, proof: &[[u8; 32]]) -> bool {
+        merkle::verify_proof(&self.root, self.entries_len, index, entry, proof)
+    }
 }
 
 /// Lockup entry in the lockup pool.
@@ -83,8 +1079,148 @@ pub struct Lockup {
     /// Additional metadata, may contain an address or any other bytes (like an
     /// IP address).
     pub metadata: [u8; 32],
+    /// The authority proposed to take over the lockup, if any.
+    ///
+    /// # Note
+    ///
+    /// Set to the default pubkey when no transfer is pending. The proposed
+    /// authority has no control over the lockup until it accepts.
+    pub proposed_authority: Pubkey,
+    /// The earliest time the lockup may be unlocked, if it was created with a
+    /// fixed term.
+    pub committed_end_timestamp: Option<NonZeroU64>,
+    /// The schedule tokens are released on, if this is a vesting lockup.
+    ///
+    /// # Note
+    ///
+    /// Zeroed for regular lockups.
+    pub vesting: VestingSchedule,
+    /// Amount of tokens already withdrawn from a vesting lockup.
+    pub withdrawn_amount: u64,
+    /// The last time the lockup's metadata was updated, or zero if it never
+    /// was.
+    pub metadata_updated_timestamp: u64,
+    /// Lockup flags, see `Lockup::FLAG_*`.
+    pub flags: u64,
+    /// The pool's reward-per-token index as of the lockup's last reward
+    /// checkpoint.
+    pub reward_per_token_checkpoint: PodU128,
+    /// Rewards earned up to the lockup's last reward checkpoint & not yet
+    /// claimed.
+    pub unclaimed_rewards: u64,
+    /// When the lockup's cooldown ends & its tokens may be withdrawn, fixed
+    /// when it is unlocked.
+    ///
+    /// # Note
+    ///
+    /// Later changes to the pool's cooldown do not affect unlocked lockups.
+    pub cooldown_end_timestamp: Option<NonZeroU64>,
 }
 
 impl Lockup {
     pub const LEN: usize = std::mem::size_of::<Lockup>();
+    /// The length of lockup accounts created before lockups tracked their
+    /// authority transfers, vesting, flags & rewards, see `MigrateLockup`.
+    ///
+    /// # Note
+    ///
+    /// The legacy layout is a prefix of the current one, ending before the
+    /// proposed authority. Other instructions reject legacy lockups with
+    /// `PaladinLockupError::LockupNotMigrated`.
+    pub const LEGACY_LEN: usize = 160;
+
+    /// Set while the lockup holds an entry in its pool.
+    ///
+    /// # Note
+    ///
+    /// Instructions evicting a lockup from the pool require its account and
+    /// clear this flag.
+    pub const FLAG_IN_POOL: u64 = 1 << 0;
+
+    /// Whether the lockup releases its tokens on a vesting schedule.
+    pub fn is_vesting(&self) -> bool {
+        self.vesting != VestingSchedule::default()
+    }
+
+    /// Whether the lockup holds an entry in its pool.
+    pub fn in_pool(&self) -> bool {
+        self.flags & Self::FLAG_IN_POOL != 0
+    }
+
+    /// The amount of tokens earning rewards, i.e. the lockup's amount until
+    /// it is unlocked.
+    pub fn reward_amount(&self) -> u64 {
+        match self.lockup_end_timestamp {
+            None => self.amount,
+            Some(_) => 0,
+        }
+    }
+
+    /// The rewards the lockup can claim, given the pool's current
+    /// reward-per-token index.
+    pub fn pending_rewards(&self, reward_per_token: PodU128) -> u64 {
+        let index_increase =
+            u128::from(reward_per_token).wrapping_sub(self.reward_per_token_checkpoint.into());
+        let earned = u128::from(self.reward_amount())
+            .saturating_mul(index_increase)
+            .checked_div(LockupPoolRewards::REWARD_PER_TOKEN_SCALE)
+            .unwrap_or(0);
+
+        self.unclaimed_rewards
+            .saturating_add(u64::try_from(earned).unwrap_or(u64::MAX))
+    }
+
+    /// Checkpoints the lockup's rewards at the pool's current reward-per-token
+    /// index. Must be called before changing the lockup's reward amount.
+    pub(crate) fn accrue_rewards(&mut self, reward_per_token: PodU128) {
+        self.unclaimed_rewards = self.pending_rewards(reward_per_token);
+        self.reward_per_token_checkpoint = reward_per_token;
+    }
+}
+
+/// A linear vesting schedule.
+#[derive(Default, Clone, Copy, Debug, PartialEq, ShankType, Pod, Zeroable)]
+#[repr(C)]
+pub struct VestingSchedule {
+    /// The time vesting starts accruing from.
+    pub start_timestamp: u64,
+    /// The time before which nothing is vested.
+    pub cliff_timestamp: u64,
+    /// The time everything is vested.
+    pub end_timestamp: u64,
+    /// The length of each vesting step, or zero to vest continuously.
+    pub period_seconds: u64,
+}
+
+impl VestingSchedule {
+    /// Whether the schedule is well formed, i.e.
+    /// `start <= cliff <= end`, `start < end` and the period fits within the
+    /// schedule.
+    pub fn is_valid(&self) -> bool {
+        self.start_timestamp < self.end_timestamp
+            && self.start_timestamp <= self.cliff_timestamp
+            && self.cliff_timestamp <= self.end_timestamp
+            && self.period_seconds <= self.end_timestamp.saturating_sub(self.start_timestamp)
+    }
+
+    /// Returns how much of `total_amount` has vested at `timestamp`.
+    pub fn vested_amount(&self, total_amount: u64, timestamp: u64) -> u64 {
+        if timestamp < self.cliff_timestamp {
+            return 0;
+        }
+        if timestamp >= self.end_timestamp {
+            return total_amount;
+        }
+
+        // Round the elapsed time down to a whole number of periods.
+        let elapsed = timestamp.saturating_sub(self.start_timestamp);
+        let elapsed = elapsed.saturating_sub(elapsed.checked_rem(self.period_seconds).unwrap_or(0));
+        let duration = self.end_timestamp.saturating_sub(self.start_timestamp);
+
+        u128::from(total_amount)
+            .saturating_mul(u128::from(elapsed))
+            .checked_div(u128::from(duration))
+            .and_then(|vested| u64::try_from(vested).ok())
+            .unwrap_or(0)
+    }
 }
@@ -0,0 +1,350 @@
+#![cfg(feature = "test-sbf")]
+
+mod setup;
+
+use {
+    paladin_lockup_program::{
+        error::PaladinLockupError,
+        state::{LockupPool, LockupPoolEntry, LockupPoolShard, LockupPoolShardInfo},
+    },
+    setup::{
+        lockup_pool_data, lockup_pool_state, setup, setup_lockup_pool_shard,
+        setup_lockup_pool_with_data, setup_lockup_pool_with_state,
+    },
+    solana_program_test::*,
+    solana_sdk::{
+        instruction::InstructionError,
+        pubkey::Pubkey,
+        signature::Keypair,
+        signer::Signer,
+        transaction::{Transaction, TransactionError},
+    },
+    test_case::test_case,
+};
+
+fn pool_entry(amount: u64) -> LockupPoolEntry {
+    LockupPoolEntry {
+        lockup: Pubkey::new_unique(),
+        amount,
+        metadata: [0; 32],
+    }
+}
+
+async fn setup_uninitialized_shard(context: &mut ProgramTestContext, address: &Pubkey) {
+    setup_lockup_pool_with_data(context, address, vec![0; LockupPoolShard::account_len(8)]).await;
+}
+
+async fn get_lockup_pool_state(
+    context: &mut ProgramTestContext,
+    pool: &Pubkey,
+) -> (LockupPool, Vec<LockupPoolEntry>) {
+    let account = context
+        .banks_client
+        .get_account(*pool)
+        .await
+        .unwrap()
+        .unwrap();
+    let (lockup_pool, entries) = LockupPool::unpack(&account.data).unwrap();
+    (*lockup_pool, entries[..lockup_pool.entries_len].to_vec())
+}
+
+async fn get_lockup_pool_shard_state(
+    context: &mut ProgramTestContext,
+    shard: &Pubkey,
+) -> (LockupPoolShard, Vec<LockupPoolEntry>) {
+    let account = context
+        .banks_client
+        .get_account(*shard)
+        .await
+        .unwrap()
+        .unwrap();
+    let (shard, entries) = LockupPoolShard::unpack(&account.data).unwrap();
+    (*shard, entries[..shard.entries_len].to_vec())
+}
+
+#[test_case(Pubkey::new_unique(); "incorrect_authority")]
+#[test_case(Pubkey::default(); "no_authority")]
+#[tokio::test]
+async fn fail_incorrect_authority(authority: Pubkey) {
+    let mut context = setup().start_with_context().await;
+
+    let pool_authority = Keypair::new();
+    let mint = Pubkey::new_unique();
+
+    // Create the lockup pool account.
+    let pool = Pubkey::new_unique();
+    let state = LockupPool {
+        authority,
+        ..lockup_pool_state(mint)
+    };
+    setup_lockup_pool_with_state(&mut context, &pool, &state).await;
+
+    let shard = Pubkey::new_unique();
+    setup_uninitialized_shard(&mut context, &shard).await;
+
+    let instruction = paladin_lockup_program::instruction::add_lockup_pool_shard(
+        &pool_authority.pubkey(),
+        pool,
+        &state,
+        &shard,
+        1_000,
+    );
+
+    let transaction = Transaction::new_signed_with_payer(
+        &[instruction],
+        Some(&context.payer.pubkey()),
+        &[&context.payer, &pool_authority],
+        context.last_blockhash,
+    );
+
+    let err = context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap_err()
+        .unwrap();
+
+    assert_eq!(
+        err,
+        TransactionError::InstructionError(0, InstructionError::IncorrectAuthority)
+    );
+}
+
+#[test_case(0; "zero")]
+#[test_case(1_000; "not_below_last_shard")]
+#[tokio::test]
+async fn fail_invalid_shard_boundary(max_amount: u64) {
+    let mut context = setup().start_with_context().await;
+
+    let pool_authority = Keypair::new();
+    let mint = Pubkey::new_unique();
+
+    // Create the lockup pool account, with a shard covering amounts below
+    // 1,000.
+    let pool = Pubkey::new_unique();
+    let last_shard = Pubkey::new_unique();
+    let mut state = LockupPool {
+        authority: pool_authority.pubkey(),
+        shards_len: 1,
+        ..lockup_pool_state(mint)
+    };
+    state.shards[0] = LockupPoolShardInfo {
+        address: last_shard,
+        max_amount: 1_000,
+    };
+    setup_lockup_pool_with_state(&mut context, &pool, &state).await;
+    setup_lockup_pool_shard(&mut context, &last_shard, &pool, &[], 8).await;
+
+    let shard = Pubkey::new_unique();
+    setup_uninitialized_shard(&mut context, &shard).await;
+
+    let instruction = paladin_lockup_program::instruction::add_lockup_pool_shard(
+        &pool_authority.pubkey(),
+        pool,
+        &state,
+        &shard,
+        max_amount,
+    );
+
+    let transaction = Transaction::new_signed_with_payer(
+        &[instruction],
+        Some(&context.payer.pubkey()),
+        &[&context.payer, &pool_authority],
+        context.last_blockhash,
+    );
+
+    let err = context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap_err()
+        .unwrap();
+
+    assert_eq!(
+        err,
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(PaladinLockupError::InvalidShardBoundary as u32)
+        )
+    );
+}
+
+#[tokio::test]
+async fn fail_shard_already_initialized() {
+    let mut context = setup().start_with_context().await;
+
+    let pool_authority = Keypair::new();
+    let mint = Pubkey::new_unique();
+
+    // Create the lockup pool account.
+    let pool = Pubkey::new_unique();
+    let state = LockupPool {
+        authority: pool_authority.pubkey(),
+        ..lockup_pool_state(mint)
+    };
+    setup_lockup_pool_with_state(&mut context, &pool, &state).await;
+
+    // Initialize the shard account.
+    let shard = Pubkey::new_unique();
+    setup_lockup_pool_shard(&mut context, &shard, &pool, &[], 8).await;
+
+    let instruction = paladin_lockup_program::instruction::add_lockup_pool_shard(
+        &pool_authority.pubkey(),
+        pool,
+        &state,
+        &shard,
+        1_000,
+    );
+
+    let transaction = Transaction::new_signed_with_payer(
+        &[instruction],
+        Some(&context.payer.pubkey()),
+        &[&context.payer, &pool_authority],
+        context.last_blockhash,
+    );
+
+    let err = context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap_err()
+        .unwrap();
+
+    assert_eq!(
+        err,
+        TransactionError::InstructionError(0, InstructionError::AccountAlreadyInitialized)
+    );
+}
+
+#[tokio::test]
+async fn fail_shard_capacity_exceeded() {
+    let mut context = setup().start_with_context().await;
+
+    let pool_authority = Keypair::new();
+    let mint = Pubkey::new_unique();
+
+    // Create the lockup pool account, with more entries below the boundary
+    // than the shard can hold.
+    let pool = Pubkey::new_unique();
+    let entries = (1..=10).rev().map(pool_entry).collect::<Vec<_>>();
+    let state = LockupPool {
+        authority: pool_authority.pubkey(),
+        ..lockup_pool_state(mint)
+    };
+    let data = lockup_pool_data(&state, &entries, LockupPool::INITIAL_CAPACITY);
+    setup_lockup_pool_with_data(&mut context, &pool, data).await;
+
+    let shard = Pubkey::new_unique();
+    setup_uninitialized_shard(&mut context, &shard).await;
+
+    let instruction = paladin_lockup_program::instruction::add_lockup_pool_shard(
+        &pool_authority.pubkey(),
+        pool,
+        &state,
+        &shard,
+        1_000,
+    );
+
+    let transaction = Transaction::new_signed_with_payer(
+        &[instruction],
+        Some(&context.payer.pubkey()),
+        &[&context.payer, &pool_authority],
+        context.last_blockhash,
+    );
+
+    let err = context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap_err()
+        .unwrap();
+
+    assert_eq!(
+        err,
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(PaladinLockupError::ShardCapacityExceeded as u32)
+        )
+    );
+}
+
+#[test_case(false; "first_shard")]
+#[test_case(true; "second_shard")]
+#[tokio::test]
+async fn success(has_shard: bool) {
+    let mut context = setup().start_with_context().await;
+
+    let pool_authority = Keypair::new();
+    let mint = Pubkey::new_unique();
+
+    let entries = [500, 400, 300, 200, 100].map(pool_entry);
+
+    // Create the lockup pool account, either holding all entries or sharing
+    // them with an existing shard.
+    let pool = Pubkey::new_unique();
+    let last_shard = Pubkey::new_unique();
+    let (mut state, root_len) = match has_shard {
+        false => (lockup_pool_state(mint), entries.len()),
+        true => {
+            let mut state = lockup_pool_state(mint);
+            state.shards[0] = LockupPoolShardInfo {
+                address: last_shard,
+                max_amount: 350,
+            };
+            state.shards_len = 1;
+            setup_lockup_pool_shard(&mut context, &last_shard, &pool, &entries[2..], 8).await;
+            (state, 2)
+        }
+    };
+    state.authority = pool_authority.pubkey();
+    let data = lockup_pool_data(&state, &entries[..root_len], LockupPool::INITIAL_CAPACITY);
+    setup_lockup_pool_with_data(&mut context, &pool, data).await;
+
+    let shard = Pubkey::new_unique();
+    setup_uninitialized_shard(&mut context, &shard).await;
+
+    let max_amount = 250;
+    let instruction = paladin_lockup_program::instruction::add_lockup_pool_shard(
+        &pool_authority.pubkey(),
+        pool,
+        &state,
+        &shard,
+        max_amount,
+    );
+
+    let transaction = Transaction::new_signed_with_payer(
+        &[instruction],
+        Some(&context.payer.pubkey()),
+        &[&context.payer, &pool_authority],
+        context.last_blockhash,
+    );
+
+    context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap();
+
+    // The shard was registered with the pool.
+    let (lockup_pool, pool_entries) = get_lockup_pool_state(&mut context, &pool).await;
+    assert_eq!(lockup_pool.shards().last().unwrap().address, shard);
+    assert_eq!(lockup_pool.shards().last().unwrap().max_amount, max_amount);
+
+    // The entries below the boundary were moved into the shard.
+    let (shard_state, shard_entries) = get_lockup_pool_shard_state(&mut context, &shard).await;
+    assert_eq!(shard_state.pool, pool);
+    assert_eq!(shard_entries, &entries[3..]);
+    match has_shard {
+        false => {
+            assert_eq!(lockup_pool.shards_len, 1);
+            assert_eq!(pool_entries, &entries[..3]);
+        }
+        true => {
+            assert_eq!(lockup_pool.shards_len, 2);
+            assert_eq!(pool_entries, &entries[..2]);
+            let (_, last_shard_entries) =
+                get_lockup_pool_shard_state(&mut context, &last_shard).await;
+            assert_eq!(last_shard_entries, &entries[2..3]);
+        }
+    }
+}
@@ -0,0 +1,356 @@
+#![cfg(feature = "test-sbf")]
+
+mod setup;
+
+use {
+    paladin_lockup_program::{
+        error::PaladinLockupError,
+        state::{
+            get_reward_authority_address, Lockup, LockupPool, LockupPoolRewards, PodU128,
+            VestingSchedule,
+        },
+    },
+    setup::{
+        lockup_pool_state, setup, setup_lockup, setup_lockup_pool_with_state, setup_mint,
+        setup_token_account,
+    },
+    solana_program_test::*,
+    solana_sdk::{
+        account::Account,
+        clock::Clock,
+        instruction::InstructionError,
+        pubkey::Pubkey,
+        signature::Keypair,
+        signer::Signer,
+        transaction::{Transaction, TransactionError},
+    },
+    spl_associated_token_account::get_associated_token_address_with_program_id,
+    spl_discriminator::SplDiscriminate,
+    spl_token_2022::{extension::StateWithExtensions, state::Account as TokenAccount},
+};
+
+fn get_token_account_balance(token_account: &Account) -> u64 {
+    StateWithExtensions::<TokenAccount>::unpack(&token_account.data)
+        .unwrap()
+        .base
+        .amount
+}
+
+/// Sets up a lockup pool with the provided reward index & flags, a funded
+/// reward vault, and a lockup checkpointed at the start of the index.
+async fn setup_rewards(
+    context: &mut ProgramTestContext,
+    pool: &Pubkey,
+    lockup: &Pubkey,
+    mint: &Pubkey,
+    authority: &Pubkey,
+    reward_per_token: u128,
+    flags: u64,
+) -> (Pubkey, Pubkey) {
+    let clock = context.banks_client.get_sysvar::<Clock>().await.unwrap();
+
+    setup_lockup_pool_with_state(
+        context,
+        pool,
+        &LockupPool {
+            rewards: LockupPoolRewards {
+                reward_per_token: reward_per_token.into(),
+                deposited_amount: 10_000,
+                claimed_amount: 0,
+                ..LockupPoolRewards::default()
+            },
+            flags,
+            ..lockup_pool_state(*mint)
+        },
+    )
+    .await;
+
+    setup_lockup(
+        context,
+        lockup,
+        Lockup {
+            discriminator: Lockup::SPL_DISCRIMINATOR.into(),
+            amount: 10_000,
+            authority: *authority,
+            lockup_start_timestamp: clock.unix_timestamp as u64,
+            lockup_end_timestamp: None,
+            mint: *mint,
+            pool: *pool,
+            metadata: [0; 32],
+            proposed_authority: Pubkey::default(),
+            committed_end_timestamp: None,
+            vesting: VestingSchedule::default(),
+            withdrawn_amount: 0,
+            metadata_updated_timestamp: 0,
+            flags: 0,
+            reward_per_token_checkpoint: PodU128::default(),
+            unclaimed_rewards: 0,
+            cooldown_end_timestamp: None,
+        },
+    )
+    .await;
+
+    let token_destination =
+        get_associated_token_address_with_program_id(authority, mint, &spl_token_2022::id());
+    setup_token_account(context, &token_destination, authority, mint, 0).await;
+
+    let reward_authority = get_reward_authority_address(pool, &paladin_lockup_program::id());
+    let reward_vault = get_associated_token_address_with_program_id(
+        &reward_authority,
+        mint,
+        &spl_token_2022::id(),
+    );
+    setup_token_account(context, &reward_vault, &reward_authority, mint, 10_000).await;
+
+    setup_mint(context, mint, &Pubkey::new_unique(), 1_000_000).await;
+
+    (token_destination, reward_vault)
+}
+
+#[tokio::test]
+async fn fail_incorrect_lockup_authority() {
+    let mint = Pubkey::new_unique();
+    let authority = Keypair::new();
+    let lockup = Pubkey::new_unique();
+
+    let mut context = setup().start_with_context().await;
+
+    // Create the lockup with a different authority.
+    let pool = Pubkey::new_unique();
+    let (token_destination, _) = setup_rewards(
+        &mut context,
+        &pool,
+        &lockup,
+        &mint,
+        &Pubkey::new_unique(),
+        LockupPoolRewards::REWARD_PER_TOKEN_SCALE / 10,
+        0,
+    )
+    .await;
+
+    let instruction = paladin_lockup_program::instruction::claim_rewards(
+        &authority.pubkey(),
+        &token_destination,
+        pool,
+        &lockup,
+        &mint,
+        &spl_token_2022::id(),
+    );
+
+    let transaction = Transaction::new_signed_with_payer(
+        &[instruction],
+        Some(&context.payer.pubkey()),
+        &[&context.payer, &authority],
+        context.last_blockhash,
+    );
+
+    let err = context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap_err()
+        .unwrap();
+
+    assert_eq!(
+        err,
+        TransactionError::InstructionError(0, InstructionError::IncorrectAuthority)
+    );
+}
+
+#[tokio::test]
+async fn fail_no_rewards() {
+    let mint = Pubkey::new_unique();
+    let authority = Keypair::new();
+    let lockup = Pubkey::new_unique();
+
+    let mut context = setup().start_with_context().await;
+
+    // No rewards were distributed.
+    let pool = Pubkey::new_unique();
+    let (token_destination, _) = setup_rewards(
+        &mut context,
+        &pool,
+        &lockup,
+        &mint,
+        &authority.pubkey(),
+        0,
+        0,
+    )
+    .await;
+
+    let instruction = paladin_lockup_program::instruction::claim_rewards(
+        &authority.pubkey(),
+        &token_destination,
+        pool,
+        &lockup,
+        &mint,
+        &spl_token_2022::id(),
+    );
+
+    let transaction = Transaction::new_signed_with_payer(
+        &[instruction],
+        Some(&context.payer.pubkey()),
+        &[&context.payer, &authority],
+        context.last_blockhash,
+    );
+
+    let err = context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap_err()
+        .unwrap();
+
+    assert_eq!(
+        err,
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(PaladinLockupError::NoRewards as u32)
+        )
+    );
+}
+
+#[tokio::test]
+async fn fail_withdrawals_paused() {
+    let mint = Pubkey::new_unique();
+    let authority = Keypair::new();
+    let lockup = Pubkey::new_unique();
+
+    let mut context = setup().start_with_context().await;
+
+    // Pause withdrawals, which also pauses reward claims.
+    let pool = Pubkey::new_unique();
+    let (token_destination, _) = setup_rewards(
+        &mut context,
+        &pool,
+        &lockup,
+        &mint,
+        &authority.pubkey(),
+        LockupPoolRewards::REWARD_PER_TOKEN_SCALE / 10,
+        LockupPool::FLAG_WITHDRAW_PAUSED,
+    )
+    .await;
+
+    let instruction = paladin_lockup_program::instruction::claim_rewards(
+        &authority.pubkey(),
+        &token_destination,
+        pool,
+        &lockup,
+        &mint,
+        &spl_token_2022::id(),
+    );
+
+    let transaction = Transaction::new_signed_with_payer(
+        &[instruction],
+        Some(&context.payer.pubkey()),
+        &[&context.payer, &authority],
+        context.last_blockhash,
+    );
+
+    let err = context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap_err()
+        .unwrap();
+
+    assert_eq!(
+        err,
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(PaladinLockupError::PoolPaused as u32)
+        )
+    );
+}
+
+#[tokio::test]
+async fn success() {
+    let mint = Pubkey::new_unique();
+    let authority = Keypair::new();
+    let lockup = Pubkey::new_unique();
+
+    let mut context = setup().start_with_context().await;
+
+    // 0.25 tokens of rewards per locked token.
+    let pool = Pubkey::new_unique();
+    let (token_destination, reward_vault) = setup_rewards(
+        &mut context,
+        &pool,
+        &lockup,
+        &mint,
+        &authority.pubkey(),
+        LockupPoolRewards::REWARD_PER_TOKEN_SCALE / 4,
+        0,
+    )
+    .await;
+
+    let instruction = paladin_lockup_program::instruction::claim_rewards(
+        &authority.pubkey(),
+        &token_destination,
+        pool,
+        &lockup,
+        &mint,
+        &spl_token_2022::id(),
+    );
+
+    let transaction = Transaction::new_signed_with_payer(
+        &[instruction],
+        Some(&context.payer.pubkey()),
+        &[&context.payer, &authority],
+        context.last_blockhash,
+    );
+
+    context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap();
+
+    // Check the lockup was checkpointed.
+    let account = context
+        .banks_client
+        .get_account(lockup)
+        .await
+        .unwrap()
+        .unwrap();
+    let lockup_state = bytemuck::from_bytes::<Lockup>(&account.data);
+    assert_eq!(
+        u128::from(lockup_state.reward_per_token_checkpoint),
+        LockupPoolRewards::REWARD_PER_TOKEN_SCALE / 4
+    );
+    assert_eq!(lockup_state.unclaimed_rewards, 0);
+
+    // Check the pool's claimed rewards.
+    let account = context
+        .banks_client
+        .get_account(pool)
+        .await
+        .unwrap()
+        .unwrap();
+    let (lockup_pool, _) = LockupPool::unpack(&account.data).unwrap();
+    assert_eq!(lockup_pool.rewards.claimed_amount, 2_500);
+
+    // Check the resulting token account balances.
+    assert_eq!(
+        get_token_account_balance(
+            &context
+                .banks_client
+                .get_account(token_destination)
+                .await
+                .unwrap()
+                .unwrap()
+        ),
+        2_500
+    );
+    assert_eq!(
+        get_token_account_balance(
+            &context
+                .banks_client
+                .get_account(reward_vault)
+                .await
+                .unwrap()
+                .unwrap()
+        ),
+        7_500
+    );
+}
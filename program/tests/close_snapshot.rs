@@ -0,0 +1,289 @@
+#![cfg(feature = "test-sbf")]
+
+mod setup;
+
+use {
+    paladin_lockup_program::{
+        error::PaladinLockupError,
+        state::{get_lockup_pool_snapshot_address, LockupPoolEntry, LockupPoolSnapshot},
+    },
+    setup::{setup, setup_lockup_pool_with_data},
+    solana_program_test::*,
+    solana_sdk::{
+        clock::Clock,
+        entrypoint::MAX_PERMITTED_DATA_INCREASE,
+        instruction::InstructionError,
+        pubkey::Pubkey,
+        signature::Keypair,
+        signer::Signer,
+        transaction::{Transaction, TransactionError},
+    },
+    spl_discriminator::SplDiscriminate,
+    test_case::test_case,
+};
+
+/// Sets up a snapshot taken in the current epoch.
+async fn setup_snapshot(context: &mut ProgramTestContext, address: &Pubkey, rent_payer: &Pubkey) {
+    let clock = context.banks_client.get_sysvar::<Clock>().await.unwrap();
+    let entries = [LockupPoolEntry {
+        lockup: Pubkey::new_unique(),
+        amount: 10_000,
+        metadata: [0; 32],
+    }];
+
+    let mut data = vec![0; LockupPoolSnapshot::account_len(entries.len())];
+    let (snapshot, snapshot_entries) = LockupPoolSnapshot::unpack_mut(&mut data).unwrap();
+    *snapshot = LockupPoolSnapshot {
+        discriminator: LockupPoolSnapshot::SPL_DISCRIMINATOR.into(),
+        pool: Pubkey::new_unique(),
+        rent_payer: *rent_payer,
+        slot: clock.slot,
+        epoch: clock.epoch,
+        timestamp: clock.unix_timestamp as u64,
+        entries_len: entries.len(),
+    };
+    snapshot_entries.copy_from_slice(&entries);
+    setup_lockup_pool_with_data(context, address, data).await;
+}
+
+/// Sets up a snapshot started, but not completed, in the current epoch.
+async fn setup_incomplete_snapshot(
+    context: &mut ProgramTestContext,
+    pool: &Pubkey,
+    rent_payer: &Pubkey,
+) -> Pubkey {
+    let clock = context.banks_client.get_sysvar::<Clock>().await.unwrap();
+    let address =
+        get_lockup_pool_snapshot_address(pool, clock.epoch, &paladin_lockup_program::id());
+
+    let mut data = vec![0; MAX_PERMITTED_DATA_INCREASE];
+    let snapshot =
+        bytemuck::from_bytes_mut::<LockupPoolSnapshot>(&mut data[..LockupPoolSnapshot::LEN]);
+    snapshot.pool = *pool;
+    snapshot.rent_payer = *rent_payer;
+    snapshot.epoch = clock.epoch;
+    setup_lockup_pool_with_data(context, &address, data).await;
+
+    address
+}
+
+async fn advance_epoch(context: &mut ProgramTestContext) {
+    let mut clock = context.banks_client.get_sysvar::<Clock>().await.unwrap();
+    clock.epoch = clock.epoch.saturating_add(1);
+    context.set_sysvar(&clock);
+}
+
+#[tokio::test]
+async fn fail_rent_payer_not_signer() {
+    let mut context = setup().start_with_context().await;
+
+    let rent_payer = Keypair::new();
+    let snapshot = Pubkey::new_unique();
+    setup_snapshot(&mut context, &snapshot, &rent_payer.pubkey()).await;
+
+    let mut instruction = paladin_lockup_program::instruction::close_snapshot(
+        &rent_payer.pubkey(),
+        &Pubkey::new_unique(),
+        &snapshot,
+    );
+    instruction.accounts[0].is_signer = false; // Not signer.
+
+    let transaction = Transaction::new_signed_with_payer(
+        &[instruction],
+        Some(&context.payer.pubkey()),
+        &[&context.payer],
+        context.last_blockhash,
+    );
+
+    let err = context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap_err()
+        .unwrap();
+
+    assert_eq!(
+        err,
+        TransactionError::InstructionError(0, InstructionError::MissingRequiredSignature)
+    );
+}
+
+#[tokio::test]
+async fn fail_snapshot_not_taken() {
+    let mut context = setup().start_with_context().await;
+
+    let rent_payer = Keypair::new();
+    let snapshot = Pubkey::new_unique();
+    setup_lockup_pool_with_data(
+        &mut context,
+        &snapshot,
+        vec![0; LockupPoolSnapshot::account_len(1)],
+    )
+    .await;
+
+    let instruction = paladin_lockup_program::instruction::close_snapshot(
+        &rent_payer.pubkey(),
+        &Pubkey::new_unique(),
+        &snapshot,
+    );
+
+    let transaction = Transaction::new_signed_with_payer(
+        &[instruction],
+        Some(&context.payer.pubkey()),
+        &[&context.payer, &rent_payer],
+        context.last_blockhash,
+    );
+
+    let err = context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap_err()
+        .unwrap();
+
+    assert_eq!(
+        err,
+        TransactionError::InstructionError(0, InstructionError::UninitializedAccount)
+    );
+}
+
+#[tokio::test]
+async fn fail_incorrect_rent_payer() {
+    let mut context = setup().start_with_context().await;
+
+    let rent_payer = Keypair::new();
+    let snapshot = Pubkey::new_unique();
+    setup_snapshot(&mut context, &snapshot, &Pubkey::new_unique()).await;
+
+    let instruction = paladin_lockup_program::instruction::close_snapshot(
+        &rent_payer.pubkey(),
+        &Pubkey::new_unique(),
+        &snapshot,
+    );
+
+    let transaction = Transaction::new_signed_with_payer(
+        &[instruction],
+        Some(&context.payer.pubkey()),
+        &[&context.payer, &rent_payer],
+        context.last_blockhash,
+    );
+
+    let err = context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap_err()
+        .unwrap();
+
+    assert_eq!(
+        err,
+        TransactionError::InstructionError(0, InstructionError::IncorrectAuthority)
+    );
+}
+
+#[test_case(true; "complete")]
+#[test_case(false; "incomplete")]
+#[tokio::test]
+async fn fail_snapshot_epoch_active(complete: bool) {
+    let mut context = setup().start_with_context().await;
+
+    let rent_payer = Keypair::new();
+    let snapshot = if complete {
+        let snapshot = Pubkey::new_unique();
+        setup_snapshot(&mut context, &snapshot, &rent_payer.pubkey()).await;
+        snapshot
+    } else {
+        setup_incomplete_snapshot(&mut context, &Pubkey::new_unique(), &rent_payer.pubkey()).await
+    };
+
+    let instruction = paladin_lockup_program::instruction::close_snapshot(
+        &rent_payer.pubkey(),
+        &Pubkey::new_unique(),
+        &snapshot,
+    );
+
+    let transaction = Transaction::new_signed_with_payer(
+        &[instruction],
+        Some(&context.payer.pubkey()),
+        &[&context.payer, &rent_payer],
+        context.last_blockhash,
+    );
+
+    let err = context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap_err()
+        .unwrap();
+
+    assert_eq!(
+        err,
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(PaladinLockupError::SnapshotEpochActive as u32)
+        )
+    );
+}
+
+#[test_case(true; "complete")]
+#[test_case(false; "incomplete")]
+#[tokio::test]
+async fn success(complete: bool) {
+    let mut context = setup().start_with_context().await;
+
+    let rent_payer = Keypair::new();
+    let lamport_destination = Pubkey::new_unique();
+    let snapshot = if complete {
+        let snapshot = Pubkey::new_unique();
+        setup_snapshot(&mut context, &snapshot, &rent_payer.pubkey()).await;
+        snapshot
+    } else {
+        setup_incomplete_snapshot(&mut context, &Pubkey::new_unique(), &rent_payer.pubkey()).await
+    };
+    advance_epoch(&mut context).await;
+
+    let snapshot_lamports = context
+        .banks_client
+        .get_account(snapshot)
+        .await
+        .unwrap()
+        .unwrap()
+        .lamports;
+
+    let instruction = paladin_lockup_program::instruction::close_snapshot(
+        &rent_payer.pubkey(),
+        &lamport_destination,
+        &snapshot,
+    );
+
+    let transaction = Transaction::new_signed_with_payer(
+        &[instruction],
+        Some(&context.payer.pubkey()),
+        &[&context.payer, &rent_payer],
+        context.last_blockhash,
+    );
+
+    context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap();
+
+    // The snapshot was closed, returning its rent.
+    assert!(context
+        .banks_client
+        .get_account(snapshot)
+        .await
+        .unwrap()
+        .is_none());
+    assert_eq!(
+        context
+            .banks_client
+            .get_account(lamport_destination)
+            .await
+            .unwrap()
+            .unwrap()
+            .lamports,
+        snapshot_lamports
+    );
+}
@@ -0,0 +1,268 @@
+#![cfg(feature = "test-sbf")]
+
+mod setup;
+
+use {
+    paladin_lockup_program::{
+        error::PaladinLockupError,
+        state::{
+            get_escrow_authority_address, get_lockup_address, Lockup, LockupPool, LockupPoolEntry,
+            PodU128, VestingSchedule,
+        },
+    },
+    setup::{
+        lockup_pool_state, setup, setup_lockup_pool, setup_mint, setup_system_account,
+        setup_token_account,
+    },
+    solana_program_test::*,
+    solana_sdk::{
+        clock::Clock,
+        compute_budget::ComputeBudgetInstruction,
+        instruction::InstructionError,
+        pubkey::Pubkey,
+        rent::Rent,
+        signature::Keypair,
+        signer::Signer,
+        transaction::{Transaction, TransactionError},
+    },
+    spl_associated_token_account::get_associated_token_address_with_program_id,
+    spl_discriminator::SplDiscriminate,
+    spl_token_2022::{extension::StateWithExtensions, state::Account as TokenAccount},
+    test_case::test_case,
+};
+
+async fn check_token_account_balance(
+    context: &mut ProgramTestContext,
+    token_account_address: &Pubkey,
+    check_amount: u64,
+) {
+    let account = context
+        .banks_client
+        .get_account(*token_account_address)
+        .await
+        .expect("get_account")
+        .expect("account not found");
+    let actual_amount = StateWithExtensions::<TokenAccount>::unpack(&account.data)
+        .unwrap()
+        .base
+        .amount;
+    assert_eq!(actual_amount, check_amount);
+}
+
+#[tokio::test]
+async fn fail_incorrect_lockup_address() {
+    let rent_payer = Keypair::new();
+    let lockup_authority = Keypair::new();
+    let mint = Pubkey::new_unique();
+    let token_owner = Keypair::new();
+    let token_account = get_associated_token_address_with_program_id(
+        &token_owner.pubkey(),
+        &mint,
+        &spl_token_2022::id(),
+    );
+
+    let mut context = setup().start_with_context().await;
+    setup_system_account(&mut context, &rent_payer.pubkey(), 1_000_000_000).await;
+
+    // Create the lockup pool account.
+    let pool = Pubkey::new_unique();
+    setup_lockup_pool(&mut context, &pool, mint).await;
+
+    let mut instruction = paladin_lockup_program::instruction::create_lockup(
+        &rent_payer.pubkey(),
+        &lockup_authority.pubkey(),
+        &token_owner.pubkey(),
+        &token_account,
+        pool,
+        &lockup_pool_state(mint),
+        &[],
+        &mint,
+        Pubkey::new_unique().to_bytes(),
+        10_000,
+        None,
+        0,
+        &spl_token_2022::id(),
+        false,
+    );
+    instruction.accounts[6].pubkey = get_lockup_address(
+        &pool,
+        &lockup_authority.pubkey(),
+        1, // Incorrect nonce.
+        &paladin_lockup_program::id(),
+    );
+
+    let transaction = Transaction::new_signed_with_payer(
+        &[instruction],
+        Some(&context.payer.pubkey()),
+        &[&context.payer, &rent_payer, &token_owner],
+        context.last_blockhash,
+    );
+
+    let err = context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap_err()
+        .unwrap();
+
+    assert_eq!(
+        err,
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(PaladinLockupError::IncorrectLockupAddress as u32)
+        )
+    );
+}
+
+#[test_case(false; "new_address")]
+#[test_case(true; "prefunded_address")]
+#[tokio::test]
+async fn success(prefunded: bool) {
+    let rent_payer = Keypair::new();
+    let lockup_authority = Keypair::new();
+    let mint = Pubkey::new_unique();
+    let amount = 10_000;
+    let nonce = 42;
+
+    let token_owner = Keypair::new();
+    let token_account = get_associated_token_address_with_program_id(
+        &token_owner.pubkey(),
+        &mint,
+        &spl_token_2022::id(),
+    );
+
+    let escrow_authority = get_escrow_authority_address(&paladin_lockup_program::id());
+    let escrow_token_account = get_associated_token_address_with_program_id(
+        &escrow_authority,
+        &mint,
+        &spl_token_2022::id(),
+    );
+
+    let metadata = Pubkey::new_unique();
+
+    let mut context = setup().start_with_context().await;
+    setup_system_account(&mut context, &rent_payer.pubkey(), 1_000_000_000).await;
+    setup_token_account(
+        &mut context,
+        &token_account,
+        &token_owner.pubkey(),
+        &mint,
+        amount,
+    )
+    .await;
+    setup_token_account(
+        &mut context,
+        &escrow_token_account,
+        &escrow_authority,
+        &mint,
+        0,
+    )
+    .await;
+    setup_mint(&mut context, &mint, &Pubkey::new_unique(), 1_000_000).await;
+
+    // Create the lockup pool account.
+    let pool = Pubkey::new_unique();
+    setup_lockup_pool(&mut context, &pool, mint).await;
+
+    // Someone may have sent lamports to the lockup address ahead of time.
+    let lockup = get_lockup_address(
+        &pool,
+        &lockup_authority.pubkey(),
+        nonce,
+        &paladin_lockup_program::id(),
+    );
+    if prefunded {
+        setup_system_account(&mut context, &lockup, 0).await;
+    }
+
+    let cu_limit = ComputeBudgetInstruction::set_compute_unit_limit(400_000);
+    let instruction = paladin_lockup_program::instruction::create_lockup(
+        &rent_payer.pubkey(),
+        &lockup_authority.pubkey(),
+        &token_owner.pubkey(),
+        &token_account,
+        pool,
+        &lockup_pool_state(mint),
+        &[],
+        &mint,
+        metadata.to_bytes(),
+        amount,
+        None,
+        nonce,
+        &spl_token_2022::id(),
+        false,
+    );
+
+    let transaction = Transaction::new_signed_with_payer(
+        &[cu_limit, instruction],
+        Some(&context.payer.pubkey()),
+        &[&context.payer, &rent_payer, &token_owner],
+        context.last_blockhash,
+    );
+
+    // For checks later.
+    let clock = context.banks_client.get_sysvar::<Clock>().await.unwrap();
+
+    context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap();
+
+    // Check the lockup account was created at the derived address.
+    let lockup_account = context
+        .banks_client
+        .get_account(lockup)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(lockup_account.owner, paladin_lockup_program::id());
+    assert_eq!(
+        lockup_account.lamports,
+        Rent::default().minimum_balance(Lockup::LEN)
+    );
+    assert_eq!(
+        bytemuck::from_bytes::<Lockup>(&lockup_account.data),
+        &Lockup {
+            discriminator: Lockup::SPL_DISCRIMINATOR.into(),
+            amount,
+            authority: lockup_authority.pubkey(),
+            lockup_start_timestamp: clock.unix_timestamp as u64,
+            lockup_end_timestamp: None,
+            mint,
+            pool,
+            metadata: metadata.to_bytes(),
+            proposed_authority: Pubkey::default(),
+            committed_end_timestamp: None,
+            vesting: VestingSchedule::default(),
+            withdrawn_amount: 0,
+            metadata_updated_timestamp: 0,
+            flags: Lockup::FLAG_IN_POOL,
+            reward_per_token_checkpoint: PodU128::default(),
+            unclaimed_rewards: 0,
+            cooldown_end_timestamp: None,
+        },
+    );
+
+    // Check the lockup was entered into the pool.
+    let lockup_pool = context
+        .banks_client
+        .get_account(pool)
+        .await
+        .unwrap()
+        .unwrap();
+    let (lockup_pool, pool_entries) = LockupPool::unpack(&lockup_pool.data).unwrap();
+    assert_eq!(lockup_pool.entries_len, 1);
+    assert_eq!(
+        pool_entries[0],
+        LockupPoolEntry {
+            lockup,
+            amount,
+            metadata: metadata.to_bytes(),
+        }
+    );
+
+    // Validate tokens were transferred from the token account to the escrow.
+    check_token_account_balance(&mut context, &token_account, 0).await;
+    check_token_account_balance(&mut context, &escrow_token_account, amount).await;
+}
@@ -0,0 +1,442 @@
+#![cfg(feature = "test-sbf")]
+
+mod setup;
+
+use {
+    paladin_lockup_program::{
+        error::PaladinLockupError,
+        state::{
+            get_reward_authority_address, Lockup, LockupPool, LockupPoolRewards, LockupPoolStats,
+            PodU128, VestingSchedule,
+        },
+    },
+    setup::{
+        add_seconds_to_clock, lockup_pool_state, setup, setup_lockup, setup_lockup_pool_with_state,
+        setup_mint, setup_token_account,
+    },
+    solana_program_test::*,
+    solana_sdk::{
+        account::Account,
+        clock::Clock,
+        instruction::InstructionError,
+        pubkey::Pubkey,
+        signature::Keypair,
+        signer::Signer,
+        transaction::{Transaction, TransactionError},
+    },
+    spl_associated_token_account::get_associated_token_address_with_program_id,
+    spl_discriminator::SplDiscriminate,
+    spl_token_2022::{extension::StateWithExtensions, state::Account as TokenAccount},
+};
+
+fn get_token_account_balance(token_account: &Account) -> u64 {
+    StateWithExtensions::<TokenAccount>::unpack(&token_account.data)
+        .unwrap()
+        .base
+        .amount
+}
+
+/// Sets up a lockup pool with the provided active amount, along with the
+/// depositor's token account and the pool's reward vault.
+async fn setup_rewards(
+    context: &mut ProgramTestContext,
+    pool: &Pubkey,
+    mint: &Pubkey,
+    depositor: &Pubkey,
+    active_amount: u64,
+) -> (Pubkey, Pubkey) {
+    setup_lockup_pool_with_state(
+        context,
+        pool,
+        &LockupPool {
+            stats: LockupPoolStats {
+                active_amount,
+                active_lockups: u64::from(active_amount > 0),
+                ..LockupPoolStats::default()
+            },
+            ..lockup_pool_state(*mint)
+        },
+    )
+    .await;
+
+    let depositor_token_account =
+        get_associated_token_address_with_program_id(depositor, mint, &spl_token_2022::id());
+    setup_token_account(context, &depositor_token_account, depositor, mint, 10_000).await;
+
+    let reward_authority = get_reward_authority_address(pool, &paladin_lockup_program::id());
+    let reward_vault = get_associated_token_address_with_program_id(
+        &reward_authority,
+        mint,
+        &spl_token_2022::id(),
+    );
+    setup_token_account(context, &reward_vault, &reward_authority, mint, 0).await;
+
+    setup_mint(context, mint, &Pubkey::new_unique(), 1_000_000).await;
+
+    (depositor_token_account, reward_vault)
+}
+
+#[tokio::test]
+async fn fail_incorrect_reward_vault() {
+    let mint = Pubkey::new_unique();
+    let depositor = Keypair::new();
+
+    let mut context = setup().start_with_context().await;
+
+    let pool = Pubkey::new_unique();
+    let (depositor_token_account, _) =
+        setup_rewards(&mut context, &pool, &mint, &depositor.pubkey(), 10_000).await;
+
+    let mut instruction = paladin_lockup_program::instruction::deposit_rewards(
+        &depositor.pubkey(),
+        &depositor_token_account,
+        pool,
+        &mint,
+        1_000,
+        &spl_token_2022::id(),
+    );
+    instruction.accounts[4].pubkey = Pubkey::new_unique(); // Incorrect reward vault.
+
+    let transaction = Transaction::new_signed_with_payer(
+        &[instruction],
+        Some(&context.payer.pubkey()),
+        &[&context.payer, &depositor],
+        context.last_blockhash,
+    );
+
+    let err = context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap_err()
+        .unwrap();
+
+    assert_eq!(
+        err,
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(PaladinLockupError::IncorrectRewardVault as u32)
+        )
+    );
+}
+
+#[tokio::test]
+async fn fail_no_active_lockups() {
+    let mint = Pubkey::new_unique();
+    let depositor = Keypair::new();
+
+    let mut context = setup().start_with_context().await;
+
+    let pool = Pubkey::new_unique();
+    let (depositor_token_account, _) =
+        setup_rewards(&mut context, &pool, &mint, &depositor.pubkey(), 0).await;
+
+    let instruction = paladin_lockup_program::instruction::deposit_rewards(
+        &depositor.pubkey(),
+        &depositor_token_account,
+        pool,
+        &mint,
+        1_000,
+        &spl_token_2022::id(),
+    );
+
+    let transaction = Transaction::new_signed_with_payer(
+        &[instruction],
+        Some(&context.payer.pubkey()),
+        &[&context.payer, &depositor],
+        context.last_blockhash,
+    );
+
+    let err = context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap_err()
+        .unwrap();
+
+    assert_eq!(
+        err,
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(PaladinLockupError::NoActiveLockups as u32)
+        )
+    );
+}
+
+#[tokio::test]
+async fn fail_lockups_paused() {
+    let mint = Pubkey::new_unique();
+    let depositor = Keypair::new();
+
+    let mut context = setup().start_with_context().await;
+
+    let pool = Pubkey::new_unique();
+    let (depositor_token_account, _) =
+        setup_rewards(&mut context, &pool, &mint, &depositor.pubkey(), 10_000).await;
+
+    // Pause lockups, which also pauses reward deposits.
+    setup_lockup_pool_with_state(
+        &mut context,
+        &pool,
+        &LockupPool {
+            flags: LockupPool::FLAG_LOCKUP_PAUSED,
+            stats: LockupPoolStats {
+                active_amount: 10_000,
+                active_lockups: 1,
+                ..LockupPoolStats::default()
+            },
+            ..lockup_pool_state(mint)
+        },
+    )
+    .await;
+
+    let instruction = paladin_lockup_program::instruction::deposit_rewards(
+        &depositor.pubkey(),
+        &depositor_token_account,
+        pool,
+        &mint,
+        1_000,
+        &spl_token_2022::id(),
+    );
+
+    let transaction = Transaction::new_signed_with_payer(
+        &[instruction],
+        Some(&context.payer.pubkey()),
+        &[&context.payer, &depositor],
+        context.last_blockhash,
+    );
+
+    let err = context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap_err()
+        .unwrap();
+
+    assert_eq!(
+        err,
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(PaladinLockupError::PoolPaused as u32)
+        )
+    );
+}
+
+#[tokio::test]
+async fn success() {
+    let mint = Pubkey::new_unique();
+    let depositor = Keypair::new();
+
+    let mut context = setup().start_with_context().await;
+
+    let pool = Pubkey::new_unique();
+    let (depositor_token_account, reward_vault) =
+        setup_rewards(&mut context, &pool, &mint, &depositor.pubkey(), 4_000).await;
+
+    let clock = context.banks_client.get_sysvar::<Clock>().await.unwrap();
+
+    let instruction = paladin_lockup_program::instruction::deposit_rewards(
+        &depositor.pubkey(),
+        &depositor_token_account,
+        pool,
+        &mint,
+        1_000,
+        &spl_token_2022::id(),
+    );
+
+    let transaction = Transaction::new_signed_with_payer(
+        &[instruction],
+        Some(&context.payer.pubkey()),
+        &[&context.payer, &depositor],
+        context.last_blockhash,
+    );
+
+    context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap();
+
+    // Check the pool's reward index.
+    let account = context
+        .banks_client
+        .get_account(pool)
+        .await
+        .unwrap()
+        .unwrap();
+    let (lockup_pool, _) = LockupPool::unpack(&account.data).unwrap();
+    assert_eq!(lockup_pool.rewards.reward_per_token, PodU128::default());
+    assert_eq!(
+        u128::from(lockup_pool.rewards.reward_rate),
+        1_000 * LockupPoolRewards::REWARD_PER_TOKEN_SCALE
+            / u128::from(LockupPoolRewards::REWARD_PERIOD_SECONDS)
+    );
+    assert_eq!(
+        lockup_pool.rewards.period_end_timestamp,
+        clock.unix_timestamp as u64 + LockupPoolRewards::REWARD_PERIOD_SECONDS
+    );
+    assert_eq!(
+        lockup_pool.rewards.updated_timestamp,
+        clock.unix_timestamp as u64
+    );
+    assert_eq!(lockup_pool.rewards.deposited_amount, 1_000);
+
+    // Check the resulting token account balances.
+    assert_eq!(
+        get_token_account_balance(
+            &context
+                .banks_client
+                .get_account(depositor_token_account)
+                .await
+                .unwrap()
+                .unwrap()
+        ),
+        9_000
+    );
+    assert_eq!(
+        get_token_account_balance(
+            &context
+                .banks_client
+                .get_account(reward_vault)
+                .await
+                .unwrap()
+                .unwrap()
+        ),
+        1_000
+    );
+}
+
+#[tokio::test]
+async fn success_streamed_to_lockup_created_before_deposit() {
+    let mint = Pubkey::new_unique();
+    let depositor = Keypair::new();
+    let authority = Keypair::new();
+    let lockup = Pubkey::new_unique();
+
+    let mut context = setup().start_with_context().await;
+
+    let pool = Pubkey::new_unique();
+    let (depositor_token_account, _) =
+        setup_rewards(&mut context, &pool, &mint, &depositor.pubkey(), 4_000).await;
+
+    // The pool's only lockup, created just before the deposit.
+    let clock = context.banks_client.get_sysvar::<Clock>().await.unwrap();
+    setup_lockup(
+        &mut context,
+        &lockup,
+        Lockup {
+            discriminator: Lockup::SPL_DISCRIMINATOR.into(),
+            amount: 4_000,
+            authority: authority.pubkey(),
+            lockup_start_timestamp: clock.unix_timestamp as u64,
+            lockup_end_timestamp: None,
+            mint,
+            pool,
+            metadata: [0; 32],
+            proposed_authority: Pubkey::default(),
+            committed_end_timestamp: None,
+            vesting: VestingSchedule::default(),
+            withdrawn_amount: 0,
+            metadata_updated_timestamp: 0,
+            flags: Lockup::FLAG_IN_POOL,
+            reward_per_token_checkpoint: PodU128::default(),
+            unclaimed_rewards: 0,
+            cooldown_end_timestamp: None,
+        },
+    )
+    .await;
+
+    let token_destination = get_associated_token_address_with_program_id(
+        &authority.pubkey(),
+        &mint,
+        &spl_token_2022::id(),
+    );
+    setup_token_account(
+        &mut context,
+        &token_destination,
+        &authority.pubkey(),
+        &mint,
+        0,
+    )
+    .await;
+
+    let deposit = paladin_lockup_program::instruction::deposit_rewards(
+        &depositor.pubkey(),
+        &depositor_token_account,
+        pool,
+        &mint,
+        1_000,
+        &spl_token_2022::id(),
+    );
+    let claim = paladin_lockup_program::instruction::claim_rewards(
+        &authority.pubkey(),
+        &token_destination,
+        pool,
+        &lockup,
+        &mint,
+        &spl_token_2022::id(),
+    );
+
+    // Claiming right after the deposit yields nothing, as none of the
+    // deposit has been streamed yet.
+    let transaction = Transaction::new_signed_with_payer(
+        &[deposit],
+        Some(&context.payer.pubkey()),
+        &[&context.payer, &depositor],
+        context.last_blockhash,
+    );
+    context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap();
+
+    let transaction = Transaction::new_signed_with_payer(
+        std::slice::from_ref(&claim),
+        Some(&context.payer.pubkey()),
+        &[&context.payer, &authority],
+        context.last_blockhash,
+    );
+    let err = context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap_err()
+        .unwrap();
+    assert_eq!(
+        err,
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(PaladinLockupError::NoRewards as u32)
+        )
+    );
+
+    // Halfway through the period, half of the deposit has been streamed.
+    add_seconds_to_clock(&mut context, LockupPoolRewards::REWARD_PERIOD_SECONDS / 2).await;
+    let last_blockhash = context.get_new_latest_blockhash().await.unwrap();
+
+    let transaction = Transaction::new_signed_with_payer(
+        &[claim],
+        Some(&context.payer.pubkey()),
+        &[&context.payer, &authority],
+        last_blockhash,
+    );
+    context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap();
+
+    // Rounding leaves the last token unpaid.
+    assert_eq!(
+        get_token_account_balance(
+            &context
+                .banks_client
+                .get_account(token_destination)
+                .await
+                .unwrap()
+                .unwrap()
+        ),
+        499
+    );
+}
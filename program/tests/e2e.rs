@@ -7,10 +7,16 @@ mod setup;
 use {
     paladin_lockup_program::{
         error::PaladinLockupError,
-        state::{get_escrow_authority_address, Lockup, LockupPool, LockupPoolEntry},
+        state::{
+            get_escrow_authority_address, get_lockup_pool_address, Lockup, LockupPool,
+            LockupPoolEntry, PodU128, VestingSchedule,
+        },
         LOCKUP_COOLDOWN_SECONDS,
     },
-    setup::{add_seconds_to_clock, setup, setup_mint, setup_token_account},
+    setup::{
+        add_seconds_to_clock, initialize_lockup_pool_instructions, lockup_pool_state, setup,
+        setup_mint, setup_token_account,
+    },
     solana_program_test::*,
     solana_sdk::{
         clock::Clock,
@@ -103,6 +109,7 @@ async fn send_transaction_with_expected_err(
 #[tokio::test]
 async fn test_e2e() {
     let mint = Pubkey::new_unique();
+    let mint_authority = Keypair::new();
 
     let alice = Keypair::new();
     let alice_token_account =
@@ -150,36 +157,31 @@ async fn test_e2e() {
             0,
         )
         .await;
-        setup_mint(&mut context, &mint, &Pubkey::new_unique(), 1_000_000).await;
+        setup_mint(&mut context, &mint, &mint_authority.pubkey(), 1_000_000).await;
     }
 
-    // Create a lockup pool
+    // Create a lockup pool, administered by the mint authority
     let clock = context
         .banks_client
         .get_sysvar::<Clock>()
         .await
         .expect("get_sysvar");
     let rent = context.banks_client.get_rent().await.expect("get_rent");
-    let pool = Keypair::new();
-    {
-        let space = std::mem::size_of::<LockupPool>();
-
-        send_transaction(
-            &mut context,
-            &[
-                system_instruction::transfer(
-                    &payer.pubkey(),
-                    &pool.pubkey(),
-                    rent.minimum_balance(space),
-                ),
-                system_instruction::allocate(&pool.pubkey(), space as u64),
-                system_instruction::assign(&pool.pubkey(), &paladin_lockup_program::id()),
-                paladin_lockup_program::instruction::initialize_lockup_pool(pool.pubkey(), mint),
-            ],
-            &[&payer, &pool],
-        )
-        .await;
-    }
+    let pool = get_lockup_pool_address(&mint, 0, &paladin_lockup_program::id());
+    send_transaction(
+        &mut context,
+        &initialize_lockup_pool_instructions(
+            &payer.pubkey(),
+            mint,
+            0,
+            LOCKUP_COOLDOWN_SECONDS,
+            0,
+            Some(&mint_authority.pubkey()),
+            false,
+        ),
+        &[&payer, &mint_authority],
+    )
+    .await;
 
     // Create a lockup for Alice.
     let metadata = Pubkey::new_unique();
@@ -202,13 +204,16 @@ async fn test_e2e() {
                     &alice.pubkey(),
                     &alice.pubkey(),
                     &alice_token_account,
-                    pool.pubkey(),
+                    pool,
+                    &lockup_pool_state(mint),
+                    &[],
                     &alice_lockup.pubkey(),
                     &mint,
                     metadata.to_bytes(),
                     alice_lockup_amount,
+                    None,
                     &spl_token_2022::id(),
-                    &[],
+                    false,
                 ),
             ],
             &[&payer, &alice, &alice_lockup],
@@ -228,8 +233,17 @@ async fn test_e2e() {
                 lockup_start_timestamp: expected_lockup_start,
                 lockup_end_timestamp: None,
                 mint,
-                pool: pool.pubkey(),
+                pool,
                 metadata: metadata.to_bytes(),
+                proposed_authority: Pubkey::default(),
+                committed_end_timestamp: None,
+                vesting: VestingSchedule::default(),
+                withdrawn_amount: 0,
+                metadata_updated_timestamp: 0,
+                flags: Lockup::FLAG_IN_POOL,
+                reward_per_token_checkpoint: PodU128::default(),
+                unclaimed_rewards: 0,
+                cooldown_end_timestamp: None,
             },
         )
         .await;
@@ -244,14 +258,14 @@ async fn test_e2e() {
         // Assert - Lockup pool includes the new lockup.
         let lockup_pool = context
             .banks_client
-            .get_account(pool.pubkey())
+            .get_account(pool)
             .await
             .unwrap()
             .unwrap();
-        let actual_lockup = bytemuck::from_bytes::<LockupPool>(&lockup_pool.data);
+        let (actual_lockup, pool_entries) = LockupPool::unpack(&lockup_pool.data).unwrap();
         assert_eq!(actual_lockup.entries_len, 1);
         assert_eq!(
-            actual_lockup.entries[0],
+            pool_entries[0],
             LockupPoolEntry {
                 amount: alice_lockup_amount,
                 lockup: alice_lockup.pubkey(),
@@ -272,8 +286,13 @@ async fn test_e2e() {
                 &alice.pubkey(),
                 &alice_token_account,
                 &alice_lockup.pubkey(),
+                alice_lockup_amount,
+                pool,
+                &lockup_pool_state(mint),
+                &[],
                 &mint,
                 &spl_token_2022::id(),
+                false,
             )],
             &[&payer, &alice],
             TransactionError::InstructionError(
@@ -292,8 +311,10 @@ async fn test_e2e() {
                 ComputeBudgetInstruction::set_compute_unit_limit(400_000),
                 paladin_lockup_program::instruction::unlock(
                     &alice.pubkey(),
-                    pool.pubkey(),
+                    pool,
+                    &lockup_pool_state(mint),
                     &alice_lockup.pubkey(),
+                    alice_lockup_amount,
                 ),
             ],
             &[&payer, &alice],
@@ -313,8 +334,13 @@ async fn test_e2e() {
                 &alice.pubkey(),
                 &alice_token_account,
                 &alice_lockup.pubkey(),
+                alice_lockup_amount,
+                pool,
+                &lockup_pool_state(mint),
+                &[],
                 &mint,
                 &spl_token_2022::id(),
+                false,
             )],
             &[&payer, &alice],
         )
@@ -338,12 +364,12 @@ async fn test_e2e() {
         // Assert - Lockup pool no longer includes the lockup.
         let lockup_pool = context
             .banks_client
-            .get_account(pool.pubkey())
+            .get_account(pool)
             .await
             .unwrap()
             .unwrap();
-        let actual_lockup = bytemuck::from_bytes::<LockupPool>(&lockup_pool.data);
+        let (actual_lockup, pool_entries) = LockupPool::unpack(&lockup_pool.data).unwrap();
         assert_eq!(actual_lockup.entries_len, 0);
-        assert_eq!(actual_lockup.entries[0], LockupPoolEntry::default(),);
+        assert_eq!(pool_entries[0], LockupPoolEntry::default(),);
     }
 }
@@ -0,0 +1,934 @@
+#![cfg(feature = "test-sbf")]
+
+mod setup;
+
+use {
+    paladin_lockup_program::{
+        error::PaladinLockupError,
+        state::{
+            get_escrow_authority_address, Lockup, LockupPool, LockupPoolEntry, LockupPoolShard,
+            LockupPoolShardInfo, PodU128, VestingSchedule,
+        },
+    },
+    setup::{
+        lockup_pool_data, lockup_pool_state, setup, setup_lockup, setup_lockup_pool,
+        setup_lockup_pool_shard, setup_lockup_pool_with_data, setup_lockup_pool_with_entries,
+        setup_lockup_pool_with_state, setup_mint, setup_token_account,
+    },
+    solana_program_test::*,
+    solana_sdk::{
+        instruction::{AccountMeta, InstructionError},
+        pubkey::Pubkey,
+        signature::Keypair,
+        signer::Signer,
+        transaction::{Transaction, TransactionError},
+    },
+    spl_associated_token_account::get_associated_token_address_with_program_id,
+    spl_discriminator::SplDiscriminate,
+    spl_token_2022::{extension::StateWithExtensions, state::Account as TokenAccount},
+    std::num::NonZeroU64,
+    test_case::test_case,
+};
+
+async fn check_token_account_balance(
+    context: &mut ProgramTestContext,
+    token_account_address: &Pubkey,
+    check_amount: u64,
+) {
+    let account = context
+        .banks_client
+        .get_account(*token_account_address)
+        .await
+        .expect("get_account")
+        .expect("account not found");
+    let actual_amount = StateWithExtensions::<TokenAccount>::unpack(&account.data)
+        .unwrap()
+        .base
+        .amount;
+    assert_eq!(actual_amount, check_amount);
+}
+
+#[tokio::test]
+async fn fail_lockup_authority_not_signer() {
+    let mut context = setup().start_with_context().await;
+
+    let lockup_authority = Keypair::new();
+    let token_owner = Keypair::new();
+    let mint = Pubkey::new_unique();
+    let token_account = get_associated_token_address_with_program_id(
+        &token_owner.pubkey(),
+        &mint,
+        &spl_token_2022::id(),
+    );
+    let lockup = Pubkey::new_unique();
+
+    // Create the lockup pool account.
+    let pool = Pubkey::new_unique();
+    setup_lockup_pool(&mut context, &pool, mint).await;
+
+    setup_lockup(
+        &mut context,
+        &lockup,
+        Lockup {
+            discriminator: Lockup::SPL_DISCRIMINATOR.into(),
+            amount: 10_000,
+            authority: lockup_authority.pubkey(),
+            lockup_start_timestamp: 10,
+            lockup_end_timestamp: None,
+            mint,
+            pool,
+            metadata: Pubkey::new_unique().to_bytes(),
+            proposed_authority: Pubkey::default(),
+            committed_end_timestamp: None,
+            vesting: VestingSchedule::default(),
+            withdrawn_amount: 0,
+            metadata_updated_timestamp: 0,
+            flags: 0,
+            reward_per_token_checkpoint: PodU128::default(),
+            unclaimed_rewards: 0,
+            cooldown_end_timestamp: None,
+        },
+    )
+    .await;
+
+    let mut instruction = paladin_lockup_program::instruction::increase_lockup(
+        &lockup_authority.pubkey(),
+        &token_owner.pubkey(),
+        &token_account,
+        pool,
+        &lockup_pool_state(mint),
+        &[],
+        &lockup,
+        10_000,
+        &mint,
+        1_000,
+        &spl_token_2022::id(),
+        false,
+    );
+    instruction.accounts[0].is_signer = false; // Lockup authority not signing.
+
+    let transaction = Transaction::new_signed_with_payer(
+        &[instruction],
+        Some(&context.payer.pubkey()),
+        &[&context.payer, &token_owner],
+        context.last_blockhash,
+    );
+
+    let err = context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap_err()
+        .unwrap();
+
+    assert_eq!(
+        err,
+        TransactionError::InstructionError(0, InstructionError::MissingRequiredSignature)
+    );
+}
+
+#[tokio::test]
+async fn fail_incorrect_lockup_authority() {
+    let mut context = setup().start_with_context().await;
+
+    let lockup_authority = Keypair::new();
+    let token_owner = Keypair::new();
+    let mint = Pubkey::new_unique();
+    let token_account = get_associated_token_address_with_program_id(
+        &token_owner.pubkey(),
+        &mint,
+        &spl_token_2022::id(),
+    );
+    let lockup = Pubkey::new_unique();
+
+    // Create the lockup pool account.
+    let pool = Pubkey::new_unique();
+    setup_lockup_pool(&mut context, &pool, mint).await;
+
+    setup_lockup(
+        &mut context,
+        &lockup,
+        Lockup {
+            discriminator: Lockup::SPL_DISCRIMINATOR.into(),
+            amount: 10_000,
+            authority: Pubkey::new_unique(), // Incorrect lockup authority.
+            lockup_start_timestamp: 10,
+            lockup_end_timestamp: None,
+            mint,
+            pool,
+            metadata: Pubkey::new_unique().to_bytes(),
+            proposed_authority: Pubkey::default(),
+            committed_end_timestamp: None,
+            vesting: VestingSchedule::default(),
+            withdrawn_amount: 0,
+            metadata_updated_timestamp: 0,
+            flags: 0,
+            reward_per_token_checkpoint: PodU128::default(),
+            unclaimed_rewards: 0,
+            cooldown_end_timestamp: None,
+        },
+    )
+    .await;
+
+    let instruction = paladin_lockup_program::instruction::increase_lockup(
+        &lockup_authority.pubkey(),
+        &token_owner.pubkey(),
+        &token_account,
+        pool,
+        &lockup_pool_state(mint),
+        &[],
+        &lockup,
+        10_000,
+        &mint,
+        1_000,
+        &spl_token_2022::id(),
+        false,
+    );
+
+    let transaction = Transaction::new_signed_with_payer(
+        &[instruction],
+        Some(&context.payer.pubkey()),
+        &[&context.payer, &lockup_authority, &token_owner],
+        context.last_blockhash,
+    );
+
+    let err = context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap_err()
+        .unwrap();
+
+    assert_eq!(
+        err,
+        TransactionError::InstructionError(0, InstructionError::IncorrectAuthority)
+    );
+}
+
+#[tokio::test]
+async fn fail_lockup_already_unlocked() {
+    let mut context = setup().start_with_context().await;
+
+    let lockup_authority = Keypair::new();
+    let token_owner = Keypair::new();
+    let mint = Pubkey::new_unique();
+    let token_account = get_associated_token_address_with_program_id(
+        &token_owner.pubkey(),
+        &mint,
+        &spl_token_2022::id(),
+    );
+    let lockup = Pubkey::new_unique();
+
+    // Create the lockup pool account.
+    let pool = Pubkey::new_unique();
+    setup_lockup_pool(&mut context, &pool, mint).await;
+
+    setup_lockup(
+        &mut context,
+        &lockup,
+        Lockup {
+            discriminator: Lockup::SPL_DISCRIMINATOR.into(),
+            amount: 10_000,
+            authority: lockup_authority.pubkey(),
+            lockup_start_timestamp: 10,
+            lockup_end_timestamp: NonZeroU64::new(20), // Already unlocked.
+            mint,
+            pool,
+            metadata: Pubkey::new_unique().to_bytes(),
+            proposed_authority: Pubkey::default(),
+            committed_end_timestamp: None,
+            vesting: VestingSchedule::default(),
+            withdrawn_amount: 0,
+            metadata_updated_timestamp: 0,
+            flags: 0,
+            reward_per_token_checkpoint: PodU128::default(),
+            unclaimed_rewards: 0,
+            cooldown_end_timestamp: None,
+        },
+    )
+    .await;
+
+    let instruction = paladin_lockup_program::instruction::increase_lockup(
+        &lockup_authority.pubkey(),
+        &token_owner.pubkey(),
+        &token_account,
+        pool,
+        &lockup_pool_state(mint),
+        &[],
+        &lockup,
+        10_000,
+        &mint,
+        1_000,
+        &spl_token_2022::id(),
+        false,
+    );
+
+    let transaction = Transaction::new_signed_with_payer(
+        &[instruction],
+        Some(&context.payer.pubkey()),
+        &[&context.payer, &lockup_authority, &token_owner],
+        context.last_blockhash,
+    );
+
+    let err = context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap_err()
+        .unwrap();
+
+    assert_eq!(
+        err,
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(PaladinLockupError::LockupAlreadyUnlocked as u32)
+        )
+    );
+}
+
+#[tokio::test]
+async fn fail_incorrect_pool() {
+    let mut context = setup().start_with_context().await;
+
+    let lockup_authority = Keypair::new();
+    let token_owner = Keypair::new();
+    let mint = Pubkey::new_unique();
+    let token_account = get_associated_token_address_with_program_id(
+        &token_owner.pubkey(),
+        &mint,
+        &spl_token_2022::id(),
+    );
+    let lockup = Pubkey::new_unique();
+
+    // Create the lockup pool accounts.
+    let pool1 = Pubkey::new_unique();
+    setup_lockup_pool(&mut context, &pool1, mint).await;
+    let pool2 = Pubkey::new_unique();
+    setup_lockup_pool(&mut context, &pool2, mint).await;
+
+    setup_lockup(
+        &mut context,
+        &lockup,
+        Lockup {
+            discriminator: Lockup::SPL_DISCRIMINATOR.into(),
+            amount: 10_000,
+            authority: lockup_authority.pubkey(),
+            lockup_start_timestamp: 10,
+            lockup_end_timestamp: None,
+            mint,
+            pool: pool1,
+            metadata: Pubkey::new_unique().to_bytes(),
+            proposed_authority: Pubkey::default(),
+            committed_end_timestamp: None,
+            vesting: VestingSchedule::default(),
+            withdrawn_amount: 0,
+            metadata_updated_timestamp: 0,
+            flags: 0,
+            reward_per_token_checkpoint: PodU128::default(),
+            unclaimed_rewards: 0,
+            cooldown_end_timestamp: None,
+        },
+    )
+    .await;
+
+    let instruction = paladin_lockup_program::instruction::increase_lockup(
+        &lockup_authority.pubkey(),
+        &token_owner.pubkey(),
+        &token_account,
+        pool2,
+        &lockup_pool_state(mint),
+        &[],
+        &lockup,
+        10_000,
+        &mint,
+        1_000,
+        &spl_token_2022::id(),
+        false,
+    );
+
+    let transaction = Transaction::new_signed_with_payer(
+        &[instruction],
+        Some(&context.payer.pubkey()),
+        &[&context.payer, &lockup_authority, &token_owner],
+        context.last_blockhash,
+    );
+
+    let err = context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap_err()
+        .unwrap();
+
+    assert_eq!(
+        err,
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(PaladinLockupError::IncorrectPool as u32)
+        )
+    );
+}
+
+#[test_case(true; "in_pool")]
+#[test_case(false; "evicted")]
+#[tokio::test]
+async fn success(lockup_in_pool: bool) {
+    let mut context = setup().start_with_context().await;
+
+    let lockup_authority = Keypair::new();
+    let token_owner = Keypair::new();
+    let mint = Pubkey::new_unique();
+    let token_account = get_associated_token_address_with_program_id(
+        &token_owner.pubkey(),
+        &mint,
+        &spl_token_2022::id(),
+    );
+    let escrow_authority = get_escrow_authority_address(&paladin_lockup_program::id());
+    let escrow_token_account = get_associated_token_address_with_program_id(
+        &escrow_authority,
+        &mint,
+        &spl_token_2022::id(),
+    );
+    let lockup = Pubkey::new_unique();
+    let metadata = Pubkey::new_unique().to_bytes();
+
+    setup_mint(&mut context, &mint, &Pubkey::new_unique(), 1_000_000).await;
+    setup_token_account(
+        &mut context,
+        &token_account,
+        &token_owner.pubkey(),
+        &mint,
+        5_000,
+    )
+    .await;
+    setup_token_account(
+        &mut context,
+        &escrow_token_account,
+        &escrow_authority,
+        &mint,
+        1_000,
+    )
+    .await;
+
+    // Create the lockup pool account, ranking another lockup above ours.
+    let pool = Pubkey::new_unique();
+    let other_entry = LockupPoolEntry {
+        lockup: Pubkey::new_unique(),
+        amount: 2_000,
+        metadata: Pubkey::new_unique().to_bytes(),
+    };
+    let own_entry = LockupPoolEntry {
+        lockup,
+        amount: 1_000,
+        metadata,
+    };
+    let entries = match lockup_in_pool {
+        true => vec![other_entry, own_entry],
+        false => vec![other_entry],
+    };
+    setup_lockup_pool_with_entries(&mut context, &pool, mint, &entries).await;
+
+    let state = Lockup {
+        discriminator: Lockup::SPL_DISCRIMINATOR.into(),
+        amount: 1_000,
+        authority: lockup_authority.pubkey(),
+        lockup_start_timestamp: 10,
+        lockup_end_timestamp: None,
+        mint,
+        pool,
+        metadata,
+        proposed_authority: Pubkey::default(),
+        committed_end_timestamp: None,
+        vesting: VestingSchedule::default(),
+        withdrawn_amount: 0,
+        metadata_updated_timestamp: 0,
+        flags: Lockup::FLAG_IN_POOL, // Kept by lockups evicted before their migration.
+        reward_per_token_checkpoint: PodU128::default(),
+        unclaimed_rewards: 0,
+        cooldown_end_timestamp: None,
+    };
+    setup_lockup(&mut context, &lockup, state).await;
+
+    let instruction = paladin_lockup_program::instruction::increase_lockup(
+        &lockup_authority.pubkey(),
+        &token_owner.pubkey(),
+        &token_account,
+        pool,
+        &lockup_pool_state(mint),
+        &[],
+        &lockup,
+        1_000,
+        &mint,
+        4_000,
+        &spl_token_2022::id(),
+        false,
+    );
+
+    let transaction = Transaction::new_signed_with_payer(
+        &[instruction],
+        Some(&context.payer.pubkey()),
+        &[&context.payer, &lockup_authority, &token_owner],
+        context.last_blockhash,
+    );
+
+    context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap();
+
+    // Check the lockup account.
+    let lockup_account = context
+        .banks_client
+        .get_account(lockup)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(
+        bytemuck::from_bytes::<Lockup>(&lockup_account.data),
+        &Lockup {
+            amount: 5_000,
+            ..state
+        }
+    );
+
+    // Check the lockup moved to the top of the pool.
+    let lockup_pool = context
+        .banks_client
+        .get_account(pool)
+        .await
+        .unwrap()
+        .unwrap();
+    let (lockup_pool, pool_entries) = LockupPool::unpack(&lockup_pool.data).unwrap();
+    assert_eq!(lockup_pool.entries_len, 2);
+    assert_eq!(
+        pool_entries[0],
+        LockupPoolEntry {
+            lockup,
+            amount: 5_000,
+            metadata,
+        }
+    );
+    assert_eq!(pool_entries[1], other_entry);
+
+    // Validate tokens were transferred from the token account to the escrow.
+    check_token_account_balance(&mut context, &token_account, 1_000).await;
+    check_token_account_balance(&mut context, &escrow_token_account, 5_000).await;
+}
+
+#[test_case(LockupPool::FLAG_LOCKUPS_DISABLED, PaladinLockupError::LockupsDisabled; "lockups_disabled")]
+#[test_case(LockupPool::FLAG_LOCKUP_PAUSED, PaladinLockupError::PoolPaused; "lockups_paused")]
+#[tokio::test]
+async fn fail_pool_config(flags: u64, expected_err: PaladinLockupError) {
+    let mut context = setup().start_with_context().await;
+
+    let lockup_authority = Keypair::new();
+    let token_owner = Keypair::new();
+    let mint = Pubkey::new_unique();
+    let token_account = get_associated_token_address_with_program_id(
+        &token_owner.pubkey(),
+        &mint,
+        &spl_token_2022::id(),
+    );
+    let lockup = Pubkey::new_unique();
+
+    // Create the lockup pool account with the provided flags.
+    let pool = Pubkey::new_unique();
+    setup_lockup_pool_with_state(
+        &mut context,
+        &pool,
+        &LockupPool {
+            flags,
+            ..lockup_pool_state(mint)
+        },
+    )
+    .await;
+
+    setup_lockup(
+        &mut context,
+        &lockup,
+        Lockup {
+            discriminator: Lockup::SPL_DISCRIMINATOR.into(),
+            amount: 10_000,
+            authority: lockup_authority.pubkey(),
+            lockup_start_timestamp: 10,
+            lockup_end_timestamp: None,
+            mint,
+            pool,
+            metadata: Pubkey::new_unique().to_bytes(),
+            proposed_authority: Pubkey::default(),
+            committed_end_timestamp: None,
+            vesting: VestingSchedule::default(),
+            withdrawn_amount: 0,
+            metadata_updated_timestamp: 0,
+            flags: Lockup::FLAG_IN_POOL,
+            reward_per_token_checkpoint: PodU128::default(),
+            unclaimed_rewards: 0,
+            cooldown_end_timestamp: None,
+        },
+    )
+    .await;
+
+    let instruction = paladin_lockup_program::instruction::increase_lockup(
+        &lockup_authority.pubkey(),
+        &token_owner.pubkey(),
+        &token_account,
+        pool,
+        &lockup_pool_state(mint),
+        &[],
+        &lockup,
+        10_000,
+        &mint,
+        1_000,
+        &spl_token_2022::id(),
+        false,
+    );
+
+    let transaction = Transaction::new_signed_with_payer(
+        &[instruction],
+        Some(&context.payer.pubkey()),
+        &[&context.payer, &lockup_authority, &token_owner],
+        context.last_blockhash,
+    );
+
+    let err = context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap_err()
+        .unwrap();
+
+    assert_eq!(
+        err,
+        TransactionError::InstructionError(0, InstructionError::Custom(expected_err as u32))
+    );
+}
+
+#[tokio::test]
+async fn success_evicted_below_pool_minimum() {
+    let mut context = setup().start_with_context().await;
+
+    let lockup_authority = Keypair::new();
+    let token_owner = Keypair::new();
+    let mint = Pubkey::new_unique();
+    let token_account = get_associated_token_address_with_program_id(
+        &token_owner.pubkey(),
+        &mint,
+        &spl_token_2022::id(),
+    );
+    let escrow_authority = get_escrow_authority_address(&paladin_lockup_program::id());
+    let escrow_token_account = get_associated_token_address_with_program_id(
+        &escrow_authority,
+        &mint,
+        &spl_token_2022::id(),
+    );
+    let lockup = Pubkey::new_unique();
+
+    setup_mint(&mut context, &mint, &Pubkey::new_unique(), 1_000_000).await;
+    setup_token_account(
+        &mut context,
+        &token_account,
+        &token_owner.pubkey(),
+        &mint,
+        5_000,
+    )
+    .await;
+    setup_token_account(
+        &mut context,
+        &escrow_token_account,
+        &escrow_authority,
+        &mint,
+        1_000,
+    )
+    .await;
+
+    // Create a full lockup pool account, whose smallest lockup still outranks
+    // ours once increased.
+    let pool = Pubkey::new_unique();
+    let entries = [
+        LockupPoolEntry {
+            lockup: Pubkey::new_unique(),
+            amount: 3_000,
+            metadata: Pubkey::new_unique().to_bytes(),
+        },
+        LockupPoolEntry {
+            lockup: Pubkey::new_unique(),
+            amount: 2_000,
+            metadata: Pubkey::new_unique().to_bytes(),
+        },
+    ];
+    let data = lockup_pool_data(&lockup_pool_state(mint), &entries, entries.len());
+    setup_lockup_pool_with_data(&mut context, &pool, data).await;
+
+    let state = Lockup {
+        discriminator: Lockup::SPL_DISCRIMINATOR.into(),
+        amount: 1_000,
+        authority: lockup_authority.pubkey(),
+        lockup_start_timestamp: 10,
+        lockup_end_timestamp: None,
+        mint,
+        pool,
+        metadata: Pubkey::new_unique().to_bytes(),
+        proposed_authority: Pubkey::default(),
+        committed_end_timestamp: None,
+        vesting: VestingSchedule::default(),
+        withdrawn_amount: 0,
+        metadata_updated_timestamp: 0,
+        flags: 0,
+        reward_per_token_checkpoint: PodU128::default(),
+        unclaimed_rewards: 0,
+        cooldown_end_timestamp: None,
+    };
+    setup_lockup(&mut context, &lockup, state).await;
+
+    let instruction = paladin_lockup_program::instruction::increase_lockup(
+        &lockup_authority.pubkey(),
+        &token_owner.pubkey(),
+        &token_account,
+        pool,
+        &lockup_pool_state(mint),
+        &[],
+        &lockup,
+        1_000,
+        &mint,
+        500,
+        &spl_token_2022::id(),
+        false,
+    );
+
+    let transaction = Transaction::new_signed_with_payer(
+        &[instruction],
+        Some(&context.payer.pubkey()),
+        &[&context.payer, &lockup_authority, &token_owner],
+        context.last_blockhash,
+    );
+
+    context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap();
+
+    // Check the lockup holds the tokens, but stays out of the pool.
+    let lockup_account = context
+        .banks_client
+        .get_account(lockup)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(
+        bytemuck::from_bytes::<Lockup>(&lockup_account.data),
+        &Lockup {
+            amount: 1_500,
+            ..state
+        }
+    );
+    let lockup_pool = context
+        .banks_client
+        .get_account(pool)
+        .await
+        .unwrap()
+        .unwrap();
+    let (lockup_pool, pool_entries) = LockupPool::unpack(&lockup_pool.data).unwrap();
+    assert_eq!(lockup_pool.entries_len, 2);
+    assert_eq!(pool_entries, entries);
+
+    // Validate tokens were transferred from the token account to the escrow.
+    check_token_account_balance(&mut context, &token_account, 4_500).await;
+    check_token_account_balance(&mut context, &escrow_token_account, 1_500).await;
+}
+
+#[tokio::test]
+async fn success_spills_into_full_last_shard() {
+    let mut context = setup().start_with_context().await;
+
+    let lockup_authority = Keypair::new();
+    let token_owner = Keypair::new();
+    let mint = Pubkey::new_unique();
+    let token_account = get_associated_token_address_with_program_id(
+        &token_owner.pubkey(),
+        &mint,
+        &spl_token_2022::id(),
+    );
+    let escrow_authority = get_escrow_authority_address(&paladin_lockup_program::id());
+    let escrow_token_account = get_associated_token_address_with_program_id(
+        &escrow_authority,
+        &mint,
+        &spl_token_2022::id(),
+    );
+    let lockup = Pubkey::new_unique();
+    let metadata = Pubkey::new_unique().to_bytes();
+
+    setup_mint(&mut context, &mint, &Pubkey::new_unique(), 1_000_000).await;
+    setup_token_account(
+        &mut context,
+        &token_account,
+        &token_owner.pubkey(),
+        &mint,
+        5_000,
+    )
+    .await;
+    setup_token_account(
+        &mut context,
+        &escrow_token_account,
+        &escrow_authority,
+        &mint,
+        1_000,
+    )
+    .await;
+
+    let lockup_state = |amount: u64| Lockup {
+        discriminator: Lockup::SPL_DISCRIMINATOR.into(),
+        amount,
+        authority: lockup_authority.pubkey(),
+        lockup_start_timestamp: 10,
+        lockup_end_timestamp: None,
+        mint,
+        pool: Pubkey::default(),
+        metadata,
+        proposed_authority: Pubkey::default(),
+        committed_end_timestamp: None,
+        vesting: VestingSchedule::default(),
+        withdrawn_amount: 0,
+        metadata_updated_timestamp: 0,
+        flags: Lockup::FLAG_IN_POOL,
+        reward_per_token_checkpoint: PodU128::default(),
+        unclaimed_rewards: 0,
+        cooldown_end_timestamp: None,
+    };
+
+    // Create a full lockup pool holding two tied lockups, above a full shard
+    // that only has room for one of them.
+    let pool = Pubkey::new_unique();
+    let shard = Pubkey::new_unique();
+    let tied_entries = [
+        LockupPoolEntry {
+            lockup: Pubkey::new_unique(),
+            amount: 3_000,
+            metadata,
+        },
+        LockupPoolEntry {
+            lockup: Pubkey::new_unique(),
+            amount: 3_000,
+            metadata,
+        },
+    ];
+    let shard_entry = LockupPoolEntry {
+        lockup: Pubkey::new_unique(),
+        amount: 2_000,
+        metadata,
+    };
+    let mut pool_state = lockup_pool_state(mint);
+    pool_state.shards[0] = LockupPoolShardInfo {
+        address: shard,
+        max_amount: 3_000,
+    };
+    pool_state.shards_len = 1;
+    let data = lockup_pool_data(&pool_state, &tied_entries, tied_entries.len());
+    setup_lockup_pool_with_data(&mut context, &pool, data).await;
+    setup_lockup_pool_shard(&mut context, &shard, &pool, &[shard_entry], 1).await;
+    for entry in [tied_entries[1], shard_entry] {
+        let state = Lockup {
+            pool,
+            ..lockup_state(entry.amount)
+        };
+        setup_lockup(&mut context, &entry.lockup, state).await;
+    }
+
+    // Our lockup was evicted earlier.
+    let state = Lockup {
+        pool,
+        flags: 0,
+        ..lockup_state(1_000)
+    };
+    setup_lockup(&mut context, &lockup, state).await;
+
+    let mut instruction = paladin_lockup_program::instruction::increase_lockup(
+        &lockup_authority.pubkey(),
+        &token_owner.pubkey(),
+        &token_account,
+        pool,
+        &pool_state,
+        &[shard_entry],
+        &lockup,
+        state.amount,
+        &mint,
+        4_000,
+        &spl_token_2022::id(),
+        false,
+    );
+    // The tie evicts the second tied lockup too, which the builder can't
+    // tell from the shard's entries.
+    instruction
+        .accounts
+        .push(AccountMeta::new(tied_entries[1].lockup, false));
+
+    let transaction = Transaction::new_signed_with_payer(
+        &[instruction],
+        Some(&context.payer.pubkey()),
+        &[&context.payer, &lockup_authority, &token_owner],
+        context.last_blockhash,
+    );
+
+    context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap();
+
+    // Check the lockup rejoined the pool.
+    let lockup_account = context
+        .banks_client
+        .get_account(lockup)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(
+        bytemuck::from_bytes::<Lockup>(&lockup_account.data),
+        &Lockup {
+            amount: 5_000,
+            flags: Lockup::FLAG_IN_POOL,
+            ..state
+        }
+    );
+
+    // Both tied lockups spilled into the shard, which kept the first of them,
+    // evicting its smallest entry & the second one.
+    let lockup_pool = context
+        .banks_client
+        .get_account(pool)
+        .await
+        .unwrap()
+        .unwrap();
+    let (lockup_pool, pool_entries) = LockupPool::unpack(&lockup_pool.data).unwrap();
+    assert_eq!(lockup_pool.entries_len, 1);
+    assert_eq!(
+        pool_entries[0],
+        LockupPoolEntry {
+            lockup,
+            amount: 5_000,
+            metadata,
+        }
+    );
+    assert_eq!(lockup_pool.shards[0].max_amount, 3_001);
+    let shard_account = context
+        .banks_client
+        .get_account(shard)
+        .await
+        .unwrap()
+        .unwrap();
+    let (shard_state, shard_entries) = LockupPoolShard::unpack(&shard_account.data).unwrap();
+    assert_eq!(shard_state.entries_len, 1);
+    assert_eq!(shard_entries[0], tied_entries[0]);
+
+    // Check the evicted lockups left the pool.
+    for evicted in [tied_entries[1].lockup, shard_entry.lockup] {
+        let evicted_account = context
+            .banks_client
+            .get_account(evicted)
+            .await
+            .unwrap()
+            .unwrap();
+        assert!(!bytemuck::from_bytes::<Lockup>(&evicted_account.data).in_pool());
+    }
+}
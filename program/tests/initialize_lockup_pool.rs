@@ -3,8 +3,20 @@
 mod setup;
 
 use {
-    paladin_lockup_program::state::{LockupPool, LockupPoolEntry},
-    setup::{setup, setup_mint},
+    bytemuck::Pod,
+    paladin_lockup_program::{
+        error::PaladinLockupError,
+        state::{
+            get_lockup_pool_address, LockupPool, LockupPoolEntry, LockupPoolRewards,
+            LockupPoolShardInfo, LockupPoolStats,
+        },
+        LOCKUP_COOLDOWN_SECONDS, MAX_LOCKUP_COOLDOWN_SECONDS, MAX_METADATA_UPDATE_INTERVAL_SECONDS,
+    },
+    setup::{
+        initialize_lockup_pool_instructions, setup, setup_fixed_supply_mint, setup_mint,
+        setup_mint_with_extension, setup_mint_with_program_id, setup_mint_with_transfer_fee,
+        setup_system_account,
+    },
     solana_program_test::*,
     solana_sdk::{
         instruction::InstructionError,
@@ -12,32 +24,75 @@ use {
         rent::Rent,
         signature::Keypair,
         signer::Signer,
-        system_instruction,
         transaction::{Transaction, TransactionError},
     },
     spl_discriminator::SplDiscriminate,
+    spl_token_2022::extension::{
+        default_account_state::DefaultAccountState, metadata_pointer::MetadataPointer,
+        mint_close_authority::MintCloseAuthority, permanent_delegate::PermanentDelegate, Extension,
+    },
+    test_case::test_case,
 };
 
+#[test_case(0, false, false, false, spl_token_2022::id(); "canonical_pool")]
+#[test_case(1, false, false, false, spl_token_2022::id(); "secondary_pool")]
+#[test_case(0, true, false, false, spl_token_2022::id(); "prefunded_pool")]
+#[test_case(0, false, true, false, spl_token_2022::id(); "fixed_supply_mint")]
+#[test_case(0, false, false, true, spl_token_2022::id(); "isolated_escrow")]
+#[test_case(0, false, false, false, spl_token::id(); "spl_token_mint")]
 #[tokio::test]
-async fn ok_initialize() {
+async fn ok_initialize(
+    pool_id: u64,
+    prefunded: bool,
+    fixed_supply: bool,
+    isolated_escrow: bool,
+    token_program_id: Pubkey,
+) {
     let mut context = setup().start_with_context().await;
-    let pool = Keypair::new();
+    let payer = Keypair::new();
     let mint = Pubkey::new_unique();
+    let mint_authority = Keypair::new();
+    let pool = get_lockup_pool_address(&mint, pool_id, &paladin_lockup_program::id());
 
-    // Setup the mint.
-    setup_mint(&mut context, &mint, &Pubkey::new_unique(), 100).await;
+    // Setup the payer & mint.
+    setup_system_account(&mut context, &payer.pubkey(), 1_000_000_000).await;
+    if fixed_supply {
+        setup_fixed_supply_mint(&mut context, &mint, 100).await;
+    } else {
+        setup_mint_with_program_id(
+            &mut context,
+            &mint,
+            &mint_authority.pubkey(),
+            100,
+            &token_program_id,
+        )
+        .await;
+    }
 
-    // Initialize the pool.
-    let rent = Rent::default().minimum_balance(LockupPool::LEN);
-    let fund = system_instruction::transfer(&context.payer.pubkey(), &pool.pubkey(), rent);
-    let allocate = system_instruction::allocate(&pool.pubkey(), LockupPool::LEN as u64);
-    let assign = system_instruction::assign(&pool.pubkey(), &paladin_lockup_program::ID);
-    let initialize_lockup_pool =
-        paladin_lockup_program::instruction::initialize_lockup_pool(pool.pubkey(), mint);
+    // Someone may have sent lamports to the pool address ahead of time.
+    if prefunded {
+        setup_system_account(&mut context, &pool, 0).await;
+    }
+
+    // Initialize the pool, administered by the mint authority if there is
+    // one. Pools without an authority use the default configuration.
+    let metadata_update_interval_seconds = if fixed_supply { 0 } else { 60 };
+    let mut signers = vec![&context.payer, &payer];
+    if !fixed_supply {
+        signers.push(&mint_authority);
+    }
     let tx = Transaction::new_signed_with_payer(
-        &[fund, allocate, assign, initialize_lockup_pool],
+        &initialize_lockup_pool_instructions(
+            &payer.pubkey(),
+            mint,
+            pool_id,
+            LOCKUP_COOLDOWN_SECONDS,
+            metadata_update_interval_seconds,
+            (!fixed_supply).then(|| mint_authority.pubkey()).as_ref(),
+            isolated_escrow,
+        ),
         Some(&context.payer.pubkey()),
-        &[&context.payer, &pool],
+        &signers,
         context.last_blockhash,
     );
     context.banks_client.process_transaction(tx).await.unwrap();
@@ -45,52 +100,461 @@ async fn ok_initialize() {
     // The pool matches the expected state.
     let pool = context
         .banks_client
-        .get_account(pool.pubkey())
+        .get_account(pool)
         .await
         .unwrap()
-        .unwrap()
-        .data;
-    let pool = bytemuck::from_bytes::<LockupPool>(&pool);
+        .unwrap();
+    assert_eq!(pool.owner, paladin_lockup_program::id());
+    assert_eq!(
+        pool.lamports,
+        Rent::default().minimum_balance(LockupPool::account_len(LockupPool::INITIAL_CAPACITY))
+    );
+    let (pool, pool_entries) = LockupPool::unpack(&pool.data).unwrap();
+    assert_eq!(pool_entries.len(), LockupPool::INITIAL_CAPACITY);
+    assert!(pool_entries
+        .iter()
+        .all(|entry| entry == &LockupPoolEntry::default()));
     assert_eq!(
         pool,
         &LockupPool {
             discriminator: LockupPool::SPL_DISCRIMINATOR.into(),
             mint,
-            entries: [LockupPoolEntry::default(); 1024],
+            cooldown_seconds: LOCKUP_COOLDOWN_SECONDS,
+            metadata_update_interval_seconds,
+            authority: if fixed_supply {
+                Pubkey::default()
+            } else {
+                mint_authority.pubkey()
+            },
+            min_lockup_amount: 0,
+            flags: if isolated_escrow {
+                LockupPool::FLAG_ISOLATED_ESCROW
+            } else {
+                0
+            },
             entries_len: 0,
+            shards_len: 0,
+            shards: [LockupPoolShardInfo::default(); LockupPool::MAX_SHARDS],
+            stats: LockupPoolStats::default(),
+            rewards: LockupPoolRewards::default(),
         }
     );
 }
 
+#[test_case(false, true; "custom_authority")]
+#[test_case(true, true; "fixed_supply_custom_authority")]
+#[test_case(false, false; "no_authority")]
 #[tokio::test]
-async fn err_duplicate_initialize() {
+async fn ok_initialize_secondary_pool_authority(fixed_supply: bool, with_authority: bool) {
+    let mut context = setup().start_with_context().await;
+    let payer = Keypair::new();
+    let mint = Pubkey::new_unique();
+    let pool_authority = Keypair::new();
+    let pool = get_lockup_pool_address(&mint, 1, &paladin_lockup_program::id());
+
+    // Setup the payer & mint.
+    setup_system_account(&mut context, &payer.pubkey(), 1_000_000_000).await;
+    if fixed_supply {
+        setup_fixed_supply_mint(&mut context, &mint, 100).await;
+    } else {
+        setup_mint(&mut context, &mint, &Pubkey::new_unique(), 100).await;
+    }
+
+    // Initialize a secondary pool, administered by an authority other than
+    // the mint authority, if any.
+    let mut signers = vec![&context.payer, &payer];
+    if with_authority {
+        signers.push(&pool_authority);
+    }
+    let tx = Transaction::new_signed_with_payer(
+        &initialize_lockup_pool_instructions(
+            &payer.pubkey(),
+            mint,
+            1,
+            LOCKUP_COOLDOWN_SECONDS,
+            0,
+            with_authority.then(|| pool_authority.pubkey()).as_ref(),
+            false,
+        ),
+        Some(&context.payer.pubkey()),
+        &signers,
+        context.last_blockhash,
+    );
+    context.banks_client.process_transaction(tx).await.unwrap();
+
+    let pool = context
+        .banks_client
+        .get_account(pool)
+        .await
+        .unwrap()
+        .unwrap();
+    let (pool, _) = LockupPool::unpack(&pool.data).unwrap();
+    assert_eq!(
+        pool.authority,
+        if with_authority {
+            pool_authority.pubkey()
+        } else {
+            Pubkey::default()
+        }
+    );
+}
+
+#[tokio::test]
+async fn ok_initialize_transfer_fee_mint() {
     let mut context = setup().start_with_context().await;
-    let pool = Keypair::new();
+    let payer = Keypair::new();
     let mint = Pubkey::new_unique();
+    let mint_authority = Keypair::new();
+    let pool = get_lockup_pool_address(&mint, 0, &paladin_lockup_program::id());
+
+    // Setup the payer & a mint with a 1% transfer fee.
+    setup_system_account(&mut context, &payer.pubkey(), 1_000_000_000).await;
+    setup_mint_with_transfer_fee(&mut context, &mint, &mint_authority.pubkey(), 100, 100, 10).await;
 
-    // Setup the mint.
+    // Initialize the pool.
+    let tx = Transaction::new_signed_with_payer(
+        &initialize_lockup_pool_instructions(
+            &payer.pubkey(),
+            mint,
+            0,
+            LOCKUP_COOLDOWN_SECONDS,
+            0,
+            Some(&mint_authority.pubkey()),
+            false,
+        ),
+        Some(&context.payer.pubkey()),
+        &[&context.payer, &payer, &mint_authority],
+        context.last_blockhash,
+    );
+    context.banks_client.process_transaction(tx).await.unwrap();
+
+    // The pool is initialized for the mint.
+    let pool = context
+        .banks_client
+        .get_account(pool)
+        .await
+        .unwrap()
+        .unwrap();
+    let (pool, _) = LockupPool::unpack(&pool.data).unwrap();
+    assert_eq!(pool.discriminator, LockupPool::SPL_DISCRIMINATOR_SLICE);
+    assert_eq!(pool.mint, mint);
+}
+
+/// Initializes a pool for a mint with the provided extension, returning the
+/// index of the instruction that validates the mint along with the result.
+async fn initialize_with_mint_extension<V: Extension + Pod + Default>(
+    extension: V,
+) -> (u8, Result<(), BanksClientError>) {
+    let mut context = setup().start_with_context().await;
+    let payer = Keypair::new();
+    let mint = Pubkey::new_unique();
+    let mint_authority = Keypair::new();
+
+    // Setup the payer & mint.
+    setup_system_account(&mut context, &payer.pubkey(), 1_000_000_000).await;
+    setup_mint_with_extension(
+        &mut context,
+        &mint,
+        &mint_authority.pubkey(),
+        100,
+        extension,
+    )
+    .await;
+
+    let initialize_lockup_pool = initialize_lockup_pool_instructions(
+        &payer.pubkey(),
+        mint,
+        0,
+        LOCKUP_COOLDOWN_SECONDS,
+        0,
+        Some(&mint_authority.pubkey()),
+        false,
+    );
+    let tx = Transaction::new_signed_with_payer(
+        &initialize_lockup_pool,
+        Some(&context.payer.pubkey()),
+        &[&context.payer, &payer, &mint_authority],
+        context.last_blockhash,
+    );
+
+    (
+        (initialize_lockup_pool.len() - 1) as u8,
+        context.banks_client.process_transaction(tx).await,
+    )
+}
+
+#[tokio::test]
+async fn ok_initialize_metadata_pointer_mint() {
+    let (_, result) = initialize_with_mint_extension(MetadataPointer::default()).await;
+    result.unwrap();
+}
+
+#[tokio::test]
+async fn err_permanent_delegate_mint() {
+    let (index, result) = initialize_with_mint_extension(PermanentDelegate::default()).await;
+
+    assert_eq!(
+        result.unwrap_err().unwrap(),
+        TransactionError::InstructionError(
+            index,
+            InstructionError::Custom(PaladinLockupError::UnsupportedMintExtension as u32)
+        )
+    );
+}
+
+#[tokio::test]
+async fn err_default_account_state_mint() {
+    let (index, result) = initialize_with_mint_extension(DefaultAccountState::default()).await;
+
+    assert_eq!(
+        result.unwrap_err().unwrap(),
+        TransactionError::InstructionError(
+            index,
+            InstructionError::Custom(PaladinLockupError::UnsupportedMintExtension as u32)
+        )
+    );
+}
+
+#[tokio::test]
+async fn err_mint_close_authority_mint() {
+    let (index, result) = initialize_with_mint_extension(MintCloseAuthority::default()).await;
+
+    assert_eq!(
+        result.unwrap_err().unwrap(),
+        TransactionError::InstructionError(
+            index,
+            InstructionError::Custom(PaladinLockupError::UnsupportedMintExtension as u32)
+        )
+    );
+}
+
+#[tokio::test]
+async fn err_incorrect_pool_address() {
+    let mut context = setup().start_with_context().await;
+    let payer = Keypair::new();
+    let mint = Pubkey::new_unique();
+
+    // Setup the payer & mint.
+    setup_system_account(&mut context, &payer.pubkey(), 1_000_000_000).await;
     setup_mint(&mut context, &mint, &Pubkey::new_unique(), 100).await;
 
+    // Attempt to initialize a pool at a non-canonical address.
+    let mut initialize_lockup_pool = paladin_lockup_program::instruction::initialize_lockup_pool(
+        &payer.pubkey(),
+        mint,
+        0,
+        LOCKUP_COOLDOWN_SECONDS,
+        0,
+        None,
+        false,
+    );
+    initialize_lockup_pool.accounts[1].pubkey = Pubkey::new_unique();
+    let tx = Transaction::new_signed_with_payer(
+        &[initialize_lockup_pool],
+        Some(&context.payer.pubkey()),
+        &[&context.payer, &payer],
+        context.last_blockhash,
+    );
+    let err = context
+        .banks_client
+        .process_transaction(tx)
+        .await
+        .unwrap_err()
+        .unwrap();
+
+    assert_eq!(
+        err,
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(PaladinLockupError::IncorrectLockupPoolAddress as u32)
+        )
+    );
+}
+
+#[tokio::test]
+async fn err_incorrect_pool_authority() {
+    let mut context = setup().start_with_context().await;
+    let payer = Keypair::new();
+    let mint = Pubkey::new_unique();
+    let pool_authority = Keypair::new();
+
+    // Setup the payer & mint.
+    setup_system_account(&mut context, &payer.pubkey(), 1_000_000_000).await;
+    setup_mint(&mut context, &mint, &Pubkey::new_unique(), 100).await;
+
+    // Attempt to initialize the pool with an authority other than the mint
+    // authority.
+    let initialize_lockup_pool = initialize_lockup_pool_instructions(
+        &payer.pubkey(),
+        mint,
+        0,
+        LOCKUP_COOLDOWN_SECONDS,
+        0,
+        Some(&pool_authority.pubkey()),
+        false,
+    );
+    let tx = Transaction::new_signed_with_payer(
+        &initialize_lockup_pool,
+        Some(&context.payer.pubkey()),
+        &[&context.payer, &payer, &pool_authority],
+        context.last_blockhash,
+    );
+    let err = context
+        .banks_client
+        .process_transaction(tx)
+        .await
+        .unwrap_err()
+        .unwrap();
+
+    assert_eq!(
+        err,
+        TransactionError::InstructionError(
+            (initialize_lockup_pool.len() - 1) as u8,
+            InstructionError::IncorrectAuthority
+        )
+    );
+}
+
+#[tokio::test]
+async fn err_missing_pool_authority() {
+    let mut context = setup().start_with_context().await;
+    let payer = Keypair::new();
+    let mint = Pubkey::new_unique();
+
+    // Setup the payer & mint.
+    setup_system_account(&mut context, &payer.pubkey(), 1_000_000_000).await;
+    setup_mint(&mut context, &mint, &Pubkey::new_unique(), 100).await;
+
+    // Attempt to initialize the pool without the mint authority.
+    let initialize_lockup_pool = initialize_lockup_pool_instructions(
+        &payer.pubkey(),
+        mint,
+        0,
+        LOCKUP_COOLDOWN_SECONDS,
+        0,
+        None,
+        false,
+    );
+    let tx = Transaction::new_signed_with_payer(
+        &initialize_lockup_pool,
+        Some(&context.payer.pubkey()),
+        &[&context.payer, &payer],
+        context.last_blockhash,
+    );
+    let err = context
+        .banks_client
+        .process_transaction(tx)
+        .await
+        .unwrap_err()
+        .unwrap();
+
+    assert_eq!(
+        err,
+        TransactionError::InstructionError(
+            (initialize_lockup_pool.len() - 1) as u8,
+            InstructionError::MissingRequiredSignature
+        )
+    );
+}
+
+#[test_case(MAX_LOCKUP_COOLDOWN_SECONDS + 1, 0, false; "cooldown_too_long")]
+#[test_case(
+    LOCKUP_COOLDOWN_SECONDS,
+    MAX_METADATA_UPDATE_INTERVAL_SECONDS + 1,
+    false;
+    "metadata_update_interval_too_long"
+)]
+#[test_case(0, 0, true; "fixed_supply_custom_cooldown")]
+#[test_case(LOCKUP_COOLDOWN_SECONDS, 60, true; "fixed_supply_custom_metadata_update_interval")]
+#[tokio::test]
+async fn err_invalid_pool_config(
+    cooldown_seconds: u64,
+    metadata_update_interval_seconds: u64,
+    fixed_supply: bool,
+) {
+    let mut context = setup().start_with_context().await;
+    let payer = Keypair::new();
+    let mint = Pubkey::new_unique();
+    let mint_authority = Keypair::new();
+
+    // Setup the payer & mint.
+    setup_system_account(&mut context, &payer.pubkey(), 1_000_000_000).await;
+    if fixed_supply {
+        setup_fixed_supply_mint(&mut context, &mint, 100).await;
+    } else {
+        setup_mint(&mut context, &mint, &mint_authority.pubkey(), 100).await;
+    }
+
+    // Attempt to initialize the pool with the provided configuration.
+    let mut signers = vec![&context.payer, &payer];
+    if !fixed_supply {
+        signers.push(&mint_authority);
+    }
+    let initialize_lockup_pool = initialize_lockup_pool_instructions(
+        &payer.pubkey(),
+        mint,
+        0,
+        cooldown_seconds,
+        metadata_update_interval_seconds,
+        (!fixed_supply).then(|| mint_authority.pubkey()).as_ref(),
+        false,
+    );
+    let tx = Transaction::new_signed_with_payer(
+        &initialize_lockup_pool,
+        Some(&context.payer.pubkey()),
+        &signers,
+        context.last_blockhash,
+    );
+    let err = context
+        .banks_client
+        .process_transaction(tx)
+        .await
+        .unwrap_err()
+        .unwrap();
+
+    assert_eq!(
+        err,
+        TransactionError::InstructionError(
+            (initialize_lockup_pool.len() - 1) as u8,
+            InstructionError::Custom(PaladinLockupError::InvalidPoolConfig as u32)
+        )
+    );
+}
+
+#[tokio::test]
+async fn err_duplicate_initialize() {
+    let mut context = setup().start_with_context().await;
+    let payer = Keypair::new();
+    let mint = Pubkey::new_unique();
+    let mint_authority = Keypair::new();
+
+    // Setup the payer & mint.
+    setup_system_account(&mut context, &payer.pubkey(), 1_000_000_000).await;
+    setup_mint(&mut context, &mint, &mint_authority.pubkey(), 100).await;
+
     // Initialize the pool once.
-    let rent = Rent::default().minimum_balance(LockupPool::LEN);
-    let fund = system_instruction::transfer(&context.payer.pubkey(), &pool.pubkey(), rent);
-    let allocate = system_instruction::allocate(&pool.pubkey(), LockupPool::LEN as u64);
-    let assign = system_instruction::assign(&pool.pubkey(), &paladin_lockup_program::ID);
-    let initialize_lockup_pool =
-        paladin_lockup_program::instruction::initialize_lockup_pool(pool.pubkey(), mint);
+    let initialize_lockup_pool = initialize_lockup_pool_instructions(
+        &payer.pubkey(),
+        mint,
+        0,
+        LOCKUP_COOLDOWN_SECONDS,
+        0,
+        Some(&mint_authority.pubkey()),
+        false,
+    );
     let tx = Transaction::new_signed_with_payer(
-        &[fund, allocate, assign, initialize_lockup_pool.clone()],
+        &initialize_lockup_pool,
         Some(&context.payer.pubkey()),
-        &[&context.payer, &pool],
+        &[&context.payer, &payer, &mint_authority],
         context.last_blockhash,
     );
     context.banks_client.process_transaction(tx).await.unwrap();
 
     // Attempt to initialize the pool again.
     let tx = Transaction::new_signed_with_payer(
-        &[initialize_lockup_pool],
+        &initialize_lockup_pool[..1],
         Some(&context.payer.pubkey()),
-        &[&context.payer],
+        &[&context.payer, &payer, &mint_authority],
         context.last_blockhash,
     );
     let err = context
@@ -5,16 +5,25 @@ mod setup;
 use {
     paladin_lockup_program::{
         error::PaladinLockupError,
-        state::{get_escrow_authority_address, Lockup, LockupPool},
+        state::{
+            get_escrow_authority_address, get_pool_escrow_authority_address, Lockup, LockupPool,
+            LockupPoolEntry, LockupPoolShard, LockupPoolShardInfo, LockupPoolStats, PodU128,
+            VestingSchedule,
+        },
     },
     rand::Rng,
-    setup::{setup, setup_lockup_pool, setup_mint, setup_token_account},
+    setup::{
+        lockup_pool_state, setup, setup_lockup, setup_lockup_pool, setup_lockup_pool_shard,
+        setup_lockup_pool_with_entries, setup_lockup_pool_with_state, setup_mint,
+        setup_mint_with_program_id, setup_mint_with_transfer_fee, setup_token_account,
+        setup_token_account_with_program_id, setup_transfer_fee_token_account,
+    },
     solana_program_test::*,
     solana_sdk::{
         account::{Account, AccountSharedData},
         clock::Clock,
         compute_budget::ComputeBudgetInstruction,
-        instruction::InstructionError,
+        instruction::{Instruction, InstructionError},
         pubkey::Pubkey,
         signature::Keypair,
         signer::Signer,
@@ -70,12 +79,15 @@ async fn fail_incorrect_lockup_owner() {
         &token_owner.pubkey(),
         &token_account,
         pool,
+        &lockup_pool_state(mint),
+        &[],
         &lockup,
         &mint,
         metadata.to_bytes(),
         10_000,
+        None,
         &spl_token_2022::id(),
-        &[],
+        false,
     );
 
     let transaction = Transaction::new_signed_with_payer(
@@ -142,12 +154,15 @@ async fn fail_lockup_not_enough_space() {
         &token_owner.pubkey(),
         &token_account,
         pool,
+        &lockup_pool_state(mint),
+        &[],
         &lockup,
         &mint,
         metadata.to_bytes(),
         10_000,
+        None,
         &spl_token_2022::id(),
-        &[],
+        false,
     );
 
     let transaction = Transaction::new_signed_with_payer(
@@ -222,12 +237,15 @@ async fn fail_lockup_already_initialized() {
         &token_owner.pubkey(),
         &token_account,
         pool,
+        &lockup_pool_state(mint),
+        &[],
         &lockup,
         &mint,
         metadata.to_bytes(),
         10_000,
+        None,
         &spl_token_2022::id(),
-        &[],
+        false,
     );
 
     let transaction = Transaction::new_signed_with_payer(
@@ -295,12 +313,15 @@ async fn fail_incorrect_escrow_authority_address() {
         &token_owner.pubkey(),
         &token_account,
         pool,
+        &lockup_pool_state(mint),
+        &[],
         &lockup,
         &mint,
         metadata.to_bytes(),
         10_000,
+        None,
         &spl_token_2022::id(),
-        &[],
+        false,
     );
     instruction.accounts[5].pubkey = Pubkey::new_unique(); // Incorrect escrow authority address.
 
@@ -372,12 +393,15 @@ async fn fail_incorrect_escrow_token_account_address() {
         &token_owner.pubkey(),
         &token_account,
         pool,
+        &lockup_pool_state(mint),
+        &[],
         &lockup,
         &mint,
         metadata.to_bytes(),
         10_000,
+        None,
         &spl_token_2022::id(),
-        &[],
+        false,
     );
     instruction.accounts[6].pubkey = Pubkey::new_unique(); // Incorrect escrow token account address.
 
@@ -404,6 +428,168 @@ async fn fail_incorrect_escrow_token_account_address() {
     );
 }
 
+#[tokio::test]
+async fn fail_incorrect_token_program() {
+    let mut context = setup().start_with_context().await;
+
+    let lockup_authority = Keypair::new();
+    let mint = Pubkey::new_unique();
+    let token_owner = Keypair::new();
+    let token_account = get_associated_token_address_with_program_id(
+        &token_owner.pubkey(),
+        &mint,
+        &spl_token_2022::id(),
+    );
+    let lockup = Pubkey::new_unique();
+    let metadata = Pubkey::new_unique();
+
+    // Create the lockup pool account.
+    let pool = Pubkey::new_unique();
+    setup_lockup_pool(&mut context, &pool, mint).await;
+
+    setup_token_account(
+        &mut context,
+        &token_account,
+        &token_owner.pubkey(),
+        &mint,
+        10_000,
+    )
+    .await;
+    setup_mint(&mut context, &mint, &Pubkey::new_unique(), 1_000_000).await;
+
+    // Set up the lockup account correctly.
+    {
+        let rent = context.banks_client.get_rent().await.unwrap();
+        let space = std::mem::size_of::<Lockup>();
+        let lamports = rent.minimum_balance(space);
+
+        context.set_account(
+            &lockup,
+            &AccountSharedData::new(lamports, space, &paladin_lockup_program::id()),
+        );
+    }
+
+    // The mint is owned by Token-2022, not SPL Token.
+    let instruction = paladin_lockup_program::instruction::lockup(
+        &lockup_authority.pubkey(),
+        &token_owner.pubkey(),
+        &token_account,
+        pool,
+        &lockup_pool_state(mint),
+        &[],
+        &lockup,
+        &mint,
+        metadata.to_bytes(),
+        10_000,
+        None,
+        &spl_token::id(),
+        false,
+    );
+
+    let transaction = Transaction::new_signed_with_payer(
+        &[instruction],
+        Some(&context.payer.pubkey()),
+        &[&context.payer, &token_owner],
+        context.last_blockhash,
+    );
+
+    let err = context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap_err()
+        .unwrap();
+
+    assert_eq!(
+        err,
+        TransactionError::InstructionError(0, InstructionError::IncorrectProgramId)
+    );
+}
+
+#[test_case(LockupPool::FLAG_LOCKUPS_DISABLED, PaladinLockupError::LockupsDisabled; "lockups_disabled")]
+#[test_case(LockupPool::FLAG_LOCKUP_PAUSED, PaladinLockupError::PoolPaused; "lockups_paused")]
+#[tokio::test]
+async fn fail_pool_config(flags: u64, expected_err: PaladinLockupError) {
+    let mut context = setup().start_with_context().await;
+
+    let lockup_authority = Keypair::new();
+    let mint = Pubkey::new_unique();
+    let token_owner = Keypair::new();
+    let token_account = get_associated_token_address_with_program_id(
+        &token_owner.pubkey(),
+        &mint,
+        &spl_token_2022::id(),
+    );
+    let metadata = Pubkey::new_unique();
+
+    // Create the lockup pool account with the provided flags.
+    let pool = Pubkey::new_unique();
+    setup_lockup_pool_with_state(
+        &mut context,
+        &pool,
+        &LockupPool {
+            flags,
+            ..lockup_pool_state(mint)
+        },
+    )
+    .await;
+
+    // Create the lockup account.
+    let lockup = Pubkey::new_unique();
+    setup_token_account(
+        &mut context,
+        &token_account,
+        &token_owner.pubkey(),
+        &mint,
+        10_000,
+    )
+    .await;
+    {
+        let rent = context.banks_client.get_rent().await.unwrap();
+        let space = std::mem::size_of::<Lockup>();
+        let lamports = rent.minimum_balance(space);
+        context.set_account(
+            &lockup,
+            &AccountSharedData::new(lamports, space, &paladin_lockup_program::id()),
+        );
+    }
+
+    let instruction = paladin_lockup_program::instruction::lockup(
+        &lockup_authority.pubkey(),
+        &token_owner.pubkey(),
+        &token_account,
+        pool,
+        &lockup_pool_state(mint),
+        &[],
+        &lockup,
+        &mint,
+        metadata.to_bytes(),
+        10_000,
+        None,
+        &spl_token_2022::id(),
+        false,
+    );
+
+    let transaction = Transaction::new_signed_with_payer(
+        &[instruction],
+        Some(&context.payer.pubkey()),
+        &[&context.payer, &token_owner],
+        context.last_blockhash,
+    );
+
+    let err = context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap_err()
+        .unwrap();
+
+    assert_eq!(
+        err,
+        TransactionError::InstructionError(0, InstructionError::Custom(expected_err as u32))
+    );
+}
+
 async fn check_token_account_balance(
     context: &mut ProgramTestContext,
     token_account_address: &Pubkey,
@@ -422,11 +608,12 @@ async fn check_token_account_balance(
     assert_eq!(actual_amount, check_amount);
 }
 
-#[test_case(1)]
-#[test_case(1_000_000_000)]
-#[test_case(1_000_000_000_000_000)]
+#[test_case(1, spl_token_2022::id(); "token_2022_1")]
+#[test_case(1_000_000_000, spl_token_2022::id(); "token_2022_1_000_000_000")]
+#[test_case(1_000_000_000_000_000, spl_token_2022::id(); "token_2022_1_000_000_000_000_000")]
+#[test_case(1_000_000_000, spl_token::id(); "spl_token_1_000_000_000")]
 #[tokio::test]
-async fn success(amount: u64) {
+async fn success(amount: u64, token_program_id: Pubkey) {
     let lockup_authority = Keypair::new();
     let mint = Pubkey::new_unique();
 
@@ -434,38 +621,44 @@ async fn success(amount: u64) {
     let token_account = get_associated_token_address_with_program_id(
         &token_owner.pubkey(),
         &mint,
-        &spl_token_2022::id(),
+        &token_program_id,
     );
     let token_account_starting_token_balance = amount;
 
     let escrow_authority = get_escrow_authority_address(&paladin_lockup_program::id());
-    let escrow_token_account = get_associated_token_address_with_program_id(
-        &escrow_authority,
-        &mint,
-        &spl_token_2022::id(),
-    );
+    let escrow_token_account =
+        get_associated_token_address_with_program_id(&escrow_authority, &mint, &token_program_id);
 
     let lockup = Pubkey::new_unique();
     let metadata = Pubkey::new_unique();
 
     let mut context = setup().start_with_context().await;
-    setup_token_account(
+    setup_token_account_with_program_id(
         &mut context,
         &token_account,
         &token_owner.pubkey(),
         &mint,
         token_account_starting_token_balance,
+        &token_program_id,
     )
     .await;
-    setup_token_account(
+    setup_token_account_with_program_id(
         &mut context,
         &escrow_token_account,
         &escrow_authority,
         &mint,
         0,
+        &token_program_id,
+    )
+    .await;
+    setup_mint_with_program_id(
+        &mut context,
+        &mint,
+        &Pubkey::new_unique(),
+        1_000_000,
+        &token_program_id,
     )
     .await;
-    setup_mint(&mut context, &mint, &Pubkey::new_unique(), 1_000_000).await;
 
     // Create the lockup pool account.
     let pool = Pubkey::new_unique();
@@ -489,12 +682,15 @@ async fn success(amount: u64) {
         &token_owner.pubkey(),
         &token_account,
         pool,
+        &lockup_pool_state(mint),
+        &[],
         &lockup,
         &mint,
         metadata.to_bytes(),
         amount,
-        &spl_token_2022::id(),
-        &[],
+        None,
+        &token_program_id,
+        false,
     );
 
     let transaction = Transaction::new_signed_with_payer(
@@ -531,9 +727,34 @@ async fn success(amount: u64) {
             mint,
             pool,
             metadata: metadata.to_bytes(),
+            proposed_authority: Pubkey::default(),
+            committed_end_timestamp: None,
+            vesting: VestingSchedule::default(),
+            withdrawn_amount: 0,
+            metadata_updated_timestamp: 0,
+            flags: Lockup::FLAG_IN_POOL,
+            reward_per_token_checkpoint: PodU128::default(),
+            unclaimed_rewards: 0,
+            cooldown_end_timestamp: None,
         },
     );
 
+    // Check the pool's stats.
+    let lockup_pool = context
+        .banks_client
+        .get_account(pool)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(
+        LockupPool::unpack(&lockup_pool.data).unwrap().0.stats,
+        LockupPoolStats {
+            active_amount: amount,
+            active_lockups: 1,
+            ..LockupPoolStats::default()
+        }
+    );
+
     // Validate tokens were transferred from the token account to the escrow.
     check_token_account_balance(
         &mut context,
@@ -544,12 +765,14 @@ async fn success(amount: u64) {
     check_token_account_balance(&mut context, &escrow_token_account, amount).await;
 }
 
+#[test_case(false; "program_escrow")]
+#[test_case(true; "pool_escrow")]
 #[tokio::test]
-async fn lockup_pool_scenarios() {
-    let mut context = setup().start_with_context().await;
-
+async fn isolated_escrow(pool_escrow: bool) {
     let lockup_authority = Keypair::new();
     let mint = Pubkey::new_unique();
+    let amount = 10_000;
+
     let token_owner = Keypair::new();
     let token_account = get_associated_token_address_with_program_id(
         &token_owner.pubkey(),
@@ -557,30 +780,26 @@ async fn lockup_pool_scenarios() {
         &spl_token_2022::id(),
     );
 
-    // Setup the mint.
-    setup_mint(&mut context, &mint, &Pubkey::new_unique(), 100).await;
-
-    // Create the lockup pool account.
     let pool = Pubkey::new_unique();
-    setup_lockup_pool(&mut context, &pool, mint).await;
+    let escrow_authority = get_pool_escrow_authority_address(&pool, &paladin_lockup_program::id());
+    let escrow_token_account = get_associated_token_address_with_program_id(
+        &escrow_authority,
+        &mint,
+        &spl_token_2022::id(),
+    );
 
-    // Create the token account.
-    let token_amount = 1_000_000_000;
-    setup_mint(&mut context, &mint, &Pubkey::new_unique(), token_amount).await;
+    let lockup = Pubkey::new_unique();
+    let metadata = Pubkey::new_unique();
+
+    let mut context = setup().start_with_context().await;
     setup_token_account(
         &mut context,
         &token_account,
         &token_owner.pubkey(),
         &mint,
-        token_amount,
+        amount,
     )
     .await;
-    let escrow_authority = get_escrow_authority_address(&paladin_lockup_program::id());
-    let escrow_token_account = get_associated_token_address_with_program_id(
-        &escrow_authority,
-        &mint,
-        &spl_token_2022::id(),
-    );
     setup_token_account(
         &mut context,
         &escrow_token_account,
@@ -589,166 +808,999 @@ async fn lockup_pool_scenarios() {
         0,
     )
     .await;
+    setup_mint(&mut context, &mint, &Pubkey::new_unique(), 1_000_000).await;
 
-    // Setup max lockup accounts.
-    let mut lockups = [Pubkey::default(); LockupPool::LOCKUP_CAPACITY];
-    for i in 0..LockupPool::LOCKUP_CAPACITY {
-        let lockup = Pubkey::new_unique();
-        lockups[i] = lockup;
+    // Create the lockup pool account, with its own escrow.
+    setup_lockup_pool_with_state(
+        &mut context,
+        &pool,
+        &LockupPool {
+            flags: LockupPool::FLAG_ISOLATED_ESCROW,
+            ..lockup_pool_state(mint)
+        },
+    )
+    .await;
 
-        initialize_lockup(
-            &mut context,
-            lockup_authority.pubkey(),
-            &token_owner,
-            token_account,
-            pool,
-            lockup,
-            mint,
-            (i + 1) as u64,
-        )
-        .await
-        .unwrap();
-    }
+    // Set up the lockup account correctly.
+    {
+        let rent = context.banks_client.get_rent().await.unwrap();
+        let space = std::mem::size_of::<Lockup>();
+        let lamports = rent.minimum_balance(space);
 
-    // Sanity - Check lockup accounts are as expected.
-    let lockup_pool = context
-        .banks_client
-        .get_account(pool)
-        .await
-        .unwrap()
-        .unwrap();
-    let lockup_pool = bytemuck::from_bytes::<LockupPool>(&lockup_pool.data);
-    for (i, lockup) in lockups.iter().rev().enumerate() {
-        assert_eq!(&lockup_pool.entries[i].lockup, lockup);
-        assert_eq!(
-            lockup_pool.entries[i].amount,
-            (LockupPool::LOCKUP_CAPACITY - i) as u64
+        context.set_account(
+            &lockup,
+            &AccountSharedData::new(lamports, space, &paladin_lockup_program::id()),
         );
     }
 
-    // Act - Insert another lock with 100 weight, evicts the smallest.
-    initialize_lockup(
-        &mut context,
-        lockup_authority.pubkey(),
-        &token_owner,
-        token_account,
+    let instruction = paladin_lockup_program::instruction::lockup(
+        &lockup_authority.pubkey(),
+        &token_owner.pubkey(),
+        &token_account,
         pool,
-        Pubkey::new_unique(),
+        &lockup_pool_state(mint),
+        &[],
+        &lockup,
+        &mint,
+        metadata.to_bytes(),
+        amount,
+        None,
+        &spl_token_2022::id(),
+        pool_escrow,
+    );
+
+    let transaction = Transaction::new_signed_with_payer(
+        &[instruction],
+        Some(&context.payer.pubkey()),
+        &[&context.payer, &token_owner],
+        context.last_blockhash,
+    );
+
+    let result = context.banks_client.process_transaction(transaction).await;
+
+    if !pool_escrow {
+        // The program-wide escrow is rejected.
+        assert_eq!(
+            result.unwrap_err().unwrap(),
+            TransactionError::InstructionError(
+                0,
+                InstructionError::Custom(
+                    PaladinLockupError::IncorrectEscrowAuthorityAddress as u32
+                )
+            )
+        );
+        return;
+    }
+    result.unwrap();
+
+    // Validate tokens were transferred into the pool's own escrow.
+    check_token_account_balance(&mut context, &token_account, 0).await;
+    check_token_account_balance(&mut context, &escrow_token_account, amount).await;
+}
+
+#[tokio::test]
+async fn success_transfer_fee() {
+    let lockup_authority = Keypair::new();
+    let mint = Pubkey::new_unique();
+
+    let token_owner = Keypair::new();
+    let token_account = get_associated_token_address_with_program_id(
+        &token_owner.pubkey(),
+        &mint,
+        &spl_token_2022::id(),
+    );
+
+    let escrow_authority = get_escrow_authority_address(&paladin_lockup_program::id());
+    let escrow_token_account = get_associated_token_address_with_program_id(
+        &escrow_authority,
+        &mint,
+        &spl_token_2022::id(),
+    );
+
+    let lockup = Pubkey::new_unique();
+    let metadata = Pubkey::new_unique();
+
+    let mut context = setup().start_with_context().await;
+    setup_transfer_fee_token_account(
+        &mut context,
+        &token_account,
+        &token_owner.pubkey(),
+        &mint,
+        10_000,
+    )
+    .await;
+    setup_transfer_fee_token_account(
+        &mut context,
+        &escrow_token_account,
+        &escrow_authority,
+        &mint,
+        0,
+    )
+    .await;
+    // 1% transfer fee.
+    setup_mint_with_transfer_fee(
+        &mut context,
+        &mint,
+        &Pubkey::new_unique(),
+        1_000_000,
+        100,
+        u64::MAX,
+    )
+    .await;
+
+    // Create the lockup pool account.
+    let pool = Pubkey::new_unique();
+    setup_lockup_pool(&mut context, &pool, mint).await;
+
+    // Set up the lockup account correctly.
+    {
+        let rent = context.banks_client.get_rent().await.unwrap();
+        let space = std::mem::size_of::<Lockup>();
+        let lamports = rent.minimum_balance(space);
+
+        context.set_account(
+            &lockup,
+            &AccountSharedData::new(lamports, space, &paladin_lockup_program::id()),
+        );
+    }
+
+    let instruction = paladin_lockup_program::instruction::lockup(
+        &lockup_authority.pubkey(),
+        &token_owner.pubkey(),
+        &token_account,
+        pool,
+        &lockup_pool_state(mint),
+        &[],
+        &lockup,
+        &mint,
+        metadata.to_bytes(),
+        10_000,
+        None,
+        &spl_token_2022::id(),
+        false,
+    );
+
+    let transaction = Transaction::new_signed_with_payer(
+        &[instruction],
+        Some(&context.payer.pubkey()),
+        &[&context.payer, &token_owner],
+        context.last_blockhash,
+    );
+
+    context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap();
+
+    // The lockup & pool only count the amount received, net of the fee.
+    let lockup_state = get_lockup_state(&mut context, &lockup).await;
+    assert_eq!(lockup_state.amount, 9_900);
+
+    let lockup_pool = context
+        .banks_client
+        .get_account(pool)
+        .await
+        .unwrap()
+        .unwrap();
+    let (lockup_pool_state, lockup_pool_entries) = LockupPool::unpack(&lockup_pool.data).unwrap();
+    assert_eq!(lockup_pool_state.stats.active_amount, 9_900);
+    assert_eq!(lockup_pool_entries[0].amount, 9_900);
+
+    check_token_account_balance(&mut context, &token_account, 0).await;
+    check_token_account_balance(&mut context, &escrow_token_account, 9_900).await;
+}
+
+#[test_case(0, 9_999; "below_minimum_amount")]
+#[test_case(100, 10_000; "below_minimum_amount_net_of_fee")]
+#[tokio::test]
+async fn fail_amount_too_low(transfer_fee_basis_points: u16, amount: u64) {
+    let lockup_authority = Keypair::new();
+    let mint = Pubkey::new_unique();
+
+    let token_owner = Keypair::new();
+    let token_account = get_associated_token_address_with_program_id(
+        &token_owner.pubkey(),
+        &mint,
+        &spl_token_2022::id(),
+    );
+
+    let escrow_authority = get_escrow_authority_address(&paladin_lockup_program::id());
+    let escrow_token_account = get_associated_token_address_with_program_id(
+        &escrow_authority,
+        &mint,
+        &spl_token_2022::id(),
+    );
+
+    let lockup = Pubkey::new_unique();
+    let metadata = Pubkey::new_unique();
+
+    let mut context = setup().start_with_context().await;
+    setup_transfer_fee_token_account(
+        &mut context,
+        &token_account,
+        &token_owner.pubkey(),
+        &mint,
+        10_000,
+    )
+    .await;
+    setup_transfer_fee_token_account(
+        &mut context,
+        &escrow_token_account,
+        &escrow_authority,
+        &mint,
+        0,
+    )
+    .await;
+    setup_mint_with_transfer_fee(
+        &mut context,
+        &mint,
+        &Pubkey::new_unique(),
+        1_000_000,
+        transfer_fee_basis_points,
+        u64::MAX,
+    )
+    .await;
+
+    // Create the lockup pool account, requiring lockups of at least 10,000
+    // tokens.
+    let pool = Pubkey::new_unique();
+    setup_lockup_pool_with_state(
+        &mut context,
+        &pool,
+        &LockupPool {
+            min_lockup_amount: 10_000,
+            ..lockup_pool_state(mint)
+        },
+    )
+    .await;
+
+    // Set up the lockup account correctly.
+    {
+        let rent = context.banks_client.get_rent().await.unwrap();
+        let space = std::mem::size_of::<Lockup>();
+        let lamports = rent.minimum_balance(space);
+
+        context.set_account(
+            &lockup,
+            &AccountSharedData::new(lamports, space, &paladin_lockup_program::id()),
+        );
+    }
+
+    let instruction = paladin_lockup_program::instruction::lockup(
+        &lockup_authority.pubkey(),
+        &token_owner.pubkey(),
+        &token_account,
+        pool,
+        &lockup_pool_state(mint),
+        &[],
+        &lockup,
+        &mint,
+        metadata.to_bytes(),
+        amount,
+        None,
+        &spl_token_2022::id(),
+        false,
+    );
+
+    let transaction = Transaction::new_signed_with_payer(
+        &[instruction],
+        Some(&context.payer.pubkey()),
+        &[&context.payer, &token_owner],
+        context.last_blockhash,
+    );
+
+    let err = context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap_err()
+        .unwrap();
+
+    assert_eq!(
+        err,
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(PaladinLockupError::AmountTooLow as u32)
+        )
+    );
+}
+
+#[tokio::test]
+async fn lockup_pool_scenarios() {
+    let mut context = setup().start_with_context().await;
+
+    let lockup_authority = Keypair::new();
+    let mint = Pubkey::new_unique();
+    let token_owner = Keypair::new();
+    let token_account = get_associated_token_address_with_program_id(
+        &token_owner.pubkey(),
+        &mint,
+        &spl_token_2022::id(),
+    );
+
+    // Setup the mint.
+    setup_mint(&mut context, &mint, &Pubkey::new_unique(), 100).await;
+
+    // Create the lockup pool account.
+    let pool = Pubkey::new_unique();
+    setup_lockup_pool(&mut context, &pool, mint).await;
+
+    // Create the token account.
+    let token_amount = 1_000_000_000;
+    setup_mint(&mut context, &mint, &Pubkey::new_unique(), token_amount).await;
+    setup_token_account(
+        &mut context,
+        &token_account,
+        &token_owner.pubkey(),
+        &mint,
+        token_amount,
+    )
+    .await;
+    let escrow_authority = get_escrow_authority_address(&paladin_lockup_program::id());
+    let escrow_token_account = get_associated_token_address_with_program_id(
+        &escrow_authority,
+        &mint,
+        &spl_token_2022::id(),
+    );
+    setup_token_account(
+        &mut context,
+        &escrow_token_account,
+        &escrow_authority,
+        &mint,
+        0,
+    )
+    .await;
+
+    // Setup max lockup accounts.
+    let mut lockups = [Pubkey::default(); LockupPool::INITIAL_CAPACITY];
+    for i in 0..LockupPool::INITIAL_CAPACITY {
+        let lockup = Pubkey::new_unique();
+        lockups[i] = lockup;
+
+        initialize_lockup(
+            &mut context,
+            lockup_authority.pubkey(),
+            &token_owner,
+            token_account,
+            pool,
+            lockup,
+            mint,
+            (i + 1) as u64,
+            &[],
+        )
+        .await
+        .unwrap();
+    }
+
+    // Sanity - Check lockup accounts are as expected.
+    let lockup_pool = context
+        .banks_client
+        .get_account(pool)
+        .await
+        .unwrap()
+        .unwrap();
+    let (_, pool_entries) = LockupPool::unpack(&lockup_pool.data).unwrap();
+    for (i, lockup) in lockups.iter().rev().enumerate() {
+        assert_eq!(&pool_entries[i].lockup, lockup);
+        assert_eq!(
+            pool_entries[i].amount,
+            (LockupPool::INITIAL_CAPACITY - i) as u64
+        );
+    }
+
+    // Act - Insert another lock with 100 weight, evicts the smallest.
+    initialize_lockup(
+        &mut context,
+        lockup_authority.pubkey(),
+        &token_owner,
+        token_account,
+        pool,
+        Pubkey::new_unique(),
+        mint,
+        100,
+        pool_entries,
+    )
+    .await
+    .unwrap();
+
+    // Assert - The smallest was evicted & locks are in correct sort order.
+    let lockup_pool = context
+        .banks_client
+        .get_account(pool)
+        .await
+        .unwrap()
+        .unwrap();
+    let (lockup_pool, pool_entries) = LockupPool::unpack(&lockup_pool.data).unwrap();
+    let mut sorted_entries = pool_entries.to_vec();
+    sorted_entries.sort_by_key(|entry| Reverse(entry.amount));
+    assert_eq!(lockup_pool.entries_len, LockupPool::INITIAL_CAPACITY);
+    assert_eq!(pool_entries, sorted_entries);
+    assert_eq!(pool_entries[LockupPool::INITIAL_CAPACITY - 1].amount, 2);
+
+    // Act - Try to insert a lock that is smaller than the smallest lock.
+    assert_eq!(
+        initialize_lockup(
+            &mut context,
+            lockup_authority.pubkey(),
+            &token_owner,
+            token_account,
+            pool,
+            Pubkey::new_unique(),
+            mint,
+            1,
+            &[],
+        )
+        .await
+        .unwrap_err()
+        .unwrap(),
+        TransactionError::InstructionError(
+            1, // 0 is compute budget IX
+            InstructionError::Custom(PaladinLockupError::AmountTooLow as u32)
+        )
+    );
+
+    // Act - Unlock the smallest lock.
+    let to_unlock = pool_entries[lockup_pool.entries_len - 1];
+    let instruction = paladin_lockup_program::instruction::unlock(
+        &lockup_authority.pubkey(),
+        pool,
+        &lockup_pool_state(mint),
+        &to_unlock.lockup,
+        to_unlock.amount,
+    );
+    let transaction = Transaction::new_signed_with_payer(
+        &[instruction],
+        Some(&context.payer.pubkey()),
+        &[&context.payer, &lockup_authority],
+        context.last_blockhash,
+    );
+    context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap();
+
+    // Assert - Smallest lock is removed.
+    let lockup_pool = context
+        .banks_client
+        .get_account(pool)
+        .await
+        .unwrap()
+        .unwrap();
+    let (lockup_pool, pool_entries) = LockupPool::unpack(&lockup_pool.data).unwrap();
+    let mut sorted_entries = pool_entries.to_vec();
+    sorted_entries.sort_by_key(|entry| Reverse(entry.amount));
+    assert_eq!(lockup_pool.entries_len, LockupPool::INITIAL_CAPACITY - 1);
+    assert_eq!(pool_entries, sorted_entries);
+    assert_eq!(pool_entries[lockup_pool.entries_len - 1].amount, 3);
+
+    // Act - Unlock a random lock.
+    let index = rand::thread_rng().gen_range(0..lockup_pool.entries_len);
+    let to_unlock = pool_entries[index];
+    let instruction = paladin_lockup_program::instruction::unlock(
+        &lockup_authority.pubkey(),
+        pool,
+        &lockup_pool_state(mint),
+        &to_unlock.lockup,
+        to_unlock.amount,
+    );
+    let transaction = Transaction::new_signed_with_payer(
+        &[
+            ComputeBudgetInstruction::set_compute_unit_limit(500_000),
+            instruction,
+        ],
+        Some(&context.payer.pubkey()),
+        &[&context.payer, &lockup_authority],
+        context.last_blockhash,
+    );
+    context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap();
+
+    // Assert - Smallest lock is removed.
+    let lockup_pool = context
+        .banks_client
+        .get_account(pool)
+        .await
+        .unwrap()
+        .unwrap();
+    let (lockup_pool, pool_entries) = LockupPool::unpack(&lockup_pool.data).unwrap();
+    let mut sorted_entries = pool_entries.to_vec();
+    sorted_entries.sort_by_key(|entry| Reverse(entry.amount));
+    assert_eq!(lockup_pool.entries_len, LockupPool::INITIAL_CAPACITY - 2);
+    assert_eq!(pool_entries, sorted_entries);
+    assert_eq!(pool_entries[lockup_pool.entries_len - 1].amount, 3);
+}
+
+#[tokio::test]
+async fn evicted_lockup_unlocks_after_equal_amount_insert() {
+    let mut context = setup().start_with_context().await;
+
+    let lockup_authority = Keypair::new();
+    let mint = Pubkey::new_unique();
+    let token_owner = Keypair::new();
+    let token_account = get_associated_token_address_with_program_id(
+        &token_owner.pubkey(),
+        &mint,
+        &spl_token_2022::id(),
+    );
+    let escrow_authority = get_escrow_authority_address(&paladin_lockup_program::id());
+    let escrow_token_account = get_associated_token_address_with_program_id(
+        &escrow_authority,
+        &mint,
+        &spl_token_2022::id(),
+    );
+
+    // Setup the mint & token accounts.
+    setup_mint(&mut context, &mint, &Pubkey::new_unique(), 1_000_000).await;
+    setup_token_account(
+        &mut context,
+        &token_account,
+        &token_owner.pubkey(),
+        &mint,
+        1_000_000,
+    )
+    .await;
+    setup_token_account(
+        &mut context,
+        &escrow_token_account,
+        &escrow_authority,
+        &mint,
+        0,
+    )
+    .await;
+
+    // Fill the pool, ranking the lockup to be evicted last.
+    let pool = Pubkey::new_unique();
+    let evicted_lockup = Pubkey::new_unique();
+    let unlocked_lockup = Pubkey::new_unique();
+    let mut entries = (0..LockupPool::INITIAL_CAPACITY)
+        .map(|i| LockupPoolEntry {
+            lockup: Pubkey::new_unique(),
+            amount: (LockupPool::INITIAL_CAPACITY - i) as u64,
+            metadata: [0; 32],
+        })
+        .collect::<Vec<_>>();
+    entries[0].lockup = unlocked_lockup;
+    entries[LockupPool::INITIAL_CAPACITY - 1].lockup = evicted_lockup;
+    setup_lockup_pool_with_entries(&mut context, &pool, mint, &entries).await;
+    for entry in [entries[0], entries[LockupPool::INITIAL_CAPACITY - 1]] {
+        setup_lockup(
+            &mut context,
+            &entry.lockup,
+            Lockup {
+                discriminator: Lockup::SPL_DISCRIMINATOR.into(),
+                amount: entry.amount,
+                authority: lockup_authority.pubkey(),
+                lockup_start_timestamp: 10,
+                lockup_end_timestamp: None,
+                mint,
+                pool,
+                metadata: entry.metadata,
+                proposed_authority: Pubkey::default(),
+                committed_end_timestamp: None,
+                vesting: VestingSchedule::default(),
+                withdrawn_amount: 0,
+                metadata_updated_timestamp: 0,
+                flags: Lockup::FLAG_IN_POOL,
+                reward_per_token_checkpoint: PodU128::default(),
+                unclaimed_rewards: 0,
+                cooldown_end_timestamp: None,
+            },
+        )
+        .await;
+    }
+
+    // Act - A larger lockup evicts the smallest one.
+    let larger_lockup = Pubkey::new_unique();
+    {
+        let rent = context.banks_client.get_rent().await.unwrap();
+        let space = std::mem::size_of::<Lockup>();
+        context.set_account(
+            &larger_lockup,
+            &AccountSharedData::new(
+                rent.minimum_balance(space),
+                space,
+                &paladin_lockup_program::id(),
+            ),
+        );
+    }
+    send_transaction(
+        &mut context,
+        &[paladin_lockup_program::instruction::lockup(
+            &lockup_authority.pubkey(),
+            &token_owner.pubkey(),
+            &token_account,
+            pool,
+            &lockup_pool_state(mint),
+            &entries,
+            &larger_lockup,
+            &mint,
+            Pubkey::new_unique().to_bytes(),
+            100,
+            None,
+            &spl_token_2022::id(),
+            false,
+        )],
+        &[&token_owner],
+    )
+    .await
+    .unwrap();
+
+    // Assert - The evicted lockup left the pool.
+    let state = get_lockup_state(&mut context, &evicted_lockup).await;
+    assert!(!state.in_pool());
+
+    // Act - Another lockup leaves the pool, freeing up space.
+    send_transaction(
+        &mut context,
+        &[paladin_lockup_program::instruction::unlock(
+            &lockup_authority.pubkey(),
+            pool,
+            &lockup_pool_state(mint),
+            &unlocked_lockup,
+            LockupPool::INITIAL_CAPACITY as u64,
+        )],
+        &[&lockup_authority],
+    )
+    .await
+    .unwrap();
+
+    // Act - A new lockup with the evicted lockup's amount takes the free slot.
+    let equal_lockup = Pubkey::new_unique();
+    initialize_lockup(
+        &mut context,
+        lockup_authority.pubkey(),
+        &token_owner,
+        token_account,
+        pool,
+        equal_lockup,
         mint,
-        100,
+        1,
+        &[],
     )
     .await
     .unwrap();
 
-    // Assert - The smallest was evicted & locks are in correct sort order.
+    // Act - The evicted lockup can still be unlocked.
+    send_transaction(
+        &mut context,
+        &[paladin_lockup_program::instruction::unlock(
+            &lockup_authority.pubkey(),
+            pool,
+            &lockup_pool_state(mint),
+            &evicted_lockup,
+            1,
+        )],
+        &[&lockup_authority],
+    )
+    .await
+    .unwrap();
+
+    // Assert - The evicted lockup was unlocked & left the new entry in place.
+    let state = get_lockup_state(&mut context, &evicted_lockup).await;
+    assert!(state.lockup_end_timestamp.is_some());
+    assert!(!state.in_pool());
     let lockup_pool = context
         .banks_client
         .get_account(pool)
         .await
         .unwrap()
         .unwrap();
-    let lockup_pool = bytemuck::from_bytes::<LockupPool>(&lockup_pool.data);
-    let mut lockup_pool_sorted = *lockup_pool;
-    lockup_pool_sorted
-        .entries
-        .sort_by_key(|entry| Reverse(entry.amount));
-    assert_eq!(lockup_pool.entries_len, LockupPool::LOCKUP_CAPACITY);
-    assert_eq!(lockup_pool, &lockup_pool_sorted);
+    let (lockup_pool, pool_entries) = LockupPool::unpack(&lockup_pool.data).unwrap();
+    assert_eq!(lockup_pool.entries_len, LockupPool::INITIAL_CAPACITY);
+    assert_eq!(pool_entries[0].lockup, entries[1].lockup);
     assert_eq!(
-        lockup_pool.entries[LockupPool::LOCKUP_CAPACITY - 1].amount,
-        2
+        pool_entries[LockupPool::INITIAL_CAPACITY - 1].lockup,
+        equal_lockup
     );
+    assert!(pool_entries
+        .iter()
+        .all(|entry| entry.lockup != evicted_lockup));
+}
 
-    // Act - Try to insert a lock that is smaller than the smallest lock.
-    assert_eq!(
-        initialize_lockup(
-            &mut context,
-            lockup_authority.pubkey(),
-            &token_owner,
-            token_account,
-            pool,
-            Pubkey::new_unique(),
-            mint,
-            1,
-        )
-        .await
-        .unwrap_err()
-        .unwrap(),
-        TransactionError::InstructionError(
-            1, // 0 is compute budget IX
-            InstructionError::Custom(PaladinLockupError::AmountTooLow as u32)
-        )
+#[test_case(false; "shard_not_provided")]
+#[test_case(true; "shard_provided")]
+#[tokio::test]
+async fn sharded_pool(provide_shard: bool) {
+    let lockup_authority = Keypair::new();
+    let mint = Pubkey::new_unique();
+    let amount = 10_000;
+
+    let token_owner = Keypair::new();
+    let token_account = get_associated_token_address_with_program_id(
+        &token_owner.pubkey(),
+        &mint,
+        &spl_token_2022::id(),
     );
 
-    // Act - Unlock the smallest lock.
-    let to_unlock = lockup_pool.entries[lockup_pool.entries_len - 1].lockup;
-    let instruction =
-        paladin_lockup_program::instruction::unlock(&lockup_authority.pubkey(), pool, &to_unlock);
-    let transaction = Transaction::new_signed_with_payer(
-        &[instruction],
-        Some(&context.payer.pubkey()),
-        &[&context.payer, &lockup_authority],
-        context.last_blockhash,
+    let escrow_authority = get_escrow_authority_address(&paladin_lockup_program::id());
+    let escrow_token_account = get_associated_token_address_with_program_id(
+        &escrow_authority,
+        &mint,
+        &spl_token_2022::id(),
     );
-    context
+
+    let lockup = Pubkey::new_unique();
+    let metadata = Pubkey::new_unique();
+
+    let mut context = setup().start_with_context().await;
+    setup_token_account(
+        &mut context,
+        &token_account,
+        &token_owner.pubkey(),
+        &mint,
+        amount,
+    )
+    .await;
+    setup_token_account(
+        &mut context,
+        &escrow_token_account,
+        &escrow_authority,
+        &mint,
+        0,
+    )
+    .await;
+    setup_mint(&mut context, &mint, &Pubkey::new_unique(), 1_000_000).await;
+
+    // Create a lockup pool with a shard covering the lockup's amount.
+    let pool = Pubkey::new_unique();
+    let shard = Pubkey::new_unique();
+    let mut state = lockup_pool_state(mint);
+    state.shards[0] = LockupPoolShardInfo {
+        address: shard,
+        max_amount: 1_000_000,
+    };
+    state.shards_len = 1;
+    setup_lockup_pool_with_state(&mut context, &pool, &state).await;
+    setup_lockup_pool_shard(&mut context, &shard, &pool, &[], 8).await;
+
+    // Set up the lockup account correctly.
+    {
+        let rent = context.banks_client.get_rent().await.unwrap();
+        let space = std::mem::size_of::<Lockup>();
+        let lamports = rent.minimum_balance(space);
+
+        context.set_account(
+            &lockup,
+            &AccountSharedData::new(lamports, space, &paladin_lockup_program::id()),
+        );
+    }
+
+    let mut instruction = paladin_lockup_program::instruction::lockup(
+        &lockup_authority.pubkey(),
+        &token_owner.pubkey(),
+        &token_account,
+        pool,
+        &state,
+        &[],
+        &lockup,
+        &mint,
+        metadata.to_bytes(),
+        amount,
+        None,
+        &spl_token_2022::id(),
+        false,
+    );
+    if !provide_shard {
+        instruction.accounts.pop();
+    }
+    let result = send_transaction(&mut context, &[instruction], &[&token_owner]).await;
+
+    if !provide_shard {
+        assert_eq!(
+            result.unwrap_err().unwrap(),
+            TransactionError::InstructionError(
+                1,
+                InstructionError::Custom(PaladinLockupError::MissingLockupPoolShard as u32)
+            )
+        );
+        return;
+    }
+    result.unwrap();
+
+    // The entry went into the shard rather than the pool itself.
+    let lockup_pool = context
         .banks_client
-        .process_transaction(transaction)
+        .get_account(pool)
+        .await
+        .unwrap()
+        .unwrap();
+    let (lockup_pool, _) = LockupPool::unpack(&lockup_pool.data).unwrap();
+    assert_eq!(lockup_pool.entries_len, 0);
+    let shard = context
+        .banks_client
+        .get_account(shard)
         .await
+        .unwrap()
         .unwrap();
+    let (shard, shard_entries) = LockupPoolShard::unpack(&shard.data).unwrap();
+    assert_eq!(shard.entries_len, 1);
+    assert_eq!(
+        shard_entries[0],
+        LockupPoolEntry {
+            lockup,
+            amount,
+            metadata: metadata.to_bytes(),
+        }
+    );
+}
 
-    // Assert - Smallest lock is removed.
+#[tokio::test]
+async fn sharded_pool_full_shard() {
+    let lockup_authority = Keypair::new();
+    let mint = Pubkey::new_unique();
+    let amount = 55_000;
+
+    let token_owner = Keypair::new();
+    let token_account = get_associated_token_address_with_program_id(
+        &token_owner.pubkey(),
+        &mint,
+        &spl_token_2022::id(),
+    );
+
+    let escrow_authority = get_escrow_authority_address(&paladin_lockup_program::id());
+    let escrow_token_account = get_associated_token_address_with_program_id(
+        &escrow_authority,
+        &mint,
+        &spl_token_2022::id(),
+    );
+
+    let lockup = Pubkey::new_unique();
+    let metadata = Pubkey::new_unique();
+
+    let mut context = setup().start_with_context().await;
+    setup_token_account(
+        &mut context,
+        &token_account,
+        &token_owner.pubkey(),
+        &mint,
+        amount,
+    )
+    .await;
+    setup_token_account(
+        &mut context,
+        &escrow_token_account,
+        &escrow_authority,
+        &mint,
+        0,
+    )
+    .await;
+    setup_mint(&mut context, &mint, &Pubkey::new_unique(), 1_000_000).await;
+
+    // Create a lockup pool with a full shard covering the lockup's amount &
+    // an empty shard below it.
+    let pool = Pubkey::new_unique();
+    let full_shard = Pubkey::new_unique();
+    let lower_shard = Pubkey::new_unique();
+    let mut state = lockup_pool_state(mint);
+    state.shards[0] = LockupPoolShardInfo {
+        address: full_shard,
+        max_amount: 1_000_000,
+    };
+    state.shards[1] = LockupPoolShardInfo {
+        address: lower_shard,
+        max_amount: 1_000,
+    };
+    state.shards_len = 2;
+    setup_lockup_pool_with_state(&mut context, &pool, &state).await;
+    let full_shard_entries: Vec<LockupPoolEntry> = (1..=8)
+        .rev()
+        .map(|i| LockupPoolEntry {
+            lockup: Pubkey::new_unique(),
+            amount: i * 10_000,
+            metadata: [0; 32],
+        })
+        .collect();
+    setup_lockup_pool_shard(&mut context, &full_shard, &pool, &full_shard_entries, 8).await;
+    setup_lockup_pool_shard(&mut context, &lower_shard, &pool, &[], 8).await;
+
+    // Set up the lockup account correctly.
+    {
+        let rent = context.banks_client.get_rent().await.unwrap();
+        let space = std::mem::size_of::<Lockup>();
+        let lamports = rent.minimum_balance(space);
+
+        context.set_account(
+            &lockup,
+            &AccountSharedData::new(lamports, space, &paladin_lockup_program::id()),
+        );
+    }
+
+    let instruction = paladin_lockup_program::instruction::lockup(
+        &lockup_authority.pubkey(),
+        &token_owner.pubkey(),
+        &token_account,
+        pool,
+        &state,
+        &[],
+        &lockup,
+        &mint,
+        metadata.to_bytes(),
+        amount,
+        None,
+        &spl_token_2022::id(),
+        false,
+    );
+    send_transaction(&mut context, &[instruction], &[&token_owner])
+        .await
+        .unwrap();
+
+    // The full shard's smallest entry moved into the shard below, whose
+    // boundary was raised above it.
     let lockup_pool = context
         .banks_client
         .get_account(pool)
         .await
         .unwrap()
         .unwrap();
-    let lockup_pool = bytemuck::from_bytes::<LockupPool>(&lockup_pool.data);
-    let mut lockup_pool_sorted = *lockup_pool;
-    lockup_pool_sorted
-        .entries
-        .sort_by_key(|entry| Reverse(entry.amount));
-    assert_eq!(lockup_pool.entries_len, LockupPool::LOCKUP_CAPACITY - 1);
-    assert_eq!(lockup_pool, &lockup_pool_sorted);
-    assert_eq!(lockup_pool.entries[lockup_pool.entries_len - 1].amount, 3);
+    let (lockup_pool, _) = LockupPool::unpack(&lockup_pool.data).unwrap();
+    assert_eq!(lockup_pool.shards[0].max_amount, 1_000_000);
+    assert_eq!(lockup_pool.shards[1].max_amount, 10_001);
 
-    // Act - Unlock a random lock.
-    let index = rand::thread_rng().gen_range(0..lockup_pool.entries_len);
-    let to_unlock = lockup_pool.entries[index].lockup;
-    let instruction =
-        paladin_lockup_program::instruction::unlock(&lockup_authority.pubkey(), pool, &to_unlock);
-    let transaction = Transaction::new_signed_with_payer(
-        &[
-            ComputeBudgetInstruction::set_compute_unit_limit(500_000),
-            instruction,
-        ],
-        Some(&context.payer.pubkey()),
-        &[&context.payer, &lockup_authority],
-        context.last_blockhash,
+    let shard = context
+        .banks_client
+        .get_account(full_shard)
+        .await
+        .unwrap()
+        .unwrap();
+    let (shard, shard_entries) = LockupPoolShard::unpack(&shard.data).unwrap();
+    assert_eq!(shard.entries_len, 8);
+    assert_eq!(&shard_entries[..3], &full_shard_entries[..3]);
+    assert_eq!(
+        shard_entries[3],
+        LockupPoolEntry {
+            lockup,
+            amount,
+            metadata: metadata.to_bytes(),
+        }
     );
-    context
+    assert_eq!(&shard_entries[4..8], &full_shard_entries[3..7]);
+
+    let shard = context
         .banks_client
-        .process_transaction(transaction)
+        .get_account(lower_shard)
         .await
+        .unwrap()
         .unwrap();
+    let (shard, shard_entries) = LockupPoolShard::unpack(&shard.data).unwrap();
+    assert_eq!(shard.entries_len, 1);
+    assert_eq!(shard_entries[0], full_shard_entries[7]);
+}
 
-    // Assert - Smallest lock is removed.
-    let lockup_pool = context
+async fn get_lockup_state(context: &mut ProgramTestContext, lockup: &Pubkey) -> Lockup {
+    let account = context
         .banks_client
-        .get_account(pool)
+        .get_account(*lockup)
         .await
         .unwrap()
         .unwrap();
-    let lockup_pool = bytemuck::from_bytes::<LockupPool>(&lockup_pool.data);
-    let mut lockup_pool_sorted = *lockup_pool;
-    lockup_pool_sorted
-        .entries
-        .sort_by_key(|entry| Reverse(entry.amount));
-    assert_eq!(lockup_pool.entries_len, LockupPool::LOCKUP_CAPACITY - 2);
-    assert_eq!(lockup_pool, &lockup_pool_sorted);
-    assert_eq!(lockup_pool.entries[lockup_pool.entries_len - 1].amount, 3);
+    *bytemuck::from_bytes::<Lockup>(&account.data)
+}
+
+async fn send_transaction(
+    context: &mut ProgramTestContext,
+    instructions: &[Instruction],
+    signers: &[&Keypair],
+) -> Result<(), BanksClientError> {
+    let cu_limit = ComputeBudgetInstruction::set_compute_unit_limit(400_000);
+    let instructions = [&[cu_limit], instructions].concat();
+    let payer = context.payer.insecure_clone();
+    let signers = [&[&payer], signers].concat();
+    let blockhash = context.get_new_latest_blockhash().await.unwrap();
+    let transaction = Transaction::new_signed_with_payer(
+        &instructions,
+        Some(&payer.pubkey()),
+        &signers,
+        blockhash,
+    );
+    context.banks_client.process_transaction(transaction).await
 }
 
+#[allow(clippy::too_many_arguments)]
 async fn initialize_lockup(
     context: &mut ProgramTestContext,
     lockup_authority: Pubkey,
@@ -758,6 +1810,7 @@ async fn initialize_lockup(
     lockup: Pubkey,
     mint: Pubkey,
     amount: u64,
+    pool_entries: &[LockupPoolEntry],
 ) -> Result<(), BanksClientError> {
     // Setup native account.
     let rent = context.banks_client.get_rent().await.unwrap();
@@ -775,12 +1828,15 @@ async fn initialize_lockup(
         &token_owner.pubkey(),
         &token_account,
         pool,
+        &lockup_pool_state(mint),
+        pool_entries,
         &lockup,
         &mint,
         Pubkey::new_unique().to_bytes(), // Metadata.
         amount,
+        None,
         &spl_token_2022::id(),
-        &[],
+        false,
     );
     let transaction = Transaction::new_signed_with_payer(
         &[cu_limit, instruction],
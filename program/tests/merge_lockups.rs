@@ -0,0 +1,543 @@
+#![cfg(feature = "test-sbf")]
+
+mod setup;
+
+use {
+    paladin_lockup_program::{
+        error::PaladinLockupError,
+        state::{Lockup, LockupPool, LockupPoolEntry, PodU128, VestingSchedule},
+    },
+    setup::{
+        lockup_pool_data, lockup_pool_state, setup, setup_lockup, setup_lockup_pool,
+        setup_lockup_pool_with_data, setup_lockup_pool_with_entries, setup_system_account,
+    },
+    solana_program_test::*,
+    solana_sdk::{
+        instruction::InstructionError,
+        pubkey::Pubkey,
+        signature::Keypair,
+        signer::Signer,
+        transaction::{Transaction, TransactionError},
+    },
+    spl_discriminator::SplDiscriminate,
+    std::num::NonZeroU64,
+};
+
+fn lockup_state(authority: Pubkey, mint: Pubkey, pool: Pubkey, amount: u64) -> Lockup {
+    Lockup {
+        discriminator: Lockup::SPL_DISCRIMINATOR.into(),
+        amount,
+        authority,
+        lockup_start_timestamp: 10,
+        lockup_end_timestamp: None,
+        mint,
+        pool,
+        metadata: Pubkey::new_unique().to_bytes(),
+        proposed_authority: Pubkey::default(),
+        committed_end_timestamp: None,
+        vesting: VestingSchedule::default(),
+        withdrawn_amount: 0,
+        metadata_updated_timestamp: 0,
+        flags: Lockup::FLAG_IN_POOL,
+        reward_per_token_checkpoint: PodU128::default(),
+        unclaimed_rewards: 0,
+        cooldown_end_timestamp: None,
+    }
+}
+
+#[tokio::test]
+async fn fail_incorrect_lockup_authority() {
+    let mut context = setup().start_with_context().await;
+
+    let authority = Keypair::new();
+    let destination = Pubkey::new_unique();
+    let source = Pubkey::new_unique();
+    let mint = Pubkey::new_unique();
+
+    // Create the lockup pool account.
+    let pool = Pubkey::new_unique();
+    setup_lockup_pool(&mut context, &pool, mint).await;
+
+    setup_lockup(
+        &mut context,
+        &destination,
+        lockup_state(authority.pubkey(), mint, pool, 10_000),
+    )
+    .await;
+    setup_lockup(
+        &mut context,
+        &source,
+        lockup_state(Pubkey::new_unique(), mint, pool, 10_000), // Incorrect authority.
+    )
+    .await;
+
+    let instruction = paladin_lockup_program::instruction::merge_lockups(
+        &authority.pubkey(),
+        &authority.pubkey(),
+        pool,
+        &lockup_pool_state(mint),
+        &[],
+        &destination,
+        10_000,
+        &source,
+        10_000,
+    );
+
+    let transaction = Transaction::new_signed_with_payer(
+        &[instruction],
+        Some(&context.payer.pubkey()),
+        &[&context.payer, &authority],
+        context.last_blockhash,
+    );
+
+    let err = context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap_err()
+        .unwrap();
+
+    assert_eq!(
+        err,
+        TransactionError::InstructionError(0, InstructionError::IncorrectAuthority)
+    );
+}
+
+#[tokio::test]
+async fn fail_incorrect_pool() {
+    let mut context = setup().start_with_context().await;
+
+    let authority = Keypair::new();
+    let destination = Pubkey::new_unique();
+    let source = Pubkey::new_unique();
+    let mint = Pubkey::new_unique();
+
+    // Create the lockup pool accounts.
+    let pool1 = Pubkey::new_unique();
+    setup_lockup_pool(&mut context, &pool1, mint).await;
+    let pool2 = Pubkey::new_unique();
+    setup_lockup_pool(&mut context, &pool2, mint).await;
+
+    setup_lockup(
+        &mut context,
+        &destination,
+        lockup_state(authority.pubkey(), mint, pool1, 10_000),
+    )
+    .await;
+    setup_lockup(
+        &mut context,
+        &source,
+        lockup_state(authority.pubkey(), mint, pool2, 10_000), // Different pool.
+    )
+    .await;
+
+    let instruction = paladin_lockup_program::instruction::merge_lockups(
+        &authority.pubkey(),
+        &authority.pubkey(),
+        pool1,
+        &lockup_pool_state(mint),
+        &[],
+        &destination,
+        10_000,
+        &source,
+        10_000,
+    );
+
+    let transaction = Transaction::new_signed_with_payer(
+        &[instruction],
+        Some(&context.payer.pubkey()),
+        &[&context.payer, &authority],
+        context.last_blockhash,
+    );
+
+    let err = context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap_err()
+        .unwrap();
+
+    assert_eq!(
+        err,
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(PaladinLockupError::IncorrectPool as u32)
+        )
+    );
+}
+
+#[tokio::test]
+async fn fail_lamport_destination_is_source() {
+    let mut context = setup().start_with_context().await;
+
+    let authority = Keypair::new();
+    let destination = Pubkey::new_unique();
+    let source = Pubkey::new_unique();
+    let mint = Pubkey::new_unique();
+
+    // Create the lockup pool account.
+    let pool = Pubkey::new_unique();
+    setup_lockup_pool(&mut context, &pool, mint).await;
+
+    setup_lockup(
+        &mut context,
+        &destination,
+        lockup_state(authority.pubkey(), mint, pool, 10_000),
+    )
+    .await;
+    setup_lockup(
+        &mut context,
+        &source,
+        lockup_state(authority.pubkey(), mint, pool, 10_000),
+    )
+    .await;
+
+    let instruction = paladin_lockup_program::instruction::merge_lockups(
+        &authority.pubkey(),
+        &source, // The source lockup's own account.
+        pool,
+        &lockup_pool_state(mint),
+        &[],
+        &destination,
+        10_000,
+        &source,
+        10_000,
+    );
+
+    let transaction = Transaction::new_signed_with_payer(
+        &[instruction],
+        Some(&context.payer.pubkey()),
+        &[&context.payer, &authority],
+        context.last_blockhash,
+    );
+
+    let err = context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap_err()
+        .unwrap();
+
+    assert_eq!(
+        err,
+        TransactionError::InstructionError(0, InstructionError::InvalidArgument)
+    );
+}
+
+#[tokio::test]
+async fn fail_lockup_already_unlocked() {
+    let mut context = setup().start_with_context().await;
+
+    let authority = Keypair::new();
+    let destination = Pubkey::new_unique();
+    let source = Pubkey::new_unique();
+    let mint = Pubkey::new_unique();
+
+    // Create the lockup pool account.
+    let pool = Pubkey::new_unique();
+    setup_lockup_pool(&mut context, &pool, mint).await;
+
+    setup_lockup(
+        &mut context,
+        &destination,
+        lockup_state(authority.pubkey(), mint, pool, 10_000),
+    )
+    .await;
+    setup_lockup(
+        &mut context,
+        &source,
+        Lockup {
+            lockup_end_timestamp: NonZeroU64::new(20), // Already unlocked.
+            ..lockup_state(authority.pubkey(), mint, pool, 10_000)
+        },
+    )
+    .await;
+
+    let instruction = paladin_lockup_program::instruction::merge_lockups(
+        &authority.pubkey(),
+        &authority.pubkey(),
+        pool,
+        &lockup_pool_state(mint),
+        &[],
+        &destination,
+        10_000,
+        &source,
+        10_000,
+    );
+
+    let transaction = Transaction::new_signed_with_payer(
+        &[instruction],
+        Some(&context.payer.pubkey()),
+        &[&context.payer, &authority],
+        context.last_blockhash,
+    );
+
+    let err = context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap_err()
+        .unwrap();
+
+    assert_eq!(
+        err,
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(PaladinLockupError::LockupAlreadyUnlocked as u32)
+        )
+    );
+}
+
+#[tokio::test]
+async fn success() {
+    let mut context = setup().start_with_context().await;
+
+    let authority = Keypair::new();
+    let lamport_destination = Pubkey::new_unique();
+    let destination = Pubkey::new_unique();
+    let source = Pubkey::new_unique();
+    let mint = Pubkey::new_unique();
+
+    setup_system_account(&mut context, &lamport_destination, 0).await;
+
+    let destination_state = Lockup {
+        lockup_start_timestamp: 200,
+        ..lockup_state(authority.pubkey(), mint, Pubkey::default(), 3_000)
+    };
+    let source_state = Lockup {
+        lockup_start_timestamp: 100,
+        ..lockup_state(authority.pubkey(), mint, Pubkey::default(), 2_000)
+    };
+
+    // Create the lockup pool account, with another lockup ranked between ours.
+    let pool = Pubkey::new_unique();
+    let other_entry = LockupPoolEntry {
+        lockup: Pubkey::new_unique(),
+        amount: 4_000,
+        metadata: Pubkey::new_unique().to_bytes(),
+    };
+    setup_lockup_pool_with_entries(
+        &mut context,
+        &pool,
+        mint,
+        &[
+            other_entry,
+            LockupPoolEntry {
+                lockup: destination,
+                amount: destination_state.amount,
+                metadata: destination_state.metadata,
+            },
+            LockupPoolEntry {
+                lockup: source,
+                amount: source_state.amount,
+                metadata: source_state.metadata,
+            },
+        ],
+    )
+    .await;
+
+    let destination_state = Lockup {
+        pool,
+        ..destination_state
+    };
+    let source_state = Lockup {
+        pool,
+        ..source_state
+    };
+    setup_lockup(&mut context, &destination, destination_state).await;
+    setup_lockup(&mut context, &source, source_state).await;
+
+    let source_lamports = context
+        .banks_client
+        .get_account(source)
+        .await
+        .unwrap()
+        .unwrap()
+        .lamports;
+    let lamport_destination_lamports = context
+        .banks_client
+        .get_account(lamport_destination)
+        .await
+        .unwrap()
+        .unwrap()
+        .lamports;
+
+    let instruction = paladin_lockup_program::instruction::merge_lockups(
+        &authority.pubkey(),
+        &lamport_destination,
+        pool,
+        &lockup_pool_state(mint),
+        &[],
+        &destination,
+        destination_state.amount,
+        &source,
+        source_state.amount,
+    );
+
+    let transaction = Transaction::new_signed_with_payer(
+        &[instruction],
+        Some(&context.payer.pubkey()),
+        &[&context.payer, &authority],
+        context.last_blockhash,
+    );
+
+    context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap();
+
+    // Check the destination lockup holds the merged amount & earlier start.
+    let destination_account = context
+        .banks_client
+        .get_account(destination)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(
+        bytemuck::from_bytes::<Lockup>(&destination_account.data),
+        &Lockup {
+            amount: 5_000,
+            lockup_start_timestamp: 100,
+            ..destination_state
+        }
+    );
+
+    // Check the source lockup was closed and its rent returned.
+    assert!(context
+        .banks_client
+        .get_account(source)
+        .await
+        .unwrap()
+        .is_none());
+    let lamport_destination_account = context
+        .banks_client
+        .get_account(lamport_destination)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(
+        lamport_destination_account.lamports,
+        lamport_destination_lamports.saturating_add(source_lamports)
+    );
+
+    // Check the pool holds a single, re-ranked entry for the merged lockup.
+    let lockup_pool = context
+        .banks_client
+        .get_account(pool)
+        .await
+        .unwrap()
+        .unwrap();
+    let (lockup_pool, pool_entries) = LockupPool::unpack(&lockup_pool.data).unwrap();
+    assert_eq!(lockup_pool.entries_len, 2);
+    assert_eq!(
+        pool_entries[0],
+        LockupPoolEntry {
+            lockup: destination,
+            amount: 5_000,
+            metadata: destination_state.metadata,
+        }
+    );
+    assert_eq!(pool_entries[1], other_entry);
+}
+
+#[tokio::test]
+async fn success_below_pool_minimum() {
+    let mut context = setup().start_with_context().await;
+
+    let authority = Keypair::new();
+    let lamport_destination = Pubkey::new_unique();
+    let destination = Pubkey::new_unique();
+    let source = Pubkey::new_unique();
+    let mint = Pubkey::new_unique();
+
+    setup_system_account(&mut context, &lamport_destination, 0).await;
+
+    // Create a full lockup pool account, whose smallest lockup still outranks
+    // the merged lockup.
+    let pool = Pubkey::new_unique();
+    let entries = [
+        LockupPoolEntry {
+            lockup: Pubkey::new_unique(),
+            amount: 4_000,
+            metadata: Pubkey::new_unique().to_bytes(),
+        },
+        LockupPoolEntry {
+            lockup: Pubkey::new_unique(),
+            amount: 3_500,
+            metadata: Pubkey::new_unique().to_bytes(),
+        },
+    ];
+    let data = lockup_pool_data(&lockup_pool_state(mint), &entries, entries.len());
+    setup_lockup_pool_with_data(&mut context, &pool, data).await;
+
+    // Both lockups were evicted earlier.
+    let destination_state = Lockup {
+        flags: 0,
+        ..lockup_state(authority.pubkey(), mint, pool, 1_000)
+    };
+    let source_state = Lockup {
+        flags: 0,
+        ..lockup_state(authority.pubkey(), mint, pool, 2_000)
+    };
+    setup_lockup(&mut context, &destination, destination_state).await;
+    setup_lockup(&mut context, &source, source_state).await;
+
+    let instruction = paladin_lockup_program::instruction::merge_lockups(
+        &authority.pubkey(),
+        &lamport_destination,
+        pool,
+        &lockup_pool_state(mint),
+        &[],
+        &destination,
+        destination_state.amount,
+        &source,
+        source_state.amount,
+    );
+
+    let transaction = Transaction::new_signed_with_payer(
+        &[instruction],
+        Some(&context.payer.pubkey()),
+        &[&context.payer, &authority],
+        context.last_blockhash,
+    );
+
+    context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap();
+
+    // Check the destination lockup holds the merged amount, but stays out of
+    // the pool.
+    let destination_account = context
+        .banks_client
+        .get_account(destination)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(
+        bytemuck::from_bytes::<Lockup>(&destination_account.data),
+        &Lockup {
+            amount: 3_000,
+            ..destination_state
+        }
+    );
+    assert!(context
+        .banks_client
+        .get_account(source)
+        .await
+        .unwrap()
+        .is_none());
+
+    // Check the pool is unchanged.
+    let lockup_pool = context
+        .banks_client
+        .get_account(pool)
+        .await
+        .unwrap()
+        .unwrap();
+    let (lockup_pool, pool_entries) = LockupPool::unpack(&lockup_pool.data).unwrap();
+    assert_eq!(lockup_pool.entries_len, 2);
+    assert_eq!(pool_entries, entries);
+}
@@ -0,0 +1,552 @@
+#![cfg(feature = "test-sbf")]
+
+mod setup;
+
+use {
+    paladin_lockup_program::{
+        error::PaladinLockupError,
+        state::{
+            get_escrow_authority_address, Lockup, LockupPool, LockupPoolEntry, LockupPoolRewards,
+            LockupPoolStats, PodU128, VestingSchedule,
+        },
+        LOCKUP_COOLDOWN_SECONDS,
+    },
+    setup::{
+        add_seconds_to_clock, lockup_pool_data, lockup_pool_state, setup, setup_lockup,
+        setup_lockup_pool, setup_lockup_pool_with_data, setup_mint, setup_token_account,
+    },
+    solana_program_test::*,
+    solana_sdk::{
+        account::{Account, AccountSharedData},
+        compute_budget::ComputeBudgetInstruction,
+        instruction::InstructionError,
+        pubkey::Pubkey,
+        rent::Rent,
+        signature::Keypair,
+        signer::Signer,
+        transaction::{Transaction, TransactionError},
+    },
+    spl_associated_token_account::get_associated_token_address_with_program_id,
+    spl_discriminator::SplDiscriminate,
+    spl_token_2022::{extension::StateWithExtensions, state::Account as TokenAccount},
+    std::num::NonZeroU64,
+};
+
+fn lockup_state(authority: Pubkey, mint: Pubkey, pool: Pubkey) -> Lockup {
+    Lockup {
+        discriminator: Lockup::SPL_DISCRIMINATOR.into(),
+        amount: 10_000,
+        authority,
+        lockup_start_timestamp: 10,
+        lockup_end_timestamp: None,
+        mint,
+        pool,
+        metadata: [7; 32],
+        proposed_authority: Pubkey::default(),
+        committed_end_timestamp: None,
+        vesting: VestingSchedule::default(),
+        withdrawn_amount: 0,
+        metadata_updated_timestamp: 0,
+        flags: 0,
+        reward_per_token_checkpoint: PodU128::default(),
+        unclaimed_rewards: 0,
+        cooldown_end_timestamp: None,
+    }
+}
+
+/// Sets up a lockup account in the legacy layout, funded for its legacy size.
+async fn setup_legacy_lockup(context: &mut ProgramTestContext, address: &Pubkey, state: Lockup) {
+    let data = bytemuck::bytes_of(&state)[..Lockup::LEGACY_LEN].to_vec();
+    let lamports = Rent::default().minimum_balance(data.len());
+
+    context.set_account(
+        address,
+        &AccountSharedData::from(Account {
+            lamports,
+            data,
+            owner: paladin_lockup_program::id(),
+            ..Account::default()
+        }),
+    );
+}
+
+async fn get_lockup_state(context: &mut ProgramTestContext, lockup: &Pubkey) -> Lockup {
+    let account = context
+        .banks_client
+        .get_account(*lockup)
+        .await
+        .unwrap()
+        .unwrap();
+    *bytemuck::from_bytes::<Lockup>(&account.data)
+}
+
+async fn get_token_account_balance(context: &mut ProgramTestContext, address: &Pubkey) -> u64 {
+    let account = context
+        .banks_client
+        .get_account(*address)
+        .await
+        .unwrap()
+        .unwrap();
+    StateWithExtensions::<TokenAccount>::unpack(&account.data)
+        .unwrap()
+        .base
+        .amount
+}
+
+#[tokio::test]
+async fn fail_lockup_not_legacy() {
+    let mint = Pubkey::new_unique();
+    let pool = Pubkey::new_unique();
+    let lockup = Pubkey::new_unique();
+
+    let mut context = setup().start_with_context().await;
+    setup_lockup_pool(&mut context, &pool, mint).await;
+
+    // The lockup is already in the current layout.
+    setup_lockup(
+        &mut context,
+        &lockup,
+        lockup_state(Pubkey::new_unique(), mint, pool),
+    )
+    .await;
+
+    let transaction = Transaction::new_signed_with_payer(
+        &[paladin_lockup_program::instruction::migrate_lockup(
+            &context.payer.pubkey(),
+            &lockup,
+            10_000,
+            pool,
+            &lockup_pool_state(mint),
+        )],
+        Some(&context.payer.pubkey()),
+        &[&context.payer],
+        context.last_blockhash,
+    );
+
+    let err = context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap_err()
+        .unwrap();
+
+    assert_eq!(
+        err,
+        TransactionError::InstructionError(0, InstructionError::InvalidAccountData)
+    );
+}
+
+#[tokio::test]
+async fn fail_incorrect_pool() {
+    let mint = Pubkey::new_unique();
+    let pool = Pubkey::new_unique();
+    let lockup = Pubkey::new_unique();
+
+    let mut context = setup().start_with_context().await;
+    setup_lockup_pool(&mut context, &pool, mint).await;
+
+    // The lockup belongs to another pool.
+    setup_legacy_lockup(
+        &mut context,
+        &lockup,
+        lockup_state(Pubkey::new_unique(), mint, Pubkey::new_unique()),
+    )
+    .await;
+
+    let transaction = Transaction::new_signed_with_payer(
+        &[paladin_lockup_program::instruction::migrate_lockup(
+            &context.payer.pubkey(),
+            &lockup,
+            10_000,
+            pool,
+            &lockup_pool_state(mint),
+        )],
+        Some(&context.payer.pubkey()),
+        &[&context.payer],
+        context.last_blockhash,
+    );
+
+    let err = context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap_err()
+        .unwrap();
+
+    assert_eq!(
+        err,
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(PaladinLockupError::IncorrectPool as u32)
+        )
+    );
+}
+
+#[tokio::test]
+async fn success_unlock_and_withdraw() {
+    let mint = Pubkey::new_unique();
+    let pool = Pubkey::new_unique();
+    let lockup = Pubkey::new_unique();
+
+    let authority = Keypair::new();
+    let token_account = get_associated_token_address_with_program_id(
+        &authority.pubkey(),
+        &mint,
+        &spl_token_2022::id(),
+    );
+    let escrow_authority = get_escrow_authority_address(&paladin_lockup_program::id());
+    let escrow_token_account = get_associated_token_address_with_program_id(
+        &escrow_authority,
+        &mint,
+        &spl_token_2022::id(),
+    );
+
+    let mut context = setup().start_with_context().await;
+
+    // Setup a pool holding the legacy lockup's entry, which has already
+    // received rewards.
+    let rewards = LockupPoolRewards {
+        reward_per_token: PodU128::from(LockupPoolRewards::REWARD_PER_TOKEN_SCALE),
+        ..LockupPoolRewards::default()
+    };
+    let entry = LockupPoolEntry {
+        lockup,
+        amount: 10_000,
+        metadata: [7; 32],
+    };
+    let data = lockup_pool_data(
+        &LockupPool {
+            rewards,
+            ..lockup_pool_state(mint)
+        },
+        &[entry],
+        LockupPool::INITIAL_CAPACITY,
+    );
+    setup_lockup_pool_with_data(&mut context, &pool, data).await;
+    setup_legacy_lockup(
+        &mut context,
+        &lockup,
+        lockup_state(authority.pubkey(), mint, pool),
+    )
+    .await;
+    setup_mint(&mut context, &mint, &Pubkey::new_unique(), 1_000_000).await;
+    setup_token_account(&mut context, &token_account, &authority.pubkey(), &mint, 0).await;
+    setup_token_account(
+        &mut context,
+        &escrow_token_account,
+        &escrow_authority,
+        &mint,
+        10_000,
+    )
+    .await;
+
+    // Migrate the lockup.
+    let transaction = Transaction::new_signed_with_payer(
+        &[paladin_lockup_program::instruction::migrate_lockup(
+            &context.payer.pubkey(),
+            &lockup,
+            10_000,
+            pool,
+            &lockup_pool_state(mint),
+        )],
+        Some(&context.payer.pubkey()),
+        &[&context.payer],
+        context.last_blockhash,
+    );
+    context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap();
+
+    // The lockup was grown, holds its entry & only earns future rewards.
+    let account = context
+        .banks_client
+        .get_account(lockup)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(
+        account.lamports,
+        Rent::default().minimum_balance(Lockup::LEN)
+    );
+    assert_eq!(
+        get_lockup_state(&mut context, &lockup).await,
+        Lockup {
+            flags: Lockup::FLAG_IN_POOL,
+            reward_per_token_checkpoint: rewards.reward_per_token,
+            ..lockup_state(authority.pubkey(), mint, pool)
+        }
+    );
+
+    // The pool counts the lockup as active.
+    let account = context
+        .banks_client
+        .get_account(pool)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(
+        LockupPool::unpack(&account.data).unwrap().0.stats,
+        LockupPoolStats {
+            active_amount: 10_000,
+            active_lockups: 1,
+            ..LockupPoolStats::default()
+        }
+    );
+
+    // Unlock the lockup, which removes its entry.
+    let transaction = Transaction::new_signed_with_payer(
+        &[paladin_lockup_program::instruction::unlock(
+            &authority.pubkey(),
+            pool,
+            &lockup_pool_state(mint),
+            &lockup,
+            entry.amount,
+        )],
+        Some(&context.payer.pubkey()),
+        &[&context.payer, &authority],
+        context.last_blockhash,
+    );
+    context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap();
+
+    let account = context
+        .banks_client
+        .get_account(pool)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(LockupPool::unpack(&account.data).unwrap().0.entries_len, 0);
+    assert!(get_lockup_state(&mut context, &lockup)
+        .await
+        .lockup_end_timestamp
+        .is_some());
+
+    // Withdraw once the cooldown has passed.
+    add_seconds_to_clock(&mut context, LOCKUP_COOLDOWN_SECONDS).await;
+    let transaction = Transaction::new_signed_with_payer(
+        &[paladin_lockup_program::instruction::withdraw(
+            &authority.pubkey(),
+            &authority.pubkey(),
+            &token_account,
+            &lockup,
+            entry.amount,
+            pool,
+            &lockup_pool_state(mint),
+            &[],
+            &mint,
+            &spl_token_2022::id(),
+            false,
+        )],
+        Some(&context.payer.pubkey()),
+        &[&context.payer, &authority],
+        context.last_blockhash,
+    );
+    context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap();
+
+    // The tokens were returned & the lockup was closed.
+    assert_eq!(
+        get_token_account_balance(&mut context, &token_account).await,
+        10_000
+    );
+    assert_eq!(
+        get_token_account_balance(&mut context, &escrow_token_account).await,
+        0
+    );
+    assert!(context
+        .banks_client
+        .get_account(lockup)
+        .await
+        .unwrap()
+        .is_none());
+
+    let account = context
+        .banks_client
+        .get_account(pool)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(
+        LockupPool::unpack(&account.data).unwrap().0.stats,
+        LockupPoolStats {
+            withdrawn_amount: 10_000,
+            ..LockupPoolStats::default()
+        }
+    );
+}
+
+#[tokio::test]
+async fn success_unlocked_lockup() {
+    let mint = Pubkey::new_unique();
+    let pool = Pubkey::new_unique();
+    let lockup = Pubkey::new_unique();
+
+    let mut context = setup().start_with_context().await;
+    setup_lockup_pool(&mut context, &pool, mint).await;
+
+    // The legacy lockup was unlocked before the migration.
+    let state = Lockup {
+        lockup_end_timestamp: NonZeroU64::new(20),
+        ..lockup_state(Pubkey::new_unique(), mint, pool)
+    };
+    setup_legacy_lockup(&mut context, &lockup, state).await;
+
+    let transaction = Transaction::new_signed_with_payer(
+        &[paladin_lockup_program::instruction::migrate_lockup(
+            &context.payer.pubkey(),
+            &lockup,
+            state.amount,
+            pool,
+            &lockup_pool_state(mint),
+        )],
+        Some(&context.payer.pubkey()),
+        &[&context.payer],
+        context.last_blockhash,
+    );
+    context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap();
+
+    // The lockup is out of the pool & counted as in cooldown, which ends
+    // the pool's cooldown after it was unlocked.
+    assert_eq!(
+        get_lockup_state(&mut context, &lockup).await,
+        Lockup {
+            cooldown_end_timestamp: NonZeroU64::new(20 + LOCKUP_COOLDOWN_SECONDS),
+            ..state
+        }
+    );
+    let account = context
+        .banks_client
+        .get_account(pool)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(
+        LockupPool::unpack(&account.data).unwrap().0.stats,
+        LockupPoolStats {
+            unlocking_amount: 10_000,
+            ..LockupPoolStats::default()
+        }
+    );
+}
+
+#[tokio::test]
+async fn success_evicted_after_migration() {
+    let mint = Pubkey::new_unique();
+    let pool = Pubkey::new_unique();
+    let lockup = Pubkey::new_unique();
+    let other_lockup = Pubkey::new_unique();
+
+    let mut context = setup().start_with_context().await;
+
+    // Setup a full pool whose smallest entry is the legacy lockup's.
+    let state = Lockup {
+        amount: 1,
+        ..lockup_state(Pubkey::new_unique(), mint, pool)
+    };
+    let mut entries = (0..LockupPool::INITIAL_CAPACITY)
+        .map(|i| LockupPoolEntry {
+            lockup: Pubkey::new_unique(),
+            amount: (LockupPool::INITIAL_CAPACITY - i) as u64,
+            metadata: [0; 32],
+        })
+        .collect::<Vec<_>>();
+    entries[LockupPool::INITIAL_CAPACITY - 1].lockup = lockup;
+    let data = lockup_pool_data(
+        &lockup_pool_state(mint),
+        &entries,
+        LockupPool::INITIAL_CAPACITY,
+    );
+    setup_lockup_pool_with_data(&mut context, &pool, data).await;
+    setup_legacy_lockup(&mut context, &lockup, state).await;
+
+    // A larger lockup cannot take the legacy lockup's place until it is
+    // migrated.
+    setup_lockup(
+        &mut context,
+        &other_lockup,
+        Lockup {
+            amount: 100,
+            ..lockup_state(Pubkey::new_unique(), mint, pool)
+        },
+    )
+    .await;
+    let reinsert_lockup = [
+        ComputeBudgetInstruction::set_compute_unit_limit(400_000),
+        paladin_lockup_program::instruction::reinsert_lockup(
+            pool,
+            &lockup_pool_state(mint),
+            &entries,
+            &other_lockup,
+            100,
+        ),
+    ];
+    let transaction = Transaction::new_signed_with_payer(
+        &reinsert_lockup,
+        Some(&context.payer.pubkey()),
+        &[&context.payer],
+        context.last_blockhash,
+    );
+    let err = context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap_err()
+        .unwrap();
+    assert_eq!(
+        err,
+        TransactionError::InstructionError(1, InstructionError::InvalidAccountData)
+    );
+
+    // Migrate the lockup, which still holds its entry.
+    let transaction = Transaction::new_signed_with_payer(
+        &[paladin_lockup_program::instruction::migrate_lockup(
+            &context.payer.pubkey(),
+            &lockup,
+            state.amount,
+            pool,
+            &lockup_pool_state(mint),
+        )],
+        Some(&context.payer.pubkey()),
+        &[&context.payer],
+        context.last_blockhash,
+    );
+    context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap();
+    assert_eq!(
+        get_lockup_state(&mut context, &lockup).await,
+        Lockup {
+            flags: Lockup::FLAG_IN_POOL,
+            ..state
+        }
+    );
+
+    // The larger lockup now evicts the migrated lockup, clearing its flag.
+    let blockhash = context.get_new_latest_blockhash().await.unwrap();
+    let transaction = Transaction::new_signed_with_payer(
+        &reinsert_lockup,
+        Some(&context.payer.pubkey()),
+        &[&context.payer],
+        blockhash,
+    );
+    context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap();
+    assert_eq!(get_lockup_state(&mut context, &lockup).await, state);
+}
@@ -0,0 +1,336 @@
+#![cfg(feature = "test-sbf")]
+
+mod setup;
+
+use {
+    paladin_lockup_program::{
+        error::PaladinLockupError,
+        state::{Lockup, LockupPool, LockupPoolEntry, LockupPoolStats, PodU128, VestingSchedule},
+        LOCKUP_COOLDOWN_SECONDS,
+    },
+    setup::{lockup_pool_state, setup, setup_fixed_supply_mint, setup_lockup_pool, setup_mint},
+    solana_program_test::*,
+    solana_sdk::{
+        account::{Account, AccountSharedData},
+        instruction::InstructionError,
+        pubkey::Pubkey,
+        rent::Rent,
+        signature::Keypair,
+        signer::Signer,
+        transaction::{Transaction, TransactionError},
+    },
+    spl_discriminator::SplDiscriminate,
+    test_case::test_case,
+};
+
+/// Sets up a lockup pool account in the legacy layout, funded for its legacy
+/// size.
+async fn setup_legacy_lockup_pool(
+    context: &mut ProgramTestContext,
+    address: &Pubkey,
+    mint: &Pubkey,
+    entries: &[LockupPoolEntry],
+) {
+    let mut data = Vec::with_capacity(LockupPool::LEGACY_LEN);
+    data.extend_from_slice(LockupPool::SPL_DISCRIMINATOR_SLICE);
+    data.extend_from_slice(mint.as_ref());
+    let mut legacy_entries = [LockupPoolEntry::default(); LockupPool::INITIAL_CAPACITY];
+    legacy_entries[..entries.len()].copy_from_slice(entries);
+    data.extend_from_slice(bytemuck::cast_slice(&legacy_entries));
+    data.extend_from_slice(&entries.len().to_le_bytes());
+    assert_eq!(data.len(), LockupPool::LEGACY_LEN);
+
+    context.set_account(
+        address,
+        &AccountSharedData::from(Account {
+            lamports: Rent::default().minimum_balance(data.len()),
+            data,
+            owner: paladin_lockup_program::id(),
+            ..Account::default()
+        }),
+    );
+}
+
+/// Sets up a lockup account in the legacy layout, funded for its legacy size.
+async fn setup_legacy_lockup(context: &mut ProgramTestContext, address: &Pubkey, state: Lockup) {
+    let data = bytemuck::bytes_of(&state)[..Lockup::LEGACY_LEN].to_vec();
+
+    context.set_account(
+        address,
+        &AccountSharedData::from(Account {
+            lamports: Rent::default().minimum_balance(data.len()),
+            data,
+            owner: paladin_lockup_program::id(),
+            ..Account::default()
+        }),
+    );
+}
+
+#[tokio::test]
+async fn fail_pool_not_legacy() {
+    let mint = Pubkey::new_unique();
+    let pool = Pubkey::new_unique();
+
+    let mut context = setup().start_with_context().await;
+    setup_mint(&mut context, &mint, &Pubkey::new_unique(), 1_000_000).await;
+
+    // The pool is already in the current layout.
+    setup_lockup_pool(&mut context, &pool, mint).await;
+
+    let transaction = Transaction::new_signed_with_payer(
+        &[paladin_lockup_program::instruction::migrate_lockup_pool(
+            &context.payer.pubkey(),
+            pool,
+            &mint,
+        )],
+        Some(&context.payer.pubkey()),
+        &[&context.payer],
+        context.last_blockhash,
+    );
+
+    let err = context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap_err()
+        .unwrap();
+
+    assert_eq!(
+        err,
+        TransactionError::InstructionError(0, InstructionError::InvalidAccountData)
+    );
+}
+
+#[tokio::test]
+async fn fail_update_cooldown_before_migration() {
+    let mint = Pubkey::new_unique();
+    let pool = Pubkey::new_unique();
+    let pool_authority = Keypair::new();
+
+    let mut context = setup().start_with_context().await;
+
+    // Legacy pools have no cooldown to update until they are migrated.
+    setup_legacy_lockup_pool(&mut context, &pool, &mint, &[]).await;
+
+    let transaction = Transaction::new_signed_with_payer(
+        &[paladin_lockup_program::instruction::update_pool_config(
+            &pool_authority.pubkey(),
+            pool,
+            None,
+            Some(LOCKUP_COOLDOWN_SECONDS / 2),
+            None,
+            None,
+            None,
+        )],
+        Some(&context.payer.pubkey()),
+        &[&context.payer, &pool_authority],
+        context.last_blockhash,
+    );
+
+    let err = context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap_err()
+        .unwrap();
+
+    assert_eq!(
+        err,
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(PaladinLockupError::LockupPoolNotMigrated as u32)
+        )
+    );
+}
+
+#[tokio::test]
+async fn fail_incorrect_mint() {
+    let mint = Pubkey::new_unique();
+    let other_mint = Pubkey::new_unique();
+    let pool = Pubkey::new_unique();
+
+    let mut context = setup().start_with_context().await;
+    setup_mint(&mut context, &other_mint, &Pubkey::new_unique(), 1_000_000).await;
+    setup_legacy_lockup_pool(&mut context, &pool, &mint, &[]).await;
+
+    let transaction = Transaction::new_signed_with_payer(
+        &[paladin_lockup_program::instruction::migrate_lockup_pool(
+            &context.payer.pubkey(),
+            pool,
+            &other_mint, // Incorrect mint.
+        )],
+        Some(&context.payer.pubkey()),
+        &[&context.payer],
+        context.last_blockhash,
+    );
+
+    let err = context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap_err()
+        .unwrap();
+
+    assert_eq!(
+        err,
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(PaladinLockupError::IncorrectMint as u32)
+        )
+    );
+}
+
+#[test_case(true; "with_mint_authority")]
+#[test_case(false; "fixed_supply_mint")]
+#[tokio::test]
+async fn success(with_mint_authority: bool) {
+    let mint = Pubkey::new_unique();
+    let mint_authority = Pubkey::new_unique();
+    let pool = Pubkey::new_unique();
+
+    let authority = Keypair::new();
+    let lockup = Pubkey::new_unique();
+
+    let mut context = setup().start_with_context().await;
+    if with_mint_authority {
+        setup_mint(&mut context, &mint, &mint_authority, 1_000_000).await;
+    } else {
+        setup_fixed_supply_mint(&mut context, &mint, 1_000_000).await;
+    }
+
+    // Setup a legacy pool with a few entries, the smallest of which belongs to
+    // a legacy lockup.
+    let entries = [
+        LockupPoolEntry {
+            lockup: Pubkey::new_unique(),
+            amount: 30_000,
+            metadata: [1; 32],
+        },
+        LockupPoolEntry {
+            lockup: Pubkey::new_unique(),
+            amount: 20_000,
+            metadata: [2; 32],
+        },
+        LockupPoolEntry {
+            lockup,
+            amount: 10_000,
+            metadata: [3; 32],
+        },
+    ];
+    setup_legacy_lockup_pool(&mut context, &pool, &mint, &entries).await;
+    setup_legacy_lockup(
+        &mut context,
+        &lockup,
+        Lockup {
+            discriminator: Lockup::SPL_DISCRIMINATOR.into(),
+            amount: 10_000,
+            authority: authority.pubkey(),
+            lockup_start_timestamp: 10,
+            lockup_end_timestamp: None,
+            mint,
+            pool,
+            metadata: [3; 32],
+            proposed_authority: Pubkey::default(),
+            committed_end_timestamp: None,
+            vesting: VestingSchedule::default(),
+            withdrawn_amount: 0,
+            metadata_updated_timestamp: 0,
+            flags: 0,
+            reward_per_token_checkpoint: PodU128::default(),
+            unclaimed_rewards: 0,
+            cooldown_end_timestamp: None,
+        },
+    )
+    .await;
+
+    // Migrate the pool.
+    let transaction = Transaction::new_signed_with_payer(
+        &[paladin_lockup_program::instruction::migrate_lockup_pool(
+            &context.payer.pubkey(),
+            pool,
+            &mint,
+        )],
+        Some(&context.payer.pubkey()),
+        &[&context.payer],
+        context.last_blockhash,
+    );
+    context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap();
+
+    // The pool has the default configuration & kept its entries.
+    let account = context
+        .banks_client
+        .get_account(pool)
+        .await
+        .unwrap()
+        .unwrap();
+    let pool_len = LockupPool::account_len(LockupPool::INITIAL_CAPACITY);
+    assert_eq!(account.data.len(), pool_len);
+    assert_eq!(account.lamports, Rent::default().minimum_balance(pool_len));
+    let (pool_state, pool_entries) = LockupPool::unpack(&account.data).unwrap();
+    assert_eq!(
+        pool_state,
+        &LockupPool {
+            cooldown_seconds: LOCKUP_COOLDOWN_SECONDS,
+            authority: if with_mint_authority {
+                mint_authority
+            } else {
+                Pubkey::default()
+            },
+            entries_len: entries.len(),
+            ..lockup_pool_state(mint)
+        }
+    );
+    assert_eq!(&pool_entries[..entries.len()], entries);
+    assert!(pool_entries[entries.len()..]
+        .iter()
+        .all(|entry| entry == &LockupPoolEntry::default()));
+
+    // The pool's legacy lockups can now be migrated & unlocked.
+    let transaction = Transaction::new_signed_with_payer(
+        &[
+            paladin_lockup_program::instruction::migrate_lockup(
+                &context.payer.pubkey(),
+                &lockup,
+                10_000,
+                pool,
+                &lockup_pool_state(mint),
+            ),
+            paladin_lockup_program::instruction::unlock(
+                &authority.pubkey(),
+                pool,
+                &lockup_pool_state(mint),
+                &lockup,
+                10_000,
+            ),
+        ],
+        Some(&context.payer.pubkey()),
+        &[&context.payer, &authority],
+        context.last_blockhash,
+    );
+    context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap();
+
+    let account = context
+        .banks_client
+        .get_account(pool)
+        .await
+        .unwrap()
+        .unwrap();
+    let (pool_state, pool_entries) = LockupPool::unpack(&account.data).unwrap();
+    assert_eq!(pool_state.entries_len, 2);
+    assert_eq!(&pool_entries[..2], &entries[..2]);
+    assert_eq!(
+        pool_state.stats,
+        LockupPoolStats {
+            unlocking_amount: 10_000,
+            ..LockupPoolStats::default()
+        }
+    );
+}
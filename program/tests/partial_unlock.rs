@@ -0,0 +1,592 @@
+#![cfg(feature = "test-sbf")]
+
+mod setup;
+
+use {
+    paladin_lockup_program::{
+        error::PaladinLockupError,
+        state::{
+            Lockup, LockupPool, LockupPoolEntry, LockupPoolShard, LockupPoolShardInfo, PodU128,
+            VestingSchedule,
+        },
+        LOCKUP_COOLDOWN_SECONDS,
+    },
+    setup::{
+        lockup_pool_data, lockup_pool_state, setup, setup_lockup, setup_lockup_pool,
+        setup_lockup_pool_shard, setup_lockup_pool_with_data, setup_lockup_pool_with_entries,
+    },
+    solana_program_test::*,
+    solana_sdk::{
+        account::AccountSharedData,
+        clock::Clock,
+        instruction::InstructionError,
+        pubkey::Pubkey,
+        signature::Keypair,
+        signer::Signer,
+        transaction::{Transaction, TransactionError},
+    },
+    spl_discriminator::SplDiscriminate,
+    std::num::NonZeroU64,
+    test_case::test_case,
+};
+
+async fn setup_unlocked_lockup_account(context: &mut ProgramTestContext, address: &Pubkey) {
+    let rent = context.banks_client.get_rent().await.unwrap();
+    let space = std::mem::size_of::<Lockup>();
+    let lamports = rent.minimum_balance(space);
+    context.set_account(
+        address,
+        &AccountSharedData::new(lamports, space, &paladin_lockup_program::id()),
+    );
+}
+
+#[tokio::test]
+async fn fail_unlocked_lockup_already_initialized() {
+    let mut context = setup().start_with_context().await;
+
+    let authority = Keypair::new();
+    let lockup = Pubkey::new_unique();
+    let unlocked_lockup = Pubkey::new_unique();
+    let mint = Pubkey::new_unique();
+
+    // Create the lockup pool account.
+    let pool = Pubkey::new_unique();
+    setup_lockup_pool(&mut context, &pool, mint).await;
+
+    let state = Lockup {
+        discriminator: Lockup::SPL_DISCRIMINATOR.into(),
+        amount: 10_000,
+        authority: authority.pubkey(),
+        lockup_start_timestamp: 10,
+        lockup_end_timestamp: None,
+        mint,
+        pool,
+        metadata: Pubkey::new_unique().to_bytes(),
+        proposed_authority: Pubkey::default(),
+        committed_end_timestamp: None,
+        vesting: VestingSchedule::default(),
+        withdrawn_amount: 0,
+        metadata_updated_timestamp: 0,
+        flags: 0,
+        reward_per_token_checkpoint: PodU128::default(),
+        unclaimed_rewards: 0,
+        cooldown_end_timestamp: None,
+    };
+    setup_lockup(&mut context, &lockup, state).await;
+    setup_lockup(&mut context, &unlocked_lockup, state).await; // Already initialized.
+
+    let instruction = paladin_lockup_program::instruction::partial_unlock(
+        &authority.pubkey(),
+        pool,
+        &lockup_pool_state(mint),
+        &[],
+        &lockup,
+        10_000,
+        &unlocked_lockup,
+        1_000,
+    );
+
+    let transaction = Transaction::new_signed_with_payer(
+        &[instruction],
+        Some(&context.payer.pubkey()),
+        &[&context.payer, &authority],
+        context.last_blockhash,
+    );
+
+    let err = context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap_err()
+        .unwrap();
+
+    assert_eq!(
+        err,
+        TransactionError::InstructionError(0, InstructionError::AccountAlreadyInitialized)
+    );
+}
+
+#[tokio::test]
+async fn fail_lockup_already_unlocked() {
+    let mut context = setup().start_with_context().await;
+
+    let authority = Keypair::new();
+    let lockup = Pubkey::new_unique();
+    let unlocked_lockup = Pubkey::new_unique();
+    let mint = Pubkey::new_unique();
+
+    // Create the lockup pool account.
+    let pool = Pubkey::new_unique();
+    setup_lockup_pool(&mut context, &pool, mint).await;
+
+    setup_lockup(
+        &mut context,
+        &lockup,
+        Lockup {
+            discriminator: Lockup::SPL_DISCRIMINATOR.into(),
+            amount: 10_000,
+            authority: authority.pubkey(),
+            lockup_start_timestamp: 10,
+            lockup_end_timestamp: NonZeroU64::new(20), // Already unlocked.
+            mint,
+            pool,
+            metadata: Pubkey::new_unique().to_bytes(),
+            proposed_authority: Pubkey::default(),
+            committed_end_timestamp: None,
+            vesting: VestingSchedule::default(),
+            withdrawn_amount: 0,
+            metadata_updated_timestamp: 0,
+            flags: 0,
+            reward_per_token_checkpoint: PodU128::default(),
+            unclaimed_rewards: 0,
+            cooldown_end_timestamp: None,
+        },
+    )
+    .await;
+    setup_unlocked_lockup_account(&mut context, &unlocked_lockup).await;
+
+    let instruction = paladin_lockup_program::instruction::partial_unlock(
+        &authority.pubkey(),
+        pool,
+        &lockup_pool_state(mint),
+        &[],
+        &lockup,
+        10_000,
+        &unlocked_lockup,
+        1_000,
+    );
+
+    let transaction = Transaction::new_signed_with_payer(
+        &[instruction],
+        Some(&context.payer.pubkey()),
+        &[&context.payer, &authority],
+        context.last_blockhash,
+    );
+
+    let err = context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap_err()
+        .unwrap();
+
+    assert_eq!(
+        err,
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(PaladinLockupError::LockupAlreadyUnlocked as u32)
+        )
+    );
+}
+
+#[test_case(10_000; "entire_lockup")]
+#[test_case(10_001; "more_than_lockup")]
+#[tokio::test]
+async fn fail_invalid_unlock_amount(amount: u64) {
+    let mut context = setup().start_with_context().await;
+
+    let authority = Keypair::new();
+    let lockup = Pubkey::new_unique();
+    let unlocked_lockup = Pubkey::new_unique();
+    let mint = Pubkey::new_unique();
+
+    // Create the lockup pool account.
+    let pool = Pubkey::new_unique();
+    setup_lockup_pool(&mut context, &pool, mint).await;
+
+    setup_lockup(
+        &mut context,
+        &lockup,
+        Lockup {
+            discriminator: Lockup::SPL_DISCRIMINATOR.into(),
+            amount: 10_000,
+            authority: authority.pubkey(),
+            lockup_start_timestamp: 10,
+            lockup_end_timestamp: None,
+            mint,
+            pool,
+            metadata: Pubkey::new_unique().to_bytes(),
+            proposed_authority: Pubkey::default(),
+            committed_end_timestamp: None,
+            vesting: VestingSchedule::default(),
+            withdrawn_amount: 0,
+            metadata_updated_timestamp: 0,
+            flags: 0,
+            reward_per_token_checkpoint: PodU128::default(),
+            unclaimed_rewards: 0,
+            cooldown_end_timestamp: None,
+        },
+    )
+    .await;
+    setup_unlocked_lockup_account(&mut context, &unlocked_lockup).await;
+
+    let instruction = paladin_lockup_program::instruction::partial_unlock(
+        &authority.pubkey(),
+        pool,
+        &lockup_pool_state(mint),
+        &[],
+        &lockup,
+        10_000,
+        &unlocked_lockup,
+        amount,
+    );
+
+    let transaction = Transaction::new_signed_with_payer(
+        &[instruction],
+        Some(&context.payer.pubkey()),
+        &[&context.payer, &authority],
+        context.last_blockhash,
+    );
+
+    let err = context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap_err()
+        .unwrap();
+
+    assert_eq!(
+        err,
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(PaladinLockupError::InvalidUnlockAmount as u32)
+        )
+    );
+}
+
+#[tokio::test]
+async fn success() {
+    let mut context = setup().start_with_context().await;
+
+    let authority = Keypair::new();
+    let lockup = Pubkey::new_unique();
+    let unlocked_lockup = Pubkey::new_unique();
+    let mint = Pubkey::new_unique();
+    let metadata = Pubkey::new_unique().to_bytes();
+    let clock = context.banks_client.get_sysvar::<Clock>().await.unwrap();
+    let start = clock.unix_timestamp as u64;
+
+    // Create the lockup pool account, with the lockup ranked first.
+    let pool = Pubkey::new_unique();
+    let other_entry = LockupPoolEntry {
+        lockup: Pubkey::new_unique(),
+        amount: 5_000,
+        metadata: Pubkey::new_unique().to_bytes(),
+    };
+    setup_lockup_pool_with_entries(
+        &mut context,
+        &pool,
+        mint,
+        &[
+            LockupPoolEntry {
+                lockup,
+                amount: 10_000,
+                metadata,
+            },
+            other_entry,
+        ],
+    )
+    .await;
+
+    let state = Lockup {
+        discriminator: Lockup::SPL_DISCRIMINATOR.into(),
+        amount: 10_000,
+        authority: authority.pubkey(),
+        lockup_start_timestamp: start,
+        lockup_end_timestamp: None,
+        mint,
+        pool,
+        metadata,
+        proposed_authority: Pubkey::default(),
+        committed_end_timestamp: None,
+        vesting: VestingSchedule::default(),
+        withdrawn_amount: 0,
+        metadata_updated_timestamp: 0,
+        flags: Lockup::FLAG_IN_POOL,
+        reward_per_token_checkpoint: PodU128::default(),
+        unclaimed_rewards: 0,
+        cooldown_end_timestamp: None,
+    };
+    setup_lockup(&mut context, &lockup, state).await;
+    setup_unlocked_lockup_account(&mut context, &unlocked_lockup).await;
+
+    let instruction = paladin_lockup_program::instruction::partial_unlock(
+        &authority.pubkey(),
+        pool,
+        &lockup_pool_state(mint),
+        &[],
+        &lockup,
+        10_000,
+        &unlocked_lockup,
+        6_000,
+    );
+
+    let transaction = Transaction::new_signed_with_payer(
+        &[instruction],
+        Some(&context.payer.pubkey()),
+        &[&context.payer, &authority],
+        context.last_blockhash,
+    );
+
+    context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap();
+
+    // Check the remaining lockup is still active.
+    let lockup_account = context
+        .banks_client
+        .get_account(lockup)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(
+        bytemuck::from_bytes::<Lockup>(&lockup_account.data),
+        &Lockup {
+            amount: 4_000,
+            ..state
+        }
+    );
+
+    // Check the unlocked lockup has started its cooldown.
+    let unlocked_lockup_account = context
+        .banks_client
+        .get_account(unlocked_lockup)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(
+        bytemuck::from_bytes::<Lockup>(&unlocked_lockup_account.data),
+        &Lockup {
+            amount: 6_000,
+            lockup_end_timestamp: NonZeroU64::new(start),
+            cooldown_end_timestamp: NonZeroU64::new(start + LOCKUP_COOLDOWN_SECONDS),
+            flags: 0,
+            ..state
+        }
+    );
+
+    // Check the remaining lockup was re-ranked in the pool.
+    let lockup_pool = context
+        .banks_client
+        .get_account(pool)
+        .await
+        .unwrap()
+        .unwrap();
+    let (lockup_pool, pool_entries) = LockupPool::unpack(&lockup_pool.data).unwrap();
+    assert_eq!(lockup_pool.entries_len, 2);
+    assert_eq!(pool_entries[0], other_entry);
+    assert_eq!(
+        pool_entries[1],
+        LockupPoolEntry {
+            lockup,
+            amount: 4_000,
+            metadata,
+        }
+    );
+}
+
+#[tokio::test]
+async fn success_pending_authority_not_copied() {
+    let mut context = setup().start_with_context().await;
+
+    let authority = Keypair::new();
+    let proposed_authority = Pubkey::new_unique();
+    let lockup = Pubkey::new_unique();
+    let unlocked_lockup = Pubkey::new_unique();
+    let mint = Pubkey::new_unique();
+    let clock = context.banks_client.get_sysvar::<Clock>().await.unwrap();
+    let start = clock.unix_timestamp as u64;
+
+    let pool = Pubkey::new_unique();
+    setup_lockup_pool(&mut context, &pool, mint).await;
+
+    // The lockup has an authority transfer pending.
+    let state = Lockup {
+        discriminator: Lockup::SPL_DISCRIMINATOR.into(),
+        amount: 10_000,
+        authority: authority.pubkey(),
+        lockup_start_timestamp: start,
+        lockup_end_timestamp: None,
+        mint,
+        pool,
+        metadata: Pubkey::new_unique().to_bytes(),
+        proposed_authority,
+        committed_end_timestamp: None,
+        vesting: VestingSchedule::default(),
+        withdrawn_amount: 0,
+        metadata_updated_timestamp: 0,
+        flags: 0,
+        reward_per_token_checkpoint: PodU128::default(),
+        unclaimed_rewards: 0,
+        cooldown_end_timestamp: None,
+    };
+    setup_lockup(&mut context, &lockup, state).await;
+    setup_unlocked_lockup_account(&mut context, &unlocked_lockup).await;
+
+    let transaction = Transaction::new_signed_with_payer(
+        &[paladin_lockup_program::instruction::partial_unlock(
+            &authority.pubkey(),
+            pool,
+            &lockup_pool_state(mint),
+            &[],
+            &lockup,
+            10_000,
+            &unlocked_lockup,
+            6_000,
+        )],
+        Some(&context.payer.pubkey()),
+        &[&context.payer, &authority],
+        context.last_blockhash,
+    );
+    context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap();
+
+    // The remaining lockup keeps the pending transfer.
+    let lockup_account = context
+        .banks_client
+        .get_account(lockup)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(
+        bytemuck::from_bytes::<Lockup>(&lockup_account.data).proposed_authority,
+        proposed_authority
+    );
+
+    // The unlocked lockup stays with the current authority only.
+    let unlocked_lockup_account = context
+        .banks_client
+        .get_account(unlocked_lockup)
+        .await
+        .unwrap()
+        .unwrap();
+    let unlocked_state = bytemuck::from_bytes::<Lockup>(&unlocked_lockup_account.data);
+    assert_eq!(unlocked_state.authority, authority.pubkey());
+    assert_eq!(unlocked_state.proposed_authority, Pubkey::default());
+}
+
+#[tokio::test]
+async fn success_drops_out_of_full_last_shard() {
+    let mut context = setup().start_with_context().await;
+
+    let authority = Keypair::new();
+    let lockup = Pubkey::new_unique();
+    let unlocked_lockup = Pubkey::new_unique();
+    let mint = Pubkey::new_unique();
+    let metadata = Pubkey::new_unique().to_bytes();
+    let clock = context.banks_client.get_sysvar::<Clock>().await.unwrap();
+    let start = clock.unix_timestamp as u64;
+
+    // Create the lockup pool account, holding the lockup above a full shard
+    // whose smallest entry outranks the lockup's remaining amount.
+    let pool = Pubkey::new_unique();
+    let shard = Pubkey::new_unique();
+    let shard_entry = LockupPoolEntry {
+        lockup: Pubkey::new_unique(),
+        amount: 3_000,
+        metadata: Pubkey::new_unique().to_bytes(),
+    };
+    let mut pool_state = lockup_pool_state(mint);
+    pool_state.shards[0] = LockupPoolShardInfo {
+        address: shard,
+        max_amount: 5_000,
+    };
+    pool_state.shards_len = 1;
+    let data = lockup_pool_data(
+        &pool_state,
+        &[LockupPoolEntry {
+            lockup,
+            amount: 10_000,
+            metadata,
+        }],
+        LockupPool::INITIAL_CAPACITY,
+    );
+    setup_lockup_pool_with_data(&mut context, &pool, data).await;
+    setup_lockup_pool_shard(&mut context, &shard, &pool, &[shard_entry], 1).await;
+
+    let state = Lockup {
+        discriminator: Lockup::SPL_DISCRIMINATOR.into(),
+        amount: 10_000,
+        authority: authority.pubkey(),
+        lockup_start_timestamp: start,
+        lockup_end_timestamp: None,
+        mint,
+        pool,
+        metadata,
+        proposed_authority: Pubkey::default(),
+        committed_end_timestamp: None,
+        vesting: VestingSchedule::default(),
+        withdrawn_amount: 0,
+        metadata_updated_timestamp: 0,
+        flags: Lockup::FLAG_IN_POOL,
+        reward_per_token_checkpoint: PodU128::default(),
+        unclaimed_rewards: 0,
+        cooldown_end_timestamp: None,
+    };
+    setup_lockup(&mut context, &lockup, state).await;
+    setup_unlocked_lockup_account(&mut context, &unlocked_lockup).await;
+
+    let instruction = paladin_lockup_program::instruction::partial_unlock(
+        &authority.pubkey(),
+        pool,
+        &pool_state,
+        &[],
+        &lockup,
+        state.amount,
+        &unlocked_lockup,
+        8_000,
+    );
+
+    let transaction = Transaction::new_signed_with_payer(
+        &[instruction],
+        Some(&context.payer.pubkey()),
+        &[&context.payer, &authority],
+        context.last_blockhash,
+    );
+
+    context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap();
+
+    // Check the remaining lockup is still active, but left the pool.
+    let lockup_account = context
+        .banks_client
+        .get_account(lockup)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(
+        bytemuck::from_bytes::<Lockup>(&lockup_account.data),
+        &Lockup {
+            amount: 2_000,
+            flags: 0,
+            ..state
+        }
+    );
+
+    // Check neither the pool nor the shard holds the lockup.
+    let lockup_pool = context
+        .banks_client
+        .get_account(pool)
+        .await
+        .unwrap()
+        .unwrap();
+    let (lockup_pool, _) = LockupPool::unpack(&lockup_pool.data).unwrap();
+    assert_eq!(lockup_pool.entries_len, 0);
+    let shard_account = context
+        .banks_client
+        .get_account(shard)
+        .await
+        .unwrap()
+        .unwrap();
+    let (shard_state, shard_entries) = LockupPoolShard::unpack(&shard_account.data).unwrap();
+    assert_eq!(shard_state.entries_len, 1);
+    assert_eq!(shard_entries[0], shard_entry);
+}
@@ -0,0 +1,320 @@
+#![cfg(feature = "test-sbf")]
+
+mod setup;
+
+use {
+    paladin_lockup_program::{
+        error::PaladinLockupError,
+        state::{LockupPool, LockupPoolEntry, LockupPoolShard, LockupPoolShardInfo},
+    },
+    setup::{
+        lockup_pool_data, lockup_pool_state, setup, setup_lockup_pool_shard,
+        setup_lockup_pool_with_data,
+    },
+    solana_program_test::*,
+    solana_sdk::{
+        instruction::InstructionError,
+        pubkey::Pubkey,
+        signature::Keypair,
+        signer::Signer,
+        transaction::{Transaction, TransactionError},
+    },
+    test_case::test_case,
+};
+
+fn pool_entry(amount: u64) -> LockupPoolEntry {
+    LockupPoolEntry {
+        lockup: Pubkey::new_unique(),
+        amount,
+        metadata: [0; 32],
+    }
+}
+
+/// Sets up a lockup pool holding `[500, 400]`, with a shard covering amounts
+/// below 350 holding `[300, 250]`, and a shard covering amounts below 200
+/// holding `[150, 100]`.
+async fn setup_sharded_pool(
+    context: &mut ProgramTestContext,
+    pool: &Pubkey,
+    pool_authority: &Pubkey,
+    shard_capacity: usize,
+) -> (LockupPool, [LockupPoolEntry; 6]) {
+    let entries = [500, 400, 300, 250, 150, 100].map(pool_entry);
+    let shards = [Pubkey::new_unique(), Pubkey::new_unique()];
+
+    let mut state = LockupPool {
+        authority: *pool_authority,
+        shards_len: 2,
+        ..lockup_pool_state(Pubkey::new_unique())
+    };
+    state.shards[0] = LockupPoolShardInfo {
+        address: shards[0],
+        max_amount: 350,
+    };
+    state.shards[1] = LockupPoolShardInfo {
+        address: shards[1],
+        max_amount: 200,
+    };
+    let data = lockup_pool_data(&state, &entries[..2], LockupPool::INITIAL_CAPACITY);
+    setup_lockup_pool_with_data(context, pool, data).await;
+    setup_lockup_pool_shard(context, &shards[0], pool, &entries[2..4], shard_capacity).await;
+    setup_lockup_pool_shard(context, &shards[1], pool, &entries[4..], shard_capacity).await;
+
+    (state, entries)
+}
+
+async fn get_lockup_pool_state(
+    context: &mut ProgramTestContext,
+    pool: &Pubkey,
+) -> (LockupPool, Vec<LockupPoolEntry>) {
+    let account = context
+        .banks_client
+        .get_account(*pool)
+        .await
+        .unwrap()
+        .unwrap();
+    let (lockup_pool, entries) = LockupPool::unpack(&account.data).unwrap();
+    (*lockup_pool, entries[..lockup_pool.entries_len].to_vec())
+}
+
+async fn get_lockup_pool_shard_entries(
+    context: &mut ProgramTestContext,
+    shard: &Pubkey,
+) -> Vec<LockupPoolEntry> {
+    let account = context
+        .banks_client
+        .get_account(*shard)
+        .await
+        .unwrap()
+        .unwrap();
+    let (shard, entries) = LockupPoolShard::unpack(&account.data).unwrap();
+    entries[..shard.entries_len].to_vec()
+}
+
+#[tokio::test]
+async fn fail_incorrect_authority() {
+    let mut context = setup().start_with_context().await;
+
+    let pool_authority = Keypair::new();
+
+    // Create the lockup pool account, with a different authority.
+    let pool = Pubkey::new_unique();
+    let (state, _) = setup_sharded_pool(&mut context, &pool, &Pubkey::new_unique(), 8).await;
+
+    let instruction = paladin_lockup_program::instruction::rebalance_lockup_pool_shard(
+        &pool_authority.pubkey(),
+        pool,
+        &state,
+        0,
+        300,
+    );
+
+    let transaction = Transaction::new_signed_with_payer(
+        &[instruction],
+        Some(&context.payer.pubkey()),
+        &[&context.payer, &pool_authority],
+        context.last_blockhash,
+    );
+
+    let err = context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap_err()
+        .unwrap();
+
+    assert_eq!(
+        err,
+        TransactionError::InstructionError(0, InstructionError::IncorrectAuthority)
+    );
+}
+
+#[tokio::test]
+async fn fail_incorrect_shard() {
+    let mut context = setup().start_with_context().await;
+
+    let pool_authority = Keypair::new();
+
+    // Create the lockup pool account.
+    let pool = Pubkey::new_unique();
+    let (state, _) = setup_sharded_pool(&mut context, &pool, &pool_authority.pubkey(), 8).await;
+
+    // Create a shard that is not part of the pool.
+    let shard = Pubkey::new_unique();
+    setup_lockup_pool_shard(&mut context, &shard, &pool, &[], 8).await;
+
+    let mut instruction = paladin_lockup_program::instruction::rebalance_lockup_pool_shard(
+        &pool_authority.pubkey(),
+        pool,
+        &state,
+        0,
+        300,
+    );
+    instruction.accounts[2].pubkey = shard; // Incorrect shard.
+
+    let transaction = Transaction::new_signed_with_payer(
+        &[instruction],
+        Some(&context.payer.pubkey()),
+        &[&context.payer, &pool_authority],
+        context.last_blockhash,
+    );
+
+    let err = context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap_err()
+        .unwrap();
+
+    assert_eq!(
+        err,
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(PaladinLockupError::IncorrectLockupPoolShard as u32)
+        )
+    );
+}
+
+#[test_case(0, 200; "not_above_lower_shard")]
+#[test_case(1, 350; "not_below_upper_shard")]
+#[test_case(1, 0; "zero")]
+#[tokio::test]
+async fn fail_invalid_shard_boundary(shard_index: usize, max_amount: u64) {
+    let mut context = setup().start_with_context().await;
+
+    let pool_authority = Keypair::new();
+
+    // Create the lockup pool account.
+    let pool = Pubkey::new_unique();
+    let (state, _) = setup_sharded_pool(&mut context, &pool, &pool_authority.pubkey(), 8).await;
+
+    let instruction = paladin_lockup_program::instruction::rebalance_lockup_pool_shard(
+        &pool_authority.pubkey(),
+        pool,
+        &state,
+        shard_index,
+        max_amount,
+    );
+
+    let transaction = Transaction::new_signed_with_payer(
+        &[instruction],
+        Some(&context.payer.pubkey()),
+        &[&context.payer, &pool_authority],
+        context.last_blockhash,
+    );
+
+    let err = context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap_err()
+        .unwrap();
+
+    assert_eq!(
+        err,
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(PaladinLockupError::InvalidShardBoundary as u32)
+        )
+    );
+}
+
+#[tokio::test]
+async fn fail_shard_capacity_exceeded() {
+    let mut context = setup().start_with_context().await;
+
+    let pool_authority = Keypair::new();
+
+    // Create the lockup pool account, with full shards.
+    let pool = Pubkey::new_unique();
+    let (state, _) = setup_sharded_pool(&mut context, &pool, &pool_authority.pubkey(), 2).await;
+
+    let instruction = paladin_lockup_program::instruction::rebalance_lockup_pool_shard(
+        &pool_authority.pubkey(),
+        pool,
+        &state,
+        0,
+        450,
+    );
+
+    let transaction = Transaction::new_signed_with_payer(
+        &[instruction],
+        Some(&context.payer.pubkey()),
+        &[&context.payer, &pool_authority],
+        context.last_blockhash,
+    );
+
+    let err = context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap_err()
+        .unwrap();
+
+    assert_eq!(
+        err,
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(PaladinLockupError::ShardCapacityExceeded as u32)
+        )
+    );
+}
+
+#[test_case(0, 450, &[0], &[1, 2, 3], &[4, 5]; "first_shard_up")]
+#[test_case(0, 275, &[0, 1, 2], &[3], &[4, 5]; "first_shard_down")]
+#[test_case(1, 260, &[0, 1], &[2], &[3, 4, 5]; "second_shard_up")]
+#[test_case(1, 120, &[0, 1], &[2, 3, 4], &[5]; "second_shard_down")]
+#[tokio::test]
+async fn success(
+    shard_index: usize,
+    max_amount: u64,
+    expected_pool: &[usize],
+    expected_first_shard: &[usize],
+    expected_second_shard: &[usize],
+) {
+    let mut context = setup().start_with_context().await;
+
+    let pool_authority = Keypair::new();
+
+    // Create the lockup pool account.
+    let pool = Pubkey::new_unique();
+    let (state, entries) =
+        setup_sharded_pool(&mut context, &pool, &pool_authority.pubkey(), 8).await;
+
+    let instruction = paladin_lockup_program::instruction::rebalance_lockup_pool_shard(
+        &pool_authority.pubkey(),
+        pool,
+        &state,
+        shard_index,
+        max_amount,
+    );
+
+    let transaction = Transaction::new_signed_with_payer(
+        &[instruction],
+        Some(&context.payer.pubkey()),
+        &[&context.payer, &pool_authority],
+        context.last_blockhash,
+    );
+
+    context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap();
+
+    // The shard's boundary was updated.
+    let (lockup_pool, pool_entries) = get_lockup_pool_state(&mut context, &pool).await;
+    assert_eq!(lockup_pool.shards[shard_index].max_amount, max_amount);
+
+    // The entries were moved across the new boundary.
+    let expected = |indices: &[usize]| indices.iter().map(|i| entries[*i]).collect::<Vec<_>>();
+    assert_eq!(pool_entries, expected(expected_pool));
+    assert_eq!(
+        get_lockup_pool_shard_entries(&mut context, &state.shards[0].address).await,
+        expected(expected_first_shard)
+    );
+    assert_eq!(
+        get_lockup_pool_shard_entries(&mut context, &state.shards[1].address).await,
+        expected(expected_second_shard)
+    );
+}
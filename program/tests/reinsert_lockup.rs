@@ -0,0 +1,527 @@
+#![cfg(feature = "test-sbf")]
+
+mod setup;
+
+use {
+    paladin_lockup_program::{
+        error::PaladinLockupError,
+        state::{Lockup, LockupPool, LockupPoolEntry, PodU128, VestingSchedule},
+    },
+    setup::{
+        lockup_pool_state, setup, setup_lockup, setup_lockup_pool, setup_lockup_pool_with_entries,
+        setup_lockup_pool_with_state,
+    },
+    solana_program_test::*,
+    solana_sdk::{
+        compute_budget::ComputeBudgetInstruction,
+        instruction::InstructionError,
+        pubkey::Pubkey,
+        signer::Signer,
+        transaction::{Transaction, TransactionError},
+    },
+    spl_discriminator::SplDiscriminate,
+    std::num::NonZeroU64,
+    test_case::test_case,
+};
+
+fn lockup_state(mint: Pubkey, pool: Pubkey, amount: u64) -> Lockup {
+    Lockup {
+        discriminator: Lockup::SPL_DISCRIMINATOR.into(),
+        amount,
+        authority: Pubkey::new_unique(),
+        lockup_start_timestamp: 10,
+        lockup_end_timestamp: None,
+        mint,
+        pool,
+        metadata: Pubkey::new_unique().to_bytes(),
+        proposed_authority: Pubkey::default(),
+        committed_end_timestamp: None,
+        vesting: VestingSchedule::default(),
+        withdrawn_amount: 0,
+        metadata_updated_timestamp: 0,
+        flags: 0,
+        reward_per_token_checkpoint: PodU128::default(),
+        unclaimed_rewards: 0,
+        cooldown_end_timestamp: None,
+    }
+}
+
+/// Builds a full pool of entries with amounts from the pool's capacity down
+/// to one.
+fn full_pool_entries() -> Vec<LockupPoolEntry> {
+    (0..LockupPool::INITIAL_CAPACITY)
+        .map(|i| LockupPoolEntry {
+            lockup: Pubkey::new_unique(),
+            amount: (LockupPool::INITIAL_CAPACITY - i) as u64,
+            metadata: [0; 32],
+        })
+        .collect()
+}
+
+async fn get_lockup_state(context: &mut ProgramTestContext, lockup: &Pubkey) -> Lockup {
+    let account = context
+        .banks_client
+        .get_account(*lockup)
+        .await
+        .unwrap()
+        .unwrap();
+    *bytemuck::from_bytes::<Lockup>(&account.data)
+}
+
+async fn get_lockup_pool_state(
+    context: &mut ProgramTestContext,
+    pool: &Pubkey,
+) -> (LockupPool, Vec<LockupPoolEntry>) {
+    let account = context
+        .banks_client
+        .get_account(*pool)
+        .await
+        .unwrap()
+        .unwrap();
+    let (lockup_pool, entries) = LockupPool::unpack(&account.data).unwrap();
+    (*lockup_pool, entries.to_vec())
+}
+
+#[tokio::test]
+async fn fail_incorrect_pool() {
+    let mut context = setup().start_with_context().await;
+
+    let lockup = Pubkey::new_unique();
+    let mint = Pubkey::new_unique();
+
+    // Create the lockup pool account.
+    let pool = Pubkey::new_unique();
+    setup_lockup_pool(&mut context, &pool, mint).await;
+
+    setup_lockup(
+        &mut context,
+        &lockup,
+        lockup_state(mint, Pubkey::new_unique(), 10_000), // Incorrect pool.
+    )
+    .await;
+
+    let instruction = paladin_lockup_program::instruction::reinsert_lockup(
+        pool,
+        &lockup_pool_state(mint),
+        &[],
+        &lockup,
+        10_000,
+    );
+
+    let transaction = Transaction::new_signed_with_payer(
+        &[instruction],
+        Some(&context.payer.pubkey()),
+        &[&context.payer],
+        context.last_blockhash,
+    );
+
+    let err = context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap_err()
+        .unwrap();
+
+    assert_eq!(
+        err,
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(PaladinLockupError::IncorrectPool as u32)
+        )
+    );
+}
+
+#[tokio::test]
+async fn fail_lockup_already_unlocked() {
+    let mut context = setup().start_with_context().await;
+
+    let lockup = Pubkey::new_unique();
+    let mint = Pubkey::new_unique();
+
+    // Create the lockup pool account.
+    let pool = Pubkey::new_unique();
+    setup_lockup_pool(&mut context, &pool, mint).await;
+
+    setup_lockup(
+        &mut context,
+        &lockup,
+        Lockup {
+            lockup_end_timestamp: NonZeroU64::new(20), // Unlocked.
+            ..lockup_state(mint, pool, 10_000)
+        },
+    )
+    .await;
+
+    let instruction = paladin_lockup_program::instruction::reinsert_lockup(
+        pool,
+        &lockup_pool_state(mint),
+        &[],
+        &lockup,
+        10_000,
+    );
+
+    let transaction = Transaction::new_signed_with_payer(
+        &[instruction],
+        Some(&context.payer.pubkey()),
+        &[&context.payer],
+        context.last_blockhash,
+    );
+
+    let err = context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap_err()
+        .unwrap();
+
+    assert_eq!(
+        err,
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(PaladinLockupError::LockupAlreadyUnlocked as u32)
+        )
+    );
+}
+
+#[tokio::test]
+async fn fail_lockup_already_in_pool() {
+    let mut context = setup().start_with_context().await;
+
+    let lockup = Pubkey::new_unique();
+    let mint = Pubkey::new_unique();
+
+    // Create the lockup pool account, with the lockup in it.
+    let pool = Pubkey::new_unique();
+    let state = Lockup {
+        flags: Lockup::FLAG_IN_POOL,
+        ..lockup_state(mint, pool, 10_000)
+    };
+    setup_lockup_pool_with_entries(
+        &mut context,
+        &pool,
+        mint,
+        &[LockupPoolEntry {
+            lockup,
+            amount: state.amount,
+            metadata: state.metadata,
+        }],
+    )
+    .await;
+    setup_lockup(&mut context, &lockup, state).await;
+
+    let instruction = paladin_lockup_program::instruction::reinsert_lockup(
+        pool,
+        &lockup_pool_state(mint),
+        &[],
+        &lockup,
+        state.amount,
+    );
+
+    let transaction = Transaction::new_signed_with_payer(
+        &[instruction],
+        Some(&context.payer.pubkey()),
+        &[&context.payer],
+        context.last_blockhash,
+    );
+
+    let err = context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap_err()
+        .unwrap();
+
+    assert_eq!(
+        err,
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(PaladinLockupError::LockupAlreadyInPool as u32)
+        )
+    );
+}
+
+#[test_case(LockupPool::FLAG_LOCKUPS_DISABLED, PaladinLockupError::LockupsDisabled; "lockups_disabled")]
+#[test_case(LockupPool::FLAG_LOCKUP_PAUSED, PaladinLockupError::PoolPaused; "lockups_paused")]
+#[tokio::test]
+async fn fail_pool_config(flags: u64, expected_err: PaladinLockupError) {
+    let mut context = setup().start_with_context().await;
+
+    let lockup = Pubkey::new_unique();
+    let mint = Pubkey::new_unique();
+
+    // Create the lockup pool account with the provided flags.
+    let pool = Pubkey::new_unique();
+    setup_lockup_pool_with_state(
+        &mut context,
+        &pool,
+        &LockupPool {
+            flags,
+            ..lockup_pool_state(mint)
+        },
+    )
+    .await;
+
+    setup_lockup(&mut context, &lockup, lockup_state(mint, pool, 10_000)).await;
+
+    let instruction = paladin_lockup_program::instruction::reinsert_lockup(
+        pool,
+        &lockup_pool_state(mint),
+        &[],
+        &lockup,
+        10_000,
+    );
+
+    let transaction = Transaction::new_signed_with_payer(
+        &[instruction],
+        Some(&context.payer.pubkey()),
+        &[&context.payer],
+        context.last_blockhash,
+    );
+
+    let err = context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap_err()
+        .unwrap();
+
+    assert_eq!(
+        err,
+        TransactionError::InstructionError(0, InstructionError::Custom(expected_err as u32))
+    );
+}
+
+#[tokio::test]
+async fn fail_amount_too_low() {
+    let mut context = setup().start_with_context().await;
+
+    let lockup = Pubkey::new_unique();
+    let mint = Pubkey::new_unique();
+
+    // Create a full lockup pool account.
+    let pool = Pubkey::new_unique();
+    setup_lockup_pool_with_entries(&mut context, &pool, mint, &full_pool_entries()).await;
+
+    // The lockup does not outrank the smallest lockup in the pool.
+    setup_lockup(&mut context, &lockup, lockup_state(mint, pool, 1)).await;
+
+    let instruction = paladin_lockup_program::instruction::reinsert_lockup(
+        pool,
+        &lockup_pool_state(mint),
+        &[],
+        &lockup,
+        1,
+    );
+
+    let transaction = Transaction::new_signed_with_payer(
+        &[
+            ComputeBudgetInstruction::set_compute_unit_limit(400_000),
+            instruction,
+        ],
+        Some(&context.payer.pubkey()),
+        &[&context.payer],
+        context.last_blockhash,
+    );
+
+    let err = context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap_err()
+        .unwrap();
+
+    assert_eq!(
+        err,
+        TransactionError::InstructionError(
+            1,
+            InstructionError::Custom(PaladinLockupError::AmountTooLow as u32)
+        )
+    );
+}
+
+#[tokio::test]
+async fn fail_missing_evicted_lockup() {
+    let mut context = setup().start_with_context().await;
+
+    let lockup = Pubkey::new_unique();
+    let mint = Pubkey::new_unique();
+
+    // Create a full lockup pool account.
+    let pool = Pubkey::new_unique();
+    let entries = full_pool_entries();
+    let evicted_entry = entries[LockupPool::INITIAL_CAPACITY - 1];
+    setup_lockup_pool_with_entries(&mut context, &pool, mint, &entries).await;
+    setup_lockup(
+        &mut context,
+        &evicted_entry.lockup,
+        Lockup {
+            flags: Lockup::FLAG_IN_POOL,
+            ..lockup_state(mint, pool, evicted_entry.amount)
+        },
+    )
+    .await;
+
+    // The lockup outranks the smallest lockup in the pool, but the evicted
+    // lockup account is not provided.
+    let state = lockup_state(mint, pool, 100);
+    setup_lockup(&mut context, &lockup, state).await;
+
+    let instruction = paladin_lockup_program::instruction::reinsert_lockup(
+        pool,
+        &lockup_pool_state(mint),
+        &[],
+        &lockup,
+        state.amount,
+    );
+
+    let transaction = Transaction::new_signed_with_payer(
+        &[
+            ComputeBudgetInstruction::set_compute_unit_limit(400_000),
+            instruction,
+        ],
+        Some(&context.payer.pubkey()),
+        &[&context.payer],
+        context.last_blockhash,
+    );
+
+    let err = context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap_err()
+        .unwrap();
+
+    assert_eq!(
+        err,
+        TransactionError::InstructionError(
+            1,
+            InstructionError::Custom(PaladinLockupError::MissingEvictedLockup as u32)
+        )
+    );
+}
+
+#[tokio::test]
+async fn success_with_capacity() {
+    let mut context = setup().start_with_context().await;
+
+    let lockup = Pubkey::new_unique();
+    let mint = Pubkey::new_unique();
+
+    // Create the lockup pool account, with space for the lockup.
+    let pool = Pubkey::new_unique();
+    let other_entry = LockupPoolEntry {
+        lockup: Pubkey::new_unique(),
+        amount: 20_000,
+        metadata: Pubkey::new_unique().to_bytes(),
+    };
+    setup_lockup_pool_with_entries(&mut context, &pool, mint, &[other_entry]).await;
+
+    let state = lockup_state(mint, pool, 10_000);
+    setup_lockup(&mut context, &lockup, state).await;
+
+    let instruction = paladin_lockup_program::instruction::reinsert_lockup(
+        pool,
+        &lockup_pool_state(mint),
+        &[],
+        &lockup,
+        state.amount,
+    );
+
+    let transaction = Transaction::new_signed_with_payer(
+        &[instruction],
+        Some(&context.payer.pubkey()),
+        &[&context.payer],
+        context.last_blockhash,
+    );
+
+    context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap();
+
+    // The lockup is back in the pool.
+    assert_eq!(
+        get_lockup_state(&mut context, &lockup).await,
+        Lockup {
+            flags: Lockup::FLAG_IN_POOL,
+            ..state
+        }
+    );
+    let (lockup_pool, pool_entries) = get_lockup_pool_state(&mut context, &pool).await;
+    assert_eq!(lockup_pool.entries_len, 2);
+    assert_eq!(pool_entries[0], other_entry);
+    assert_eq!(
+        pool_entries[1],
+        LockupPoolEntry {
+            lockup,
+            amount: state.amount,
+            metadata: state.metadata,
+        }
+    );
+}
+
+#[tokio::test]
+async fn success_outranks_minimum() {
+    let mut context = setup().start_with_context().await;
+
+    let lockup = Pubkey::new_unique();
+    let mint = Pubkey::new_unique();
+
+    // Create a full lockup pool account.
+    let pool = Pubkey::new_unique();
+    let entries = full_pool_entries();
+    let evicted_entry = entries[LockupPool::INITIAL_CAPACITY - 1];
+    setup_lockup_pool_with_entries(&mut context, &pool, mint, &entries).await;
+    setup_lockup(
+        &mut context,
+        &evicted_entry.lockup,
+        Lockup {
+            flags: Lockup::FLAG_IN_POOL,
+            ..lockup_state(mint, pool, evicted_entry.amount)
+        },
+    )
+    .await;
+
+    // The lockup outranks the smallest lockup in the pool.
+    let state = lockup_state(mint, pool, 100);
+    setup_lockup(&mut context, &lockup, state).await;
+
+    let instruction = paladin_lockup_program::instruction::reinsert_lockup(
+        pool,
+        &lockup_pool_state(mint),
+        &entries,
+        &lockup,
+        state.amount,
+    );
+
+    let transaction = Transaction::new_signed_with_payer(
+        &[
+            ComputeBudgetInstruction::set_compute_unit_limit(400_000),
+            instruction,
+        ],
+        Some(&context.payer.pubkey()),
+        &[&context.payer],
+        context.last_blockhash,
+    );
+
+    context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap();
+
+    // The lockup took the smallest lockup's place.
+    let (lockup_pool, pool_entries) = get_lockup_pool_state(&mut context, &pool).await;
+    assert_eq!(lockup_pool.entries_len, LockupPool::INITIAL_CAPACITY);
+    assert!(pool_entries
+        .iter()
+        .any(|entry| entry.lockup == lockup && entry.amount == state.amount));
+    assert!(pool_entries
+        .iter()
+        .all(|entry| entry.lockup != evicted_entry.lockup));
+
+    // The evicted lockup left the pool.
+    let evicted_state = get_lockup_state(&mut context, &evicted_entry.lockup).await;
+    assert!(!evicted_state.in_pool());
+}
@@ -0,0 +1,500 @@
+#![cfg(feature = "test-sbf")]
+
+mod setup;
+
+use {
+    paladin_lockup_program::{
+        error::PaladinLockupError,
+        state::{Lockup, LockupPool, LockupPoolEntry, PodU128, VestingSchedule},
+    },
+    setup::{
+        lockup_pool_state, setup, setup_lockup, setup_lockup_pool, setup_lockup_pool_with_entries,
+        setup_lockup_pool_with_state,
+    },
+    solana_program_test::*,
+    solana_sdk::{
+        instruction::InstructionError,
+        pubkey::Pubkey,
+        signature::Keypair,
+        signer::Signer,
+        transaction::{Transaction, TransactionError},
+    },
+    spl_discriminator::SplDiscriminate,
+    std::num::NonZeroU64,
+    test_case::test_case,
+};
+
+#[tokio::test]
+async fn fail_incorrect_lockup_authority() {
+    let mut context = setup().start_with_context().await;
+
+    let authority = Keypair::new();
+    let lockup = Pubkey::new_unique();
+    let mint = Pubkey::new_unique();
+
+    // Create the lockup pool account.
+    let pool = Pubkey::new_unique();
+    setup_lockup_pool(&mut context, &pool, mint).await;
+
+    setup_lockup(
+        &mut context,
+        &lockup,
+        Lockup {
+            discriminator: Lockup::SPL_DISCRIMINATOR.into(),
+            amount: 10_000,
+            authority: Pubkey::new_unique(), // Incorrect authority.
+            lockup_start_timestamp: 10,
+            lockup_end_timestamp: NonZeroU64::new(20),
+            mint,
+            pool,
+            metadata: Pubkey::new_unique().to_bytes(),
+            proposed_authority: Pubkey::default(),
+            committed_end_timestamp: None,
+            vesting: VestingSchedule::default(),
+            withdrawn_amount: 0,
+            metadata_updated_timestamp: 0,
+            flags: 0,
+            reward_per_token_checkpoint: PodU128::default(),
+            unclaimed_rewards: 0,
+            cooldown_end_timestamp: None,
+        },
+    )
+    .await;
+
+    let instruction = paladin_lockup_program::instruction::relock(
+        &authority.pubkey(),
+        pool,
+        &lockup_pool_state(mint),
+        &[],
+        &lockup,
+        10_000,
+    );
+
+    let transaction = Transaction::new_signed_with_payer(
+        &[instruction],
+        Some(&context.payer.pubkey()),
+        &[&context.payer, &authority],
+        context.last_blockhash,
+    );
+
+    let err = context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap_err()
+        .unwrap();
+
+    assert_eq!(
+        err,
+        TransactionError::InstructionError(0, InstructionError::IncorrectAuthority)
+    );
+}
+
+#[tokio::test]
+async fn fail_lockup_not_unlocked() {
+    let mut context = setup().start_with_context().await;
+
+    let authority = Keypair::new();
+    let lockup = Pubkey::new_unique();
+    let mint = Pubkey::new_unique();
+
+    // Create the lockup pool account.
+    let pool = Pubkey::new_unique();
+    setup_lockup_pool(&mut context, &pool, mint).await;
+
+    setup_lockup(
+        &mut context,
+        &lockup,
+        Lockup {
+            discriminator: Lockup::SPL_DISCRIMINATOR.into(),
+            amount: 10_000,
+            authority: authority.pubkey(),
+            lockup_start_timestamp: 10,
+            lockup_end_timestamp: None, // Still active.
+            mint,
+            pool,
+            metadata: Pubkey::new_unique().to_bytes(),
+            proposed_authority: Pubkey::default(),
+            committed_end_timestamp: None,
+            vesting: VestingSchedule::default(),
+            withdrawn_amount: 0,
+            metadata_updated_timestamp: 0,
+            flags: 0,
+            reward_per_token_checkpoint: PodU128::default(),
+            unclaimed_rewards: 0,
+            cooldown_end_timestamp: None,
+        },
+    )
+    .await;
+
+    let instruction = paladin_lockup_program::instruction::relock(
+        &authority.pubkey(),
+        pool,
+        &lockup_pool_state(mint),
+        &[],
+        &lockup,
+        10_000,
+    );
+
+    let transaction = Transaction::new_signed_with_payer(
+        &[instruction],
+        Some(&context.payer.pubkey()),
+        &[&context.payer, &authority],
+        context.last_blockhash,
+    );
+
+    let err = context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap_err()
+        .unwrap();
+
+    assert_eq!(
+        err,
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(PaladinLockupError::LockupNotUnlocked as u32)
+        )
+    );
+}
+
+#[tokio::test]
+async fn fail_amount_too_low() {
+    let mut context = setup().start_with_context().await;
+
+    let authority = Keypair::new();
+    let lockup = Pubkey::new_unique();
+    let mint = Pubkey::new_unique();
+
+    // Create the lockup pool account, with a minimum above the lockup's
+    // amount.
+    let pool = Pubkey::new_unique();
+    setup_lockup_pool_with_state(
+        &mut context,
+        &pool,
+        &LockupPool {
+            min_lockup_amount: 20_000,
+            ..lockup_pool_state(mint)
+        },
+    )
+    .await;
+
+    setup_lockup(
+        &mut context,
+        &lockup,
+        Lockup {
+            discriminator: Lockup::SPL_DISCRIMINATOR.into(),
+            amount: 10_000,
+            authority: authority.pubkey(),
+            lockup_start_timestamp: 10,
+            lockup_end_timestamp: NonZeroU64::new(20),
+            mint,
+            pool,
+            metadata: Pubkey::new_unique().to_bytes(),
+            proposed_authority: Pubkey::default(),
+            committed_end_timestamp: None,
+            vesting: VestingSchedule::default(),
+            withdrawn_amount: 0,
+            metadata_updated_timestamp: 0,
+            flags: 0,
+            reward_per_token_checkpoint: PodU128::default(),
+            unclaimed_rewards: 0,
+            cooldown_end_timestamp: None,
+        },
+    )
+    .await;
+
+    let instruction = paladin_lockup_program::instruction::relock(
+        &authority.pubkey(),
+        pool,
+        &lockup_pool_state(mint),
+        &[],
+        &lockup,
+        10_000,
+    );
+
+    let transaction = Transaction::new_signed_with_payer(
+        &[instruction],
+        Some(&context.payer.pubkey()),
+        &[&context.payer, &authority],
+        context.last_blockhash,
+    );
+
+    let err = context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap_err()
+        .unwrap();
+
+    assert_eq!(
+        err,
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(PaladinLockupError::AmountTooLow as u32)
+        )
+    );
+}
+
+#[test_case(LockupPool::FLAG_LOCKUPS_DISABLED, PaladinLockupError::LockupsDisabled; "lockups_disabled")]
+#[test_case(LockupPool::FLAG_LOCKUP_PAUSED, PaladinLockupError::PoolPaused; "lockups_paused")]
+#[tokio::test]
+async fn fail_pool_config(flags: u64, expected_err: PaladinLockupError) {
+    let mut context = setup().start_with_context().await;
+
+    let authority = Keypair::new();
+    let lockup = Pubkey::new_unique();
+    let mint = Pubkey::new_unique();
+
+    // Create the lockup pool account with the provided flags.
+    let pool = Pubkey::new_unique();
+    setup_lockup_pool_with_state(
+        &mut context,
+        &pool,
+        &LockupPool {
+            flags,
+            ..lockup_pool_state(mint)
+        },
+    )
+    .await;
+
+    setup_lockup(
+        &mut context,
+        &lockup,
+        Lockup {
+            discriminator: Lockup::SPL_DISCRIMINATOR.into(),
+            amount: 10_000,
+            authority: authority.pubkey(),
+            lockup_start_timestamp: 10,
+            lockup_end_timestamp: NonZeroU64::new(20),
+            mint,
+            pool,
+            metadata: Pubkey::new_unique().to_bytes(),
+            proposed_authority: Pubkey::default(),
+            committed_end_timestamp: None,
+            vesting: VestingSchedule::default(),
+            withdrawn_amount: 0,
+            metadata_updated_timestamp: 0,
+            flags: 0,
+            reward_per_token_checkpoint: PodU128::default(),
+            unclaimed_rewards: 0,
+            cooldown_end_timestamp: None,
+        },
+    )
+    .await;
+
+    let instruction = paladin_lockup_program::instruction::relock(
+        &authority.pubkey(),
+        pool,
+        &lockup_pool_state(mint),
+        &[],
+        &lockup,
+        10_000,
+    );
+
+    let transaction = Transaction::new_signed_with_payer(
+        &[instruction],
+        Some(&context.payer.pubkey()),
+        &[&context.payer, &authority],
+        context.last_blockhash,
+    );
+
+    let err = context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap_err()
+        .unwrap();
+
+    assert_eq!(
+        err,
+        TransactionError::InstructionError(0, InstructionError::Custom(expected_err as u32))
+    );
+}
+
+#[tokio::test]
+async fn success() {
+    let mut context = setup().start_with_context().await;
+
+    let authority = Keypair::new();
+    let lockup = Pubkey::new_unique();
+    let mint = Pubkey::new_unique();
+    let metadata = Pubkey::new_unique().to_bytes();
+
+    // Create the lockup pool account.
+    let pool = Pubkey::new_unique();
+    let other_entry = LockupPoolEntry {
+        lockup: Pubkey::new_unique(),
+        amount: 5_000,
+        metadata: Pubkey::new_unique().to_bytes(),
+    };
+    setup_lockup_pool_with_entries(&mut context, &pool, mint, &[other_entry]).await;
+
+    let state = Lockup {
+        discriminator: Lockup::SPL_DISCRIMINATOR.into(),
+        amount: 10_000,
+        authority: authority.pubkey(),
+        lockup_start_timestamp: 10,
+        lockup_end_timestamp: NonZeroU64::new(20),
+        mint,
+        pool,
+        metadata,
+        proposed_authority: Pubkey::default(),
+        committed_end_timestamp: None,
+        vesting: VestingSchedule::default(),
+        withdrawn_amount: 0,
+        metadata_updated_timestamp: 0,
+        flags: 0,
+        reward_per_token_checkpoint: PodU128::default(),
+        unclaimed_rewards: 0,
+        cooldown_end_timestamp: None,
+    };
+    setup_lockup(&mut context, &lockup, state).await;
+
+    let instruction = paladin_lockup_program::instruction::relock(
+        &authority.pubkey(),
+        pool,
+        &lockup_pool_state(mint),
+        &[],
+        &lockup,
+        10_000,
+    );
+
+    let transaction = Transaction::new_signed_with_payer(
+        &[instruction],
+        Some(&context.payer.pubkey()),
+        &[&context.payer, &authority],
+        context.last_blockhash,
+    );
+
+    context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap();
+
+    // Check the lockup is active again.
+    let lockup_account = context
+        .banks_client
+        .get_account(lockup)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(
+        bytemuck::from_bytes::<Lockup>(&lockup_account.data),
+        &Lockup {
+            lockup_end_timestamp: None,
+            flags: Lockup::FLAG_IN_POOL,
+            ..state
+        }
+    );
+
+    // Check the lockup was re-inserted into the pool.
+    let lockup_pool = context
+        .banks_client
+        .get_account(pool)
+        .await
+        .unwrap()
+        .unwrap();
+    let (lockup_pool, pool_entries) = LockupPool::unpack(&lockup_pool.data).unwrap();
+    assert_eq!(lockup_pool.entries_len, 2);
+    assert_eq!(
+        pool_entries[0],
+        LockupPoolEntry {
+            lockup,
+            amount: 10_000,
+            metadata,
+        }
+    );
+    assert_eq!(pool_entries[1], other_entry);
+}
+
+#[tokio::test]
+async fn success_without_seat() {
+    let mut context = setup().start_with_context().await;
+
+    let authority = Keypair::new();
+    let lockup = Pubkey::new_unique();
+    let mint = Pubkey::new_unique();
+
+    // Create a full lockup pool account.
+    let pool = Pubkey::new_unique();
+    let entries = (0..LockupPool::INITIAL_CAPACITY)
+        .map(|_| LockupPoolEntry {
+            lockup: Pubkey::new_unique(),
+            amount: 10_000,
+            metadata: [0; 32],
+        })
+        .collect::<Vec<_>>();
+    setup_lockup_pool_with_entries(&mut context, &pool, mint, &entries).await;
+
+    let state = Lockup {
+        discriminator: Lockup::SPL_DISCRIMINATOR.into(),
+        amount: 10_000, // Not larger than the smallest entry.
+        authority: authority.pubkey(),
+        lockup_start_timestamp: 10,
+        lockup_end_timestamp: NonZeroU64::new(20),
+        mint,
+        pool,
+        metadata: Pubkey::new_unique().to_bytes(),
+        proposed_authority: Pubkey::default(),
+        committed_end_timestamp: None,
+        vesting: VestingSchedule::default(),
+        withdrawn_amount: 0,
+        metadata_updated_timestamp: 0,
+        flags: 0,
+        reward_per_token_checkpoint: PodU128::default(),
+        unclaimed_rewards: 0,
+        cooldown_end_timestamp: None,
+    };
+    setup_lockup(&mut context, &lockup, state).await;
+
+    let instruction = paladin_lockup_program::instruction::relock(
+        &authority.pubkey(),
+        pool,
+        &lockup_pool_state(mint),
+        &entries,
+        &lockup,
+        10_000,
+    );
+
+    let transaction = Transaction::new_signed_with_payer(
+        &[instruction],
+        Some(&context.payer.pubkey()),
+        &[&context.payer, &authority],
+        context.last_blockhash,
+    );
+
+    context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap();
+
+    // Check the lockup is active again, but out of the pool.
+    let lockup_account = context
+        .banks_client
+        .get_account(lockup)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(
+        bytemuck::from_bytes::<Lockup>(&lockup_account.data),
+        &Lockup {
+            lockup_end_timestamp: None,
+            ..state
+        }
+    );
+
+    // Check the pool's entries are unchanged.
+    let lockup_pool = context
+        .banks_client
+        .get_account(pool)
+        .await
+        .unwrap()
+        .unwrap();
+    let (lockup_pool, pool_entries) = LockupPool::unpack(&lockup_pool.data).unwrap();
+    assert_eq!(lockup_pool.entries_len, LockupPool::INITIAL_CAPACITY);
+    assert_eq!(pool_entries, entries);
+}
@@ -0,0 +1,324 @@
+#![cfg(feature = "test-sbf")]
+
+mod setup;
+
+use {
+    paladin_lockup_program::{
+        error::PaladinLockupError,
+        state::{Lockup, LockupPool, LockupPoolEntry, PodU128, VestingSchedule},
+    },
+    setup::{
+        lockup_pool_data, lockup_pool_state, setup, setup_lockup, setup_lockup_pool_with_data,
+        setup_lockup_pool_with_state, setup_system_account,
+    },
+    solana_program_test::*,
+    solana_sdk::{
+        entrypoint::MAX_PERMITTED_DATA_INCREASE,
+        instruction::{Instruction, InstructionError},
+        pubkey::Pubkey,
+        rent::Rent,
+        signature::Keypair,
+        signer::Signer,
+        transaction::{Transaction, TransactionError},
+    },
+    spl_discriminator::SplDiscriminate,
+    test_case::test_case,
+};
+
+/// Builds enough `ResizeLockupPool` instructions to grow the pool from its
+/// current capacity to the provided one.
+fn resize_lockup_pool_instructions(
+    funder: &Pubkey,
+    pool_authority: &Pubkey,
+    pool: Pubkey,
+    current_capacity: usize,
+    capacity: usize,
+) -> Vec<Instruction> {
+    let instruction = paladin_lockup_program::instruction::resize_lockup_pool(
+        funder,
+        pool_authority,
+        pool,
+        capacity as u64,
+    );
+    let step_capacity = MAX_PERMITTED_DATA_INCREASE / std::mem::size_of::<LockupPoolEntry>();
+
+    vec![instruction; (capacity - current_capacity).div_ceil(step_capacity)]
+}
+
+#[tokio::test]
+async fn fail_authority_not_signer() {
+    let mut context = setup().start_with_context().await;
+
+    let pool_authority = Keypair::new();
+    let mint = Pubkey::new_unique();
+
+    // Create the lockup pool account.
+    let pool = Pubkey::new_unique();
+    setup_lockup_pool_with_state(
+        &mut context,
+        &pool,
+        &LockupPool {
+            authority: pool_authority.pubkey(),
+            ..lockup_pool_state(mint)
+        },
+    )
+    .await;
+
+    let mut instruction = paladin_lockup_program::instruction::resize_lockup_pool(
+        &context.payer.pubkey(),
+        &pool_authority.pubkey(),
+        pool,
+        2_048,
+    );
+    instruction.accounts[1].is_signer = false; // Not signer.
+
+    let transaction = Transaction::new_signed_with_payer(
+        &[instruction],
+        Some(&context.payer.pubkey()),
+        &[&context.payer],
+        context.last_blockhash,
+    );
+
+    let err = context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap_err()
+        .unwrap();
+
+    assert_eq!(
+        err,
+        TransactionError::InstructionError(0, InstructionError::MissingRequiredSignature)
+    );
+}
+
+#[tokio::test]
+async fn fail_incorrect_authority() {
+    let mut context = setup().start_with_context().await;
+
+    let pool_authority = Keypair::new();
+    let mint = Pubkey::new_unique();
+
+    // Create the lockup pool account.
+    let pool = Pubkey::new_unique();
+    setup_lockup_pool_with_state(
+        &mut context,
+        &pool,
+        &LockupPool {
+            authority: Pubkey::new_unique(),
+            ..lockup_pool_state(mint)
+        },
+    )
+    .await;
+
+    let instruction = paladin_lockup_program::instruction::resize_lockup_pool(
+        &context.payer.pubkey(),
+        &pool_authority.pubkey(),
+        pool,
+        2_048,
+    );
+
+    let transaction = Transaction::new_signed_with_payer(
+        &[instruction],
+        Some(&context.payer.pubkey()),
+        &[&context.payer, &pool_authority],
+        context.last_blockhash,
+    );
+
+    let err = context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap_err()
+        .unwrap();
+
+    assert_eq!(
+        err,
+        TransactionError::InstructionError(0, InstructionError::IncorrectAuthority)
+    );
+}
+
+#[test_case(LockupPool::INITIAL_CAPACITY as u64 - 1; "shrink")]
+#[test_case(LockupPool::MAX_CAPACITY as u64 + 1; "exceeds_maximum")]
+#[tokio::test]
+async fn fail_invalid_capacity(capacity: u64) {
+    let mut context = setup().start_with_context().await;
+
+    let pool_authority = Keypair::new();
+    let mint = Pubkey::new_unique();
+
+    // Create the lockup pool account.
+    let pool = Pubkey::new_unique();
+    setup_lockup_pool_with_state(
+        &mut context,
+        &pool,
+        &LockupPool {
+            authority: pool_authority.pubkey(),
+            ..lockup_pool_state(mint)
+        },
+    )
+    .await;
+
+    let instruction = paladin_lockup_program::instruction::resize_lockup_pool(
+        &context.payer.pubkey(),
+        &pool_authority.pubkey(),
+        pool,
+        capacity,
+    );
+
+    let transaction = Transaction::new_signed_with_payer(
+        &[instruction],
+        Some(&context.payer.pubkey()),
+        &[&context.payer, &pool_authority],
+        context.last_blockhash,
+    );
+
+    let err = context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap_err()
+        .unwrap();
+
+    assert_eq!(
+        err,
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(PaladinLockupError::InvalidPoolCapacity as u32)
+        )
+    );
+}
+
+#[test_case(true; "with_authority")]
+#[test_case(false; "without_authority")]
+#[tokio::test]
+async fn success(with_authority: bool) {
+    let mut context = setup().start_with_context().await;
+
+    let funder = Keypair::new();
+    // Pools without an authority can be grown by anyone.
+    let pool_authority = if with_authority {
+        Keypair::new()
+    } else {
+        funder.insecure_clone()
+    };
+    let mint = Pubkey::new_unique();
+    let capacity = 2 * LockupPool::INITIAL_CAPACITY;
+
+    setup_system_account(&mut context, &funder.pubkey(), 1_000_000_000).await;
+
+    // Create a full lockup pool account.
+    let pool = Pubkey::new_unique();
+    let state = LockupPool {
+        authority: if with_authority {
+            pool_authority.pubkey()
+        } else {
+            Pubkey::default()
+        },
+        entries_len: LockupPool::INITIAL_CAPACITY,
+        ..lockup_pool_state(mint)
+    };
+    let entries = (0..LockupPool::INITIAL_CAPACITY)
+        .map(|i| LockupPoolEntry {
+            lockup: Pubkey::new_unique(),
+            amount: (LockupPool::INITIAL_CAPACITY - i) as u64,
+            metadata: [0; 32],
+        })
+        .collect::<Vec<_>>();
+    let data = lockup_pool_data(&state, &entries, LockupPool::INITIAL_CAPACITY);
+    setup_lockup_pool_with_data(&mut context, &pool, data).await;
+
+    // Create a lockup that does not outrank the pool's smallest lockup.
+    let lockup = Pubkey::new_unique();
+    let lockup_state = Lockup {
+        discriminator: Lockup::SPL_DISCRIMINATOR.into(),
+        amount: 1,
+        authority: Pubkey::new_unique(),
+        lockup_start_timestamp: 10,
+        lockup_end_timestamp: None,
+        mint,
+        pool,
+        metadata: [0; 32],
+        proposed_authority: Pubkey::default(),
+        committed_end_timestamp: None,
+        vesting: VestingSchedule::default(),
+        withdrawn_amount: 0,
+        metadata_updated_timestamp: 0,
+        flags: 0,
+        reward_per_token_checkpoint: PodU128::default(),
+        unclaimed_rewards: 0,
+        cooldown_end_timestamp: None,
+    };
+    setup_lockup(&mut context, &lockup, lockup_state).await;
+
+    let transaction = Transaction::new_signed_with_payer(
+        &resize_lockup_pool_instructions(
+            &funder.pubkey(),
+            &pool_authority.pubkey(),
+            pool,
+            LockupPool::INITIAL_CAPACITY,
+            capacity,
+        ),
+        Some(&context.payer.pubkey()),
+        &[&context.payer, &funder, &pool_authority],
+        context.last_blockhash,
+    );
+
+    context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap();
+
+    // The pool grew, keeping its header & entries.
+    let account = context
+        .banks_client
+        .get_account(pool)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(
+        account.lamports,
+        Rent::default().minimum_balance(LockupPool::account_len(capacity))
+    );
+    let (lockup_pool, pool_entries) = LockupPool::unpack(&account.data).unwrap();
+    assert_eq!(lockup_pool, &state);
+    assert_eq!(pool_entries.len(), capacity);
+    assert_eq!(&pool_entries[..LockupPool::INITIAL_CAPACITY], entries);
+
+    // The pool now has room for the lockup.
+    let transaction = Transaction::new_signed_with_payer(
+        &[paladin_lockup_program::instruction::reinsert_lockup(
+            pool,
+            &lockup_pool_state(mint),
+            &[],
+            &lockup,
+            1,
+        )],
+        Some(&context.payer.pubkey()),
+        &[&context.payer],
+        context.last_blockhash,
+    );
+
+    context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap();
+
+    let account = context
+        .banks_client
+        .get_account(pool)
+        .await
+        .unwrap()
+        .unwrap();
+    let (lockup_pool, pool_entries) = LockupPool::unpack(&account.data).unwrap();
+    assert_eq!(lockup_pool.entries_len, LockupPool::INITIAL_CAPACITY + 1);
+    assert!(
+        pool_entries[..lockup_pool.entries_len].contains(&LockupPoolEntry {
+            lockup,
+            amount: 1,
+            metadata: [0; 32],
+        })
+    );
+}
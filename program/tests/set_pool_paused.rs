@@ -0,0 +1,187 @@
+#![cfg(feature = "test-sbf")]
+
+mod setup;
+
+use {
+    paladin_lockup_program::state::LockupPool,
+    setup::{lockup_pool_state, setup, setup_lockup_pool_with_state},
+    solana_program_test::*,
+    solana_sdk::{
+        instruction::{Instruction, InstructionError},
+        pubkey::Pubkey,
+        signature::Keypair,
+        signer::Signer,
+        transaction::{Transaction, TransactionError},
+    },
+    test_case::test_case,
+};
+
+async fn send_transaction(
+    context: &mut ProgramTestContext,
+    instructions: &[Instruction],
+    signers: &[&Keypair],
+) -> Result<(), BanksClientError> {
+    let blockhash = context.banks_client.get_latest_blockhash().await.unwrap();
+    let transaction = Transaction::new_signed_with_payer(
+        instructions,
+        Some(&context.payer.pubkey()),
+        signers,
+        blockhash,
+    );
+    context.banks_client.process_transaction(transaction).await
+}
+
+async fn get_lockup_pool_state(context: &mut ProgramTestContext, pool: &Pubkey) -> LockupPool {
+    let account = context
+        .banks_client
+        .get_account(*pool)
+        .await
+        .unwrap()
+        .unwrap();
+    *LockupPool::unpack(&account.data).unwrap().0
+}
+
+#[tokio::test]
+async fn fail_authority_not_signer() {
+    let mut context = setup().start_with_context().await;
+
+    let pool_authority = Keypair::new();
+    let mint = Pubkey::new_unique();
+    let payer = context.payer.insecure_clone();
+
+    // Create the lockup pool account.
+    let pool = Pubkey::new_unique();
+    setup_lockup_pool_with_state(
+        &mut context,
+        &pool,
+        &LockupPool {
+            authority: pool_authority.pubkey(),
+            ..lockup_pool_state(mint)
+        },
+    )
+    .await;
+
+    let mut instruction = paladin_lockup_program::instruction::set_pool_paused(
+        &pool_authority.pubkey(),
+        pool,
+        true,
+        true,
+        true,
+    );
+    instruction.accounts[0].is_signer = false; // Not signer.
+
+    let err = send_transaction(&mut context, &[instruction], &[&payer])
+        .await
+        .unwrap_err()
+        .unwrap();
+
+    assert_eq!(
+        err,
+        TransactionError::InstructionError(0, InstructionError::MissingRequiredSignature)
+    );
+}
+
+#[test_case(Pubkey::new_unique(); "incorrect_authority")]
+#[test_case(Pubkey::default(); "no_authority")]
+#[tokio::test]
+async fn fail_incorrect_authority(authority: Pubkey) {
+    let mut context = setup().start_with_context().await;
+
+    let pool_authority = Keypair::new();
+    let mint = Pubkey::new_unique();
+    let payer = context.payer.insecure_clone();
+
+    // Create the lockup pool account.
+    let pool = Pubkey::new_unique();
+    setup_lockup_pool_with_state(
+        &mut context,
+        &pool,
+        &LockupPool {
+            authority,
+            ..lockup_pool_state(mint)
+        },
+    )
+    .await;
+
+    let instruction = paladin_lockup_program::instruction::set_pool_paused(
+        &pool_authority.pubkey(),
+        pool,
+        true,
+        true,
+        true,
+    );
+
+    let err = send_transaction(&mut context, &[instruction], &[&payer, &pool_authority])
+        .await
+        .unwrap_err()
+        .unwrap();
+
+    assert_eq!(
+        err,
+        TransactionError::InstructionError(0, InstructionError::IncorrectAuthority)
+    );
+}
+
+#[tokio::test]
+async fn success() {
+    let mut context = setup().start_with_context().await;
+
+    let pool_authority = Keypair::new();
+    let mint = Pubkey::new_unique();
+    let payer = context.payer.insecure_clone();
+
+    // Create the lockup pool account, with new lockups already disabled.
+    let pool = Pubkey::new_unique();
+    let state = LockupPool {
+        authority: pool_authority.pubkey(),
+        flags: LockupPool::FLAG_LOCKUPS_DISABLED,
+        ..lockup_pool_state(mint)
+    };
+    setup_lockup_pool_with_state(&mut context, &pool, &state).await;
+
+    // Pause unlocks & withdrawals.
+    send_transaction(
+        &mut context,
+        &[paladin_lockup_program::instruction::set_pool_paused(
+            &pool_authority.pubkey(),
+            pool,
+            false,
+            true,
+            true,
+        )],
+        &[&payer, &pool_authority],
+    )
+    .await
+    .unwrap();
+
+    let lockup_pool = get_lockup_pool_state(&mut context, &pool).await;
+    assert_eq!(
+        lockup_pool,
+        LockupPool {
+            flags: LockupPool::FLAG_LOCKUPS_DISABLED
+                | LockupPool::FLAG_UNLOCK_PAUSED
+                | LockupPool::FLAG_WITHDRAW_PAUSED,
+            ..state
+        }
+    );
+    assert!(!lockup_pool.is_paused(LockupPool::FLAG_LOCKUP_PAUSED));
+    assert!(lockup_pool.is_paused(LockupPool::FLAG_UNLOCK_PAUSED));
+    assert!(lockup_pool.is_paused(LockupPool::FLAG_WITHDRAW_PAUSED));
+
+    // Resume everything, which leaves the other flags untouched.
+    send_transaction(
+        &mut context,
+        &[paladin_lockup_program::instruction::set_pool_paused(
+            &pool_authority.pubkey(),
+            pool,
+            false,
+            false,
+            false,
+        )],
+        &[&payer, &pool_authority],
+    )
+    .await
+    .unwrap();
+
+    assert_eq!(get_lockup_pool_state(&mut context, &pool).await, state);
+}
@@ -2,18 +2,30 @@
 #![allow(dead_code)]
 
 use {
-    paladin_lockup_program::state::{Lockup, LockupPool, LockupPoolEntry},
+    bytemuck::Pod,
+    paladin_lockup_program::{
+        state::{
+            Lockup, LockupPool, LockupPoolEntry, LockupPoolRewards, LockupPoolShard,
+            LockupPoolShardInfo, LockupPoolStats,
+        },
+        LOCKUP_COOLDOWN_SECONDS,
+    },
     solana_program_test::*,
     solana_sdk::{
         account::{Account, AccountSharedData},
         clock::Clock,
+        entrypoint::MAX_PERMITTED_DATA_INCREASE,
+        instruction::Instruction,
         program_option::COption,
         pubkey::Pubkey,
         system_program,
     },
     spl_discriminator::SplDiscriminate,
     spl_token_2022::{
-        extension::{BaseStateWithExtensionsMut, ExtensionType, StateWithExtensionsMut},
+        extension::{
+            transfer_fee::{TransferFee, TransferFeeAmount, TransferFeeConfig},
+            BaseStateWithExtensionsMut, Extension, ExtensionType, StateWithExtensionsMut,
+        },
         state::{Account as TokenAccount, AccountState, Mint},
     },
 };
@@ -31,6 +43,39 @@ pub async fn setup_mint(
     mint: &Pubkey,
     mint_authority: &Pubkey,
     supply: u64,
+) {
+    setup_mint_with_program_id(context, mint, mint_authority, supply, &spl_token_2022::id()).await;
+}
+
+pub async fn setup_mint_with_program_id(
+    context: &mut ProgramTestContext,
+    mint: &Pubkey,
+    mint_authority: &Pubkey,
+    supply: u64,
+    token_program_id: &Pubkey,
+) {
+    setup_mint_with_optional_authority(
+        context,
+        mint,
+        COption::Some(*mint_authority),
+        supply,
+        token_program_id,
+    )
+    .await;
+}
+
+/// Sets up a mint without a mint authority, whose supply is fixed.
+pub async fn setup_fixed_supply_mint(context: &mut ProgramTestContext, mint: &Pubkey, supply: u64) {
+    setup_mint_with_optional_authority(context, mint, COption::None, supply, &spl_token_2022::id())
+        .await;
+}
+
+async fn setup_mint_with_optional_authority(
+    context: &mut ProgramTestContext,
+    mint: &Pubkey,
+    mint_authority: COption<Pubkey>,
+    supply: u64,
+    token_program_id: &Pubkey,
 ) {
     let account_size = ExtensionType::try_calculate_account_len::<Mint>(&[]).unwrap();
 
@@ -41,7 +86,7 @@ pub async fn setup_mint(
     {
         let mut state = StateWithExtensionsMut::<Mint>::unpack_uninitialized(&mut data).unwrap();
         state.base = Mint {
-            mint_authority: COption::Some(*mint_authority),
+            mint_authority,
             is_initialized: true,
             supply,
             ..Mint::default()
@@ -55,7 +100,7 @@ pub async fn setup_mint(
         &AccountSharedData::from(Account {
             lamports,
             data,
-            owner: spl_token_2022::id(),
+            owner: *token_program_id,
             ..Account::default()
         }),
     );
@@ -67,6 +112,25 @@ pub async fn setup_token_account(
     owner: &Pubkey,
     mint: &Pubkey,
     amount: u64,
+) {
+    setup_token_account_with_program_id(
+        context,
+        token_account,
+        owner,
+        mint,
+        amount,
+        &spl_token_2022::id(),
+    )
+    .await;
+}
+
+pub async fn setup_token_account_with_program_id(
+    context: &mut ProgramTestContext,
+    token_account: &Pubkey,
+    owner: &Pubkey,
+    mint: &Pubkey,
+    amount: u64,
+    token_program_id: &Pubkey,
 ) {
     let account_size = ExtensionType::try_calculate_account_len::<TokenAccount>(&[]).unwrap();
 
@@ -88,6 +152,114 @@ pub async fn setup_token_account(
         state.init_account_type().unwrap();
     }
 
+    context.set_account(
+        token_account,
+        &AccountSharedData::from(Account {
+            lamports,
+            data,
+            owner: *token_program_id,
+            ..Account::default()
+        }),
+    );
+}
+
+pub async fn setup_mint_with_transfer_fee(
+    context: &mut ProgramTestContext,
+    mint: &Pubkey,
+    mint_authority: &Pubkey,
+    supply: u64,
+    transfer_fee_basis_points: u16,
+    maximum_fee: u64,
+) {
+    let transfer_fee = TransferFee {
+        epoch: 0.into(),
+        maximum_fee: maximum_fee.into(),
+        transfer_fee_basis_points: transfer_fee_basis_points.into(),
+    };
+    setup_mint_with_extension(
+        context,
+        mint,
+        mint_authority,
+        supply,
+        TransferFeeConfig {
+            older_transfer_fee: transfer_fee,
+            newer_transfer_fee: transfer_fee,
+            ..TransferFeeConfig::default()
+        },
+    )
+    .await;
+}
+
+pub async fn setup_mint_with_extension<V: Extension + Pod + Default>(
+    context: &mut ProgramTestContext,
+    mint: &Pubkey,
+    mint_authority: &Pubkey,
+    supply: u64,
+    extension: V,
+) {
+    let account_size = ExtensionType::try_calculate_account_len::<Mint>(&[V::TYPE]).unwrap();
+
+    let rent = context.banks_client.get_rent().await.unwrap();
+    let lamports = rent.minimum_balance(account_size);
+
+    let mut data = vec![0; account_size];
+    {
+        let mut state = StateWithExtensionsMut::<Mint>::unpack_uninitialized(&mut data).unwrap();
+        *state.init_extension::<V>(true).unwrap() = extension;
+        state.base = Mint {
+            mint_authority: COption::Some(*mint_authority),
+            is_initialized: true,
+            supply,
+            ..Mint::default()
+        };
+        state.pack_base();
+        state.init_account_type().unwrap();
+    }
+
+    context.set_account(
+        mint,
+        &AccountSharedData::from(Account {
+            lamports,
+            data,
+            owner: spl_token_2022::id(),
+            ..Account::default()
+        }),
+    );
+}
+
+/// Sets up a token account for a mint with a transfer fee, which must be able
+/// to hold withheld fees.
+pub async fn setup_transfer_fee_token_account(
+    context: &mut ProgramTestContext,
+    token_account: &Pubkey,
+    owner: &Pubkey,
+    mint: &Pubkey,
+    amount: u64,
+) {
+    let account_size = ExtensionType::try_calculate_account_len::<TokenAccount>(&[
+        ExtensionType::TransferFeeAmount,
+    ])
+    .unwrap();
+
+    let rent = context.banks_client.get_rent().await.unwrap();
+    let lamports = rent.minimum_balance(account_size);
+
+    let mut data = vec![0; account_size];
+    {
+        let mut state =
+            StateWithExtensionsMut::<TokenAccount>::unpack_uninitialized(&mut data).unwrap();
+        state.init_extension::<TransferFeeAmount>(true).unwrap();
+        state.base = TokenAccount {
+            amount,
+            mint: *mint,
+            owner: *owner,
+            state: AccountState::Initialized,
+            ..TokenAccount::default()
+        };
+        state.pack_base();
+        state.init_account_type().unwrap();
+    }
+
     context.set_account(
         token_account,
         &AccountSharedData::from(Account {
@@ -114,15 +286,99 @@ pub async fn setup_system_account(
     );
 }
 
-pub async fn setup_lockup_pool(context: &mut ProgramTestContext, address: &Pubkey, mint: Pubkey) {
-    // Setup lockup pool account data.
-    let state = LockupPool {
+/// Builds enough `InitializeLockupPool` instructions to grow the pool to its
+/// initial size and initialize it.
+pub fn initialize_lockup_pool_instructions(
+    payer: &Pubkey,
+    mint: Pubkey,
+    pool_id: u64,
+    cooldown_seconds: u64,
+    metadata_update_interval_seconds: u64,
+    pool_authority: Option<&Pubkey>,
+    isolated_escrow: bool,
+) -> Vec<Instruction> {
+    let instruction = paladin_lockup_program::instruction::initialize_lockup_pool(
+        payer,
+        mint,
+        pool_id,
+        cooldown_seconds,
+        metadata_update_interval_seconds,
+        pool_authority,
+        isolated_escrow,
+    );
+
+    let pool_len = LockupPool::account_len(LockupPool::INITIAL_CAPACITY);
+    vec![instruction; pool_len.div_ceil(MAX_PERMITTED_DATA_INCREASE)]
+}
+
+/// Builds an initialized lockup pool header with the default configuration.
+pub fn lockup_pool_state(mint: Pubkey) -> LockupPool {
+    LockupPool {
         discriminator: LockupPool::SPL_DISCRIMINATOR.into(),
         mint,
-        entries: [LockupPoolEntry::default(); 1024],
+        cooldown_seconds: LOCKUP_COOLDOWN_SECONDS,
+        metadata_update_interval_seconds: 0,
+        authority: Pubkey::default(),
+        min_lockup_amount: 0,
+        flags: 0,
         entries_len: 0,
+        shards_len: 0,
+        shards: [LockupPoolShardInfo::default(); LockupPool::MAX_SHARDS],
+        stats: LockupPoolStats::default(),
+        rewards: LockupPoolRewards::default(),
+    }
+}
+
+/// Builds lockup pool account data with the provided capacity, holding the
+/// provided entries.
+pub fn lockup_pool_data(
+    state: &LockupPool,
+    entries: &[LockupPoolEntry],
+    capacity: usize,
+) -> Vec<u8> {
+    let mut data = vec![0; LockupPool::account_len(capacity)];
+    let pool = LockupPool::unpack_mut(&mut data).unwrap();
+    *pool.header = LockupPool {
+        entries_len: entries.len(),
+        ..*state
     };
-    let data = bytemuck::bytes_of(&state).to_vec();
+    pool.entries[..entries.len()].copy_from_slice(entries);
+    data
+}
+
+pub async fn setup_lockup_pool(context: &mut ProgramTestContext, address: &Pubkey, mint: Pubkey) {
+    setup_lockup_pool_with_entries(context, address, mint, &[]).await;
+}
+
+pub async fn setup_lockup_pool_with_entries(
+    context: &mut ProgramTestContext,
+    address: &Pubkey,
+    mint: Pubkey,
+    entries: &[LockupPoolEntry],
+) {
+    let data = lockup_pool_data(
+        &lockup_pool_state(mint),
+        entries,
+        LockupPool::INITIAL_CAPACITY,
+    );
+    setup_lockup_pool_with_data(context, address, data).await;
+}
+
+pub async fn setup_lockup_pool_with_state(
+    context: &mut ProgramTestContext,
+    address: &Pubkey,
+    state: &LockupPool,
+) {
+    let data = lockup_pool_data(state, &[], LockupPool::INITIAL_CAPACITY);
+    setup_lockup_pool_with_data(context, address, data).await;
+}
+
+pub async fn setup_lockup_pool_with_data(
+    context: &mut ProgramTestContext,
+    address: &Pubkey,
+    data: Vec<u8>,
+) {
+    // Setup lockup pool account data.
     let rent = context.banks_client.get_rent().await.unwrap();
     let lamports = rent.minimum_balance(data.len());
 
@@ -137,6 +393,24 @@ pub async fn setup_lockup_pool(context: &mut ProgramTestContext, address: &Pubke
     );
 }
 
+pub async fn setup_lockup_pool_shard(
+    context: &mut ProgramTestContext,
+    address: &Pubkey,
+    pool: &Pubkey,
+    entries: &[LockupPoolEntry],
+    capacity: usize,
+) {
+    let mut data = vec![0; LockupPoolShard::account_len(capacity)];
+    let shard = LockupPoolShard::unpack_mut(&mut data).unwrap();
+    *shard.header = LockupPoolShard {
+        discriminator: LockupPoolShard::SPL_DISCRIMINATOR.into(),
+        pool: *pool,
+        entries_len: entries.len(),
+    };
+    shard.entries[..entries.len()].copy_from_slice(entries);
+    setup_lockup_pool_with_data(context, address, data).await;
+}
+
 pub async fn setup_lockup(context: &mut ProgramTestContext, address: &Pubkey, state: Lockup) {
     let data = bytemuck::bytes_of(&state).to_vec();
     let rent = context.banks_client.get_rent().await.unwrap();
@@ -0,0 +1,341 @@
+#![cfg(feature = "test-sbf")]
+
+mod setup;
+
+use {
+    paladin_lockup_program::{
+        error::PaladinLockupError,
+        state::{
+            get_lockup_pool_snapshot_address, LockupPool, LockupPoolEntry, LockupPoolShardInfo,
+            LockupPoolSnapshot,
+        },
+    },
+    setup::{
+        lockup_pool_data, lockup_pool_state, setup, setup_lockup_pool_shard,
+        setup_lockup_pool_with_data, setup_system_account,
+    },
+    solana_program_test::*,
+    solana_sdk::{
+        clock::Clock,
+        entrypoint::MAX_PERMITTED_DATA_INCREASE,
+        instruction::{Instruction, InstructionError},
+        pubkey::Pubkey,
+        rent::Rent,
+        signature::Keypair,
+        signer::Signer,
+        transaction::{Transaction, TransactionError},
+    },
+    test_case::test_case,
+};
+
+fn pool_entries(amounts: impl Iterator<Item = u64>) -> Vec<LockupPoolEntry> {
+    amounts
+        .map(|amount| LockupPoolEntry {
+            lockup: Pubkey::new_unique(),
+            amount,
+            metadata: Pubkey::new_unique().to_bytes(),
+        })
+        .collect()
+}
+
+/// Builds enough `SnapshotPool` instructions to grow the snapshot to fit the
+/// provided number of entries and take it.
+fn snapshot_pool_instructions(
+    payer: &Pubkey,
+    pool: Pubkey,
+    pool_state: &LockupPool,
+    epoch: u64,
+    entries_len: usize,
+) -> Vec<Instruction> {
+    let instruction =
+        paladin_lockup_program::instruction::snapshot_pool(payer, pool, pool_state, epoch);
+
+    vec![
+        instruction;
+        LockupPoolSnapshot::account_len(entries_len).div_ceil(MAX_PERMITTED_DATA_INCREASE)
+    ]
+}
+
+/// Sets up a lockup pool holding the provided entries, split across the pool
+/// itself and a shard holding those below the provided amount (if any).
+async fn setup_pool(
+    context: &mut ProgramTestContext,
+    pool: &Pubkey,
+    entries: &[LockupPoolEntry],
+    shard_max_amount: Option<u64>,
+) -> LockupPool {
+    let mut state = lockup_pool_state(Pubkey::new_unique());
+    let split = match shard_max_amount {
+        Some(max_amount) => {
+            let shard = Pubkey::new_unique();
+            state.shards[0] = LockupPoolShardInfo {
+                address: shard,
+                max_amount,
+            };
+            state.shards_len = 1;
+
+            let split = entries
+                .iter()
+                .position(|entry| entry.amount < max_amount)
+                .unwrap_or(entries.len());
+            setup_lockup_pool_shard(context, &shard, pool, &entries[split..], entries.len()).await;
+            split
+        }
+        None => entries.len(),
+    };
+    let data = lockup_pool_data(&state, &entries[..split], LockupPool::INITIAL_CAPACITY);
+    setup_lockup_pool_with_data(context, pool, data).await;
+
+    state
+}
+
+#[tokio::test]
+async fn fail_incorrect_snapshot_address() {
+    let mut context = setup().start_with_context().await;
+    let clock = context.banks_client.get_sysvar::<Clock>().await.unwrap();
+
+    let pool = Pubkey::new_unique();
+    let state = setup_pool(&mut context, &pool, &[], None).await;
+
+    let instruction = paladin_lockup_program::instruction::snapshot_pool(
+        &context.payer.pubkey(),
+        pool,
+        &state,
+        clock.epoch + 1, // Incorrect epoch.
+    );
+
+    let transaction = Transaction::new_signed_with_payer(
+        &[instruction],
+        Some(&context.payer.pubkey()),
+        &[&context.payer],
+        context.last_blockhash,
+    );
+
+    let err = context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap_err()
+        .unwrap();
+
+    assert_eq!(
+        err,
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(PaladinLockupError::IncorrectLockupPoolSnapshotAddress as u32)
+        )
+    );
+}
+
+#[tokio::test]
+async fn fail_missing_shard() {
+    let mut context = setup().start_with_context().await;
+    let clock = context.banks_client.get_sysvar::<Clock>().await.unwrap();
+
+    let pool = Pubkey::new_unique();
+    let entries = pool_entries([500, 400, 300].into_iter());
+    setup_pool(&mut context, &pool, &entries, Some(350)).await;
+
+    let instruction = paladin_lockup_program::instruction::snapshot_pool(
+        &context.payer.pubkey(),
+        pool,
+        &lockup_pool_state(Pubkey::new_unique()), // Without the shard.
+        clock.epoch,
+    );
+
+    let transaction = Transaction::new_signed_with_payer(
+        &[instruction],
+        Some(&context.payer.pubkey()),
+        &[&context.payer],
+        context.last_blockhash,
+    );
+
+    let err = context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap_err()
+        .unwrap();
+
+    assert_eq!(
+        err,
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(PaladinLockupError::MissingLockupPoolShard as u32)
+        )
+    );
+}
+
+#[tokio::test]
+async fn fail_snapshot_already_taken() {
+    let mut context = setup().start_with_context().await;
+    let clock = context.banks_client.get_sysvar::<Clock>().await.unwrap();
+
+    let pool = Pubkey::new_unique();
+    let entries = pool_entries([500, 400, 300].into_iter());
+    let state = setup_pool(&mut context, &pool, &entries, None).await;
+
+    let instruction = paladin_lockup_program::instruction::snapshot_pool(
+        &context.payer.pubkey(),
+        pool,
+        &state,
+        clock.epoch,
+    );
+
+    let transaction = Transaction::new_signed_with_payer(
+        std::slice::from_ref(&instruction),
+        Some(&context.payer.pubkey()),
+        &[&context.payer],
+        context.last_blockhash,
+    );
+
+    context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap();
+
+    // Snapshot the pool again, within the same epoch.
+    let blockhash = context.get_new_latest_blockhash().await.unwrap();
+    let transaction = Transaction::new_signed_with_payer(
+        &[instruction],
+        Some(&context.payer.pubkey()),
+        &[&context.payer],
+        blockhash,
+    );
+
+    let err = context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap_err()
+        .unwrap();
+
+    assert_eq!(
+        err,
+        TransactionError::InstructionError(0, InstructionError::AccountAlreadyInitialized)
+    );
+}
+
+#[test_case(0, None; "empty")]
+#[test_case(10, None; "single_instruction")]
+#[test_case(LockupPool::INITIAL_CAPACITY, None; "multiple_instructions")]
+#[test_case(10, Some(5); "sharded")]
+#[tokio::test]
+async fn success(entries_len: usize, shard_max_amount: Option<u64>) {
+    let mut context = setup().start_with_context().await;
+    let clock = context.banks_client.get_sysvar::<Clock>().await.unwrap();
+
+    let pool = Pubkey::new_unique();
+    let entries = pool_entries((0..entries_len as u64).rev());
+    let state = setup_pool(&mut context, &pool, &entries, shard_max_amount).await;
+
+    let transaction = Transaction::new_signed_with_payer(
+        &snapshot_pool_instructions(
+            &context.payer.pubkey(),
+            pool,
+            &state,
+            clock.epoch,
+            entries_len,
+        ),
+        Some(&context.payer.pubkey()),
+        &[&context.payer],
+        context.last_blockhash,
+    );
+
+    context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap();
+
+    // Check the snapshot account.
+    let snapshot =
+        get_lockup_pool_snapshot_address(&pool, clock.epoch, &paladin_lockup_program::id());
+    let account = context
+        .banks_client
+        .get_account(snapshot)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(account.owner, paladin_lockup_program::id());
+    assert_eq!(
+        account.lamports,
+        Rent::default().minimum_balance(LockupPoolSnapshot::account_len(entries_len))
+    );
+
+    let (snapshot_state, snapshot_entries) = LockupPoolSnapshot::unpack(&account.data).unwrap();
+    assert_eq!(snapshot_state.pool, pool);
+    assert_eq!(snapshot_state.rent_payer, context.payer.pubkey());
+    assert_eq!(snapshot_state.slot, clock.slot);
+    assert_eq!(snapshot_state.epoch, clock.epoch);
+    assert_eq!(snapshot_state.timestamp, clock.unix_timestamp as u64);
+    assert_eq!(snapshot_state.entries_len, entries_len);
+    assert_eq!(snapshot_entries, entries);
+}
+
+#[tokio::test]
+async fn success_completed_by_another_payer() {
+    let mut context = setup().start_with_context().await;
+    let clock = context.banks_client.get_sysvar::<Clock>().await.unwrap();
+
+    let pool = Pubkey::new_unique();
+    let entries_len = LockupPool::INITIAL_CAPACITY;
+    let entries = pool_entries((0..entries_len as u64).rev());
+    let state = setup_pool(&mut context, &pool, &entries, None).await;
+
+    // Start the snapshot, without completing it.
+    let starter = Keypair::new();
+    setup_system_account(&mut context, &starter.pubkey(), 1_000_000_000).await;
+    let transaction = Transaction::new_signed_with_payer(
+        &[paladin_lockup_program::instruction::snapshot_pool(
+            &starter.pubkey(),
+            pool,
+            &state,
+            clock.epoch,
+        )],
+        Some(&context.payer.pubkey()),
+        &[&context.payer, &starter],
+        context.last_blockhash,
+    );
+    context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap();
+
+    // Complete it with another payer.
+    let mut instructions = snapshot_pool_instructions(
+        &context.payer.pubkey(),
+        pool,
+        &state,
+        clock.epoch,
+        entries_len,
+    );
+    instructions.pop();
+    let transaction = Transaction::new_signed_with_payer(
+        &instructions,
+        Some(&context.payer.pubkey()),
+        &[&context.payer],
+        context.last_blockhash,
+    );
+    context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap();
+
+    // The snapshot was taken, with whoever started it as its rent payer.
+    let snapshot =
+        get_lockup_pool_snapshot_address(&pool, clock.epoch, &paladin_lockup_program::id());
+    let account = context
+        .banks_client
+        .get_account(snapshot)
+        .await
+        .unwrap()
+        .unwrap();
+    let (snapshot_state, snapshot_entries) = LockupPoolSnapshot::unpack(&account.data).unwrap();
+    assert_eq!(snapshot_state.rent_payer, starter.pubkey());
+    assert_eq!(snapshot_state.entries_len, entries_len);
+    assert_eq!(snapshot_entries, entries);
+}
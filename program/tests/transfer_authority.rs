@@ -0,0 +1,321 @@
+#![cfg(feature = "test-sbf")]
+
+mod setup;
+
+use {
+    paladin_lockup_program::{
+        error::PaladinLockupError,
+        state::{Lockup, PodU128, VestingSchedule},
+    },
+    setup::{lockup_pool_state, setup, setup_lockup, setup_lockup_pool},
+    solana_program_test::*,
+    solana_sdk::{
+        account::{Account, AccountSharedData},
+        instruction::{Instruction, InstructionError},
+        pubkey::Pubkey,
+        rent::Rent,
+        signature::Keypair,
+        signer::Signer,
+        transaction::{Transaction, TransactionError},
+    },
+    spl_discriminator::SplDiscriminate,
+};
+
+async fn send_transaction(
+    context: &mut ProgramTestContext,
+    instructions: &[Instruction],
+    signers: &[&Keypair],
+) -> Result<(), BanksClientError> {
+    let blockhash = context.banks_client.get_latest_blockhash().await.unwrap();
+    let transaction = Transaction::new_signed_with_payer(
+        instructions,
+        Some(&context.payer.pubkey()),
+        signers,
+        blockhash,
+    );
+    context.banks_client.process_transaction(transaction).await
+}
+
+async fn get_lockup_state(context: &mut ProgramTestContext, lockup: &Pubkey) -> Lockup {
+    let account = context
+        .banks_client
+        .get_account(*lockup)
+        .await
+        .unwrap()
+        .unwrap();
+    *bytemuck::from_bytes::<Lockup>(&account.data)
+}
+
+#[tokio::test]
+async fn fail_propose_incorrect_lockup_authority() {
+    let mut context = setup().start_with_context().await;
+
+    let authority = Keypair::new();
+    let lockup = Pubkey::new_unique();
+
+    setup_lockup(
+        &mut context,
+        &lockup,
+        Lockup {
+            discriminator: Lockup::SPL_DISCRIMINATOR.into(),
+            amount: 10_000,
+            authority: Pubkey::new_unique(), // Incorrect authority.
+            lockup_start_timestamp: 10,
+            lockup_end_timestamp: None,
+            mint: Pubkey::new_unique(),
+            pool: Pubkey::new_unique(),
+            metadata: Pubkey::new_unique().to_bytes(),
+            proposed_authority: Pubkey::default(),
+            committed_end_timestamp: None,
+            vesting: VestingSchedule::default(),
+            withdrawn_amount: 0,
+            metadata_updated_timestamp: 0,
+            flags: 0,
+            reward_per_token_checkpoint: PodU128::default(),
+            unclaimed_rewards: 0,
+            cooldown_end_timestamp: None,
+        },
+    )
+    .await;
+
+    let instruction = paladin_lockup_program::instruction::propose_authority(
+        &authority.pubkey(),
+        &lockup,
+        &Pubkey::new_unique(),
+    );
+    let payer = context.payer.insecure_clone();
+
+    let err = send_transaction(&mut context, &[instruction], &[&payer, &authority])
+        .await
+        .unwrap_err()
+        .unwrap();
+
+    assert_eq!(
+        err,
+        TransactionError::InstructionError(0, InstructionError::IncorrectAuthority)
+    );
+}
+
+#[tokio::test]
+async fn fail_propose_legacy_lockup() {
+    let mut context = setup().start_with_context().await;
+
+    let authority = Keypair::new();
+    let lockup = Pubkey::new_unique();
+
+    // Legacy lockups end before the proposed authority.
+    let state = Lockup {
+        discriminator: Lockup::SPL_DISCRIMINATOR.into(),
+        amount: 10_000,
+        authority: authority.pubkey(),
+        lockup_start_timestamp: 10,
+        lockup_end_timestamp: None,
+        mint: Pubkey::new_unique(),
+        pool: Pubkey::new_unique(),
+        metadata: Pubkey::new_unique().to_bytes(),
+        proposed_authority: Pubkey::default(),
+        committed_end_timestamp: None,
+        vesting: VestingSchedule::default(),
+        withdrawn_amount: 0,
+        metadata_updated_timestamp: 0,
+        flags: 0,
+        reward_per_token_checkpoint: PodU128::default(),
+        unclaimed_rewards: 0,
+        cooldown_end_timestamp: None,
+    };
+    let data = bytemuck::bytes_of(&state)[..Lockup::LEGACY_LEN].to_vec();
+    context.set_account(
+        &lockup,
+        &AccountSharedData::from(Account {
+            lamports: Rent::default().minimum_balance(data.len()),
+            data,
+            owner: paladin_lockup_program::id(),
+            ..Account::default()
+        }),
+    );
+
+    let instruction = paladin_lockup_program::instruction::propose_authority(
+        &authority.pubkey(),
+        &lockup,
+        &Pubkey::new_unique(),
+    );
+    let payer = context.payer.insecure_clone();
+
+    let err = send_transaction(&mut context, &[instruction], &[&payer, &authority])
+        .await
+        .unwrap_err()
+        .unwrap();
+
+    assert_eq!(
+        err,
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(PaladinLockupError::LockupNotMigrated as u32)
+        )
+    );
+}
+
+#[tokio::test]
+async fn fail_accept_not_proposed_authority() {
+    let mut context = setup().start_with_context().await;
+
+    let authority = Keypair::new();
+    let proposed_authority = Keypair::new();
+    let lockup = Pubkey::new_unique();
+
+    setup_lockup(
+        &mut context,
+        &lockup,
+        Lockup {
+            discriminator: Lockup::SPL_DISCRIMINATOR.into(),
+            amount: 10_000,
+            authority: authority.pubkey(),
+            lockup_start_timestamp: 10,
+            lockup_end_timestamp: None,
+            mint: Pubkey::new_unique(),
+            pool: Pubkey::new_unique(),
+            metadata: Pubkey::new_unique().to_bytes(),
+            proposed_authority: Pubkey::new_unique(), // Someone else was proposed.
+            committed_end_timestamp: None,
+            vesting: VestingSchedule::default(),
+            withdrawn_amount: 0,
+            metadata_updated_timestamp: 0,
+            flags: 0,
+            reward_per_token_checkpoint: PodU128::default(),
+            unclaimed_rewards: 0,
+            cooldown_end_timestamp: None,
+        },
+    )
+    .await;
+
+    let instruction = paladin_lockup_program::instruction::accept_authority(
+        &proposed_authority.pubkey(),
+        &lockup,
+    );
+    let payer = context.payer.insecure_clone();
+
+    let err = send_transaction(&mut context, &[instruction], &[&payer, &proposed_authority])
+        .await
+        .unwrap_err()
+        .unwrap();
+
+    assert_eq!(
+        err,
+        TransactionError::InstructionError(0, InstructionError::IncorrectAuthority)
+    );
+}
+
+#[tokio::test]
+async fn success() {
+    let mut context = setup().start_with_context().await;
+
+    let authority = Keypair::new();
+    let proposed_authority = Keypair::new();
+    let lockup = Pubkey::new_unique();
+    let mint = Pubkey::new_unique();
+    let payer = context.payer.insecure_clone();
+
+    // Create the lockup pool account.
+    let pool = Pubkey::new_unique();
+    setup_lockup_pool(&mut context, &pool, mint).await;
+
+    let state = Lockup {
+        discriminator: Lockup::SPL_DISCRIMINATOR.into(),
+        amount: 10_000,
+        authority: authority.pubkey(),
+        lockup_start_timestamp: 10,
+        lockup_end_timestamp: None,
+        mint,
+        pool,
+        metadata: Pubkey::new_unique().to_bytes(),
+        proposed_authority: Pubkey::default(),
+        committed_end_timestamp: None,
+        vesting: VestingSchedule::default(),
+        withdrawn_amount: 0,
+        metadata_updated_timestamp: 0,
+        flags: 0,
+        reward_per_token_checkpoint: PodU128::default(),
+        unclaimed_rewards: 0,
+        cooldown_end_timestamp: None,
+    };
+    setup_lockup(&mut context, &lockup, state).await;
+
+    // Propose the new authority.
+    send_transaction(
+        &mut context,
+        &[paladin_lockup_program::instruction::propose_authority(
+            &authority.pubkey(),
+            &lockup,
+            &proposed_authority.pubkey(),
+        )],
+        &[&payer, &authority],
+    )
+    .await
+    .unwrap();
+    assert_eq!(
+        get_lockup_state(&mut context, &lockup).await,
+        Lockup {
+            proposed_authority: proposed_authority.pubkey(),
+            ..state
+        }
+    );
+
+    // The proposed authority cannot unlock before accepting.
+    let err = send_transaction(
+        &mut context,
+        &[paladin_lockup_program::instruction::unlock(
+            &proposed_authority.pubkey(),
+            pool,
+            &lockup_pool_state(mint),
+            &lockup,
+            10_000,
+        )],
+        &[&payer, &proposed_authority],
+    )
+    .await
+    .unwrap_err()
+    .unwrap();
+    assert_eq!(
+        err,
+        TransactionError::InstructionError(0, InstructionError::IncorrectAuthority)
+    );
+
+    // Accept the new authority.
+    send_transaction(
+        &mut context,
+        &[paladin_lockup_program::instruction::accept_authority(
+            &proposed_authority.pubkey(),
+            &lockup,
+        )],
+        &[&payer, &proposed_authority],
+    )
+    .await
+    .unwrap();
+    assert_eq!(
+        get_lockup_state(&mut context, &lockup).await,
+        Lockup {
+            authority: proposed_authority.pubkey(),
+            ..state
+        }
+    );
+
+    // The previous authority can no longer unlock.
+    let err = send_transaction(
+        &mut context,
+        &[paladin_lockup_program::instruction::unlock(
+            &authority.pubkey(),
+            pool,
+            &lockup_pool_state(mint),
+            &lockup,
+            10_000,
+        )],
+        &[&payer, &authority],
+    )
+    .await
+    .unwrap_err()
+    .unwrap();
+    assert_eq!(
+        err,
+        TransactionError::InstructionError(0, InstructionError::IncorrectAuthority)
+    );
+}
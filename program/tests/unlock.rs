@@ -3,8 +3,18 @@
 mod setup;
 
 use {
-    paladin_lockup_program::{error::PaladinLockupError, state::Lockup},
-    setup::{setup, setup_lockup, setup_lockup_pool},
+    paladin_lockup_program::{
+        error::PaladinLockupError,
+        state::{
+            Lockup, LockupPool, LockupPoolEntry, LockupPoolShard, LockupPoolShardInfo,
+            LockupPoolStats, PodU128, VestingSchedule,
+        },
+        LOCKUP_COOLDOWN_SECONDS,
+    },
+    setup::{
+        lockup_pool_state, setup, setup_lockup, setup_lockup_pool, setup_lockup_pool_shard,
+        setup_lockup_pool_with_state,
+    },
     solana_program_test::*,
     solana_sdk::{
         account::AccountSharedData,
@@ -17,6 +27,7 @@ use {
     },
     spl_discriminator::SplDiscriminate,
     std::num::NonZeroU64,
+    test_case::test_case,
 };
 
 #[tokio::test]
@@ -31,8 +42,13 @@ async fn fail_unlock_authority_not_signer() {
     let pool = Pubkey::new_unique();
     setup_lockup_pool(&mut context, &pool, mint).await;
 
-    let mut instruction =
-        paladin_lockup_program::instruction::unlock(&authority.pubkey(), pool, &lockup);
+    let mut instruction = paladin_lockup_program::instruction::unlock(
+        &authority.pubkey(),
+        pool,
+        &lockup_pool_state(mint),
+        &lockup,
+        10_000,
+    );
     instruction.accounts[0].is_signer = false;
 
     let transaction = Transaction::new_signed_with_payer(
@@ -78,8 +94,13 @@ async fn fail_unlock_not_enough_space() {
         );
     }
 
-    let instruction =
-        paladin_lockup_program::instruction::unlock(&authority.pubkey(), pool, &lockup);
+    let instruction = paladin_lockup_program::instruction::unlock(
+        &authority.pubkey(),
+        pool,
+        &lockup_pool_state(mint),
+        &lockup,
+        10_000,
+    );
 
     let transaction = Transaction::new_signed_with_payer(
         &[instruction],
@@ -124,8 +145,13 @@ async fn fail_unlock_uninitialized() {
         );
     }
 
-    let instruction =
-        paladin_lockup_program::instruction::unlock(&authority.pubkey(), pool, &lockup);
+    let instruction = paladin_lockup_program::instruction::unlock(
+        &authority.pubkey(),
+        pool,
+        &lockup_pool_state(mint),
+        &lockup,
+        10_000,
+    );
 
     let transaction = Transaction::new_signed_with_payer(
         &[instruction],
@@ -171,12 +197,26 @@ async fn fail_incorrect_lockup_authority() {
             mint: Pubkey::new_unique(),
             pool,
             metadata: Pubkey::new_unique().to_bytes(),
+            proposed_authority: Pubkey::default(),
+            committed_end_timestamp: None,
+            vesting: VestingSchedule::default(),
+            withdrawn_amount: 0,
+            metadata_updated_timestamp: 0,
+            flags: 0,
+            reward_per_token_checkpoint: PodU128::default(),
+            unclaimed_rewards: 0,
+            cooldown_end_timestamp: None,
         },
     )
     .await;
 
-    let instruction =
-        paladin_lockup_program::instruction::unlock(&authority.pubkey(), pool, &lockup);
+    let instruction = paladin_lockup_program::instruction::unlock(
+        &authority.pubkey(),
+        pool,
+        &lockup_pool_state(mint),
+        &lockup,
+        10_000,
+    );
 
     let transaction = Transaction::new_signed_with_payer(
         &[instruction],
@@ -226,12 +266,26 @@ async fn fail_unlock_already_unlocked() {
             mint: Pubkey::new_unique(),
             pool,
             metadata: Pubkey::new_unique().to_bytes(),
+            proposed_authority: Pubkey::default(),
+            committed_end_timestamp: None,
+            vesting: VestingSchedule::default(),
+            withdrawn_amount: 0,
+            metadata_updated_timestamp: 0,
+            flags: 0,
+            reward_per_token_checkpoint: PodU128::default(),
+            unclaimed_rewards: 0,
+            cooldown_end_timestamp: None,
         },
     )
     .await;
 
-    let instruction =
-        paladin_lockup_program::instruction::unlock(&authority.pubkey(), pool, &lockup);
+    let instruction = paladin_lockup_program::instruction::unlock(
+        &authority.pubkey(),
+        pool,
+        &lockup_pool_state(mint),
+        &lockup,
+        10_000,
+    );
 
     let transaction = Transaction::new_signed_with_payer(
         &[instruction],
@@ -282,12 +336,26 @@ async fn fail_unlock_incorrect_pool() {
             mint: Pubkey::new_unique(),
             pool: pool1,
             metadata: Pubkey::new_unique().to_bytes(),
+            proposed_authority: Pubkey::default(),
+            committed_end_timestamp: None,
+            vesting: VestingSchedule::default(),
+            withdrawn_amount: 0,
+            metadata_updated_timestamp: 0,
+            flags: 0,
+            reward_per_token_checkpoint: PodU128::default(),
+            unclaimed_rewards: 0,
+            cooldown_end_timestamp: None,
         },
     )
     .await;
 
-    let instruction =
-        paladin_lockup_program::instruction::unlock(&authority.pubkey(), pool2, &lockup);
+    let instruction = paladin_lockup_program::instruction::unlock(
+        &authority.pubkey(),
+        pool2,
+        &lockup_pool_state(mint),
+        &lockup,
+        10_000,
+    );
 
     let transaction = Transaction::new_signed_with_payer(
         &[instruction],
@@ -313,7 +381,83 @@ async fn fail_unlock_incorrect_pool() {
 }
 
 #[tokio::test]
-async fn success() {
+async fn fail_unlocks_paused() {
+    let mut context = setup().start_with_context().await;
+
+    let authority = Keypair::new();
+    let lockup = Pubkey::new_unique();
+    let mint = Pubkey::new_unique();
+
+    // Create the lockup pool account with unlocks paused.
+    let pool = Pubkey::new_unique();
+    setup_lockup_pool_with_state(
+        &mut context,
+        &pool,
+        &LockupPool {
+            flags: LockupPool::FLAG_UNLOCK_PAUSED,
+            ..lockup_pool_state(mint)
+        },
+    )
+    .await;
+
+    setup_lockup(
+        &mut context,
+        &lockup,
+        Lockup {
+            discriminator: Lockup::SPL_DISCRIMINATOR.into(),
+            amount: 10_000,
+            authority: authority.pubkey(),
+            lockup_start_timestamp: 10,
+            lockup_end_timestamp: None,
+            mint,
+            pool,
+            metadata: Pubkey::new_unique().to_bytes(),
+            proposed_authority: Pubkey::default(),
+            committed_end_timestamp: None,
+            vesting: VestingSchedule::default(),
+            withdrawn_amount: 0,
+            metadata_updated_timestamp: 0,
+            flags: 0,
+            reward_per_token_checkpoint: PodU128::default(),
+            unclaimed_rewards: 0,
+            cooldown_end_timestamp: None,
+        },
+    )
+    .await;
+
+    let instruction = paladin_lockup_program::instruction::unlock(
+        &authority.pubkey(),
+        pool,
+        &lockup_pool_state(mint),
+        &lockup,
+        10_000,
+    );
+
+    let transaction = Transaction::new_signed_with_payer(
+        &[instruction],
+        Some(&context.payer.pubkey()),
+        &[&context.payer, &authority],
+        context.last_blockhash,
+    );
+
+    let err = context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap_err()
+        .unwrap();
+
+    assert_eq!(
+        err,
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(PaladinLockupError::PoolPaused as u32)
+        )
+    );
+}
+
+#[tokio::test]
+async fn fail_unlock_lockup_term_active() {
     let mut context = setup().start_with_context().await;
 
     let authority = Keypair::new();
@@ -338,12 +482,110 @@ async fn success() {
             mint: Pubkey::new_unique(),
             pool,
             metadata: Pubkey::new_unique().to_bytes(),
+            proposed_authority: Pubkey::default(),
+            committed_end_timestamp: NonZeroU64::new(start.saturating_add(1)), // Term not ended.
+            vesting: VestingSchedule::default(),
+            withdrawn_amount: 0,
+            metadata_updated_timestamp: 0,
+            flags: 0,
+            reward_per_token_checkpoint: PodU128::default(),
+            unclaimed_rewards: 0,
+            cooldown_end_timestamp: None,
+        },
+    )
+    .await;
+
+    let instruction = paladin_lockup_program::instruction::unlock(
+        &authority.pubkey(),
+        pool,
+        &lockup_pool_state(mint),
+        &lockup,
+        10_000,
+    );
+
+    let transaction = Transaction::new_signed_with_payer(
+        &[instruction],
+        Some(&context.payer.pubkey()),
+        &[&context.payer, &authority],
+        context.last_blockhash,
+    );
+
+    let err = context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap_err()
+        .unwrap();
+
+    assert_eq!(
+        err,
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(PaladinLockupError::LockupTermActive as u32)
+        )
+    );
+}
+
+#[test_case(false; "open_ended")]
+#[test_case(true; "term_ended")]
+#[tokio::test]
+async fn success(committed: bool) {
+    let mut context = setup().start_with_context().await;
+
+    let authority = Keypair::new();
+    let lockup = Pubkey::new_unique();
+    let mint = Pubkey::new_unique();
+    let clock = context.banks_client.get_sysvar::<Clock>().await.unwrap();
+    let start = clock.unix_timestamp as u64;
+
+    // Create the lockup pool account.
+    let pool = Pubkey::new_unique();
+    setup_lockup_pool_with_state(
+        &mut context,
+        &pool,
+        &LockupPool {
+            stats: LockupPoolStats {
+                active_amount: 10_000,
+                active_lockups: 1,
+                ..LockupPoolStats::default()
+            },
+            ..lockup_pool_state(mint)
+        },
+    )
+    .await;
+
+    setup_lockup(
+        &mut context,
+        &lockup,
+        Lockup {
+            discriminator: Lockup::SPL_DISCRIMINATOR.into(),
+            amount: 10_000,
+            authority: authority.pubkey(),
+            lockup_start_timestamp: start,
+            lockup_end_timestamp: None,
+            mint: Pubkey::new_unique(),
+            pool,
+            metadata: Pubkey::new_unique().to_bytes(),
+            proposed_authority: Pubkey::default(),
+            committed_end_timestamp: committed.then(|| NonZeroU64::new(start).unwrap()),
+            vesting: VestingSchedule::default(),
+            withdrawn_amount: 0,
+            metadata_updated_timestamp: 0,
+            flags: 0,
+            reward_per_token_checkpoint: PodU128::default(),
+            unclaimed_rewards: 0,
+            cooldown_end_timestamp: None,
         },
     )
     .await;
 
-    let instruction =
-        paladin_lockup_program::instruction::unlock(&authority.pubkey(), pool, &lockup);
+    let instruction = paladin_lockup_program::instruction::unlock(
+        &authority.pubkey(),
+        pool,
+        &lockup_pool_state(mint),
+        &lockup,
+        10_000,
+    );
 
     let transaction = Transaction::new_signed_with_payer(
         &[instruction],
@@ -367,4 +609,123 @@ async fn success() {
         .unwrap();
     let state = bytemuck::from_bytes::<Lockup>(&lockup_account.data);
     assert_eq!(state.lockup_end_timestamp.unwrap().get(), start);
+    assert_eq!(
+        state.cooldown_end_timestamp.unwrap().get(),
+        start + LOCKUP_COOLDOWN_SECONDS
+    );
+
+    // Check the pool's stats.
+    let lockup_pool = context
+        .banks_client
+        .get_account(pool)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(
+        LockupPool::unpack(&lockup_pool.data).unwrap().0.stats,
+        LockupPoolStats {
+            unlocking_amount: 10_000,
+            ..LockupPoolStats::default()
+        }
+    );
+}
+
+#[test_case(false; "shard_not_provided")]
+#[test_case(true; "shard_provided")]
+#[tokio::test]
+async fn sharded_pool(provide_shard: bool) {
+    let mut context = setup().start_with_context().await;
+
+    let authority = Keypair::new();
+    let lockup = Pubkey::new_unique();
+    let mint = Pubkey::new_unique();
+    let clock = context.banks_client.get_sysvar::<Clock>().await.unwrap();
+    let start = clock.unix_timestamp as u64;
+
+    let pool = Pubkey::new_unique();
+    let state = Lockup {
+        discriminator: Lockup::SPL_DISCRIMINATOR.into(),
+        amount: 10_000,
+        authority: authority.pubkey(),
+        lockup_start_timestamp: start,
+        lockup_end_timestamp: None,
+        mint,
+        pool,
+        metadata: Pubkey::new_unique().to_bytes(),
+        proposed_authority: Pubkey::default(),
+        committed_end_timestamp: None,
+        vesting: VestingSchedule::default(),
+        withdrawn_amount: 0,
+        metadata_updated_timestamp: 0,
+        flags: Lockup::FLAG_IN_POOL,
+        reward_per_token_checkpoint: PodU128::default(),
+        unclaimed_rewards: 0,
+        cooldown_end_timestamp: None,
+    };
+
+    // Create a lockup pool with a shard holding the lockup's entry.
+    let shard = Pubkey::new_unique();
+    let mut pool_state = lockup_pool_state(mint);
+    pool_state.shards[0] = LockupPoolShardInfo {
+        address: shard,
+        max_amount: 1_000_000,
+    };
+    pool_state.shards_len = 1;
+    setup_lockup_pool_with_state(&mut context, &pool, &pool_state).await;
+    setup_lockup_pool_shard(
+        &mut context,
+        &shard,
+        &pool,
+        &[LockupPoolEntry {
+            lockup,
+            amount: state.amount,
+            metadata: state.metadata,
+        }],
+        8,
+    )
+    .await;
+
+    setup_lockup(&mut context, &lockup, state).await;
+
+    let mut instruction = paladin_lockup_program::instruction::unlock(
+        &authority.pubkey(),
+        pool,
+        &pool_state,
+        &lockup,
+        state.amount,
+    );
+    if !provide_shard {
+        instruction.accounts.pop();
+    }
+
+    let transaction = Transaction::new_signed_with_payer(
+        &[instruction],
+        Some(&context.payer.pubkey()),
+        &[&context.payer, &authority],
+        context.last_blockhash,
+    );
+
+    let result = context.banks_client.process_transaction(transaction).await;
+
+    if !provide_shard {
+        assert_eq!(
+            result.unwrap_err().unwrap(),
+            TransactionError::InstructionError(
+                0,
+                InstructionError::Custom(PaladinLockupError::MissingLockupPoolShard as u32)
+            )
+        );
+        return;
+    }
+    result.unwrap();
+
+    // The entry was removed from the shard.
+    let shard_account = context
+        .banks_client
+        .get_account(shard)
+        .await
+        .unwrap()
+        .unwrap();
+    let (shard_state, _) = LockupPoolShard::unpack(&shard_account.data).unwrap();
+    assert_eq!(shard_state.entries_len, 0);
 }
@@ -0,0 +1,404 @@
+#![cfg(feature = "test-sbf")]
+
+mod setup;
+
+use {
+    paladin_lockup_program::{
+        error::PaladinLockupError,
+        state::{
+            Lockup, LockupPool, LockupPoolEntry, LockupPoolShardInfo, PodU128, VestingSchedule,
+        },
+    },
+    setup::{
+        add_seconds_to_clock, lockup_pool_state, setup, setup_lockup, setup_lockup_pool,
+        setup_lockup_pool_with_entries, setup_lockup_pool_with_state,
+    },
+    solana_program_test::*,
+    solana_sdk::{
+        clock::Clock,
+        instruction::{Instruction, InstructionError},
+        pubkey::Pubkey,
+        signature::Keypair,
+        signer::Signer,
+        transaction::{Transaction, TransactionError},
+    },
+    spl_discriminator::SplDiscriminate,
+    std::num::NonZeroU64,
+    test_case::test_case,
+};
+
+async fn send_transaction(
+    context: &mut ProgramTestContext,
+    instructions: &[Instruction],
+    signers: &[&Keypair],
+) -> Result<(), BanksClientError> {
+    let blockhash = context.banks_client.get_latest_blockhash().await.unwrap();
+    let transaction = Transaction::new_signed_with_payer(
+        instructions,
+        Some(&context.payer.pubkey()),
+        signers,
+        blockhash,
+    );
+    context.banks_client.process_transaction(transaction).await
+}
+
+async fn get_lockup_state(context: &mut ProgramTestContext, lockup: &Pubkey) -> Lockup {
+    let account = context
+        .banks_client
+        .get_account(*lockup)
+        .await
+        .unwrap()
+        .unwrap();
+    *bytemuck::from_bytes::<Lockup>(&account.data)
+}
+
+fn lockup_state(authority: Pubkey, mint: Pubkey, pool: Pubkey) -> Lockup {
+    Lockup {
+        discriminator: Lockup::SPL_DISCRIMINATOR.into(),
+        amount: 10_000,
+        authority,
+        lockup_start_timestamp: 10,
+        lockup_end_timestamp: None,
+        mint,
+        pool,
+        metadata: Pubkey::new_unique().to_bytes(),
+        proposed_authority: Pubkey::default(),
+        committed_end_timestamp: None,
+        vesting: VestingSchedule::default(),
+        withdrawn_amount: 0,
+        metadata_updated_timestamp: 0,
+        flags: 0,
+        reward_per_token_checkpoint: PodU128::default(),
+        unclaimed_rewards: 0,
+        cooldown_end_timestamp: None,
+    }
+}
+
+#[tokio::test]
+async fn fail_incorrect_lockup_authority() {
+    let mut context = setup().start_with_context().await;
+
+    let authority = Keypair::new();
+    let lockup = Pubkey::new_unique();
+    let mint = Pubkey::new_unique();
+
+    // Create the lockup pool account.
+    let pool = Pubkey::new_unique();
+    setup_lockup_pool(&mut context, &pool, mint).await;
+
+    setup_lockup(
+        &mut context,
+        &lockup,
+        lockup_state(Pubkey::new_unique(), mint, pool), // Incorrect authority.
+    )
+    .await;
+
+    let instruction = paladin_lockup_program::instruction::update_metadata(
+        &authority.pubkey(),
+        pool,
+        &lockup_pool_state(mint),
+        &lockup,
+        10_000,
+        Pubkey::new_unique().to_bytes(),
+    );
+    let payer = context.payer.insecure_clone();
+
+    let err = send_transaction(&mut context, &[instruction], &[&payer, &authority])
+        .await
+        .unwrap_err()
+        .unwrap();
+
+    assert_eq!(
+        err,
+        TransactionError::InstructionError(0, InstructionError::IncorrectAuthority)
+    );
+}
+
+#[tokio::test]
+async fn fail_incorrect_pool() {
+    let mut context = setup().start_with_context().await;
+
+    let authority = Keypair::new();
+    let lockup = Pubkey::new_unique();
+    let mint = Pubkey::new_unique();
+
+    // Create the lockup pool account.
+    let pool = Pubkey::new_unique();
+    setup_lockup_pool(&mut context, &pool, mint).await;
+
+    setup_lockup(
+        &mut context,
+        &lockup,
+        lockup_state(authority.pubkey(), mint, Pubkey::new_unique()), // Incorrect pool.
+    )
+    .await;
+
+    let instruction = paladin_lockup_program::instruction::update_metadata(
+        &authority.pubkey(),
+        pool,
+        &lockup_pool_state(mint),
+        &lockup,
+        10_000,
+        Pubkey::new_unique().to_bytes(),
+    );
+    let payer = context.payer.insecure_clone();
+
+    let err = send_transaction(&mut context, &[instruction], &[&payer, &authority])
+        .await
+        .unwrap_err()
+        .unwrap();
+
+    assert_eq!(
+        err,
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(PaladinLockupError::IncorrectPool as u32)
+        )
+    );
+}
+
+#[tokio::test]
+async fn fail_update_too_soon() {
+    let mut context = setup().start_with_context().await;
+
+    let authority = Keypair::new();
+    let lockup = Pubkey::new_unique();
+    let mint = Pubkey::new_unique();
+    let payer = context.payer.insecure_clone();
+
+    // Create the lockup pool account with a metadata update interval.
+    let pool = Pubkey::new_unique();
+    setup_lockup_pool_with_state(
+        &mut context,
+        &pool,
+        &LockupPool {
+            metadata_update_interval_seconds: 3_600,
+            ..lockup_pool_state(mint)
+        },
+    )
+    .await;
+
+    setup_lockup(
+        &mut context,
+        &lockup,
+        lockup_state(authority.pubkey(), mint, pool),
+    )
+    .await;
+
+    // The first update is allowed.
+    send_transaction(
+        &mut context,
+        &[paladin_lockup_program::instruction::update_metadata(
+            &authority.pubkey(),
+            pool,
+            &lockup_pool_state(mint),
+            &lockup,
+            10_000,
+            Pubkey::new_unique().to_bytes(),
+        )],
+        &[&payer, &authority],
+    )
+    .await
+    .unwrap();
+
+    // A second update within the interval is rejected.
+    add_seconds_to_clock(&mut context, 3_599).await;
+    let err = send_transaction(
+        &mut context,
+        &[paladin_lockup_program::instruction::update_metadata(
+            &authority.pubkey(),
+            pool,
+            &lockup_pool_state(mint),
+            &lockup,
+            10_000,
+            Pubkey::new_unique().to_bytes(),
+        )],
+        &[&payer, &authority],
+    )
+    .await
+    .unwrap_err()
+    .unwrap();
+    assert_eq!(
+        err,
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(PaladinLockupError::MetadataUpdateTooSoon as u32)
+        )
+    );
+
+    // Once the interval has passed, the update is allowed again.
+    add_seconds_to_clock(&mut context, 1).await;
+    send_transaction(
+        &mut context,
+        &[paladin_lockup_program::instruction::update_metadata(
+            &authority.pubkey(),
+            pool,
+            &lockup_pool_state(mint),
+            &lockup,
+            10_000,
+            Pubkey::new_unique().to_bytes(),
+        )],
+        &[&payer, &authority],
+    )
+    .await
+    .unwrap();
+}
+
+#[test_case(true; "in_pool")]
+#[test_case(false; "unlocked")]
+#[tokio::test]
+async fn success(in_pool: bool) {
+    let mut context = setup().start_with_context().await;
+    let clock = context.banks_client.get_sysvar::<Clock>().await.unwrap();
+
+    let authority = Keypair::new();
+    let lockup = Pubkey::new_unique();
+    let mint = Pubkey::new_unique();
+    let metadata = Pubkey::new_unique().to_bytes();
+    let payer = context.payer.insecure_clone();
+
+    let state = lockup_state(authority.pubkey(), mint, Pubkey::default());
+    let state = if in_pool {
+        Lockup {
+            flags: Lockup::FLAG_IN_POOL,
+            ..state
+        }
+    } else {
+        Lockup {
+            lockup_end_timestamp: NonZeroU64::new(20), // Unlocked.
+            ..state
+        }
+    };
+
+    // Create the lockup pool account.
+    let pool = Pubkey::new_unique();
+    let other_entry = LockupPoolEntry {
+        lockup: Pubkey::new_unique(),
+        amount: 5_000,
+        metadata: Pubkey::new_unique().to_bytes(),
+    };
+    let entries = if in_pool {
+        vec![
+            LockupPoolEntry {
+                lockup,
+                amount: state.amount,
+                metadata: state.metadata,
+            },
+            other_entry,
+        ]
+    } else {
+        vec![other_entry]
+    };
+    setup_lockup_pool_with_entries(&mut context, &pool, mint, &entries).await;
+
+    let state = Lockup { pool, ..state };
+    setup_lockup(&mut context, &lockup, state).await;
+
+    send_transaction(
+        &mut context,
+        &[paladin_lockup_program::instruction::update_metadata(
+            &authority.pubkey(),
+            pool,
+            &lockup_pool_state(mint),
+            &lockup,
+            5_000,
+            metadata,
+        )],
+        &[&payer, &authority],
+    )
+    .await
+    .unwrap();
+
+    // The lockup's metadata was updated.
+    assert_eq!(
+        get_lockup_state(&mut context, &lockup).await,
+        Lockup {
+            metadata,
+            metadata_updated_timestamp: clock.unix_timestamp as u64,
+            ..state
+        }
+    );
+
+    // The pool entry was updated, if the lockup was in the pool.
+    let lockup_pool = context
+        .banks_client
+        .get_account(pool)
+        .await
+        .unwrap()
+        .unwrap();
+    let (lockup_pool, pool_entries) = LockupPool::unpack(&lockup_pool.data).unwrap();
+    if in_pool {
+        assert_eq!(lockup_pool.entries_len, 2);
+        assert_eq!(
+            pool_entries[0],
+            LockupPoolEntry {
+                lockup,
+                amount: state.amount,
+                metadata,
+            }
+        );
+        assert_eq!(pool_entries[1], other_entry);
+    } else {
+        assert_eq!(lockup_pool.entries_len, 1);
+        assert_eq!(pool_entries[0], other_entry);
+    }
+}
+
+#[tokio::test]
+async fn success_not_in_pool_without_shard() {
+    let mut context = setup().start_with_context().await;
+
+    let authority = Keypair::new();
+    let lockup = Pubkey::new_unique();
+    let mint = Pubkey::new_unique();
+    let metadata = Pubkey::new_unique().to_bytes();
+    let payer = context.payer.insecure_clone();
+
+    // Create a sharded lockup pool account, whose shard covering the lockup's
+    // amount is not provided.
+    let pool = Pubkey::new_unique();
+    let mut shards = [LockupPoolShardInfo::default(); LockupPool::MAX_SHARDS];
+    shards[0] = LockupPoolShardInfo {
+        address: Pubkey::new_unique(),
+        max_amount: 1_000_000,
+    };
+    setup_lockup_pool_with_state(
+        &mut context,
+        &pool,
+        &LockupPool {
+            shards_len: 1,
+            shards,
+            ..lockup_pool_state(mint)
+        },
+    )
+    .await;
+
+    // The lockup was evicted from the pool.
+    let state = lockup_state(authority.pubkey(), mint, pool);
+    setup_lockup(&mut context, &lockup, state).await;
+
+    send_transaction(
+        &mut context,
+        &[paladin_lockup_program::instruction::update_metadata(
+            &authority.pubkey(),
+            pool,
+            &lockup_pool_state(mint),
+            &lockup,
+            state.amount,
+            metadata,
+        )],
+        &[&payer, &authority],
+    )
+    .await
+    .unwrap();
+
+    let clock = context.banks_client.get_sysvar::<Clock>().await.unwrap();
+    assert_eq!(
+        get_lockup_state(&mut context, &lockup).await,
+        Lockup {
+            metadata,
+            metadata_updated_timestamp: clock.unix_timestamp as u64,
+            ..state
+        }
+    );
+}
@@ -0,0 +1,274 @@
+#![cfg(feature = "test-sbf")]
+
+mod setup;
+
+use {
+    paladin_lockup_program::{
+        error::PaladinLockupError, state::LockupPool, LOCKUP_COOLDOWN_SECONDS,
+        MAX_LOCKUP_COOLDOWN_SECONDS, MAX_METADATA_UPDATE_INTERVAL_SECONDS,
+    },
+    setup::{lockup_pool_state, setup, setup_lockup_pool_with_state},
+    solana_program_test::*,
+    solana_sdk::{
+        instruction::InstructionError,
+        pubkey::Pubkey,
+        signature::Keypair,
+        signer::Signer,
+        transaction::{Transaction, TransactionError},
+    },
+    test_case::test_case,
+};
+
+async fn get_lockup_pool_state(context: &mut ProgramTestContext, pool: &Pubkey) -> LockupPool {
+    let account = context
+        .banks_client
+        .get_account(*pool)
+        .await
+        .unwrap()
+        .unwrap();
+    *LockupPool::unpack(&account.data).unwrap().0
+}
+
+#[tokio::test]
+async fn fail_authority_not_signer() {
+    let mut context = setup().start_with_context().await;
+
+    let pool_authority = Keypair::new();
+    let mint = Pubkey::new_unique();
+
+    // Create the lockup pool account.
+    let pool = Pubkey::new_unique();
+    setup_lockup_pool_with_state(
+        &mut context,
+        &pool,
+        &LockupPool {
+            authority: pool_authority.pubkey(),
+            ..lockup_pool_state(mint)
+        },
+    )
+    .await;
+
+    let mut instruction = paladin_lockup_program::instruction::update_pool_config(
+        &pool_authority.pubkey(),
+        pool,
+        Some(1_000),
+        None,
+        None,
+        None,
+        None,
+    );
+    instruction.accounts[0].is_signer = false; // Not signer.
+
+    let transaction = Transaction::new_signed_with_payer(
+        &[instruction],
+        Some(&context.payer.pubkey()),
+        &[&context.payer],
+        context.last_blockhash,
+    );
+
+    let err = context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap_err()
+        .unwrap();
+
+    assert_eq!(
+        err,
+        TransactionError::InstructionError(0, InstructionError::MissingRequiredSignature)
+    );
+}
+
+#[test_case(Pubkey::new_unique(); "incorrect_authority")]
+#[test_case(Pubkey::default(); "no_authority")]
+#[tokio::test]
+async fn fail_incorrect_authority(authority: Pubkey) {
+    let mut context = setup().start_with_context().await;
+
+    let pool_authority = Keypair::new();
+    let mint = Pubkey::new_unique();
+
+    // Create the lockup pool account.
+    let pool = Pubkey::new_unique();
+    setup_lockup_pool_with_state(
+        &mut context,
+        &pool,
+        &LockupPool {
+            authority,
+            ..lockup_pool_state(mint)
+        },
+    )
+    .await;
+
+    let instruction = paladin_lockup_program::instruction::update_pool_config(
+        &pool_authority.pubkey(),
+        pool,
+        Some(1_000),
+        None,
+        None,
+        None,
+        None,
+    );
+
+    let transaction = Transaction::new_signed_with_payer(
+        &[instruction],
+        Some(&context.payer.pubkey()),
+        &[&context.payer, &pool_authority],
+        context.last_blockhash,
+    );
+
+    let err = context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap_err()
+        .unwrap();
+
+    assert_eq!(
+        err,
+        TransactionError::InstructionError(0, InstructionError::IncorrectAuthority)
+    );
+}
+
+#[test_case(Some(MAX_LOCKUP_COOLDOWN_SECONDS + 1), None; "cooldown_too_long")]
+#[test_case(
+    None,
+    Some(MAX_METADATA_UPDATE_INTERVAL_SECONDS + 1);
+    "metadata_update_interval_too_long"
+)]
+#[tokio::test]
+async fn fail_invalid_pool_config(
+    cooldown_seconds: Option<u64>,
+    metadata_update_interval_seconds: Option<u64>,
+) {
+    let mut context = setup().start_with_context().await;
+
+    let pool_authority = Keypair::new();
+    let mint = Pubkey::new_unique();
+
+    // Create the lockup pool account.
+    let pool = Pubkey::new_unique();
+    setup_lockup_pool_with_state(
+        &mut context,
+        &pool,
+        &LockupPool {
+            authority: pool_authority.pubkey(),
+            ..lockup_pool_state(mint)
+        },
+    )
+    .await;
+
+    let instruction = paladin_lockup_program::instruction::update_pool_config(
+        &pool_authority.pubkey(),
+        pool,
+        None,
+        cooldown_seconds,
+        metadata_update_interval_seconds,
+        None,
+        None,
+    );
+
+    let transaction = Transaction::new_signed_with_payer(
+        &[instruction],
+        Some(&context.payer.pubkey()),
+        &[&context.payer, &pool_authority],
+        context.last_blockhash,
+    );
+
+    let err = context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap_err()
+        .unwrap();
+
+    assert_eq!(
+        err,
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(PaladinLockupError::InvalidPoolConfig as u32)
+        )
+    );
+}
+
+#[tokio::test]
+async fn success() {
+    let mut context = setup().start_with_context().await;
+
+    let pool_authority = Keypair::new();
+    let new_pool_authority = Pubkey::new_unique();
+    let mint = Pubkey::new_unique();
+
+    // Create the lockup pool account.
+    let pool = Pubkey::new_unique();
+    let state = LockupPool {
+        authority: pool_authority.pubkey(),
+        metadata_update_interval_seconds: 60,
+        ..lockup_pool_state(mint)
+    };
+    setup_lockup_pool_with_state(&mut context, &pool, &state).await;
+
+    // Update some of the settings.
+    let instruction = paladin_lockup_program::instruction::update_pool_config(
+        &pool_authority.pubkey(),
+        pool,
+        Some(1_000),
+        Some(2 * LOCKUP_COOLDOWN_SECONDS),
+        None,
+        Some(false),
+        None,
+    );
+    let transaction = Transaction::new_signed_with_payer(
+        &[instruction],
+        Some(&context.payer.pubkey()),
+        &[&context.payer, &pool_authority],
+        context.last_blockhash,
+    );
+    context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap();
+
+    // Only the provided settings were changed.
+    let state = LockupPool {
+        min_lockup_amount: 1_000,
+        cooldown_seconds: 2 * LOCKUP_COOLDOWN_SECONDS,
+        flags: LockupPool::FLAG_LOCKUPS_DISABLED,
+        ..state
+    };
+    assert_eq!(get_lockup_pool_state(&mut context, &pool).await, state);
+    assert!(!state.lockups_enabled());
+
+    // Re-enable lockups & hand over the authority.
+    let instruction = paladin_lockup_program::instruction::update_pool_config(
+        &pool_authority.pubkey(),
+        pool,
+        None,
+        None,
+        Some(0),
+        Some(true),
+        Some(new_pool_authority),
+    );
+    let transaction = Transaction::new_signed_with_payer(
+        &[instruction],
+        Some(&context.payer.pubkey()),
+        &[&context.payer, &pool_authority],
+        context.last_blockhash,
+    );
+    context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap();
+
+    assert_eq!(
+        get_lockup_pool_state(&mut context, &pool).await,
+        LockupPool {
+            metadata_update_interval_seconds: 0,
+            flags: 0,
+            authority: new_pool_authority,
+            ..state
+        }
+    );
+}
@@ -0,0 +1,264 @@
+#![cfg(feature = "test-sbf")]
+
+mod setup;
+
+use {
+    paladin_lockup_program::{
+        error::PaladinLockupError,
+        merkle::{build_proof, compute_root},
+        state::{
+            get_lockup_pool_root_address, LockupPool, LockupPoolEntry, LockupPoolRoot,
+            LockupPoolShardInfo,
+        },
+    },
+    setup::{
+        lockup_pool_data, lockup_pool_state, setup, setup_lockup_pool_shard,
+        setup_lockup_pool_with_data,
+    },
+    solana_program_test::*,
+    solana_sdk::{
+        clock::Clock,
+        instruction::InstructionError,
+        pubkey::Pubkey,
+        rent::Rent,
+        signer::Signer,
+        transaction::{Transaction, TransactionError},
+    },
+    test_case::test_case,
+};
+
+fn pool_entries(amounts: impl Iterator<Item = u64>) -> Vec<LockupPoolEntry> {
+    amounts
+        .map(|amount| LockupPoolEntry {
+            lockup: Pubkey::new_unique(),
+            amount,
+            metadata: Pubkey::new_unique().to_bytes(),
+        })
+        .collect()
+}
+
+/// Sets up a lockup pool holding the provided entries, split across the pool
+/// itself and a shard holding those below the provided amount (if any).
+async fn setup_pool(
+    context: &mut ProgramTestContext,
+    pool: &Pubkey,
+    entries: &[LockupPoolEntry],
+    shard_max_amount: Option<u64>,
+) -> LockupPool {
+    let mut state = lockup_pool_state(Pubkey::new_unique());
+    let split = match shard_max_amount {
+        Some(max_amount) => {
+            let shard = Pubkey::new_unique();
+            state.shards[0] = LockupPoolShardInfo {
+                address: shard,
+                max_amount,
+            };
+            state.shards_len = 1;
+
+            let split = entries
+                .iter()
+                .position(|entry| entry.amount < max_amount)
+                .unwrap_or(entries.len());
+            setup_lockup_pool_shard(context, &shard, pool, &entries[split..], entries.len()).await;
+            split
+        }
+        None => entries.len(),
+    };
+    let data = lockup_pool_data(&state, &entries[..split], LockupPool::INITIAL_CAPACITY);
+    setup_lockup_pool_with_data(context, pool, data).await;
+
+    state
+}
+
+async fn get_lockup_pool_root(context: &mut ProgramTestContext, pool: &Pubkey) -> LockupPoolRoot {
+    let root = get_lockup_pool_root_address(pool, &paladin_lockup_program::id());
+    let account = context
+        .banks_client
+        .get_account(root)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(account.owner, paladin_lockup_program::id());
+    assert_eq!(
+        account.lamports,
+        Rent::default().minimum_balance(LockupPoolRoot::LEN)
+    );
+    *bytemuck::from_bytes::<LockupPoolRoot>(&account.data)
+}
+
+#[tokio::test]
+async fn fail_incorrect_root_address() {
+    let mut context = setup().start_with_context().await;
+
+    let pool = Pubkey::new_unique();
+    let state = setup_pool(&mut context, &pool, &[], None).await;
+
+    let mut instruction = paladin_lockup_program::instruction::update_pool_root(
+        &context.payer.pubkey(),
+        pool,
+        &state,
+    );
+    instruction.accounts[2].pubkey = Pubkey::new_unique(); // Incorrect root address.
+
+    let transaction = Transaction::new_signed_with_payer(
+        &[instruction],
+        Some(&context.payer.pubkey()),
+        &[&context.payer],
+        context.last_blockhash,
+    );
+
+    let err = context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap_err()
+        .unwrap();
+
+    assert_eq!(
+        err,
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(PaladinLockupError::IncorrectLockupPoolRootAddress as u32)
+        )
+    );
+}
+
+#[tokio::test]
+async fn fail_missing_shard() {
+    let mut context = setup().start_with_context().await;
+
+    let pool = Pubkey::new_unique();
+    let entries = pool_entries([500, 400, 300].into_iter());
+    setup_pool(&mut context, &pool, &entries, Some(350)).await;
+
+    let instruction = paladin_lockup_program::instruction::update_pool_root(
+        &context.payer.pubkey(),
+        pool,
+        &lockup_pool_state(Pubkey::new_unique()), // Without the shard.
+    );
+
+    let transaction = Transaction::new_signed_with_payer(
+        &[instruction],
+        Some(&context.payer.pubkey()),
+        &[&context.payer],
+        context.last_blockhash,
+    );
+
+    let err = context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap_err()
+        .unwrap();
+
+    assert_eq!(
+        err,
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(PaladinLockupError::MissingLockupPoolShard as u32)
+        )
+    );
+}
+
+#[test_case(0, None; "empty")]
+#[test_case(10, None; "unsharded")]
+#[test_case(10, Some(5); "sharded")]
+#[tokio::test]
+async fn success(entries_len: usize, shard_max_amount: Option<u64>) {
+    let mut context = setup().start_with_context().await;
+    let clock = context.banks_client.get_sysvar::<Clock>().await.unwrap();
+
+    let pool = Pubkey::new_unique();
+    let entries = pool_entries((0..entries_len as u64).rev());
+    let state = setup_pool(&mut context, &pool, &entries, shard_max_amount).await;
+
+    let instruction = paladin_lockup_program::instruction::update_pool_root(
+        &context.payer.pubkey(),
+        pool,
+        &state,
+    );
+
+    let transaction = Transaction::new_signed_with_payer(
+        &[instruction],
+        Some(&context.payer.pubkey()),
+        &[&context.payer],
+        context.last_blockhash,
+    );
+
+    context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap();
+
+    // Check the root account.
+    let root = get_lockup_pool_root(&mut context, &pool).await;
+    assert_eq!(root.pool, pool);
+    assert_eq!(root.slot, clock.slot);
+    assert_eq!(root.entries_len, entries_len);
+    assert_eq!(root.root, compute_root(&entries));
+
+    // Every entry can be proven against the root.
+    for (index, entry) in entries.iter().enumerate() {
+        let proof = build_proof(&entries, index).unwrap();
+        assert!(root.verify(index, entry, &proof));
+    }
+}
+
+#[tokio::test]
+async fn success_update() {
+    let mut context = setup().start_with_context().await;
+
+    let pool = Pubkey::new_unique();
+    let entries = pool_entries([500, 400, 300].into_iter());
+    let state = setup_pool(&mut context, &pool, &entries, None).await;
+
+    let instruction = paladin_lockup_program::instruction::update_pool_root(
+        &context.payer.pubkey(),
+        pool,
+        &state,
+    );
+
+    let transaction = Transaction::new_signed_with_payer(
+        &[instruction],
+        Some(&context.payer.pubkey()),
+        &[&context.payer],
+        context.last_blockhash,
+    );
+
+    context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap();
+
+    // Change the pool's entries and update the root.
+    let entries = pool_entries([600, 500].into_iter());
+    let data = lockup_pool_data(&state, &entries, LockupPool::INITIAL_CAPACITY);
+    setup_lockup_pool_with_data(&mut context, &pool, data).await;
+
+    let instruction = paladin_lockup_program::instruction::update_pool_root(
+        &context.payer.pubkey(),
+        pool,
+        &state,
+    );
+
+    let blockhash = context.get_new_latest_blockhash().await.unwrap();
+    let transaction = Transaction::new_signed_with_payer(
+        &[instruction],
+        Some(&context.payer.pubkey()),
+        &[&context.payer],
+        blockhash,
+    );
+
+    context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap();
+
+    // The root now commits to the new entries.
+    let root = get_lockup_pool_root(&mut context, &pool).await;
+    assert_eq!(root.entries_len, entries.len());
+    assert_eq!(root.root, compute_root(&entries));
+}
@@ -0,0 +1,573 @@
+#![cfg(feature = "test-sbf")]
+
+mod setup;
+
+use {
+    paladin_lockup_program::{
+        error::PaladinLockupError,
+        state::{
+            get_escrow_authority_address, Lockup, LockupPool, LockupPoolEntry, LockupPoolShard,
+            LockupPoolShardInfo, PodU128, VestingSchedule,
+        },
+    },
+    setup::{
+        add_seconds_to_clock, lockup_pool_data, lockup_pool_state, setup, setup_lockup,
+        setup_lockup_pool, setup_lockup_pool_shard, setup_lockup_pool_with_data,
+        setup_lockup_pool_with_entries, setup_mint, setup_token_account,
+    },
+    solana_program_test::*,
+    solana_sdk::{
+        account::Account,
+        clock::Clock,
+        instruction::{Instruction, InstructionError},
+        pubkey::Pubkey,
+        signature::Keypair,
+        signer::Signer,
+        transaction::{Transaction, TransactionError},
+    },
+    spl_associated_token_account::get_associated_token_address_with_program_id,
+    spl_discriminator::SplDiscriminate,
+    spl_token_2022::{extension::StateWithExtensions, state::Account as TokenAccount},
+    test_case::test_case,
+};
+
+async fn send_transaction(
+    context: &mut ProgramTestContext,
+    instructions: &[Instruction],
+    signers: &[&Keypair],
+) -> Result<(), BanksClientError> {
+    let blockhash = context.banks_client.get_latest_blockhash().await.unwrap();
+    let transaction = Transaction::new_signed_with_payer(
+        instructions,
+        Some(&context.payer.pubkey()),
+        signers,
+        blockhash,
+    );
+    context.banks_client.process_transaction(transaction).await
+}
+
+fn get_token_account_balance(token_account: &Account) -> u64 {
+    StateWithExtensions::<TokenAccount>::unpack(&token_account.data)
+        .unwrap()
+        .base
+        .amount
+}
+
+fn vesting_lockup_state(
+    authority: Pubkey,
+    mint: Pubkey,
+    pool: Pubkey,
+    amount: u64,
+    vesting: VestingSchedule,
+) -> Lockup {
+    Lockup {
+        discriminator: Lockup::SPL_DISCRIMINATOR.into(),
+        amount,
+        authority,
+        lockup_start_timestamp: vesting.start_timestamp,
+        lockup_end_timestamp: None,
+        mint,
+        pool,
+        metadata: Pubkey::new_unique().to_bytes(),
+        proposed_authority: Pubkey::default(),
+        committed_end_timestamp: None,
+        vesting,
+        withdrawn_amount: 0,
+        metadata_updated_timestamp: 0,
+        flags: 0,
+        reward_per_token_checkpoint: PodU128::default(),
+        unclaimed_rewards: 0,
+        cooldown_end_timestamp: None,
+    }
+}
+
+#[tokio::test]
+async fn fail_invalid_schedule() {
+    let mut context = setup().start_with_context().await;
+
+    let authority = Keypair::new();
+    let mint = Pubkey::new_unique();
+
+    let instruction = paladin_lockup_program::instruction::vesting_lockup(
+        &authority.pubkey(),
+        &authority.pubkey(),
+        &Pubkey::new_unique(),
+        Pubkey::new_unique(),
+        &lockup_pool_state(mint),
+        &[],
+        &Pubkey::new_unique(),
+        &mint,
+        Pubkey::new_unique().to_bytes(),
+        10_000,
+        VestingSchedule {
+            start_timestamp: 100,
+            cliff_timestamp: 300, // Cliff after the end.
+            end_timestamp: 200,
+            period_seconds: 0,
+        },
+        &spl_token_2022::id(),
+        false,
+    );
+    let payer = context.payer.insecure_clone();
+
+    let err = send_transaction(&mut context, &[instruction], &[&payer, &authority])
+        .await
+        .unwrap_err()
+        .unwrap();
+
+    assert_eq!(
+        err,
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(PaladinLockupError::InvalidVestingSchedule as u32)
+        )
+    );
+}
+
+#[tokio::test]
+async fn fail_unlock_vesting_lockup() {
+    let mut context = setup().start_with_context().await;
+
+    let authority = Keypair::new();
+    let lockup = Pubkey::new_unique();
+    let mint = Pubkey::new_unique();
+
+    // Create the lockup pool account.
+    let pool = Pubkey::new_unique();
+    setup_lockup_pool(&mut context, &pool, mint).await;
+
+    setup_lockup(
+        &mut context,
+        &lockup,
+        vesting_lockup_state(
+            authority.pubkey(),
+            mint,
+            pool,
+            10_000,
+            VestingSchedule {
+                start_timestamp: 100,
+                cliff_timestamp: 100,
+                end_timestamp: 200,
+                period_seconds: 0,
+            },
+        ),
+    )
+    .await;
+
+    let instruction = paladin_lockup_program::instruction::unlock(
+        &authority.pubkey(),
+        pool,
+        &lockup_pool_state(mint),
+        &lockup,
+        10_000,
+    );
+    let payer = context.payer.insecure_clone();
+
+    let err = send_transaction(&mut context, &[instruction], &[&payer, &authority])
+        .await
+        .unwrap_err()
+        .unwrap();
+
+    assert_eq!(
+        err,
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(PaladinLockupError::LockupVesting as u32)
+        )
+    );
+}
+
+#[tokio::test]
+async fn fail_withdraw_before_cliff() {
+    let mut context = setup().start_with_context().await;
+    let clock = context.banks_client.get_sysvar::<Clock>().await.unwrap();
+    let now = clock.unix_timestamp as u64;
+
+    let authority = Keypair::new();
+    let lockup = Pubkey::new_unique();
+    let mint = Pubkey::new_unique();
+    let token_account = get_associated_token_address_with_program_id(
+        &authority.pubkey(),
+        &mint,
+        &spl_token_2022::id(),
+    );
+
+    // Create the lockup pool account.
+    let pool = Pubkey::new_unique();
+    setup_lockup_pool(&mut context, &pool, mint).await;
+
+    setup_lockup(
+        &mut context,
+        &lockup,
+        vesting_lockup_state(
+            authority.pubkey(),
+            mint,
+            pool,
+            10_000,
+            VestingSchedule {
+                start_timestamp: now,
+                cliff_timestamp: now + 500, // Cliff not reached.
+                end_timestamp: now + 1_000,
+                period_seconds: 0,
+            },
+        ),
+    )
+    .await;
+    add_seconds_to_clock(&mut context, 100).await;
+
+    let instruction = paladin_lockup_program::instruction::withdraw(
+        &authority.pubkey(),
+        &authority.pubkey(),
+        &token_account,
+        &lockup,
+        10_000,
+        pool,
+        &lockup_pool_state(mint),
+        &[],
+        &mint,
+        &spl_token_2022::id(),
+        false,
+    );
+    let payer = context.payer.insecure_clone();
+
+    let err = send_transaction(&mut context, &[instruction], &[&payer, &authority])
+        .await
+        .unwrap_err()
+        .unwrap();
+
+    assert_eq!(
+        err,
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(PaladinLockupError::NothingVested as u32)
+        )
+    );
+}
+
+#[test_case(0, 4_500; "continuous")]
+#[test_case(200, 4_000; "periodic")]
+#[tokio::test]
+async fn success(period_seconds: u64, expected_first_withdrawal: u64) {
+    let mut context = setup().start_with_context().await;
+    let clock = context.banks_client.get_sysvar::<Clock>().await.unwrap();
+    let now = clock.unix_timestamp as u64;
+
+    let authority = Keypair::new();
+    let lamport_destination = Pubkey::new_unique();
+    let lockup = Pubkey::new_unique();
+    let mint = Pubkey::new_unique();
+    let token_account = get_associated_token_address_with_program_id(
+        &authority.pubkey(),
+        &mint,
+        &spl_token_2022::id(),
+    );
+    let escrow_authority = get_escrow_authority_address(&paladin_lockup_program::id());
+    let escrow_token_account = get_associated_token_address_with_program_id(
+        &escrow_authority,
+        &mint,
+        &spl_token_2022::id(),
+    );
+
+    let lockup_amount = 10_000;
+    let state = vesting_lockup_state(
+        authority.pubkey(),
+        mint,
+        Pubkey::default(),
+        lockup_amount,
+        VestingSchedule {
+            start_timestamp: now,
+            cliff_timestamp: now + 250,
+            end_timestamp: now + 1_000,
+            period_seconds,
+        },
+    );
+
+    // Create the lockup pool account, with another lockup ranked below ours.
+    let pool = Pubkey::new_unique();
+    let other_entry = LockupPoolEntry {
+        lockup: Pubkey::new_unique(),
+        amount: 7_000,
+        metadata: Pubkey::new_unique().to_bytes(),
+    };
+    setup_lockup_pool_with_entries(
+        &mut context,
+        &pool,
+        mint,
+        &[
+            LockupPoolEntry {
+                lockup,
+                amount: lockup_amount,
+                metadata: state.metadata,
+            },
+            other_entry,
+        ],
+    )
+    .await;
+
+    let state = Lockup {
+        pool,
+        flags: Lockup::FLAG_IN_POOL,
+        ..state
+    };
+    setup_lockup(&mut context, &lockup, state).await;
+    setup_token_account(&mut context, &token_account, &authority.pubkey(), &mint, 0).await;
+    setup_token_account(
+        &mut context,
+        &escrow_token_account,
+        &escrow_authority,
+        &mint,
+        lockup_amount,
+    )
+    .await;
+    setup_mint(&mut context, &mint, &Pubkey::new_unique(), 1_000_000).await;
+
+    let withdraw = paladin_lockup_program::instruction::withdraw(
+        &authority.pubkey(),
+        &lamport_destination,
+        &token_account,
+        &lockup,
+        lockup_amount,
+        pool,
+        &lockup_pool_state(mint),
+        &[],
+        &mint,
+        &spl_token_2022::id(),
+        false,
+    );
+    let payer = context.payer.insecure_clone();
+
+    // Withdraw part way through the schedule.
+    add_seconds_to_clock(&mut context, 450).await;
+    send_transaction(
+        &mut context,
+        std::slice::from_ref(&withdraw),
+        &[&payer, &authority],
+    )
+    .await
+    .unwrap();
+
+    // Only the vested portion was released, and the lockup remains open.
+    let token_account_balance = get_token_account_balance(
+        &context
+            .banks_client
+            .get_account(token_account)
+            .await
+            .unwrap()
+            .unwrap(),
+    );
+    assert_eq!(token_account_balance, expected_first_withdrawal);
+    let remaining_amount = lockup_amount - expected_first_withdrawal;
+    let lockup_account = context
+        .banks_client
+        .get_account(lockup)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(
+        bytemuck::from_bytes::<Lockup>(&lockup_account.data),
+        &Lockup {
+            amount: remaining_amount,
+            withdrawn_amount: expected_first_withdrawal,
+            ..state
+        }
+    );
+
+    // The pool entry was re-ranked to the remaining amount.
+    let lockup_pool = context
+        .banks_client
+        .get_account(pool)
+        .await
+        .unwrap()
+        .unwrap();
+    let (lockup_pool, pool_entries) = LockupPool::unpack(&lockup_pool.data).unwrap();
+    assert_eq!(lockup_pool.entries_len, 2);
+    assert_eq!(pool_entries[0], other_entry);
+    assert_eq!(
+        pool_entries[1],
+        LockupPoolEntry {
+            lockup,
+            amount: remaining_amount,
+            metadata: state.metadata,
+        }
+    );
+
+    // Withdraw the rest once the schedule has ended.
+    add_seconds_to_clock(&mut context, 1_000).await;
+    send_transaction(&mut context, &[withdraw], &[&payer, &authority])
+        .await
+        .unwrap();
+
+    // Everything was released and the lockup was closed.
+    let token_account_balance = get_token_account_balance(
+        &context
+            .banks_client
+            .get_account(token_account)
+            .await
+            .unwrap()
+            .unwrap(),
+    );
+    assert_eq!(token_account_balance, lockup_amount);
+    assert!(context
+        .banks_client
+        .get_account(lockup)
+        .await
+        .unwrap()
+        .is_none());
+
+    // The lockup was removed from the pool.
+    let lockup_pool = context
+        .banks_client
+        .get_account(pool)
+        .await
+        .unwrap()
+        .unwrap();
+    let (lockup_pool, pool_entries) = LockupPool::unpack(&lockup_pool.data).unwrap();
+    assert_eq!(lockup_pool.entries_len, 1);
+    assert_eq!(pool_entries[0], other_entry);
+}
+
+#[tokio::test]
+async fn success_drops_out_of_full_last_shard() {
+    let mut context = setup().start_with_context().await;
+    let clock = context.banks_client.get_sysvar::<Clock>().await.unwrap();
+    let now = clock.unix_timestamp as u64;
+
+    let authority = Keypair::new();
+    let lamport_destination = Pubkey::new_unique();
+    let lockup = Pubkey::new_unique();
+    let mint = Pubkey::new_unique();
+    let token_account = get_associated_token_address_with_program_id(
+        &authority.pubkey(),
+        &mint,
+        &spl_token_2022::id(),
+    );
+    let escrow_authority = get_escrow_authority_address(&paladin_lockup_program::id());
+    let escrow_token_account = get_associated_token_address_with_program_id(
+        &escrow_authority,
+        &mint,
+        &spl_token_2022::id(),
+    );
+
+    let lockup_amount = 10_000;
+    let pool = Pubkey::new_unique();
+    let state = Lockup {
+        flags: Lockup::FLAG_IN_POOL,
+        ..vesting_lockup_state(
+            authority.pubkey(),
+            mint,
+            pool,
+            lockup_amount,
+            VestingSchedule {
+                start_timestamp: now,
+                cliff_timestamp: now + 250,
+                end_timestamp: now + 1_000,
+                period_seconds: 0,
+            },
+        )
+    };
+
+    // Create the lockup pool account, holding the lockup above a full shard
+    // whose smallest entry outranks the lockup once partly withdrawn.
+    let shard = Pubkey::new_unique();
+    let shard_entry = LockupPoolEntry {
+        lockup: Pubkey::new_unique(),
+        amount: 7_000,
+        metadata: Pubkey::new_unique().to_bytes(),
+    };
+    let mut pool_state = lockup_pool_state(mint);
+    pool_state.shards[0] = LockupPoolShardInfo {
+        address: shard,
+        max_amount: 8_000,
+    };
+    pool_state.shards_len = 1;
+    let data = lockup_pool_data(
+        &pool_state,
+        &[LockupPoolEntry {
+            lockup,
+            amount: lockup_amount,
+            metadata: state.metadata,
+        }],
+        LockupPool::INITIAL_CAPACITY,
+    );
+    setup_lockup_pool_with_data(&mut context, &pool, data).await;
+    setup_lockup_pool_shard(&mut context, &shard, &pool, &[shard_entry], 1).await;
+
+    setup_lockup(&mut context, &lockup, state).await;
+    setup_token_account(&mut context, &token_account, &authority.pubkey(), &mint, 0).await;
+    setup_token_account(
+        &mut context,
+        &escrow_token_account,
+        &escrow_authority,
+        &mint,
+        lockup_amount,
+    )
+    .await;
+    setup_mint(&mut context, &mint, &Pubkey::new_unique(), 1_000_000).await;
+
+    let withdraw = paladin_lockup_program::instruction::withdraw(
+        &authority.pubkey(),
+        &lamport_destination,
+        &token_account,
+        &lockup,
+        lockup_amount,
+        pool,
+        &pool_state,
+        &[],
+        &mint,
+        &spl_token_2022::id(),
+        false,
+    );
+    let payer = context.payer.insecure_clone();
+
+    // Withdraw part way through the schedule.
+    add_seconds_to_clock(&mut context, 450).await;
+    send_transaction(&mut context, &[withdraw], &[&payer, &authority])
+        .await
+        .unwrap();
+
+    // The vested portion was released, and the lockup remains open, but left
+    // the pool.
+    let token_account_balance = get_token_account_balance(
+        &context
+            .banks_client
+            .get_account(token_account)
+            .await
+            .unwrap()
+            .unwrap(),
+    );
+    assert_eq!(token_account_balance, 4_500);
+    let lockup_account = context
+        .banks_client
+        .get_account(lockup)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(
+        bytemuck::from_bytes::<Lockup>(&lockup_account.data),
+        &Lockup {
+            amount: 5_500,
+            withdrawn_amount: 4_500,
+            flags: 0,
+            ..state
+        }
+    );
+
+    // Neither the pool nor the shard holds the lockup.
+    let lockup_pool = context
+        .banks_client
+        .get_account(pool)
+        .await
+        .unwrap()
+        .unwrap();
+    let (lockup_pool, _) = LockupPool::unpack(&lockup_pool.data).unwrap();
+    assert_eq!(lockup_pool.entries_len, 0);
+    let shard_account = context
+        .banks_client
+        .get_account(shard)
+        .await
+        .unwrap()
+        .unwrap();
+    let (shard_state, shard_entries) = LockupPoolShard::unpack(&shard_account.data).unwrap();
+    assert_eq!(shard_state.entries_len, 1);
+    assert_eq!(shard_entries[0], shard_entry);
+}
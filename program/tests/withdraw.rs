@@ -5,12 +5,18 @@ mod setup;
 use {
     paladin_lockup_program::{
         error::PaladinLockupError,
-        state::{get_escrow_authority_address, Lockup},
+        state::{
+            get_escrow_authority_address, get_pool_escrow_authority_address,
+            get_reward_authority_address, Lockup, LockupPool, LockupPoolRewards, LockupPoolStats,
+            PodU128, VestingSchedule,
+        },
         LOCKUP_COOLDOWN_SECONDS,
     },
     setup::{
-        add_seconds_to_clock, setup, setup_lockup, setup_lockup_pool, setup_mint,
-        setup_token_account,
+        add_seconds_to_clock, lockup_pool_state, setup, setup_lockup, setup_lockup_pool,
+        setup_lockup_pool_with_state, setup_mint, setup_mint_with_program_id,
+        setup_mint_with_transfer_fee, setup_token_account, setup_token_account_with_program_id,
+        setup_transfer_fee_token_account,
     },
     solana_program_test::*,
     solana_sdk::{
@@ -26,6 +32,7 @@ use {
     spl_discriminator::SplDiscriminate,
     spl_token_2022::{extension::StateWithExtensions, state::Account as TokenAccount},
     std::num::NonZeroU64,
+    test_case::test_case,
 };
 
 #[tokio::test]
@@ -48,8 +55,13 @@ async fn fail_lockup_authority_not_signer() {
         &token_account,
         &token_account,
         &lockup,
+        10_000,
+        Pubkey::new_unique(),
+        &lockup_pool_state(mint),
+        &[],
         &mint,
         &spl_token_2022::id(),
+        false,
     );
     instruction.accounts[0].is_signer = false; // Authority not signer.
 
@@ -112,8 +124,13 @@ async fn fail_incorrect_lockup_owner() {
         &token_account,
         &token_account,
         &lockup,
+        10_000,
+        Pubkey::new_unique(),
+        &lockup_pool_state(mint),
+        &[],
         &mint,
         &spl_token_2022::id(),
+        false,
     );
 
     let transaction = Transaction::new_signed_with_payer(
@@ -175,8 +192,13 @@ async fn fail_lockup_not_enough_space() {
         &token_account,
         &token_account,
         &lockup,
+        10_000,
+        Pubkey::new_unique(),
+        &lockup_pool_state(mint),
+        &[],
         &mint,
         &spl_token_2022::id(),
+        false,
     );
 
     let transaction = Transaction::new_signed_with_payer(
@@ -238,8 +260,13 @@ async fn fail_lockup_already_initialized() {
         &token_account,
         &token_account,
         &lockup,
+        10_000,
+        Pubkey::new_unique(),
+        &lockup_pool_state(mint),
+        &[],
         &mint,
         &spl_token_2022::id(),
+        false,
     );
 
     let transaction = Transaction::new_signed_with_payer(
@@ -302,6 +329,17 @@ async fn fail_incorrect_escrow_authority_address() {
             mint,
             pool,
             metadata: Pubkey::new_unique().to_bytes(),
+            proposed_authority: Pubkey::default(),
+            committed_end_timestamp: None,
+            vesting: VestingSchedule::default(),
+            withdrawn_amount: 0,
+            metadata_updated_timestamp: 0,
+            flags: 0,
+            reward_per_token_checkpoint: PodU128::default(),
+            unclaimed_rewards: 0,
+            cooldown_end_timestamp: NonZeroU64::new(
+                clock.unix_timestamp as u64 + LOCKUP_COOLDOWN_SECONDS,
+            ),
         },
     )
     .await;
@@ -312,10 +350,15 @@ async fn fail_incorrect_escrow_authority_address() {
         &token_account,
         &token_account,
         &lockup,
+        10_000,
+        pool,
+        &lockup_pool_state(mint),
+        &[],
         &mint,
         &spl_token_2022::id(),
+        false,
     );
-    instruction.accounts[4].pubkey = Pubkey::new_unique(); // Incorrect escrow authority address.
+    instruction.accounts[5].pubkey = Pubkey::new_unique(); // Incorrect escrow authority address.
 
     let transaction = Transaction::new_signed_with_payer(
         &[instruction],
@@ -381,6 +424,17 @@ async fn fail_incorrect_escrow_token_account_address() {
             mint,
             pool,
             metadata: Pubkey::new_unique().to_bytes(),
+            proposed_authority: Pubkey::default(),
+            committed_end_timestamp: None,
+            vesting: VestingSchedule::default(),
+            withdrawn_amount: 0,
+            metadata_updated_timestamp: 0,
+            flags: 0,
+            reward_per_token_checkpoint: PodU128::default(),
+            unclaimed_rewards: 0,
+            cooldown_end_timestamp: NonZeroU64::new(
+                clock.unix_timestamp as u64 + LOCKUP_COOLDOWN_SECONDS,
+            ),
         },
     )
     .await;
@@ -391,10 +445,15 @@ async fn fail_incorrect_escrow_token_account_address() {
         &token_account,
         &token_account,
         &lockup,
+        10_000,
+        pool,
+        &lockup_pool_state(mint),
+        &[],
         &mint,
         &spl_token_2022::id(),
+        false,
     );
-    instruction.accounts[5].pubkey = Pubkey::new_unique(); // Incorrect escrow token account address.
+    instruction.accounts[6].pubkey = Pubkey::new_unique(); // Incorrect escrow token account address.
 
     let transaction = Transaction::new_signed_with_payer(
         &[instruction],
@@ -460,6 +519,17 @@ async fn fail_lockup_still_active() {
             mint,
             pool,
             metadata: Pubkey::new_unique().to_bytes(),
+            proposed_authority: Pubkey::default(),
+            committed_end_timestamp: None,
+            vesting: VestingSchedule::default(),
+            withdrawn_amount: 0,
+            metadata_updated_timestamp: 0,
+            flags: 0,
+            reward_per_token_checkpoint: PodU128::default(),
+            unclaimed_rewards: 0,
+            cooldown_end_timestamp: NonZeroU64::new(
+                clock.unix_timestamp as u64 + LOCKUP_COOLDOWN_SECONDS,
+            ),
         },
     )
     .await;
@@ -470,8 +540,13 @@ async fn fail_lockup_still_active() {
         &token_account,
         &token_account,
         &lockup,
+        10_000,
+        pool,
+        &lockup_pool_state(mint),
+        &[],
         &mint,
         &spl_token_2022::id(),
+        false,
     );
 
     let transaction = Transaction::new_signed_with_payer(
@@ -538,6 +613,17 @@ async fn fail_incorrect_lockup_authority() {
             mint,
             pool,
             metadata: Pubkey::new_unique().to_bytes(),
+            proposed_authority: Pubkey::default(),
+            committed_end_timestamp: None,
+            vesting: VestingSchedule::default(),
+            withdrawn_amount: 0,
+            metadata_updated_timestamp: 0,
+            flags: 0,
+            reward_per_token_checkpoint: PodU128::default(),
+            unclaimed_rewards: 0,
+            cooldown_end_timestamp: NonZeroU64::new(
+                clock.unix_timestamp as u64 + LOCKUP_COOLDOWN_SECONDS,
+            ),
         },
     )
     .await;
@@ -548,8 +634,13 @@ async fn fail_incorrect_lockup_authority() {
         &token_account,
         &token_account,
         &lockup,
+        10_000,
+        pool,
+        &lockup_pool_state(mint),
+        &[],
         &mint,
         &spl_token_2022::id(),
+        false,
     );
 
     let transaction = Transaction::new_signed_with_payer(
@@ -613,6 +704,17 @@ async fn fail_incorrect_mint() {
             mint: Pubkey::new_unique(),                                         // Incorrect mint.
             pool,
             metadata: Pubkey::new_unique().to_bytes(),
+            proposed_authority: Pubkey::default(),
+            committed_end_timestamp: None,
+            vesting: VestingSchedule::default(),
+            withdrawn_amount: 0,
+            metadata_updated_timestamp: 0,
+            flags: 0,
+            reward_per_token_checkpoint: PodU128::default(),
+            unclaimed_rewards: 0,
+            cooldown_end_timestamp: NonZeroU64::new(
+                clock.unix_timestamp as u64 + LOCKUP_COOLDOWN_SECONDS,
+            ),
         },
     )
     .await;
@@ -623,8 +725,13 @@ async fn fail_incorrect_mint() {
         &token_account,
         &token_account,
         &lockup,
+        10_000,
+        pool,
+        &lockup_pool_state(mint),
+        &[],
         &mint,
         &spl_token_2022::id(),
+        false,
     );
 
     let transaction = Transaction::new_signed_with_payer(
@@ -650,15 +757,8 @@ async fn fail_incorrect_mint() {
     );
 }
 
-fn get_token_account_balance(token_account: &Account) -> u64 {
-    StateWithExtensions::<TokenAccount>::unpack(&token_account.data)
-        .unwrap()
-        .base
-        .amount
-}
-
 #[tokio::test]
-async fn success() {
+async fn fail_incorrect_pool() {
     let mint = Pubkey::new_unique();
 
     let authority = Keypair::new();
@@ -668,27 +768,24 @@ async fn success() {
         &spl_token_2022::id(),
     );
 
-    // Just posterity.
-    let lamport_destination = Pubkey::new_unique();
-
-    let escrow_authority = get_escrow_authority_address(&paladin_lockup_program::id());
-    let escrow_token_account = get_associated_token_address_with_program_id(
-        &escrow_authority,
-        &mint,
-        &spl_token_2022::id(),
-    );
-
     let lockup = Pubkey::new_unique();
 
-    let lockup_amount = 10_000;
-
     let mut context = setup().start_with_context().await;
+
     let clock = context.banks_client.get_sysvar::<Clock>().await.unwrap();
 
     // Create the lockup pool account.
     let pool = Pubkey::new_unique();
     setup_lockup_pool(&mut context, &pool, mint).await;
 
+    setup_token_account(
+        &mut context,
+        &token_account,
+        &authority.pubkey(),
+        &mint,
+        10_000,
+    )
+    .await;
     setup_lockup(
         &mut context,
         &lockup,
@@ -699,62 +796,141 @@ async fn success() {
             lockup_start_timestamp: clock.unix_timestamp as u64,
             lockup_end_timestamp: NonZeroU64::new(clock.unix_timestamp as u64), // Unlocked.
             mint,
-            pool,
+            pool: Pubkey::new_unique(), // Incorrect pool.
             metadata: Pubkey::new_unique().to_bytes(),
+            proposed_authority: Pubkey::default(),
+            committed_end_timestamp: None,
+            vesting: VestingSchedule::default(),
+            withdrawn_amount: 0,
+            metadata_updated_timestamp: 0,
+            flags: 0,
+            reward_per_token_checkpoint: PodU128::default(),
+            unclaimed_rewards: 0,
+            cooldown_end_timestamp: NonZeroU64::new(
+                clock.unix_timestamp as u64 + LOCKUP_COOLDOWN_SECONDS,
+            ),
         },
     )
     .await;
     add_seconds_to_clock(&mut context, LOCKUP_COOLDOWN_SECONDS).await;
-    setup_token_account(
-        &mut context,
+
+    let instruction = paladin_lockup_program::instruction::withdraw(
+        &authority.pubkey(),
         &token_account,
+        &token_account,
+        &lockup,
+        10_000,
+        pool,
+        &lockup_pool_state(mint),
+        &[],
+        &mint,
+        &spl_token_2022::id(),
+        false,
+    );
+
+    let transaction = Transaction::new_signed_with_payer(
+        &[instruction],
+        Some(&context.payer.pubkey()),
+        &[&context.payer, &authority],
+        context.last_blockhash,
+    );
+
+    let err = context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap_err()
+        .unwrap();
+
+    assert_eq!(
+        err,
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(PaladinLockupError::IncorrectPool as u32)
+        )
+    );
+}
+
+#[tokio::test]
+async fn fail_pool_cooldown_active() {
+    let mint = Pubkey::new_unique();
+
+    let authority = Keypair::new();
+    let token_account = get_associated_token_address_with_program_id(
         &authority.pubkey(),
         &mint,
-        10_000,
+        &spl_token_2022::id(),
+    );
+
+    let lockup = Pubkey::new_unique();
+
+    let mut context = setup().start_with_context().await;
+
+    let clock = context.banks_client.get_sysvar::<Clock>().await.unwrap();
+
+    // Create the lockup pool account with a cooldown longer than the default.
+    let pool = Pubkey::new_unique();
+    setup_lockup_pool_with_state(
+        &mut context,
+        &pool,
+        &LockupPool {
+            cooldown_seconds: 2 * LOCKUP_COOLDOWN_SECONDS,
+            ..lockup_pool_state(mint)
+        },
     )
     .await;
+
     setup_token_account(
         &mut context,
-        &escrow_token_account,
-        &escrow_authority,
+        &token_account,
+        &authority.pubkey(),
         &mint,
         10_000,
     )
     .await;
-    setup_mint(&mut context, &mint, &Pubkey::new_unique(), 1_000_000).await;
+    setup_lockup(
+        &mut context,
+        &lockup,
+        Lockup {
+            discriminator: Lockup::SPL_DISCRIMINATOR.into(),
+            amount: 10_000,
+            authority: authority.pubkey(),
+            lockup_start_timestamp: clock.unix_timestamp as u64,
+            lockup_end_timestamp: NonZeroU64::new(clock.unix_timestamp as u64), // Unlocked.
+            mint,
+            pool,
+            metadata: Pubkey::new_unique().to_bytes(),
+            proposed_authority: Pubkey::default(),
+            committed_end_timestamp: None,
+            vesting: VestingSchedule::default(),
+            withdrawn_amount: 0,
+            metadata_updated_timestamp: 0,
+            flags: 0,
+            reward_per_token_checkpoint: PodU128::default(),
+            unclaimed_rewards: 0,
+            // Unlocked under the pool's cooldown.
+            cooldown_end_timestamp: NonZeroU64::new(
+                clock.unix_timestamp as u64 + 2 * LOCKUP_COOLDOWN_SECONDS,
+            ),
+        },
+    )
+    .await;
 
-    // For checks later.
-    let lockup_account_start_lamports = context
-        .banks_client
-        .get_account(lockup)
-        .await
-        .unwrap()
-        .unwrap()
-        .lamports;
-    let token_account_start_balance = get_token_account_balance(
-        &context
-            .banks_client
-            .get_account(token_account)
-            .await
-            .unwrap()
-            .unwrap(),
-    );
-    let escrow_token_account_start_balance = get_token_account_balance(
-        &context
-            .banks_client
-            .get_account(escrow_token_account)
-            .await
-            .unwrap()
-            .unwrap(),
-    );
+    // Advance past the default cooldown, but not the lockup's.
+    add_seconds_to_clock(&mut context, LOCKUP_COOLDOWN_SECONDS).await;
 
     let instruction = paladin_lockup_program::instruction::withdraw(
         &authority.pubkey(),
-        &lamport_destination,
+        &token_account,
         &token_account,
         &lockup,
+        10_000,
+        pool,
+        &lockup_pool_state(mint),
+        &[],
         &mint,
         &spl_token_2022::id(),
+        false,
     );
 
     let transaction = Transaction::new_signed_with_payer(
@@ -764,26 +940,153 @@ async fn success() {
         context.last_blockhash,
     );
 
-    context
+    let err = context
         .banks_client
         .process_transaction(transaction)
         .await
+        .unwrap_err()
         .unwrap();
 
-    // Check the resulting destination lamport balance.
-    let lamport_destination_end_balance = context
+    assert_eq!(
+        err,
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(PaladinLockupError::LockupActive as u32)
+        )
+    );
+}
+
+#[tokio::test]
+async fn success_cooldown_fixed_at_unlock() {
+    let mint = Pubkey::new_unique();
+
+    let authority = Keypair::new();
+    let token_account = get_associated_token_address_with_program_id(
+        &authority.pubkey(),
+        &mint,
+        &spl_token_2022::id(),
+    );
+
+    let pool_authority = Keypair::new();
+    let pool = Pubkey::new_unique();
+    let escrow_authority = get_escrow_authority_address(&paladin_lockup_program::id());
+    let escrow_token_account = get_associated_token_address_with_program_id(
+        &escrow_authority,
+        &mint,
+        &spl_token_2022::id(),
+    );
+
+    let lockup = Pubkey::new_unique();
+
+    let mut context = setup().start_with_context().await;
+    let clock = context.banks_client.get_sysvar::<Clock>().await.unwrap();
+
+    setup_lockup_pool_with_state(
+        &mut context,
+        &pool,
+        &LockupPool {
+            authority: pool_authority.pubkey(),
+            stats: LockupPoolStats {
+                active_amount: 10_000,
+                active_lockups: 1,
+                ..LockupPoolStats::default()
+            },
+            ..lockup_pool_state(mint)
+        },
+    )
+    .await;
+    setup_lockup(
+        &mut context,
+        &lockup,
+        Lockup {
+            discriminator: Lockup::SPL_DISCRIMINATOR.into(),
+            amount: 10_000,
+            authority: authority.pubkey(),
+            lockup_start_timestamp: clock.unix_timestamp as u64,
+            lockup_end_timestamp: None,
+            mint,
+            pool,
+            metadata: Pubkey::new_unique().to_bytes(),
+            proposed_authority: Pubkey::default(),
+            committed_end_timestamp: None,
+            vesting: VestingSchedule::default(),
+            withdrawn_amount: 0,
+            metadata_updated_timestamp: 0,
+            flags: 0,
+            reward_per_token_checkpoint: PodU128::default(),
+            unclaimed_rewards: 0,
+            cooldown_end_timestamp: None,
+        },
+    )
+    .await;
+    setup_token_account(&mut context, &token_account, &authority.pubkey(), &mint, 0).await;
+    setup_token_account(
+        &mut context,
+        &escrow_token_account,
+        &escrow_authority,
+        &mint,
+        10_000,
+    )
+    .await;
+    setup_mint(&mut context, &mint, &Pubkey::new_unique(), 1_000_000).await;
+
+    // Unlock the lockup, then double the pool's cooldown.
+    let transaction = Transaction::new_signed_with_payer(
+        &[
+            paladin_lockup_program::instruction::unlock(
+                &authority.pubkey(),
+                pool,
+                &lockup_pool_state(mint),
+                &lockup,
+                10_000,
+            ),
+            paladin_lockup_program::instruction::update_pool_config(
+                &pool_authority.pubkey(),
+                pool,
+                None,
+                Some(2 * LOCKUP_COOLDOWN_SECONDS),
+                None,
+                None,
+                None,
+            ),
+        ],
+        Some(&context.payer.pubkey()),
+        &[&context.payer, &authority, &pool_authority],
+        context.last_blockhash,
+    );
+    context
         .banks_client
-        .get_account(lamport_destination)
+        .process_transaction(transaction)
         .await
-        .unwrap()
-        .unwrap()
-        .lamports;
-    assert_eq!(
-        lamport_destination_end_balance,
-        lockup_account_start_lamports
+        .unwrap();
+
+    // The lockup may be withdrawn after the cooldown in effect at unlock.
+    add_seconds_to_clock(&mut context, LOCKUP_COOLDOWN_SECONDS).await;
+
+    let transaction = Transaction::new_signed_with_payer(
+        &[paladin_lockup_program::instruction::withdraw(
+            &authority.pubkey(),
+            &token_account,
+            &token_account,
+            &lockup,
+            10_000,
+            pool,
+            &lockup_pool_state(mint),
+            &[],
+            &mint,
+            &spl_token_2022::id(),
+            false,
+        )],
+        Some(&context.payer.pubkey()),
+        &[&context.payer, &authority],
+        context.last_blockhash,
     );
+    context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap();
 
-    // Check the resulting token account balances.
     let token_account_end_balance = get_token_account_balance(
         &context
             .banks_client
@@ -792,24 +1095,629 @@ async fn success() {
             .unwrap()
             .unwrap(),
     );
-    let escrow_token_account_end_balance = get_token_account_balance(
-        &context
-            .banks_client
-            .get_account(escrow_token_account)
-            .await
-            .unwrap()
-            .unwrap(),
-    );
+    assert_eq!(token_account_end_balance, 10_000);
+}
 
-    assert_eq!(
-        token_account_end_balance,
-        token_account_start_balance.saturating_add(lockup_amount)
+#[tokio::test]
+async fn fail_withdrawals_paused() {
+    let mint = Pubkey::new_unique();
+
+    let authority = Keypair::new();
+    let token_account = get_associated_token_address_with_program_id(
+        &authority.pubkey(),
+        &mint,
+        &spl_token_2022::id(),
     );
-    assert_eq!(
-        escrow_token_account_end_balance,
+
+    let lockup = Pubkey::new_unique();
+
+    let mut context = setup().start_with_context().await;
+
+    let clock = context.banks_client.get_sysvar::<Clock>().await.unwrap();
+
+    // Create the lockup pool account with withdrawals paused.
+    let pool = Pubkey::new_unique();
+    setup_lockup_pool_with_state(
+        &mut context,
+        &pool,
+        &LockupPool {
+            flags: LockupPool::FLAG_WITHDRAW_PAUSED,
+            ..lockup_pool_state(mint)
+        },
+    )
+    .await;
+
+    setup_token_account(
+        &mut context,
+        &token_account,
+        &authority.pubkey(),
+        &mint,
+        10_000,
+    )
+    .await;
+    setup_lockup(
+        &mut context,
+        &lockup,
+        Lockup {
+            discriminator: Lockup::SPL_DISCRIMINATOR.into(),
+            amount: 10_000,
+            authority: authority.pubkey(),
+            lockup_start_timestamp: clock.unix_timestamp as u64,
+            lockup_end_timestamp: NonZeroU64::new(clock.unix_timestamp as u64), // Unlocked.
+            mint,
+            pool,
+            metadata: Pubkey::new_unique().to_bytes(),
+            proposed_authority: Pubkey::default(),
+            committed_end_timestamp: None,
+            vesting: VestingSchedule::default(),
+            withdrawn_amount: 0,
+            metadata_updated_timestamp: 0,
+            flags: 0,
+            reward_per_token_checkpoint: PodU128::default(),
+            unclaimed_rewards: 0,
+            cooldown_end_timestamp: NonZeroU64::new(
+                clock.unix_timestamp as u64 + LOCKUP_COOLDOWN_SECONDS,
+            ),
+        },
+    )
+    .await;
+
+    // Advance past the cooldown.
+    add_seconds_to_clock(&mut context, LOCKUP_COOLDOWN_SECONDS).await;
+
+    let instruction = paladin_lockup_program::instruction::withdraw(
+        &authority.pubkey(),
+        &token_account,
+        &token_account,
+        &lockup,
+        10_000,
+        pool,
+        &lockup_pool_state(mint),
+        &[],
+        &mint,
+        &spl_token_2022::id(),
+        false,
+    );
+
+    let transaction = Transaction::new_signed_with_payer(
+        &[instruction],
+        Some(&context.payer.pubkey()),
+        &[&context.payer, &authority],
+        context.last_blockhash,
+    );
+
+    let err = context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap_err()
+        .unwrap();
+
+    assert_eq!(
+        err,
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(PaladinLockupError::PoolPaused as u32)
+        )
+    );
+}
+
+#[tokio::test]
+async fn success_pays_unclaimed_rewards() {
+    let mint = Pubkey::new_unique();
+
+    let authority = Keypair::new();
+    let token_account = get_associated_token_address_with_program_id(
+        &authority.pubkey(),
+        &mint,
+        &spl_token_2022::id(),
+    );
+    let escrow_authority = get_escrow_authority_address(&paladin_lockup_program::id());
+    let escrow_token_account = get_associated_token_address_with_program_id(
+        &escrow_authority,
+        &mint,
+        &spl_token_2022::id(),
+    );
+
+    let lockup = Pubkey::new_unique();
+
+    let mut context = setup().start_with_context().await;
+
+    let clock = context.banks_client.get_sysvar::<Clock>().await.unwrap();
+
+    // Create the lockup pool account, with other lockups still active.
+    let pool = Pubkey::new_unique();
+    let reward_authority = get_reward_authority_address(&pool, &paladin_lockup_program::id());
+    let reward_vault = get_associated_token_address_with_program_id(
+        &reward_authority,
+        &mint,
+        &spl_token_2022::id(),
+    );
+    let pool_state = LockupPool {
+        stats: LockupPoolStats {
+            active_amount: 20_000,
+            active_lockups: 2,
+            unlocking_amount: 10_000,
+            ..LockupPoolStats::default()
+        },
+        rewards: LockupPoolRewards {
+            reward_per_token: PodU128::default(),
+            deposited_amount: 500,
+            claimed_amount: 0,
+            ..LockupPoolRewards::default()
+        },
+        ..lockup_pool_state(mint)
+    };
+    setup_lockup_pool_with_state(&mut context, &pool, &pool_state).await;
+
+    setup_token_account(&mut context, &token_account, &authority.pubkey(), &mint, 0).await;
+    setup_token_account(
+        &mut context,
+        &escrow_token_account,
+        &escrow_authority,
+        &mint,
+        10_000,
+    )
+    .await;
+    setup_token_account(&mut context, &reward_vault, &reward_authority, &mint, 500).await;
+    setup_mint(&mut context, &mint, &Pubkey::new_unique(), 1_000_000).await;
+    setup_lockup(
+        &mut context,
+        &lockup,
+        Lockup {
+            discriminator: Lockup::SPL_DISCRIMINATOR.into(),
+            amount: 10_000,
+            authority: authority.pubkey(),
+            lockup_start_timestamp: clock.unix_timestamp as u64,
+            lockup_end_timestamp: NonZeroU64::new(clock.unix_timestamp as u64), // Unlocked.
+            mint,
+            pool,
+            metadata: Pubkey::new_unique().to_bytes(),
+            proposed_authority: Pubkey::default(),
+            committed_end_timestamp: None,
+            vesting: VestingSchedule::default(),
+            withdrawn_amount: 0,
+            metadata_updated_timestamp: 0,
+            flags: 0,
+            reward_per_token_checkpoint: PodU128::default(),
+            unclaimed_rewards: 500, // Rewards left to claim.
+            cooldown_end_timestamp: NonZeroU64::new(
+                clock.unix_timestamp as u64 + LOCKUP_COOLDOWN_SECONDS,
+            ),
+        },
+    )
+    .await;
+
+    // Advance past the cooldown.
+    add_seconds_to_clock(&mut context, LOCKUP_COOLDOWN_SECONDS).await;
+
+    let instruction = paladin_lockup_program::instruction::withdraw(
+        &authority.pubkey(),
+        &token_account,
+        &token_account,
+        &lockup,
+        10_000,
+        pool,
+        &lockup_pool_state(mint),
+        &[],
+        &mint,
+        &spl_token_2022::id(),
+        false,
+    );
+
+    let transaction = Transaction::new_signed_with_payer(
+        &[instruction],
+        Some(&context.payer.pubkey()),
+        &[&context.payer, &authority],
+        context.last_blockhash,
+    );
+
+    context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap();
+
+    // Check the principal & the rewards were withdrawn & the lockup closed.
+    let token_account = context
+        .banks_client
+        .get_account(token_account)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(get_token_account_balance(&token_account), 10_500);
+    let reward_vault = context
+        .banks_client
+        .get_account(reward_vault)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(get_token_account_balance(&reward_vault), 0);
+    assert!(context
+        .banks_client
+        .get_account(lockup)
+        .await
+        .unwrap()
+        .is_none());
+
+    // Check the rewards were recorded as claimed.
+    let lockup_pool = context
+        .banks_client
+        .get_account(pool)
+        .await
+        .unwrap()
+        .unwrap();
+    let (lockup_pool, _) = LockupPool::unpack(&lockup_pool.data).unwrap();
+    assert_eq!(
+        lockup_pool.rewards,
+        LockupPoolRewards {
+            claimed_amount: 500,
+            ..pool_state.rewards
+        }
+    );
+}
+
+fn get_token_account_balance(token_account: &Account) -> u64 {
+    StateWithExtensions::<TokenAccount>::unpack(&token_account.data)
+        .unwrap()
+        .base
+        .amount
+}
+
+#[test_case(false, spl_token_2022::id(); "program_escrow")]
+#[test_case(true, spl_token_2022::id(); "pool_escrow")]
+#[test_case(false, spl_token::id(); "spl_token")]
+#[tokio::test]
+async fn success(isolated_escrow: bool, token_program_id: Pubkey) {
+    let mint = Pubkey::new_unique();
+
+    let authority = Keypair::new();
+    let token_account =
+        get_associated_token_address_with_program_id(&authority.pubkey(), &mint, &token_program_id);
+
+    // Just posterity.
+    let lamport_destination = Pubkey::new_unique();
+
+    let pool = Pubkey::new_unique();
+    let escrow_authority = match isolated_escrow {
+        true => get_pool_escrow_authority_address(&pool, &paladin_lockup_program::id()),
+        false => get_escrow_authority_address(&paladin_lockup_program::id()),
+    };
+    let escrow_token_account =
+        get_associated_token_address_with_program_id(&escrow_authority, &mint, &token_program_id);
+
+    let lockup = Pubkey::new_unique();
+
+    let lockup_amount = 10_000;
+
+    let mut context = setup().start_with_context().await;
+    let clock = context.banks_client.get_sysvar::<Clock>().await.unwrap();
+
+    // Create the lockup pool account, counting the lockup as unlocking.
+    setup_lockup_pool_with_state(
+        &mut context,
+        &pool,
+        &LockupPool {
+            flags: match isolated_escrow {
+                true => LockupPool::FLAG_ISOLATED_ESCROW,
+                false => 0,
+            },
+            stats: LockupPoolStats {
+                unlocking_amount: lockup_amount,
+                ..LockupPoolStats::default()
+            },
+            ..lockup_pool_state(mint)
+        },
+    )
+    .await;
+
+    setup_lockup(
+        &mut context,
+        &lockup,
+        Lockup {
+            discriminator: Lockup::SPL_DISCRIMINATOR.into(),
+            amount: 10_000,
+            authority: authority.pubkey(),
+            lockup_start_timestamp: clock.unix_timestamp as u64,
+            lockup_end_timestamp: NonZeroU64::new(clock.unix_timestamp as u64), // Unlocked.
+            mint,
+            pool,
+            metadata: Pubkey::new_unique().to_bytes(),
+            proposed_authority: Pubkey::default(),
+            committed_end_timestamp: None,
+            vesting: VestingSchedule::default(),
+            withdrawn_amount: 0,
+            metadata_updated_timestamp: 0,
+            flags: 0,
+            reward_per_token_checkpoint: PodU128::default(),
+            unclaimed_rewards: 0,
+            cooldown_end_timestamp: NonZeroU64::new(
+                clock.unix_timestamp as u64 + LOCKUP_COOLDOWN_SECONDS,
+            ),
+        },
+    )
+    .await;
+    add_seconds_to_clock(&mut context, LOCKUP_COOLDOWN_SECONDS).await;
+    setup_token_account_with_program_id(
+        &mut context,
+        &token_account,
+        &authority.pubkey(),
+        &mint,
+        10_000,
+        &token_program_id,
+    )
+    .await;
+    setup_token_account_with_program_id(
+        &mut context,
+        &escrow_token_account,
+        &escrow_authority,
+        &mint,
+        10_000,
+        &token_program_id,
+    )
+    .await;
+    setup_mint_with_program_id(
+        &mut context,
+        &mint,
+        &Pubkey::new_unique(),
+        1_000_000,
+        &token_program_id,
+    )
+    .await;
+
+    // For checks later.
+    let lockup_account_start_lamports = context
+        .banks_client
+        .get_account(lockup)
+        .await
+        .unwrap()
+        .unwrap()
+        .lamports;
+    let token_account_start_balance = get_token_account_balance(
+        &context
+            .banks_client
+            .get_account(token_account)
+            .await
+            .unwrap()
+            .unwrap(),
+    );
+    let escrow_token_account_start_balance = get_token_account_balance(
+        &context
+            .banks_client
+            .get_account(escrow_token_account)
+            .await
+            .unwrap()
+            .unwrap(),
+    );
+
+    let instruction = paladin_lockup_program::instruction::withdraw(
+        &authority.pubkey(),
+        &lamport_destination,
+        &token_account,
+        &lockup,
+        lockup_amount,
+        pool,
+        &lockup_pool_state(mint),
+        &[],
+        &mint,
+        &token_program_id,
+        isolated_escrow,
+    );
+
+    let transaction = Transaction::new_signed_with_payer(
+        &[instruction],
+        Some(&context.payer.pubkey()),
+        &[&context.payer, &authority],
+        context.last_blockhash,
+    );
+
+    context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap();
+
+    // Check the resulting destination lamport balance.
+    let lamport_destination_end_balance = context
+        .banks_client
+        .get_account(lamport_destination)
+        .await
+        .unwrap()
+        .unwrap()
+        .lamports;
+    assert_eq!(
+        lamport_destination_end_balance,
+        lockup_account_start_lamports
+    );
+
+    // Check the resulting token account balances.
+    let token_account_end_balance = get_token_account_balance(
+        &context
+            .banks_client
+            .get_account(token_account)
+            .await
+            .unwrap()
+            .unwrap(),
+    );
+    let escrow_token_account_end_balance = get_token_account_balance(
+        &context
+            .banks_client
+            .get_account(escrow_token_account)
+            .await
+            .unwrap()
+            .unwrap(),
+    );
+
+    assert_eq!(
+        token_account_end_balance,
+        token_account_start_balance.saturating_add(lockup_amount)
+    );
+    assert_eq!(
+        escrow_token_account_end_balance,
         escrow_token_account_start_balance.saturating_sub(lockup_amount)
     );
 
+    // Assert the lockup account was closed.
+    assert!(context
+        .banks_client
+        .get_account(lockup)
+        .await
+        .unwrap()
+        .is_none());
+
+    // Check the pool's stats.
+    let lockup_pool = context
+        .banks_client
+        .get_account(pool)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(
+        LockupPool::unpack(&lockup_pool.data).unwrap().0.stats,
+        LockupPoolStats {
+            withdrawn_amount: lockup_amount,
+            ..LockupPoolStats::default()
+        }
+    );
+}
+
+#[tokio::test]
+async fn success_transfer_fee() {
+    let mint = Pubkey::new_unique();
+
+    let authority = Keypair::new();
+    let token_account = get_associated_token_address_with_program_id(
+        &authority.pubkey(),
+        &mint,
+        &spl_token_2022::id(),
+    );
+
+    let escrow_authority = get_escrow_authority_address(&paladin_lockup_program::id());
+    let escrow_token_account = get_associated_token_address_with_program_id(
+        &escrow_authority,
+        &mint,
+        &spl_token_2022::id(),
+    );
+
+    let lockup = Pubkey::new_unique();
+
+    let mut context = setup().start_with_context().await;
+    let clock = context.banks_client.get_sysvar::<Clock>().await.unwrap();
+
+    // Create the lockup pool account, counting the lockup as unlocking.
+    let pool = Pubkey::new_unique();
+    setup_lockup_pool_with_state(
+        &mut context,
+        &pool,
+        &LockupPool {
+            stats: LockupPoolStats {
+                unlocking_amount: 10_000,
+                ..LockupPoolStats::default()
+            },
+            ..lockup_pool_state(mint)
+        },
+    )
+    .await;
+
+    setup_lockup(
+        &mut context,
+        &lockup,
+        Lockup {
+            discriminator: Lockup::SPL_DISCRIMINATOR.into(),
+            amount: 10_000,
+            authority: authority.pubkey(),
+            lockup_start_timestamp: clock.unix_timestamp as u64,
+            lockup_end_timestamp: NonZeroU64::new(clock.unix_timestamp as u64), // Unlocked.
+            mint,
+            pool,
+            metadata: Pubkey::new_unique().to_bytes(),
+            proposed_authority: Pubkey::default(),
+            committed_end_timestamp: None,
+            vesting: VestingSchedule::default(),
+            withdrawn_amount: 0,
+            metadata_updated_timestamp: 0,
+            flags: 0,
+            reward_per_token_checkpoint: PodU128::default(),
+            unclaimed_rewards: 0,
+            cooldown_end_timestamp: NonZeroU64::new(
+                clock.unix_timestamp as u64 + LOCKUP_COOLDOWN_SECONDS,
+            ),
+        },
+    )
+    .await;
+    add_seconds_to_clock(&mut context, LOCKUP_COOLDOWN_SECONDS).await;
+    setup_transfer_fee_token_account(&mut context, &token_account, &authority.pubkey(), &mint, 0)
+        .await;
+    setup_transfer_fee_token_account(
+        &mut context,
+        &escrow_token_account,
+        &escrow_authority,
+        &mint,
+        10_000,
+    )
+    .await;
+    // 1% transfer fee.
+    setup_mint_with_transfer_fee(
+        &mut context,
+        &mint,
+        &Pubkey::new_unique(),
+        1_000_000,
+        100,
+        u64::MAX,
+    )
+    .await;
+
+    let instruction = paladin_lockup_program::instruction::withdraw(
+        &authority.pubkey(),
+        &Pubkey::new_unique(),
+        &token_account,
+        &lockup,
+        10_000,
+        pool,
+        &lockup_pool_state(mint),
+        &[],
+        &mint,
+        &spl_token_2022::id(),
+        false,
+    );
+
+    let transaction = Transaction::new_signed_with_payer(
+        &[instruction],
+        Some(&context.payer.pubkey()),
+        &[&context.payer, &authority],
+        context.last_blockhash,
+    );
+
+    context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap();
+
+    // The escrow is emptied, with the fee withheld from the destination.
+    assert_eq!(
+        get_token_account_balance(
+            &context
+                .banks_client
+                .get_account(token_account)
+                .await
+                .unwrap()
+                .unwrap()
+        ),
+        9_900
+    );
+    assert_eq!(
+        get_token_account_balance(
+            &context
+                .banks_client
+                .get_account(escrow_token_account)
+                .await
+                .unwrap()
+                .unwrap()
+        ),
+        0
+    );
+
     // Assert the lockup account was closed.
     assert!(context
         .banks_client